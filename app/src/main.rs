@@ -57,6 +57,19 @@ fn main() {
         eprintln!("No API keys set. Will not listen to API requests.");
     }
     let static_file_directory = env::var("STATIC_FILE_DIRECTORY").ok();
+    // Comma separated list of hours before a session at which reminders are sent
+    let session_reminder_offsets: Vec<_> = match env::var("SESSION_REMINDER_OFFSETS") {
+        Ok(offsets) => offsets
+            .split(',')
+            .map(|hours| {
+                hours
+                    .trim()
+                    .parse::<i64>()
+                    .expect("Could not parse SESSION_REMINDER_OFFSETS")
+            })
+            .collect(),
+        Err(_) => lib::tasks::session_reminders::DEFAULT_REMINDER_OFFSETS_HOURS.to_vec(),
+    };
 
     // Connect to the local Redis server
     let redis_url = if cfg!(feature = "bottest") {
@@ -111,12 +124,15 @@ fn main() {
                 .await
         })
         .expect("Meetup access token could not be loaded from the database");
-    let async_meetup_client = match meetup_access_token {
-        Some(meetup_access_token) => Arc::new(futures_util::lock::Mutex::new(Some(Arc::new(
-            lib::meetup::newapi::AsyncClient::new(&meetup_access_token),
-        )))),
-        None => Arc::new(futures_util::lock::Mutex::new(None)),
-    };
+    let meetup_connection =
+        lib::meetup::connection::MeetupConnection::new(meetup_access_token.as_deref());
+
+    // Load the guild configurations (from the database, a config file or the environment)
+    let guild_configs = Arc::new(
+        async_runtime
+            .block_on(lib::discord::config::GuildConfigs::load(&pool))
+            .expect("Could not load the guild configuration"),
+    );
 
     let bot_shutdown_signal = Arc::new(AtomicBool::new(false));
     let mut bot = async_runtime
@@ -125,9 +141,10 @@ fn main() {
             discord_application_id,
             redis_client.clone(),
             pool.clone(),
-            async_meetup_client.clone(),
+            meetup_connection.clone(),
             meetup_oauth2_consumer.clone(),
             stripe_client.clone(),
+            guild_configs.clone(),
             bot_shutdown_signal.clone(),
         ))
         .expect("Could not create the Discord bot");
@@ -135,6 +152,8 @@ fn main() {
         cache: bot.cache.clone().into(),
         http: bot.http.clone(),
     };
+    meetup_connection
+        .set_alerts_channel(discord_api.clone(), guild_configs.default_guild().clone());
     let bot_id = futures::executor::block_on(async {
         bot.data
             .read()
@@ -174,8 +193,9 @@ fn main() {
         ([0, 0, 0, 0], port).into(),
         redis_client.clone(),
         pool.clone(),
-        async_meetup_client.clone(),
+        meetup_connection.clone(),
         discord_api.clone(),
+        guild_configs.clone(),
         bot_name,
         stripe_webhook_signing_secret,
         stripe_client.clone(),
@@ -188,7 +208,7 @@ fn main() {
     let organizer_token_refresh_task = lib::tasks::token_refresh::organizer_token_refresh_task(
         (*meetup_oauth2_consumer).clone(),
         pool.clone(),
-        async_meetup_client.clone(),
+        meetup_connection.clone(),
     );
 
     // // Users OAuth2 token refresh task
@@ -201,30 +221,52 @@ fn main() {
     let end_of_game_task = lib::tasks::end_of_game::create_recurring_end_of_game_task(
         pool.clone(),
         discord_api.clone(),
+        guild_configs.clone(),
         bot_id,
     );
 
+    // Session reminder task
+    let session_reminder_task =
+        lib::tasks::session_reminders::create_recurring_session_reminder_task(
+            pool.clone(),
+            discord_api.clone(),
+            session_reminder_offsets,
+        );
+
     // User topic voice channel reset task
     let user_topic_voice_channel_reset_task =
         lib::tasks::user_topic_voice_channel::reset_user_topic_voice_channel_task(
             redis_client.clone(),
             discord_api.clone(),
+            guild_configs.clone(),
         );
 
     let static_file_prefix = Box::leak(format!("{}/static/", lib::urls::BASE_URL).into_boxed_str());
     let syncing_task = lib::tasks::sync::create_recurring_syncing_task(
         pool.clone(),
         redis_client.clone(),
-        async_meetup_client.clone(),
+        meetup_connection.clone(),
         discord_api.clone(),
+        guild_configs.clone(),
         bot_id,
         static_file_prefix,
     );
 
+    // Targeted syncs which don't wait for the next syncing task run
+    let sync_queue_task = lib::tasks::sync_queue::create_sync_queue_task(
+        pool.clone(),
+        redis_client.clone(),
+        discord_api.clone(),
+        guild_configs.clone(),
+        bot_id,
+    );
+
     let stripe_subscription_refresh_task =
         lib::tasks::subscription_roles::stripe_subscriptions_refresh_task(
+            pool.clone(),
             discord_api.clone(),
             stripe_client.clone(),
+            guild_configs.default_guild().clone(),
         );
 
     // Wrap the long-running tasks in abortable Futures
@@ -233,9 +275,12 @@ fn main() {
     // let (users_token_refresh_task, abort_handle_users_token_refresh_task) =
     //     future::abortable(users_token_refresh_task);
     let (end_of_game_task, abort_handle_end_of_game_task) = future::abortable(end_of_game_task);
+    let (session_reminder_task, abort_handle_session_reminder_task) =
+        future::abortable(session_reminder_task);
     let (user_topic_voice_channel_reset_task, abort_handle_user_topic_voice_channel_reset_task) =
         future::abortable(user_topic_voice_channel_reset_task);
     let (syncing_task, abort_handle_syncing_task) = future::abortable(syncing_task);
+    let (sync_queue_task, abort_handle_sync_queue_task) = future::abortable(sync_queue_task);
     let (stripe_subscription_refresh_task, abort_handle_stripe_subscription_refresh_task) =
         future::abortable(stripe_subscription_refresh_task);
 
@@ -283,6 +328,10 @@ fn main() {
             let _ = end_of_game_task.await;
             println!("End of game task shut down.");
         });
+        tokio::spawn(async {
+            let _ = session_reminder_task.await;
+            println!("Session reminder task shut down.");
+        });
         tokio::spawn(async {
             let _ = user_topic_voice_channel_reset_task.await;
             println!("User topic voice channel reset task shut down.");
@@ -291,6 +340,10 @@ fn main() {
             let _ = syncing_task.await;
             println!("Syncing task shut down.");
         });
+        tokio::spawn(async {
+            let _ = sync_queue_task.await;
+            println!("Sync queue task shut down.");
+        });
         tokio::spawn(async {
             let _ = stripe_subscription_refresh_task.await;
             println!("Stripe subscription refresh task shut down.");
@@ -318,8 +371,10 @@ fn main() {
     abort_handle_organizer_token_refresh_task.abort();
     // abort_handle_users_token_refresh_task.abort();
     abort_handle_end_of_game_task.abort();
+    abort_handle_session_reminder_task.abort();
     abort_handle_user_topic_voice_channel_reset_task.abort();
     abort_handle_syncing_task.abort();
+    abort_handle_sync_queue_task.abort();
     abort_handle_stripe_subscription_refresh_task.abort();
     abort_web_server_tx.send(()).ok();
     println!("About to shut down the tokio runtime.");
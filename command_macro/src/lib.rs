@@ -65,6 +65,195 @@ impl Parse for RegexAttribute {
     }
 }
 
+struct SlashAttribute {
+    name: syn::LitStr,
+    description: syn::LitStr,
+    template: syn::LitStr,
+    autocomplete: Option<syn::Path>,
+}
+
+impl Parse for SlashAttribute {
+    fn parse(input: ParseStream<'_>) -> syn::parse::Result<Self> {
+        let name: syn::LitStr = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let description: syn::LitStr = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let template: syn::LitStr = input.parse()?;
+        let mut autocomplete = None;
+        if !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            let key: syn::Ident = input.parse()?;
+            if key != "autocomplete" {
+                return Err(syn::Error::new(key.span(), "Expected `autocomplete = ...`"));
+            }
+            let _: Token![=] = input.parse()?;
+            autocomplete = Some(input.parse()?);
+        }
+        Ok(SlashAttribute {
+            name,
+            description,
+            template,
+            autocomplete,
+        })
+    }
+}
+
+struct SlashOptionAttribute {
+    name: syn::LitStr,
+    kind: syn::Ident,
+    description: syn::LitStr,
+    optional: bool,
+    autocomplete: bool,
+}
+
+impl Parse for SlashOptionAttribute {
+    fn parse(input: ParseStream<'_>) -> syn::parse::Result<Self> {
+        let name: syn::LitStr = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let kind: syn::Ident = input.parse()?;
+        let _: Token![,] = input.parse()?;
+        let description: syn::LitStr = input.parse()?;
+        let mut optional = false;
+        let mut autocomplete = false;
+        while !input.is_empty() {
+            let _: Token![,] = input.parse()?;
+            let flag: syn::Ident = input.parse()?;
+            match flag.to_string().as_ref() {
+                "optional" => optional = true,
+                "autocomplete" => autocomplete = true,
+                _ => return Err(syn::Error::new(flag.span(), "Unknown slash option flag")),
+            }
+        }
+        Ok(SlashOptionAttribute {
+            name,
+            kind,
+            description,
+            optional,
+            autocomplete,
+        })
+    }
+}
+
+// Discord only accepts lower case names of up to 32 characters
+fn check_slash_name(name: &syn::LitStr) -> syn::Result<()> {
+    let value = name.value();
+    let is_valid = !value.is_empty()
+        && value.chars().count() <= 32
+        && value
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if is_valid {
+        Ok(())
+    } else {
+        Err(syn::Error::new(name.span(), "Invalid slash command name"))
+    }
+}
+
+// Discord only accepts descriptions of 1 to 100 characters
+fn check_slash_description(description: &syn::LitStr) -> syn::Result<()> {
+    let length = description.value().chars().count();
+    if (1..=100).contains(&length) {
+        Ok(())
+    } else {
+        Err(syn::Error::new(
+            description.span(),
+            "Slash command descriptions need to have 1 to 100 characters",
+        ))
+    }
+}
+
+fn slash_command_tokens(
+    slash: SlashAttribute,
+    options: Vec<SlashOptionAttribute>,
+) -> syn::Result<proc_macro2::TokenStream> {
+    check_slash_name(&slash.name)?;
+    check_slash_description(&slash.description)?;
+    // Every option needs to appear in the template and vice versa
+    let template = slash.template.value();
+    for option in &options {
+        check_slash_name(&option.name)?;
+        check_slash_description(&option.description)?;
+        if !template.contains(&format!("{{{}}}", option.name.value())) {
+            return Err(syn::Error::new(
+                option.name.span(),
+                "Slash option does not appear in the command template",
+            ));
+        }
+    }
+    let mut rest = template.as_str();
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..]
+            .find('}')
+            .map(|end| start + end)
+            .ok_or_else(|| syn::Error::new(slash.template.span(), "Unclosed `{` in template"))?;
+        let placeholder = &rest[start + 1..end];
+        if !options
+            .iter()
+            .any(|option| option.name.value() == placeholder)
+        {
+            return Err(syn::Error::new(
+                slash.template.span(),
+                format!("Template refers to unknown option \"{}\"", placeholder),
+            ));
+        }
+        rest = &rest[end + 1..];
+    }
+    if options.iter().any(|option| option.autocomplete) && slash.autocomplete.is_none() {
+        return Err(syn::Error::new(
+            slash.name.span(),
+            "Slash options with autocompletion need an `autocomplete = ...` handler",
+        ));
+    }
+    let option_entries = options
+        .iter()
+        .map(|option| {
+            let kind = match option.kind.to_string().as_ref() {
+                "user" => quote!(crate::discord::commands::SlashOptionKind::User),
+                "string" => quote!(crate::discord::commands::SlashOptionKind::String),
+                "integer" => quote!(crate::discord::commands::SlashOptionKind::Integer),
+                "channel" => quote!(crate::discord::commands::SlashOptionKind::Channel),
+                _ => {
+                    return Err(syn::Error::new(
+                        option.kind.span(),
+                        "Slash option kind needs to be one of user, string, integer or channel",
+                    ))
+                }
+            };
+            let SlashOptionAttribute {
+                name, description, ..
+            } = option;
+            let required = !option.optional;
+            let autocomplete = option.autocomplete;
+            Ok(quote! { crate::discord::commands::SlashOption {
+                name: #name,
+                description: #description,
+                kind: #kind,
+                required: #required,
+                autocomplete: #autocomplete,
+            } })
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let autocomplete = match slash.autocomplete {
+        Some(path) => quote!(Some(&#path)),
+        None => quote!(None),
+    };
+    let SlashAttribute {
+        name,
+        description,
+        template,
+        ..
+    } = slash;
+    Ok(quote! {
+        Some(&crate::discord::commands::SlashCommand {
+            name: #name,
+            description: #description,
+            template: #template,
+            options: &[#(#option_entries,)*],
+            autocomplete: #autocomplete,
+        })
+    })
+}
+
 #[proc_macro_attribute]
 pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let command_fun = parse_macro_input!(item as CommandFun);
@@ -72,6 +261,8 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut command_level = None;
     let mut unknown_attrs = vec![];
     let mut help_texts = vec![];
+    let mut slash = None;
+    let mut slash_options = vec![];
     for attribute in &command_fun.attributes {
         // let meta_attribute = propagate_err!(attribute.parse_meta());
         let attr_ident = match attribute.path().get_ident() {
@@ -101,6 +292,17 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
                 }
                 command_regex = Some((regex_attribute.format_str, regex_attribute.format_args));
             }
+            "slash" => {
+                let slash_attribute = propagate_err!(attribute.parse_args::<SlashAttribute>());
+                if slash.is_some() {
+                    panic!("Multiple slash commands specified for the same command");
+                }
+                slash = Some(slash_attribute);
+            }
+            "slash_option" => {
+                let option = propagate_err!(attribute.parse_args::<SlashOptionAttribute>());
+                slash_options.push(option);
+            }
             "help" => {
                 let parser = |input: ParseStream<'_>| {
                     let command: syn::LitStr = input.parse()?;
@@ -133,6 +335,16 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
         Some(level) => panic!("Invalid command level \"{}\"", level),
     };
 
+    let slash_command = match slash {
+        Some(slash) => propagate_err!(slash_command_tokens(slash, slash_options)),
+        None => {
+            if !slash_options.is_empty() {
+                panic!("Slash options specified for a command without a slash command");
+            }
+            quote!(None)
+        }
+    };
+
     let fun_ident = command_fun.fun.sig.ident.clone();
     let regex_fun_ident = format_ident!("{}_regex", fun_ident.to_string());
     let static_instance_name = format_ident!("{}_COMMAND", fun_ident.to_string().to_uppercase());
@@ -165,6 +377,7 @@ pub fn command(_attr: TokenStream, item: TokenStream) -> TokenStream {
             level: #command_level,
             fun: &#fun_ident,
             help: &[#(#help_entries,)*],
+            slash: #slash_command,
         };
     };
     // println!("{}", output.to_string());
@@ -0,0 +1,234 @@
+use askama::Template;
+use serenity::model::id::ChannelId;
+
+use crate::{
+    db,
+    discord::api::{ChannelInfo, DiscordApi, MessageInfo},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChannelArchiveId(pub i32);
+
+// What is stored as JSON
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct ArchiveContent {
+    pub channel_id: ChannelId,
+    pub channel_name: String,
+    pub archived_time: chrono::DateTime<chrono::Utc>,
+    // Oldest message first
+    pub messages: Vec<MessageInfo>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ChannelArchive {
+    pub id: ChannelArchiveId,
+    pub event_series_id: db::EventSeriesId,
+    pub channel_name: String,
+    pub created_time: chrono::DateTime<chrono::Utc>,
+    pub num_messages: i32,
+}
+
+#[derive(Template)]
+#[template(path = "archive_start.html")]
+struct ArchiveStartTemplate<'a> {
+    channel_name: &'a str,
+    archived_time: chrono::DateTime<chrono::Utc>,
+    num_messages: usize,
+    pinned_messages: &'a [MessageInfo],
+}
+
+#[derive(Template)]
+#[template(path = "archive_messages.html")]
+struct ArchiveMessagesTemplate<'a> {
+    messages: &'a [MessageInfo],
+}
+
+const ARCHIVE_END: &str = include_str!("../templates/archive_end.html");
+
+mod filters {
+    use chrono_tz::Europe;
+
+    pub fn local_time(time: &chrono::DateTime<chrono::Utc>) -> askama::Result<String> {
+        Ok(time
+            .with_timezone(&Europe::Zurich)
+            .format("%a, %d %b %Y %H:%M")
+            .to_string())
+    }
+}
+
+// Stores the channel's history before it gets deleted. A channel is only
+// archived once, so this returns the existing archive if there is one. The
+// history is fetched and stored page by page, and rendered as a self-contained
+// static page along the way.
+pub async fn archive_channel(
+    event_series_id: db::EventSeriesId,
+    channel: &ChannelInfo,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+) -> Result<ChannelArchiveId, crate::meetup::Error> {
+    let existing_id = sqlx::query_scalar!(
+        r#"SELECT id FROM channel_archive WHERE discord_text_channel_id = $1"#,
+        channel.id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    if let Some(id) = existing_id {
+        return Ok(ChannelArchiveId(id));
+    }
+    let content = ArchiveContent {
+        channel_id: channel.id,
+        channel_name: channel.name.clone(),
+        archived_time: chrono::Utc::now(),
+        messages: vec![],
+    };
+    let json = serde_json::to_string(&content).map_err(simple_error::SimpleError::from)?;
+    // Only complete archives become visible
+    let mut tx = db_connection.begin().await?;
+    let id = sqlx::query_scalar!(
+        r#"INSERT INTO channel_archive (event_series_id, discord_text_channel_id, channel_name, num_messages, content, html)
+        VALUES ($1, $2, $3, 0, $4::text::jsonb, '')
+        RETURNING id"#,
+        event_series_id.0,
+        channel.id.get() as i64,
+        &content.channel_name,
+        json
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    let mut num_messages = 0;
+    // Pinned messages are shown at the top of the page
+    let mut pinned_messages = vec![];
+    let mut after = None;
+    loop {
+        let messages = discord_api
+            .channel_messages_after(channel.id, after)
+            .await?;
+        after = match messages.last() {
+            Some(message) => Some(message.id),
+            None => break,
+        };
+        let html = ArchiveMessagesTemplate {
+            messages: &messages,
+        }
+        .render()?;
+        let json = serde_json::to_string(&messages).map_err(simple_error::SimpleError::from)?;
+        sqlx::query!(
+            r#"UPDATE channel_archive
+            SET num_messages = num_messages + $2,
+                content = jsonb_set(content, '{messages}', (content->'messages') || $3::text::jsonb),
+                html = html || $4
+            WHERE id = $1"#,
+            id,
+            messages.len() as i32,
+            json,
+            html
+        )
+        .execute(&mut *tx)
+        .await?;
+        num_messages += messages.len();
+        pinned_messages.extend(messages.into_iter().filter(|message| message.pinned));
+    }
+    let html_start = ArchiveStartTemplate {
+        channel_name: &content.channel_name,
+        archived_time: content.archived_time,
+        num_messages,
+        pinned_messages: &pinned_messages,
+    }
+    .render()?;
+    sqlx::query!(
+        r#"UPDATE channel_archive SET html = $2 || html || $3 WHERE id = $1"#,
+        id,
+        html_start,
+        ARCHIVE_END
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    println!(
+        "Archived {} messages of channel {} as archive {}",
+        num_messages, channel.id, id
+    );
+    Ok(ChannelArchiveId(id))
+}
+
+pub async fn get(
+    archive_id: ChannelArchiveId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<ChannelArchive>, crate::meetup::Error> {
+    let archive = sqlx::query!(
+        r#"SELECT id, event_series_id, channel_name, created_time, num_messages
+        FROM channel_archive
+        WHERE id = $1"#,
+        archive_id.0
+    )
+    .map(|row| ChannelArchive {
+        id: ChannelArchiveId(row.id),
+        event_series_id: db::EventSeriesId(row.event_series_id),
+        channel_name: row.channel_name,
+        created_time: row.created_time,
+        num_messages: row.num_messages,
+    })
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(archive)
+}
+
+pub async fn get_html(
+    archive_id: ChannelArchiveId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<String>, crate::meetup::Error> {
+    let html = sqlx::query_scalar!(
+        r#"SELECT html FROM channel_archive WHERE id = $1"#,
+        archive_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(html)
+}
+
+pub async fn get_json(
+    archive_id: ChannelArchiveId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<String>, crate::meetup::Error> {
+    let json = sqlx::query_scalar!(
+        r#"SELECT content::text AS "content!" FROM channel_archive WHERE id = $1"#,
+        archive_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(json)
+}
+
+// The archives of all series the member played in or hosted, newest first
+pub async fn list_for_member(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<ChannelArchive>, crate::meetup::Error> {
+    let archives = sqlx::query!(
+        r#"SELECT id, event_series_id, channel_name, created_time, num_messages
+        FROM channel_archive
+        WHERE event_series_id IN (
+            SELECT event.event_series_id
+            FROM event
+            INNER JOIN event_participant ON event.id = event_participant.event_id
+            WHERE event_participant.member_id = $1
+            UNION
+            SELECT event.event_series_id
+            FROM event
+            INNER JOIN event_host ON event.id = event_host.event_id
+            WHERE event_host.member_id = $1
+        )
+        ORDER BY created_time DESC"#,
+        member_id.0
+    )
+    .map(|row| ChannelArchive {
+        id: ChannelArchiveId(row.id),
+        event_series_id: db::EventSeriesId(row.event_series_id),
+        channel_name: row.channel_name,
+        created_time: row.created_time,
+        num_messages: row.num_messages,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(archives)
+}
@@ -0,0 +1,254 @@
+use serenity::model::id::{ChannelId, RoleId, UserId};
+
+use crate::db;
+
+// Number of entries returned by a query if no limit is specified
+const DEFAULT_QUERY_LIMIT: i64 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ChannelCreated,
+    ChannelDeleted,
+    RoleCreated,
+    RoleDeleted,
+    RoleAssigned,
+    RoleRemoved,
+    HostPermissionsAdded,
+    HostPermissionsRemoved,
+    UserAdded,
+    UserRemoved,
+    HostAdded,
+    HostRemoved,
+    AdventureEnded,
+    MeetupLinked,
+    MeetupUnlinked,
+    SessionRescheduled,
+    SessionCancelled,
+    OrphanAdopted,
+    OrphanRelinked,
+    OrphanForgotten,
+}
+
+impl Action {
+    pub const ALL: [Action; 20] = [
+        Action::ChannelCreated,
+        Action::ChannelDeleted,
+        Action::RoleCreated,
+        Action::RoleDeleted,
+        Action::RoleAssigned,
+        Action::RoleRemoved,
+        Action::HostPermissionsAdded,
+        Action::HostPermissionsRemoved,
+        Action::UserAdded,
+        Action::UserRemoved,
+        Action::HostAdded,
+        Action::HostRemoved,
+        Action::AdventureEnded,
+        Action::MeetupLinked,
+        Action::MeetupUnlinked,
+        Action::SessionRescheduled,
+        Action::SessionCancelled,
+        Action::OrphanAdopted,
+        Action::OrphanRelinked,
+        Action::OrphanForgotten,
+    ];
+
+    // The name stored in the database
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::ChannelCreated => "channel_created",
+            Action::ChannelDeleted => "channel_deleted",
+            Action::RoleCreated => "role_created",
+            Action::RoleDeleted => "role_deleted",
+            Action::RoleAssigned => "role_assigned",
+            Action::RoleRemoved => "role_removed",
+            Action::HostPermissionsAdded => "host_permissions_added",
+            Action::HostPermissionsRemoved => "host_permissions_removed",
+            Action::UserAdded => "user_added",
+            Action::UserRemoved => "user_removed",
+            Action::HostAdded => "host_added",
+            Action::HostRemoved => "host_removed",
+            Action::AdventureEnded => "adventure_ended",
+            Action::MeetupLinked => "meetup_linked",
+            Action::MeetupUnlinked => "meetup_unlinked",
+            Action::SessionRescheduled => "session_rescheduled",
+            Action::SessionCancelled => "session_cancelled",
+            Action::OrphanAdopted => "orphan_adopted",
+            Action::OrphanRelinked => "orphan_relinked",
+            Action::OrphanForgotten => "orphan_forgotten",
+        }
+    }
+
+    pub fn from_name(action: &str) -> Option<Action> {
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.as_str() == action)
+    }
+}
+
+// What an action was performed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Channel(ChannelId),
+    Role(RoleId),
+    User(UserId),
+    Member(db::MemberId),
+    EventSeries(db::EventSeriesId),
+    Event(db::EventId),
+}
+
+impl Target {
+    fn type_and_id(&self) -> (&'static str, i64) {
+        match self {
+            Target::Channel(id) => ("channel", id.get() as i64),
+            Target::Role(id) => ("role", id.get() as i64),
+            Target::User(id) => ("user", id.get() as i64),
+            Target::Member(id) => ("member", id.0 as i64),
+            Target::EventSeries(id) => ("event_series", id.0 as i64),
+            Target::Event(id) => ("event", id.0 as i64),
+        }
+    }
+}
+
+pub struct Entry {
+    actor: Option<UserId>,
+    action: Action,
+    target: Target,
+    channel_id: Option<ChannelId>,
+    before: Option<serde_json::Value>,
+    after: Option<serde_json::Value>,
+}
+
+impl Entry {
+    pub fn new(action: Action, target: Target) -> Self {
+        Entry {
+            actor: None,
+            action,
+            target,
+            channel_id: if let Target::Channel(channel_id) = target {
+                Some(channel_id)
+            } else {
+                None
+            },
+            before: None,
+            after: None,
+        }
+    }
+
+    // The Discord user who triggered the action. Without an actor, the action
+    // is attributed to the bot itself.
+    pub fn actor(mut self, actor: UserId) -> Self {
+        self.actor = Some(actor);
+        self
+    }
+
+    pub fn channel(mut self, channel_id: ChannelId) -> Self {
+        self.channel_id = Some(channel_id);
+        self
+    }
+
+    pub fn before(mut self, before: serde_json::Value) -> Self {
+        self.before = Some(before);
+        self
+    }
+
+    pub fn after(mut self, after: serde_json::Value) -> Self {
+        self.after = Some(after);
+        self
+    }
+}
+
+// Writes an entry to the audit log.
+// Failing to do so is logged but not treated as an error, since the action
+// itself has already happened at this point.
+pub async fn record<'c>(entry: Entry, db_connection: impl sqlx::PgExecutor<'c>) {
+    let (target_type, target_id) = entry.target.type_and_id();
+    let result = sqlx::query!(
+        r#"INSERT INTO audit_log (actor_discord_id, action, target_type, target_id, channel_id, before, after)
+        VALUES ($1, $2, $3, $4, $5, $6::text::jsonb, $7::text::jsonb)"#,
+        entry.actor.map(|id| id.get() as i64),
+        entry.action.as_str(),
+        target_type,
+        target_id,
+        entry.channel_id.map(|id| id.get() as i64),
+        entry.before.map(|value| value.to_string()),
+        entry.after.map(|value| value.to_string())
+    )
+    .execute(db_connection)
+    .await;
+    if let Err(err) = result {
+        eprintln!(
+            "Could not write \"{}\" to the audit log:\n{:#?}",
+            entry.action.as_str(),
+            err
+        );
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Filter {
+    pub channel_id: Option<ChannelId>,
+    pub actor: Option<UserId>,
+    pub action: Option<Action>,
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoggedEntry {
+    pub id: i32,
+    pub action_time: chrono::DateTime<chrono::Utc>,
+    pub actor: Option<UserId>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: i64,
+    pub channel_id: Option<ChannelId>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+impl LoggedEntry {
+    // A short, Discord-formatted description of the target
+    pub fn target_mention(&self) -> String {
+        match self.target_type.as_str() {
+            "channel" => format!("<#{}>", self.target_id),
+            "role" => format!("<@&{}>", self.target_id),
+            "user" => format!("<@{}>", self.target_id),
+            _ => format!("{} {}", self.target_type, self.target_id),
+        }
+    }
+}
+
+// Returns the most recent entries matching the filter, newest first
+pub async fn query(
+    filter: &Filter,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<LoggedEntry>, crate::meetup::Error> {
+    let entries = sqlx::query!(
+        r#"SELECT id, action_time, actor_discord_id, action, target_type, target_id, channel_id, before::text, after::text
+        FROM audit_log
+        WHERE ($1::bigint IS NULL OR channel_id = $1)
+        AND ($2::bigint IS NULL OR actor_discord_id = $2)
+        AND ($3::text IS NULL OR action = $3)
+        ORDER BY action_time DESC, id DESC
+        LIMIT $4"#,
+        filter.channel_id.map(|id| id.get() as i64),
+        filter.actor.map(|id| id.get() as i64),
+        filter.action.map(|action| action.as_str()),
+        filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT)
+    )
+    .map(|row| LoggedEntry {
+        id: row.id,
+        action_time: row.action_time,
+        actor: row.actor_discord_id.map(|id| UserId::new(id as u64)),
+        action: row.action,
+        target_type: row.target_type,
+        target_id: row.target_id,
+        channel_id: row.channel_id.map(|id| ChannelId::new(id as u64)),
+        before: row.before,
+        after: row.after,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(entries)
+}
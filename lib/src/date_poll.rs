@@ -0,0 +1,428 @@
+use std::fmt::Write;
+
+use serenity::{
+    builder::{CreateEmbed, CreateMessage, EditMessage},
+    model::id::{ChannelId, MessageId},
+};
+
+use crate::db;
+
+// Upper limit for the number of candidate slots of a poll
+pub const MAX_SLOTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DatePollId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DatePollSlotId(pub i32);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    Maybe,
+    No,
+}
+
+impl Answer {
+    pub const ALL: [Answer; 3] = [Answer::Yes, Answer::Maybe, Answer::No];
+
+    // The name stored in the database and used in forms
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Answer::Yes => "yes",
+            Answer::Maybe => "maybe",
+            Answer::No => "no",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Answer> {
+        Answer::ALL
+            .iter()
+            .copied()
+            .find(|answer| answer.as_str() == name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DatePoll {
+    pub id: DatePollId,
+    pub event_series_id: db::EventSeriesId,
+    pub created_by: db::MemberId,
+    pub duration: chrono::Duration,
+    // Set once one of the slots has been scheduled
+    pub scheduled_event_id: Option<db::EventId>,
+    // Ordered by start time
+    pub slots: Vec<DatePollSlot>,
+}
+
+#[derive(Debug, Clone)]
+pub struct DatePollSlot {
+    pub id: DatePollSlotId,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub votes: Vec<(db::MemberId, Answer)>,
+}
+
+impl DatePollSlot {
+    pub fn count(&self, answer: Answer) -> usize {
+        self.votes
+            .iter()
+            .filter(|(_, vote_answer)| *vote_answer == answer)
+            .count()
+    }
+}
+
+impl DatePoll {
+    pub fn is_open(&self) -> bool {
+        self.scheduled_event_id.is_none()
+    }
+
+    // The slot most players can make, preferring fewer "no" votes and more
+    // "maybe" votes on a tie and the earlier slot after that
+    pub fn winning_slot(&self) -> Option<&DatePollSlot> {
+        self.slots
+            .iter()
+            .filter(|slot| slot.count(Answer::Yes) > 0)
+            .min_by_key(|slot| {
+                (
+                    std::cmp::Reverse(slot.count(Answer::Yes)),
+                    slot.count(Answer::No),
+                    std::cmp::Reverse(slot.count(Answer::Maybe)),
+                    slot.start_time,
+                )
+            })
+    }
+
+    pub fn slot(&self, slot_id: DatePollSlotId) -> Option<&DatePollSlot> {
+        self.slots.iter().find(|slot| slot.id == slot_id)
+    }
+
+    // Members who voted on at least one slot
+    pub fn num_voters(&self) -> usize {
+        let mut voters: Vec<_> = self
+            .slots
+            .iter()
+            .flat_map(|slot| slot.votes.iter().map(|(member_id, _)| member_id.0))
+            .collect();
+        voters.sort_unstable();
+        voters.dedup();
+        voters.len()
+    }
+}
+
+pub async fn create(
+    event_series_id: db::EventSeriesId,
+    created_by: db::MemberId,
+    start_times: &[chrono::DateTime<chrono::Utc>],
+    duration: chrono::Duration,
+    db_connection: &sqlx::PgPool,
+) -> Result<DatePollId, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    let poll_id = sqlx::query_scalar!(
+        r#"INSERT INTO date_poll (event_series_id, created_by_member_id, duration_minutes)
+        VALUES ($1, $2, $3)
+        RETURNING id"#,
+        event_series_id.0,
+        created_by.0,
+        duration.num_minutes() as i32
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    for start_time in start_times {
+        sqlx::query!(
+            r#"INSERT INTO date_poll_slot (date_poll_id, start_time) VALUES ($1, $2)"#,
+            poll_id,
+            start_time
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(DatePollId(poll_id))
+}
+
+pub async fn get(
+    poll_id: DatePollId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<DatePoll>, crate::meetup::Error> {
+    let poll = sqlx::query!(
+        r#"SELECT event_series_id, created_by_member_id, duration_minutes, scheduled_event_id
+        FROM date_poll
+        WHERE id = $1"#,
+        poll_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    let poll = match poll {
+        Some(poll) => poll,
+        None => return Ok(None),
+    };
+    let mut slots: Vec<_> = sqlx::query!(
+        r#"SELECT id, start_time FROM date_poll_slot WHERE date_poll_id = $1 ORDER BY start_time"#,
+        poll_id.0
+    )
+    .map(|row| DatePollSlot {
+        id: DatePollSlotId(row.id),
+        start_time: row.start_time,
+        votes: vec![],
+    })
+    .fetch_all(db_connection)
+    .await?;
+    let votes = sqlx::query!(
+        r#"SELECT date_poll_vote.date_poll_slot_id, date_poll_vote.member_id, date_poll_vote.answer
+        FROM date_poll_vote
+        INNER JOIN date_poll_slot ON date_poll_vote.date_poll_slot_id = date_poll_slot.id
+        WHERE date_poll_slot.date_poll_id = $1"#,
+        poll_id.0
+    )
+    .fetch_all(db_connection)
+    .await?;
+    for vote in votes {
+        let answer = match Answer::from_name(&vote.answer) {
+            Some(answer) => answer,
+            None => continue,
+        };
+        if let Some(slot) = slots
+            .iter_mut()
+            .find(|slot| slot.id.0 == vote.date_poll_slot_id)
+        {
+            slot.votes.push((db::MemberId(vote.member_id), answer));
+        }
+    }
+    Ok(Some(DatePoll {
+        id: poll_id,
+        event_series_id: db::EventSeriesId(poll.event_series_id),
+        created_by: db::MemberId(poll.created_by_member_id),
+        duration: chrono::Duration::minutes(poll.duration_minutes as i64),
+        scheduled_event_id: poll.scheduled_event_id.map(db::EventId),
+        slots,
+    }))
+}
+
+// The players and hosts of all of the series' sessions may vote
+pub async fn is_eligible_voter(
+    event_series_id: db::EventSeriesId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    db::is_series_participant(event_series_id, member_id, db_connection).await
+}
+
+// Only the poll's creator and the hosts of the series may schedule a slot
+pub async fn may_schedule(
+    poll: &DatePoll,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    if poll.created_by == member_id {
+        return Ok(true);
+    }
+    is_host(poll.event_series_id, member_id, db_connection).await
+}
+
+async fn is_host(
+    event_series_id: db::EventSeriesId,
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let event_ids: Vec<_> = db::get_events_for_series(db_connection, event_series_id)
+        .await?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+    let hosts = db::get_events_participants(&event_ids, true, db_connection).await?;
+    Ok(hosts.iter().any(|host| host.id == member_id))
+}
+
+// Replaces the member's votes with the specified ones. Slots without an
+// answer are treated as not voted on.
+pub async fn vote(
+    poll: &DatePoll,
+    member_id: db::MemberId,
+    answers: &[(DatePollSlotId, Answer)],
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    let slot_ids: Vec<_> = poll.slots.iter().map(|slot| slot.id.0).collect();
+    let mut tx = db_connection.begin().await?;
+    sqlx::query!(
+        r#"DELETE FROM date_poll_vote WHERE member_id = $1 AND date_poll_slot_id = ANY($2)"#,
+        member_id.0,
+        &slot_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+    for (slot_id, answer) in answers {
+        if !slot_ids.contains(&slot_id.0) {
+            continue;
+        }
+        sqlx::query!(
+            r#"INSERT INTO date_poll_vote (date_poll_slot_id, member_id, answer) VALUES ($1, $2, $3)"#,
+            slot_id.0,
+            member_id.0,
+            answer.as_str()
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+// Reserves an open poll for scheduling one of its slots, so that a second
+// submission can't schedule another session. Returns false if the poll is
+// already decided or being scheduled.
+pub async fn claim(
+    poll_id: DatePollId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let claimed = sqlx::query!(
+        r#"UPDATE date_poll SET closed_time = NOW()
+        WHERE id = $1 AND scheduled_event_id IS NULL AND closed_time IS NULL"#,
+        poll_id.0
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected()
+        > 0;
+    Ok(claimed)
+}
+
+// Gives up a claim if scheduling failed, so that the hosts can try again
+pub async fn release(
+    poll_id: DatePollId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"UPDATE date_poll SET closed_time = NULL WHERE id = $1 AND scheduled_event_id IS NULL"#,
+        poll_id.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+// Marks the poll as decided. Returns false if it already was.
+pub async fn close(
+    poll_id: DatePollId,
+    scheduled_event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let closed = sqlx::query!(
+        r#"UPDATE date_poll SET scheduled_event_id = $2, closed_time = NOW()
+        WHERE id = $1 AND scheduled_event_id IS NULL"#,
+        poll_id.0,
+        scheduled_event_id.0
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected()
+        > 0;
+    Ok(closed)
+}
+
+// Posts the poll's summary in the series' channel or updates the existing one
+pub async fn sync_summary(
+    poll_id: DatePollId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl crate::discord::api::DiscordApi,
+) -> Result<(), crate::meetup::Error> {
+    let poll = match get(poll_id, db_connection).await? {
+        Some(poll) => poll,
+        None => return Ok(()),
+    };
+    let summary = sqlx::query!(
+        r#"SELECT date_poll.discord_summary_channel_id, date_poll.discord_summary_message_id, event_series_text_channel.discord_id AS "discord_text_channel_id?"
+        FROM date_poll
+        INNER JOIN event_series ON date_poll.event_series_id = event_series.id
+        LEFT OUTER JOIN event_series_text_channel ON event_series.discord_text_channel_id = event_series_text_channel.discord_id AND event_series_text_channel.deleted IS NULL
+        WHERE date_poll.id = $1"#,
+        poll_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    let embed = summary_embed(&poll);
+    // Try to update the existing summary first
+    if let (Some(channel_id), Some(message_id)) = (
+        summary.discord_summary_channel_id,
+        summary.discord_summary_message_id,
+    ) {
+        let channel_id = ChannelId::new(channel_id as u64);
+        let message_id = MessageId::new(message_id as u64);
+        let edited = discord_api
+            .edit_message(
+                channel_id,
+                message_id,
+                EditMessage::new().embed(embed.clone()),
+            )
+            .await?;
+        if edited {
+            return Ok(());
+        }
+        eprintln!(
+            "The summary of date poll {} is gone, posting a new one",
+            poll_id.0
+        );
+    }
+    let channel_id = match summary.discord_text_channel_id {
+        Some(id) => ChannelId::new(id as u64),
+        // The series has no channel (anymore)
+        None => return Ok(()),
+    };
+    let message_id = discord_api
+        .send_message(
+            channel_id,
+            CreateMessage::new()
+                .content(crate::strings::DATE_POLL_CREATED)
+                .embed(embed),
+        )
+        .await?;
+    sqlx::query!(
+        r#"UPDATE date_poll SET discord_summary_channel_id = $2, discord_summary_message_id = $3 WHERE id = $1"#,
+        poll_id.0,
+        channel_id.get() as i64,
+        message_id.get() as i64
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+fn summary_embed(poll: &DatePoll) -> CreateEmbed {
+    let winning_slot_id = poll.winning_slot().map(|slot| slot.id);
+    let mut description = String::new();
+    for slot in &poll.slots {
+        let marker = if Some(slot.id) == winning_slot_id {
+            ":star: "
+        } else {
+            ""
+        };
+        writeln!(
+            &mut description,
+            "{}<t:{}:F> — :white_check_mark: {} · :grey_question: {} · :x: {}",
+            marker,
+            slot.start_time.timestamp(),
+            slot.count(Answer::Yes),
+            slot.count(Answer::Maybe),
+            slot.count(Answer::No)
+        )
+        .ok();
+    }
+    if poll.is_open() {
+        write!(
+            &mut description,
+            "\n{} voted so far. [Vote here]({}/date_poll/{}) (use the `login` command first if \
+             the page asks you to log in).",
+            poll.num_voters(),
+            crate::urls::BASE_URL,
+            poll.id.0
+        )
+        .ok();
+        CreateEmbed::new()
+            .title("When should the next session take place?")
+            .description(description)
+    } else {
+        description.push_str("\nThis poll is closed, the next session has been scheduled.");
+        CreateEmbed::new()
+            .title("Date poll (closed)")
+            .description(description)
+    }
+}
@@ -1,4 +1,4 @@
-use serenity::model::id::{ChannelId, UserId};
+use serenity::model::id::{ChannelId, GuildId, UserId};
 
 // macro_rules! create_wrapped_type {
 //     ($Wrapper:ident, $T:ident) => {
@@ -322,8 +322,29 @@ pub async fn get_or_create_member_for_discord_id(
     }
 }
 
+// Whether the member played in or hosted any of the series' sessions
+pub async fn is_series_participant(
+    event_series_id: EventSeriesId,
+    member_id: MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let event_ids: Vec<_> = get_events_for_series(db_connection, event_series_id)
+        .await?
+        .into_iter()
+        .map(|event| event.id)
+        .collect();
+    for hosts in [false, true] {
+        let members = get_events_participants(&event_ids, hosts, db_connection).await?;
+        if members.iter().any(|member| member.id == member_id) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
 // Return a list of members attending the specified events.
 // If hosts is `false` returns all guests, if `hosts` is true, returns all hosts.
+// The members' nicks are the ones from the guild of the event's series.
 pub async fn get_events_participants(
     event_ids: &[EventId],
     hosts: bool,
@@ -334,10 +355,12 @@ pub async fn get_events_participants(
     let members = if hosts {
         sqlx::query_as!(
             MemberQueryHelper,
-            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, "member".discord_nick
+            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, COALESCE(guild_member.discord_nick, "member".discord_nick) AS discord_nick
             FROM event
             INNER JOIN event_host ON event.id = event_host.event_id
             INNER JOIN "member" ON event_host.member_id = "member".id
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            LEFT JOIN guild_member ON event_series.guild_id = guild_member.guild_id AND "member".id = guild_member.member_id
             WHERE event.id = ANY($1)
             "#,
             &event_ids
@@ -345,10 +368,12 @@ pub async fn get_events_participants(
     } else {
         sqlx::query_as!(
             MemberQueryHelper,
-            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, "member".discord_nick
+            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, COALESCE(guild_member.discord_nick, "member".discord_nick) AS discord_nick
             FROM event
             INNER JOIN event_participant ON event.id = event_participant.event_id
             INNER JOIN "member" ON event_participant.member_id = "member".id
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            LEFT JOIN guild_member ON event_series.guild_id = guild_member.guild_id AND "member".id = guild_member.member_id
             WHERE event.id = ANY($1)
             "#,
             &event_ids
@@ -359,6 +384,7 @@ pub async fn get_events_participants(
 
 // Return a list of members attending the specified Meetup events.
 // If hosts is `false` returns all guests, if `hosts` is true, returns all hosts.
+// The members' nicks are the ones from the guild of the event's series.
 pub async fn get_meetup_events_participants(
     meetup_event_ids: &[String],
     hosts: bool,
@@ -368,11 +394,13 @@ pub async fn get_meetup_events_participants(
     let members = if hosts {
         sqlx::query_as!(
             MemberQueryHelper,
-            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, "member".discord_nick
+            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, COALESCE(guild_member.discord_nick, "member".discord_nick) AS discord_nick
             FROM meetup_event
             INNER JOIN event ON meetup_event.event_id = event.id
             INNER JOIN event_host ON event.id = event_host.event_id
             INNER JOIN "member" ON event_host.member_id = "member".id
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            LEFT JOIN guild_member ON event_series.guild_id = guild_member.guild_id AND "member".id = guild_member.member_id
             WHERE meetup_event.meetup_id = ANY($1)
             "#,
             meetup_event_ids
@@ -380,11 +408,13 @@ pub async fn get_meetup_events_participants(
     } else {
         sqlx::query_as!(
             MemberQueryHelper,
-            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, "member".discord_nick
+            r#"SELECT "member".id as "id!", "member".meetup_id, "member".discord_id, COALESCE(guild_member.discord_nick, "member".discord_nick) AS discord_nick
             FROM meetup_event
             INNER JOIN event ON meetup_event.event_id = event.id
             INNER JOIN event_participant ON event.id = event_participant.event_id
             INNER JOIN "member" ON event_participant.member_id = "member".id
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            LEFT JOIN guild_member ON event_series.guild_id = guild_member.guild_id AND "member".id = guild_member.member_id
             WHERE meetup_event.meetup_id = ANY($1)
             "#,
             meetup_event_ids
@@ -457,3 +487,20 @@ pub async fn discord_ids_to_members(
     .await?;
     Ok(members)
 }
+
+// The member's nick in the given guild, if the bot has seen one
+pub async fn guild_nick(
+    guild_id: GuildId,
+    member_id: MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<String>, crate::meetup::Error> {
+    let nick = sqlx::query_scalar!(
+        r#"SELECT discord_nick FROM guild_member WHERE guild_id = $1 AND member_id = $2"#,
+        guild_id.get() as i64,
+        member_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
+    Ok(nick)
+}
@@ -0,0 +1,112 @@
+use serenity::builder::CreateMessage;
+
+use super::free_spots::EventCollector;
+use crate::{db, discord::config::GuildConfigs, meetup::directives::EventDirectives, DefaultStr};
+
+impl EventCollector {
+    // Lets the hosts know about malformed or conflicting shortcodes and
+    // shortcodes referring to channels or roles which don't exist. The hosts
+    // are notified via DM (or the bot alerts channel if that's not possible),
+    // once for every new set of problems.
+    pub async fn report_directive_errors(
+        &self,
+        db_connection: &sqlx::PgPool,
+        discord_api: &crate::discord::CacheAndHttp,
+        guild_configs: &GuildConfigs,
+    ) -> Result<(), crate::meetup::Error> {
+        let guild_config = guild_configs.default_guild();
+        for event in &self.events {
+            let description = event.description.unwrap_or_str("");
+            let directives = EventDirectives::parse(description);
+            let mut errors = directives.errors.clone();
+            errors.extend(directives.validate(
+                description,
+                &discord_api.cache,
+                guild_configs,
+                guild_config.guild_id,
+            ));
+            errors.sort_by_key(|error| error.span.start);
+            let problems = errors
+                .iter()
+                .map(|error| format!("• {}", error))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let reported_problems = sqlx::query_scalar!(
+                r#"SELECT problems FROM meetup_event_directive_report WHERE meetup_id = $1"#,
+                event.id.0
+            )
+            .fetch_optional(db_connection)
+            .await?;
+            if errors.is_empty() {
+                if reported_problems.is_some() {
+                    // The host fixed the description
+                    sqlx::query!(
+                        r#"DELETE FROM meetup_event_directive_report WHERE meetup_id = $1"#,
+                        event.id.0
+                    )
+                    .execute(db_connection)
+                    .await?;
+                }
+                continue;
+            }
+            if reported_problems.as_ref() == Some(&problems) {
+                continue;
+            }
+            let title = event.title.unwrap_or_str("No title");
+            println!(
+                "Reporting shortcode problems of Meetup event {}:\n{}",
+                event.id, problems
+            );
+            let message =
+                crate::strings::EVENT_DIRECTIVE_ERRORS(title, &event.short_url, &problems);
+            let hosts = db::meetup_ids_to_members(&event.host_ids(), db_connection).await?;
+            let mut is_reported = false;
+            for host_id in hosts
+                .into_iter()
+                .filter_map(|(_, member)| member.and_then(|member| member.discord_id))
+            {
+                let result = async {
+                    host_id
+                        .create_dm_channel(discord_api)
+                        .await?
+                        .send_message(&discord_api.http, CreateMessage::new().content(&message))
+                        .await
+                }
+                .await;
+                match result {
+                    Ok(_) => is_reported = true,
+                    Err(err) => eprintln!(
+                        "Could not send the shortcode problems to host {}:\n{:#?}",
+                        host_id, err
+                    ),
+                }
+            }
+            if !is_reported {
+                match crate::discord::util::say_in_bot_alerts_channel(
+                    &message,
+                    discord_api,
+                    guild_config,
+                )
+                .await
+                {
+                    Ok(()) => is_reported = true,
+                    Err(err) => eprintln!(
+                        "Could not report the shortcode problems of Meetup event {}:\n{:#?}",
+                        event.id, err
+                    ),
+                }
+            }
+            if is_reported {
+                sqlx::query!(
+                    r#"INSERT INTO meetup_event_directive_report (meetup_id, problems) VALUES ($1, $2)
+                    ON CONFLICT (meetup_id) DO UPDATE SET problems = $2, reported_time = NOW()"#,
+                    event.id.0,
+                    problems
+                )
+                .execute(db_connection)
+                .await?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,755 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use futures::StreamExt;
+use serenity::{
+    builder::{
+        CreateChannel, CreateMessage, CreateScheduledEvent, EditChannel, EditMessage, EditRole,
+        EditScheduledEvent, GetMessages,
+    },
+    http::CacheHttp,
+    model::{
+        channel::{Channel, GuildChannel, PermissionOverwrite, PermissionOverwriteType},
+        colour::Colour,
+        guild::ScheduledEventType,
+        id::{ChannelId, GuildId, MessageId, RoleId, ScheduledEventId, UserId},
+        permissions::Permissions,
+    },
+};
+
+use super::{sync::ChannelType, CacheAndHttp};
+
+// Discord returns at most this many messages at a time
+pub const MESSAGES_PAGE_SIZE: u8 = 100;
+
+// The parts of a guild channel that the bot's tasks look at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelInfo {
+    pub id: ChannelId,
+    pub guild_id: GuildId,
+    pub name: String,
+    pub topic: Option<String>,
+    pub parent_id: Option<ChannelId>,
+    pub permission_overwrites: Vec<PermissionOverwrite>,
+    // None for categories, threads and the like
+    pub channel_type: Option<ChannelType>,
+    pub is_category: bool,
+    // The sort order among the channels of the same kind
+    pub position: u16,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleInfo {
+    pub id: RoleId,
+    pub name: String,
+}
+
+// A message as it is stored in channel archives
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct MessageInfo {
+    pub id: MessageId,
+    pub author_id: UserId,
+    pub author_name: String,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub edited_timestamp: Option<chrono::DateTime<chrono::Utc>>,
+    pub content: String,
+    pub attachments: Vec<AttachmentInfo>,
+    pub pinned: bool,
+}
+
+// Only the metadata of attachments is kept, the files stay on Discord's CDN
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct AttachmentInfo {
+    pub filename: String,
+    pub url: String,
+    pub size: u32,
+    pub content_type: Option<String>,
+}
+
+// What the bot sets on the Discord scheduled event of a session
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct ScheduledEventInfo {
+    pub name: String,
+    pub description: String,
+    pub start_time: chrono::DateTime<chrono::Utc>,
+    pub end_time: chrono::DateTime<chrono::Utc>,
+    pub location: ScheduledEventLocation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum ScheduledEventLocation {
+    Voice(ChannelId),
+    // A link or an address
+    External(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MemberInfo {
+    pub user_id: UserId,
+    pub name: String,
+    pub discriminator: Option<u16>,
+    pub roles: Vec<RoleId>,
+}
+
+// The Discord operations used by the Discord sync, the end of game task and
+// the subscription role updates. Implemented by CacheAndHttp and by an
+// in-memory fake for tests (see crate::fake).
+#[async_trait]
+pub trait DiscordApi: Send + Sync {
+    // Returns None if the channel doesn't exist (anymore)
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelInfo>, crate::meetup::Error>;
+
+    async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<ChannelId, ChannelInfo>, crate::meetup::Error>;
+
+    async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        channel_type: ChannelType,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error>;
+
+    async fn create_category(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error>;
+
+    // Deleting a channel which doesn't exist (anymore) is not an error
+    async fn delete_channel(
+        &self,
+        channel_id: ChannelId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn set_channel_topic(
+        &self,
+        channel_id: ChannelId,
+        topic: &str,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn set_channel_category(
+        &self,
+        channel_id: ChannelId,
+        category_id: ChannelId,
+    ) -> Result<(), crate::meetup::Error>;
+
+    // Changes the positions of the specified channels, the others keep theirs
+    async fn set_channel_positions(
+        &self,
+        guild_id: GuildId,
+        positions: Vec<(ChannelId, u16)>,
+    ) -> Result<(), crate::meetup::Error>;
+
+    // Creates or replaces the permission overwrite of the overwrite's role or user
+    async fn create_permission(
+        &self,
+        channel_id: ChannelId,
+        permission_overwrite: PermissionOverwrite,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn delete_permission(
+        &self,
+        channel_id: ChannelId,
+        kind: PermissionOverwriteType,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn role_exists(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error>;
+
+    async fn guild_roles(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<RoleId, RoleInfo>, crate::meetup::Error>;
+
+    // Creates a role without any permissions
+    async fn create_role(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        colour: Colour,
+    ) -> Result<RoleInfo, crate::meetup::Error>;
+
+    // Deleting a role which doesn't exist (anymore) is not an error
+    async fn delete_role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn user_name(&self, user_id: UserId) -> Result<String, crate::meetup::Error>;
+
+    async fn guild_members(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<MemberInfo>, crate::meetup::Error>;
+
+    async fn member_has_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error>;
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error>;
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: CreateMessage,
+    ) -> Result<MessageId, crate::meetup::Error>;
+
+    // Returns false if the message doesn't exist (anymore)
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        message: EditMessage,
+    ) -> Result<bool, crate::meetup::Error>;
+
+    async fn pin_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Result<(), crate::meetup::Error>;
+
+    // A page of at most MESSAGES_PAGE_SIZE messages following the specified
+    // message (or the start of the channel), oldest message first. The page is
+    // empty once the end of the channel's history is reached.
+    async fn channel_messages_after(
+        &self,
+        channel_id: ChannelId,
+        after: Option<MessageId>,
+    ) -> Result<Vec<MessageInfo>, crate::meetup::Error>;
+
+    async fn create_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<ScheduledEventId, crate::meetup::Error>;
+
+    // Returns false if the scheduled event doesn't exist (anymore)
+    async fn edit_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<bool, crate::meetup::Error>;
+
+    // Deleting a scheduled event which doesn't exist (anymore) is not an error
+    async fn delete_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<(), crate::meetup::Error>;
+
+    // The users who marked themselves as interested (at most 100)
+    async fn scheduled_event_interested_users(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<Vec<UserId>, crate::meetup::Error>;
+}
+
+impl From<serenity::model::channel::Message> for MessageInfo {
+    fn from(message: serenity::model::channel::Message) -> Self {
+        let to_utc = |timestamp: serenity::model::Timestamp| {
+            chrono::TimeZone::timestamp_opt(&chrono::Utc, timestamp.unix_timestamp(), 0)
+                .single()
+                .unwrap_or_default()
+        };
+        MessageInfo {
+            id: message.id,
+            author_id: message.author.id,
+            author_name: message.author.global_name.unwrap_or(message.author.name),
+            timestamp: to_utc(message.timestamp),
+            edited_timestamp: message.edited_timestamp.map(to_utc),
+            content: message.content,
+            attachments: message
+                .attachments
+                .into_iter()
+                .map(|attachment| AttachmentInfo {
+                    filename: attachment.filename,
+                    url: attachment.url,
+                    size: attachment.size,
+                    content_type: attachment.content_type,
+                })
+                .collect(),
+            pinned: message.pinned,
+        }
+    }
+}
+
+impl From<GuildChannel> for ChannelInfo {
+    fn from(channel: GuildChannel) -> Self {
+        ChannelInfo {
+            id: channel.id,
+            guild_id: channel.guild_id,
+            name: channel.name,
+            topic: channel.topic,
+            parent_id: channel.parent_id,
+            permission_overwrites: channel.permission_overwrites,
+            channel_type: match channel.kind {
+                serenity::model::channel::ChannelType::Text => Some(ChannelType::Text),
+                serenity::model::channel::ChannelType::Voice => Some(ChannelType::Voice),
+                _ => None,
+            },
+            is_category: channel.kind == serenity::model::channel::ChannelType::Category,
+            position: channel.position,
+        }
+    }
+}
+
+// Whether Discord answered that the requested object doesn't exist
+pub fn is_not_found(err: &serenity::Error) -> bool {
+    if let serenity::Error::Http(serenity::http::HttpError::UnsuccessfulRequest(response)) = err {
+        response.status_code == serenity::http::StatusCode::NOT_FOUND
+    } else {
+        false
+    }
+}
+
+#[async_trait]
+impl DiscordApi for CacheAndHttp {
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelInfo>, crate::meetup::Error> {
+        match channel_id.to_channel(self).await {
+            Ok(Channel::Guild(channel)) => Ok(Some(channel.into())),
+            Ok(_) => Err(simple_error::SimpleError::new(format!(
+                "Channel {} is not a guild channel",
+                channel_id
+            ))
+            .into()),
+            Err(err) if is_not_found(&err) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<ChannelId, ChannelInfo>, crate::meetup::Error> {
+        let channels = guild_id.channels(&self.http).await?;
+        Ok(channels
+            .into_iter()
+            .map(|(id, channel)| (id, channel.into()))
+            .collect())
+    }
+
+    async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        channel_type: ChannelType,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error> {
+        let channel_builder = CreateChannel::new(name)
+            .kind(match channel_type {
+                ChannelType::Text => serenity::model::channel::ChannelType::Text,
+                ChannelType::Voice => serenity::model::channel::ChannelType::Voice,
+            })
+            .permissions(permission_overwrites);
+        let channel = guild_id
+            .create_channel(self.http(), channel_builder)
+            .await?;
+        Ok(channel.into())
+    }
+
+    async fn create_category(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error> {
+        let channel_builder = CreateChannel::new(name)
+            .kind(serenity::model::channel::ChannelType::Category)
+            .permissions(permission_overwrites);
+        let channel = guild_id
+            .create_channel(self.http(), channel_builder)
+            .await?;
+        Ok(channel.into())
+    }
+
+    async fn delete_channel(
+        &self,
+        channel_id: ChannelId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        match self.http.delete_channel(channel_id, audit_log_reason).await {
+            Ok(_) => Ok(()),
+            Err(err) if is_not_found(&err) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn set_channel_topic(
+        &self,
+        channel_id: ChannelId,
+        topic: &str,
+    ) -> Result<(), crate::meetup::Error> {
+        channel_id
+            .edit(&self.http, EditChannel::new().topic(topic))
+            .await?;
+        Ok(())
+    }
+
+    async fn set_channel_category(
+        &self,
+        channel_id: ChannelId,
+        category_id: ChannelId,
+    ) -> Result<(), crate::meetup::Error> {
+        channel_id
+            .edit(&self.http, EditChannel::new().category(Some(category_id)))
+            .await?;
+        Ok(())
+    }
+
+    async fn set_channel_positions(
+        &self,
+        guild_id: GuildId,
+        positions: Vec<(ChannelId, u16)>,
+    ) -> Result<(), crate::meetup::Error> {
+        guild_id
+            .reorder_channels(
+                &self.http,
+                positions
+                    .into_iter()
+                    .map(|(channel_id, position)| (channel_id, position.into())),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn create_permission(
+        &self,
+        channel_id: ChannelId,
+        permission_overwrite: PermissionOverwrite,
+    ) -> Result<(), crate::meetup::Error> {
+        channel_id
+            .create_permission(&self.http, permission_overwrite)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_permission(
+        &self,
+        channel_id: ChannelId,
+        kind: PermissionOverwriteType,
+    ) -> Result<(), crate::meetup::Error> {
+        channel_id.delete_permission(&self.http, kind).await?;
+        Ok(())
+    }
+
+    async fn role_exists(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error> {
+        // First, check the cache
+        let role_exists = match guild_id.to_guild_cached(&self.cache) {
+            Some(guild) => guild.roles.contains_key(&role_id),
+            None => false,
+        };
+        if role_exists {
+            return Ok(true);
+        }
+        // If it was not in the cache, check Discord
+        let guild_roles = self.http.get_guild_roles(guild_id).await?;
+        Ok(guild_roles
+            .iter()
+            .any(|guild_role| guild_role.id == role_id))
+    }
+
+    async fn guild_roles(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<RoleId, RoleInfo>, crate::meetup::Error> {
+        let roles = guild_id.roles(&self.http).await?;
+        Ok(roles
+            .into_iter()
+            .map(|(id, role)| {
+                (
+                    id,
+                    RoleInfo {
+                        id,
+                        name: role.name,
+                    },
+                )
+            })
+            .collect())
+    }
+
+    async fn create_role(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        colour: Colour,
+    ) -> Result<RoleInfo, crate::meetup::Error> {
+        let role_builder = EditRole::new()
+            .name(name)
+            .colour(colour)
+            .permissions(Permissions::empty());
+        let role = guild_id.create_role(self.http(), role_builder).await?;
+        Ok(RoleInfo {
+            id: role.id,
+            name: role.name,
+        })
+    }
+
+    async fn delete_role(
+        &self,
+        guild_id: GuildId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        match self
+            .http
+            .delete_role(guild_id, role_id, audit_log_reason)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(err) if is_not_found(&err) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn user_name(&self, user_id: UserId) -> Result<String, crate::meetup::Error> {
+        Ok(user_id.to_user(self).await?.name)
+    }
+
+    // Uses the cached members if the guild is cached
+    async fn guild_members(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<Vec<MemberInfo>, crate::meetup::Error> {
+        let to_member_info = |member: &serenity::model::guild::Member| MemberInfo {
+            user_id: member.user.id,
+            name: member.user.name.clone(),
+            discriminator: member.user.discriminator.map(|d| d.get()),
+            roles: member.roles.clone(),
+        };
+        let cached_members = self.cache.guild(guild_id).map(|guild| {
+            guild
+                .members
+                .values()
+                .map(to_member_info)
+                .collect::<Vec<_>>()
+        });
+        if let Some(members) = cached_members {
+            return Ok(members);
+        }
+        let mut members = vec![];
+        let mut members_iter = guild_id.members_iter(self.http()).boxed();
+        while let Some(member) = members_iter.next().await {
+            members.push(to_member_info(&member?));
+        }
+        Ok(members)
+    }
+
+    async fn member_has_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error> {
+        let user = user_id.to_user(self).await?;
+        Ok(user.has_role(self, guild_id, role_id).await?)
+    }
+
+    async fn add_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        self.http
+            .add_member_role(guild_id, user_id, role_id, audit_log_reason)
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_member_role(
+        &self,
+        guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        self.http
+            .remove_member_role(guild_id, user_id, role_id, audit_log_reason)
+            .await?;
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: CreateMessage,
+    ) -> Result<MessageId, crate::meetup::Error> {
+        let message = channel_id.send_message(&self.http, message).await?;
+        Ok(message.id)
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        message: EditMessage,
+    ) -> Result<bool, crate::meetup::Error> {
+        match channel_id
+            .edit_message(&self.http, message_id, message)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) if is_not_found(&err) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn pin_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Result<(), crate::meetup::Error> {
+        channel_id.pin(&self.http, message_id).await?;
+        Ok(())
+    }
+
+    async fn channel_messages_after(
+        &self,
+        channel_id: ChannelId,
+        after: Option<MessageId>,
+    ) -> Result<Vec<MessageInfo>, crate::meetup::Error> {
+        // Message IDs are never 0, so this starts at the very first message
+        let after = after.unwrap_or(MessageId::new(1));
+        let builder = GetMessages::new().after(after).limit(MESSAGES_PAGE_SIZE);
+        let messages = channel_id.messages(&self.http, builder).await?;
+        // Discord returns the newest messages first
+        Ok(messages.into_iter().rev().map(Into::into).collect())
+    }
+
+    async fn create_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<ScheduledEventId, crate::meetup::Error> {
+        let (kind, start_time, end_time) = scheduled_event_kind_and_times(scheduled_event)?;
+        let builder = CreateScheduledEvent::new(kind, &scheduled_event.name, start_time)
+            .description(&scheduled_event.description)
+            .end_time(end_time);
+        let builder = match &scheduled_event.location {
+            ScheduledEventLocation::Voice(channel_id) => builder.channel_id(*channel_id),
+            ScheduledEventLocation::External(location) => builder.location(location),
+        };
+        let created_event = guild_id
+            .create_scheduled_event(self.http(), builder)
+            .await?;
+        Ok(created_event.id)
+    }
+
+    async fn edit_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<bool, crate::meetup::Error> {
+        let (kind, start_time, end_time) = scheduled_event_kind_and_times(scheduled_event)?;
+        let builder = EditScheduledEvent::new()
+            .kind(kind)
+            .name(&scheduled_event.name)
+            .description(&scheduled_event.description)
+            .start_time(start_time)
+            .end_time(end_time);
+        let builder = match &scheduled_event.location {
+            ScheduledEventLocation::Voice(channel_id) => builder.channel_id(*channel_id),
+            ScheduledEventLocation::External(location) => builder.location(location),
+        };
+        match guild_id
+            .edit_scheduled_event(self.http(), scheduled_event_id, builder)
+            .await
+        {
+            Ok(_) => Ok(true),
+            Err(err) if is_not_found(&err) => Ok(false),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn delete_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<(), crate::meetup::Error> {
+        match guild_id
+            .delete_scheduled_event(&self.http, scheduled_event_id)
+            .await
+        {
+            Ok(()) => Ok(()),
+            Err(err) if is_not_found(&err) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn scheduled_event_interested_users(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<Vec<UserId>, crate::meetup::Error> {
+        let users = guild_id
+            .scheduled_event_users(&self.http, scheduled_event_id, Some(100))
+            .await?;
+        Ok(users.into_iter().map(|user| user.user.id).collect())
+    }
+}
+
+fn scheduled_event_kind_and_times(
+    scheduled_event: &ScheduledEventInfo,
+) -> Result<
+    (
+        ScheduledEventType,
+        serenity::model::Timestamp,
+        serenity::model::Timestamp,
+    ),
+    crate::meetup::Error,
+> {
+    let kind = match scheduled_event.location {
+        ScheduledEventLocation::Voice(_) => ScheduledEventType::Voice,
+        ScheduledEventLocation::External(_) => ScheduledEventType::External,
+    };
+    let to_timestamp = |time: chrono::DateTime<chrono::Utc>| {
+        serenity::model::Timestamp::from_unix_timestamp(time.timestamp())
+            .map_err(|err| simple_error::SimpleError::new(err.to_string()))
+    };
+    Ok((
+        kind,
+        to_timestamp(scheduled_event.start_time)?,
+        to_timestamp(scheduled_event.end_time)?,
+    ))
+}
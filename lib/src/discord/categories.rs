@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use serenity::model::id::{ChannelId, GuildId};
+use simple_error::SimpleError;
+
+use super::{api::ChannelInfo, api::DiscordApi, config::GuildConfig};
+
+// Discord allows at most this many channels in a category
+pub const MAX_CHANNELS_PER_CATEGORY: usize = 50;
+
+// Keeps track of the number of channels in each category of a guild while the
+// Discord sync moves channels around
+pub struct CategoryTracker {
+    guild_id: GuildId,
+    channels: HashMap<ChannelId, ChannelInfo>,
+    num_channels: HashMap<ChannelId, usize>,
+}
+
+impl CategoryTracker {
+    pub async fn load(
+        guild_id: GuildId,
+        discord_api: &impl DiscordApi,
+    ) -> Result<Self, crate::meetup::Error> {
+        let channels = discord_api.guild_channels(guild_id).await?;
+        let mut num_channels = HashMap::new();
+        for parent_id in channels.values().filter_map(|channel| channel.parent_id) {
+            *num_channels.entry(parent_id).or_default() += 1;
+        }
+        Ok(CategoryTracker {
+            guild_id,
+            channels,
+            num_channels,
+        })
+    }
+
+    pub fn num_channels(&self, category_id: ChannelId) -> usize {
+        self.num_channels.get(&category_id).copied().unwrap_or(0)
+    }
+
+    // The first of the categories which exists and has room for another channel
+    pub fn find_free(&self, category_ids: &[ChannelId]) -> Option<ChannelId> {
+        category_ids.iter().copied().find(|category_id| {
+            self.channels.contains_key(category_id)
+                && self.num_channels(*category_id) < MAX_CHANNELS_PER_CATEGORY
+        })
+    }
+
+    // Records that a channel was moved into a category
+    pub fn moved(&mut self, channel_id: ChannelId, category_id: ChannelId) {
+        let channel = match self.channels.get_mut(&channel_id) {
+            Some(channel) => channel,
+            // A channel which was created since the tracker was loaded
+            None => {
+                *self.num_channels.entry(category_id).or_default() += 1;
+                return;
+            }
+        };
+        if let Some(old_category_id) = channel.parent_id.replace(category_id) {
+            if let Some(num_channels) = self.num_channels.get_mut(&old_category_id) {
+                *num_channels = num_channels.saturating_sub(1);
+            }
+        }
+        *self.num_channels.entry(category_id).or_default() += 1;
+    }
+
+    // Returns the first of the categories with room for another channel, and
+    // creates a numbered overflow category of the first one if all are full
+    pub async fn find_or_create_free(
+        &mut self,
+        category_ids: &[ChannelId],
+        db_connection: &sqlx::PgPool,
+        discord_api: &impl DiscordApi,
+    ) -> Result<ChannelId, crate::meetup::Error> {
+        if let Some(category_id) = self.find_free(category_ids) {
+            return Ok(category_id);
+        }
+        let primary_id = match category_ids.first() {
+            Some(&category_id) => primary_category(category_id, db_connection).await?,
+            None => return Err(SimpleError::new("No categories to choose from").into()),
+        };
+        let primary = self
+            .channels
+            .get(&primary_id)
+            .ok_or_else(|| SimpleError::new(format!("Category {} does not exist", primary_id)))?;
+        let number = sqlx::query_scalar!(
+            r#"SELECT COALESCE(MAX("number"), 1) + 1 AS "number!" FROM discord_overflow_category WHERE primary_category_id = $1"#,
+            primary_id.get() as i64
+        )
+        .fetch_one(db_connection)
+        .await?;
+        let name = format!("{} {}", primary.name, number);
+        let category = discord_api
+            .create_category(self.guild_id, &name, primary.permission_overwrites.clone())
+            .await?;
+        sqlx::query!(
+            r#"INSERT INTO discord_overflow_category (discord_id, guild_id, primary_category_id, "number") VALUES ($1, $2, $3, $4)"#,
+            category.id.get() as i64,
+            self.guild_id.get() as i64,
+            primary_id.get() as i64,
+            number
+        )
+        .execute(db_connection)
+        .await?;
+        println!(
+            "Created overflow category \"{}\" ({}) for the full category {}",
+            name, category.id, primary_id
+        );
+        let category_id = category.id;
+        self.channels.insert(category_id, category);
+        // The category is usable wherever it ended up, so only log failures
+        if let Err(err) = self
+            .move_next_to_primary(primary_id, db_connection, discord_api)
+            .await
+        {
+            eprintln!(
+                "Could not move the overflow categories of category {} next to it:\n{:#?}",
+                primary_id, err
+            );
+        }
+        Ok(category_id)
+    }
+
+    // Discord puts new categories at the end. Moves the overflow categories of
+    // a category right after it, in the order of their numbers.
+    async fn move_next_to_primary(
+        &mut self,
+        primary_id: ChannelId,
+        db_connection: &sqlx::PgPool,
+        discord_api: &impl DiscordApi,
+    ) -> Result<(), crate::meetup::Error> {
+        let overflow_category_ids: Vec<_> = sqlx::query_scalar!(
+            r#"SELECT discord_id FROM discord_overflow_category WHERE primary_category_id = $1 ORDER BY "number""#,
+            primary_id.get() as i64
+        )
+        .fetch_all(db_connection)
+        .await?
+        .into_iter()
+        .map(|id| ChannelId::new(id as u64))
+        .filter(|id| self.channels.contains_key(id))
+        .collect();
+        let mut categories: Vec<_> = self
+            .channels
+            .values()
+            .filter(|channel| channel.is_category && !overflow_category_ids.contains(&channel.id))
+            .map(|channel| (channel.position, channel.id))
+            .collect();
+        categories.sort_unstable();
+        let mut category_ids: Vec<_> = categories.into_iter().map(|(_, id)| id).collect();
+        let primary_index = category_ids
+            .iter()
+            .position(|&id| id == primary_id)
+            .ok_or_else(|| SimpleError::new(format!("Category {} does not exist", primary_id)))?;
+        category_ids.splice(primary_index + 1..primary_index + 1, overflow_category_ids);
+        let positions: Vec<_> = category_ids
+            .into_iter()
+            .enumerate()
+            .map(|(position, id)| (id, position as u16))
+            .collect();
+        discord_api
+            .set_channel_positions(self.guild_id, positions.clone())
+            .await?;
+        for (id, position) in positions {
+            if let Some(category) = self.channels.get_mut(&id) {
+                category.position = position;
+            }
+        }
+        Ok(())
+    }
+}
+
+// The configured category an overflow category was created for, or the
+// category itself if it isn't an overflow category
+async fn primary_category(
+    category_id: ChannelId,
+    db_connection: &sqlx::PgPool,
+) -> Result<ChannelId, crate::meetup::Error> {
+    let primary_id = sqlx::query_scalar!(
+        r#"SELECT primary_category_id FROM discord_overflow_category WHERE discord_id = $1"#,
+        category_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(primary_id.map_or(category_id, |id| ChannelId::new(id as u64)))
+}
+
+// Appends the overflow categories of each category right after it
+pub async fn with_overflow_categories(
+    category_ids: &[ChannelId],
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<ChannelId>, crate::meetup::Error> {
+    let primary_ids: Vec<i64> = category_ids.iter().map(|id| id.get() as i64).collect();
+    let overflow_categories = sqlx::query!(
+        r#"SELECT discord_id, primary_category_id FROM discord_overflow_category
+        WHERE primary_category_id = ANY($1)
+        ORDER BY "number""#,
+        &primary_ids
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let mut all_category_ids = vec![];
+    for &category_id in category_ids {
+        if all_category_ids.contains(&category_id) {
+            continue;
+        }
+        all_category_ids.push(category_id);
+        all_category_ids.extend(
+            overflow_categories
+                .iter()
+                .filter(|row| row.primary_category_id == category_id.get() as i64)
+                .map(|row| ChannelId::new(row.discord_id as u64)),
+        );
+    }
+    Ok(all_category_ids)
+}
+
+// All overflow categories of a guild
+pub async fn overflow_categories(
+    guild_id: GuildId,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<ChannelId>, crate::meetup::Error> {
+    let category_ids = sqlx::query!(
+        r#"SELECT discord_id FROM discord_overflow_category WHERE guild_id = $1"#,
+        guild_id.get() as i64
+    )
+    .map(|row| ChannelId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?;
+    Ok(category_ids)
+}
+
+// Deletes the overflow categories which don't contain any channels anymore
+pub async fn collapse_empty_overflow_categories(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<(), crate::meetup::Error> {
+    let category_ids = overflow_categories(guild_config.guild_id, db_connection).await?;
+    if category_ids.is_empty() {
+        return Ok(());
+    }
+    let tracker = CategoryTracker::load(guild_config.guild_id, discord_api).await?;
+    for category_id in category_ids {
+        let exists = tracker.channels.contains_key(&category_id);
+        if exists && tracker.num_channels(category_id) > 0 {
+            continue;
+        }
+        if exists {
+            discord_api
+                .delete_channel(category_id, Some("Empty overflow category"))
+                .await?;
+            println!("Deleted empty overflow category {}", category_id);
+        }
+        sqlx::query!(
+            r#"DELETE FROM discord_overflow_category WHERE discord_id = $1"#,
+            category_id.get() as i64
+        )
+        .execute(db_connection)
+        .await?;
+    }
+    Ok(())
+}
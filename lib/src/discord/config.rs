@@ -0,0 +1,317 @@
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serenity::model::{
+    channel::ChannelType,
+    id::{ChannelId, GuildId, RoleId},
+};
+use simple_error::SimpleError;
+
+// Environment variable pointing to a TOML file containing the guild
+// configuration. Only used if there is no configuration in the database.
+const GUILD_CONFIG_FILE_VAR: &str = "GUILD_CONFIG_FILE";
+// Prefix of the environment variables that can be used to provide the guild
+// configuration if there is neither a configuration in the database nor a
+// configuration file. The rest of the variable name is the upper case field
+// name and the value is parsed as a TOML value, e.g.
+// GUILD_CONFIG_CAMPAIGN_CATEGORY_IDS="[414074722259828736, 651006290998329354]"
+const GUILD_CONFIG_ENV_PREFIX: &str = "GUILD_CONFIG_";
+
+/// Discord IDs of the guild the bot manages and of the roles, categories and
+/// channels it needs to know about.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GuildConfig {
+    pub guild_id: GuildId,
+    // The default guild gets all event series which can not be associated
+    // with a guild otherwise and handles Stripe subscriptions
+    #[serde(default)]
+    pub is_default: bool,
+    pub bot_admin_role_id: RoleId,
+    pub organiser_role_id: RoleId,
+    pub game_master_role_id: RoleId,
+    #[serde(default)]
+    pub dice_roller_bot_role_id: Option<RoleId>,
+    #[serde(default)]
+    pub admin_role_id: Option<RoleId>,
+    #[serde(default)]
+    pub champion_role_id: Option<RoleId>,
+    #[serde(default)]
+    pub insider_role_id: Option<RoleId>,
+    #[serde(default)]
+    pub gm_champion_role_id: Option<RoleId>,
+    #[serde(default)]
+    pub one_shot_category_ids: Vec<ChannelId>,
+    #[serde(default)]
+    pub campaign_category_ids: Vec<ChannelId>,
+    #[serde(default)]
+    pub voice_channel_category_ids: Vec<ChannelId>,
+    #[serde(default)]
+    pub bot_alerts_channel_id: Option<ChannelId>,
+    #[serde(default)]
+    pub free_spots_channel_id: Option<ChannelId>,
+    #[serde(default)]
+    pub user_topic_voice_channel_id: Option<ChannelId>,
+}
+
+/// The configurations of all guilds the bot manages.
+#[derive(Debug, Clone)]
+pub struct GuildConfigs {
+    configs: Vec<Arc<GuildConfig>>,
+}
+
+// A config file can either contain a single guild's configuration at the top
+// level or several ones in a `guilds` array
+#[derive(Deserialize)]
+struct GuildConfigFile {
+    guilds: Vec<GuildConfig>,
+}
+
+impl GuildConfigs {
+    pub fn new(mut configs: Vec<GuildConfig>) -> Result<Self, crate::meetup::Error> {
+        if configs.is_empty() {
+            return Err(SimpleError::new("No guild configuration found").into());
+        }
+        // A single guild is always the default guild
+        if let [config] = configs.as_mut_slice() {
+            config.is_default = true;
+        }
+        let num_defaults = configs.iter().filter(|config| config.is_default).count();
+        if num_defaults != 1 {
+            return Err(SimpleError::new(format!(
+                "Exactly one guild needs to be the default guild but found {}",
+                num_defaults
+            ))
+            .into());
+        }
+        Ok(GuildConfigs {
+            configs: configs.into_iter().map(Arc::new).collect(),
+        })
+    }
+
+    // Loads the guild configurations from the database. If there are none,
+    // falls back to the TOML file specified by GUILD_CONFIG_FILE and then to
+    // the GUILD_CONFIG_* environment variables.
+    pub async fn load(db_connection: &sqlx::PgPool) -> Result<Self, crate::meetup::Error> {
+        let configs = Self::load_from_db(db_connection).await?;
+        if !configs.is_empty() {
+            println!(
+                "Loaded {} guild configuration(s) from the database",
+                configs.len()
+            );
+            return Self::new(configs);
+        }
+        if let Ok(path) = std::env::var(GUILD_CONFIG_FILE_VAR) {
+            let configs = Self::load_from_file(&path)?;
+            println!(
+                "Loaded {} guild configuration(s) from {}",
+                configs.len(),
+                path
+            );
+            return Self::new(configs);
+        }
+        let config = Self::load_from_env()?;
+        println!("Loaded guild configuration from the environment");
+        Self::new(vec![config])
+    }
+
+    async fn load_from_db(
+        db_connection: &sqlx::PgPool,
+    ) -> Result<Vec<GuildConfig>, crate::meetup::Error> {
+        let configs = sqlx::query!(r#"SELECT * FROM guild_config"#)
+            .map(|row| GuildConfig {
+                guild_id: GuildId::new(row.guild_id as u64),
+                is_default: row.is_default,
+                bot_admin_role_id: RoleId::new(row.bot_admin_role_id as u64),
+                organiser_role_id: RoleId::new(row.organiser_role_id as u64),
+                game_master_role_id: RoleId::new(row.game_master_role_id as u64),
+                dice_roller_bot_role_id: row
+                    .dice_roller_bot_role_id
+                    .map(|id| RoleId::new(id as u64)),
+                admin_role_id: row.admin_role_id.map(|id| RoleId::new(id as u64)),
+                champion_role_id: row.champion_role_id.map(|id| RoleId::new(id as u64)),
+                insider_role_id: row.insider_role_id.map(|id| RoleId::new(id as u64)),
+                gm_champion_role_id: row.gm_champion_role_id.map(|id| RoleId::new(id as u64)),
+                one_shot_category_ids: to_channel_ids(&row.one_shot_category_ids),
+                campaign_category_ids: to_channel_ids(&row.campaign_category_ids),
+                voice_channel_category_ids: to_channel_ids(&row.voice_channel_category_ids),
+                bot_alerts_channel_id: row
+                    .bot_alerts_channel_id
+                    .map(|id| ChannelId::new(id as u64)),
+                free_spots_channel_id: row
+                    .free_spots_channel_id
+                    .map(|id| ChannelId::new(id as u64)),
+                user_topic_voice_channel_id: row
+                    .user_topic_voice_channel_id
+                    .map(|id| ChannelId::new(id as u64)),
+            })
+            .fetch_all(db_connection)
+            .await?;
+        Ok(configs)
+    }
+
+    fn load_from_file(path: &str) -> Result<Vec<GuildConfig>, crate::meetup::Error> {
+        let content = std::fs::read_to_string(path).map_err(|err| {
+            SimpleError::new(format!(
+                "Could not read guild config file {}: {}",
+                path, err
+            ))
+        })?;
+        let table: toml::Table = toml::from_str(&content).map_err(|err| {
+            SimpleError::new(format!(
+                "Could not parse guild config file {}: {}",
+                path, err
+            ))
+        })?;
+        let configs = if table.contains_key("guilds") {
+            table.try_into().map(|file: GuildConfigFile| file.guilds)
+        } else {
+            table.try_into().map(|config| vec![config])
+        };
+        configs.map_err(|err| {
+            SimpleError::new(format!(
+                "Could not parse guild config file {}: {}",
+                path, err
+            ))
+            .into()
+        })
+    }
+
+    fn load_from_env() -> Result<GuildConfig, crate::meetup::Error> {
+        let mut table = toml::Table::new();
+        for (key, value) in std::env::vars() {
+            if key == GUILD_CONFIG_FILE_VAR {
+                continue;
+            }
+            let field = match key.strip_prefix(GUILD_CONFIG_ENV_PREFIX) {
+                Some(field) => field.to_lowercase(),
+                None => continue,
+            };
+            // Parse the value as a TOML value by wrapping it in a key/value pair
+            let mut parsed: toml::Table =
+                toml::from_str(&format!("value = {}", value)).map_err(|err| {
+                    SimpleError::new(format!(
+                        "Could not parse environment variable {}: {}",
+                        key, err
+                    ))
+                })?;
+            if let Some(value) = parsed.remove("value") {
+                table.insert(field, value);
+            }
+        }
+        if table.is_empty() {
+            return Err(SimpleError::new(
+                "No guild configuration found in the database, in a config file or in the \
+                 environment",
+            )
+            .into());
+        }
+        table.try_into().map_err(|err| {
+            SimpleError::new(format!(
+                "Could not parse the guild configuration from the environment: {}",
+                err
+            ))
+            .into()
+        })
+    }
+
+    pub fn get(&self, guild_id: GuildId) -> Option<&Arc<GuildConfig>> {
+        self.configs
+            .iter()
+            .find(|config| config.guild_id == guild_id)
+    }
+
+    pub fn contains(&self, guild_id: GuildId) -> bool {
+        self.get(guild_id).is_some()
+    }
+
+    pub fn default_guild(&self) -> &Arc<GuildConfig> {
+        self.configs
+            .iter()
+            .find(|config| config.is_default)
+            .expect("GuildConfigs without a default guild. This is a bug.")
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Arc<GuildConfig>> {
+        self.configs.iter()
+    }
+
+    // The configuration of the guild an event series belongs to. Series which
+    // have not been assigned to a (configured) guild belong to the default guild.
+    pub async fn for_event_series(
+        &self,
+        series_id: crate::db::EventSeriesId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<&Arc<GuildConfig>, crate::meetup::Error> {
+        let guild_id = sqlx::query_scalar!(
+            "SELECT guild_id FROM event_series WHERE id = $1",
+            series_id.0
+        )
+        .fetch_one(db_connection)
+        .await?;
+        let guild_config = guild_id
+            .and_then(|guild_id| self.get(GuildId::new(guild_id as u64)))
+            .unwrap_or_else(|| self.default_guild());
+        Ok(guild_config)
+    }
+}
+
+impl GuildConfig {
+    // Checks the configured IDs against the Discord cache and returns a
+    // description of every problem found
+    pub fn validate(&self, cache: &serenity::cache::Cache) -> Vec<String> {
+        let mut problems = vec![];
+        let guild = match cache.guild(self.guild_id) {
+            Some(guild) => guild,
+            None => {
+                problems.push(format!("Guild {} is not in the cache", self.guild_id));
+                return problems;
+            }
+        };
+        let roles = [
+            ("bot admin role", Some(self.bot_admin_role_id)),
+            ("organiser role", Some(self.organiser_role_id)),
+            ("game master role", Some(self.game_master_role_id)),
+            ("dice roller bot role", self.dice_roller_bot_role_id),
+            ("admin role", self.admin_role_id),
+            ("champion role", self.champion_role_id),
+            ("insider role", self.insider_role_id),
+            ("GM champion role", self.gm_champion_role_id),
+        ];
+        for (name, role_id) in roles {
+            if let Some(role_id) = role_id {
+                if !guild.roles.contains_key(&role_id) {
+                    problems.push(format!("The {} ({}) does not exist", name, role_id));
+                }
+            }
+        }
+        let categories = self
+            .one_shot_category_ids
+            .iter()
+            .chain(self.campaign_category_ids.iter())
+            .chain(self.voice_channel_category_ids.iter());
+        for category_id in categories {
+            match guild.channels.get(category_id) {
+                Some(channel) if channel.kind == ChannelType::Category => (),
+                Some(_) => problems.push(format!("Channel {} is not a category", category_id)),
+                None => problems.push(format!("Category {} does not exist", category_id)),
+            }
+        }
+        let channels = [
+            ("bot alerts channel", self.bot_alerts_channel_id),
+            ("free spots channel", self.free_spots_channel_id),
+            ("user topic voice channel", self.user_topic_voice_channel_id),
+        ];
+        for (name, channel_id) in channels {
+            if let Some(channel_id) = channel_id {
+                if !guild.channels.contains_key(&channel_id) {
+                    problems.push(format!("The {} ({}) does not exist", name, channel_id));
+                }
+            }
+        }
+        problems
+    }
+}
+
+fn to_channel_ids(ids: &[i64]) -> Vec<ChannelId> {
+    ids.iter().map(|&id| ChannelId::new(id as u64)).collect()
+}
@@ -1,4 +1,12 @@
+pub mod api;
+pub mod categories;
+pub mod config;
+pub mod orphans;
+pub mod rsvp;
+pub mod scheduled_events;
+pub mod series_info;
 pub mod sync;
+pub mod sync_plan;
 pub mod util;
 
 use std::sync::Arc;
@@ -38,6 +46,7 @@ pub async fn is_host(
     channel_id: ChannelId,
     user_id: UserId,
     db_connection: &sqlx::PgPool,
+    guild_config: &config::GuildConfig,
 ) -> Result<bool, crate::meetup::Error> {
     let channel = if let Channel::Guild(channel) = channel_id.to_channel(discord_api).await? {
         channel
@@ -69,7 +78,7 @@ pub async fn is_host(
         {
             let user = user_id.to_user(discord_api).await?;
             let is_host = user
-                .has_role(discord_api, sync::ids::GUILD_ID, host_role)
+                .has_role(discord_api, guild_config.guild_id, host_role)
                 .await
                 .unwrap_or(false);
             return Ok(is_host);
@@ -80,9 +89,45 @@ pub async fn is_host(
     Ok(is_host)
 }
 
+// The user's current permission overwrite in the channel
+fn user_permission_overwrite(
+    permission_overwrites: &[PermissionOverwrite],
+    user_id: UserId,
+) -> PermissionOverwrite {
+    permission_overwrites
+        .iter()
+        .find(|overwrite| PermissionOverwriteType::Member(user_id) == overwrite.kind)
+        .cloned()
+        .unwrap_or(PermissionOverwrite {
+            allow: Permissions::empty(),
+            deny: Permissions::empty(),
+            kind: PermissionOverwriteType::Member(user_id),
+        })
+}
+
+async fn existing_channel(
+    discord_api: &impl api::DiscordApi,
+    channel_id: ChannelId,
+) -> Result<api::ChannelInfo, crate::meetup::Error> {
+    discord_api.channel(channel_id).await?.ok_or_else(|| {
+        simple_error::SimpleError::new(format!("Channel {} does not exist", channel_id)).into()
+    })
+}
+
+// The permissions that add_channel_user_permissions would newly grant
+pub async fn missing_channel_user_permissions(
+    discord_api: &impl api::DiscordApi,
+    channel_id: ChannelId,
+    user_id: UserId,
+    permissions: Permissions,
+) -> Result<Permissions, crate::meetup::Error> {
+    let channel = existing_channel(discord_api, channel_id).await?;
+    Ok(permissions - user_permission_overwrite(&channel.permission_overwrites, user_id).allow)
+}
+
 // True if permissions changed, false otherwise
 pub async fn add_channel_user_permissions(
-    discord_api: &CacheAndHttp,
+    discord_api: &impl api::DiscordApi,
     channel_id: ChannelId,
     user_id: UserId,
     permissions: Permissions,
@@ -90,26 +135,14 @@ pub async fn add_channel_user_permissions(
     if permissions == Permissions::empty() {
         return Ok(false);
     }
-    let channel = if let Channel::Guild(channel) = channel_id.to_channel(discord_api).await? {
-        channel
-    } else {
-        return Err(simple_error::SimpleError::new("is_host: This is not a guild channel").into());
-    };
-    let current_permission_overwrites = channel
-        .permission_overwrites
-        .iter()
-        .find(|overwrite| PermissionOverwriteType::Member(user_id) == overwrite.kind)
-        .cloned()
-        .unwrap_or(PermissionOverwrite {
-            allow: Permissions::empty(),
-            deny: Permissions::empty(),
-            kind: PermissionOverwriteType::Member(user_id),
-        });
+    let channel = existing_channel(discord_api, channel_id).await?;
+    let current_permission_overwrites =
+        user_permission_overwrite(&channel.permission_overwrites, user_id);
     let mut new_permission_overwrites = current_permission_overwrites.clone();
     new_permission_overwrites.allow |= permissions;
     if new_permission_overwrites.allow != current_permission_overwrites.allow {
-        channel
-            .create_permission(&discord_api.http, new_permission_overwrites)
+        discord_api
+            .create_permission(channel_id, new_permission_overwrites)
             .await?;
         Ok(true)
     } else {
@@ -119,7 +152,7 @@ pub async fn add_channel_user_permissions(
 
 // True if permissions changed, false otherwise
 pub async fn remove_channel_user_permissions(
-    discord_api: &CacheAndHttp,
+    discord_api: &impl api::DiscordApi,
     channel_id: ChannelId,
     user_id: UserId,
     permissions: Permissions,
@@ -127,33 +160,21 @@ pub async fn remove_channel_user_permissions(
     if permissions == Permissions::empty() {
         return Ok(false);
     }
-    let channel = if let Channel::Guild(channel) = channel_id.to_channel(discord_api).await? {
-        channel
-    } else {
-        return Err(simple_error::SimpleError::new("is_host: This is not a guild channel").into());
-    };
-    let current_permission_overwrites = channel
-        .permission_overwrites
-        .iter()
-        .find(|overwrite| PermissionOverwriteType::Member(user_id) == overwrite.kind)
-        .cloned()
-        .unwrap_or(PermissionOverwrite {
-            allow: Permissions::empty(),
-            deny: Permissions::empty(),
-            kind: PermissionOverwriteType::Member(user_id),
-        });
+    let channel = existing_channel(discord_api, channel_id).await?;
+    let current_permission_overwrites =
+        user_permission_overwrite(&channel.permission_overwrites, user_id);
     let mut new_permission_overwrites = current_permission_overwrites.clone();
     new_permission_overwrites.allow &= !permissions;
     if new_permission_overwrites.allow != current_permission_overwrites.allow {
         if new_permission_overwrites.allow == Permissions::empty()
             && new_permission_overwrites.deny == Permissions::empty()
         {
-            channel
-                .delete_permission(&discord_api.http, new_permission_overwrites.kind)
+            discord_api
+                .delete_permission(channel_id, new_permission_overwrites.kind)
                 .await?;
         } else {
-            channel
-                .create_permission(&discord_api.http, new_permission_overwrites)
+            discord_api
+                .create_permission(channel_id, new_permission_overwrites)
                 .await?;
         }
         Ok(true)
@@ -0,0 +1,848 @@
+use std::{collections::HashSet, fmt::Write};
+
+use lazy_static::lazy_static;
+use serenity::{
+    builder::CreateMessage,
+    model::id::{ChannelId, RoleId, UserId},
+};
+use simple_error::SimpleError;
+
+use super::{
+    api::DiscordApi,
+    config::{GuildConfig, GuildConfigs},
+    sync::ChannelType,
+};
+use crate::db;
+
+const DELETION_REASON: &str = "Orphaned, deleted by a bot admin";
+
+lazy_static! {
+    // Channel roles are named "[Host names] Series name" by the Discord sync
+    static ref SERIES_ROLE_NAME_REGEX: regex::Regex =
+        regex::Regex::new(r"^\[[^\]]+\] \S").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanKind {
+    // Discord objects which no event series knows about
+    TextChannel,
+    VoiceChannel,
+    Role,
+    // Event series channels and roles which are gone from Discord
+    MissingTextChannel,
+    MissingVoiceChannel,
+    MissingRole,
+    MissingHostRole,
+}
+
+impl OrphanKind {
+    pub const ALL: [OrphanKind; 7] = [
+        OrphanKind::TextChannel,
+        OrphanKind::VoiceChannel,
+        OrphanKind::Role,
+        OrphanKind::MissingTextChannel,
+        OrphanKind::MissingVoiceChannel,
+        OrphanKind::MissingRole,
+        OrphanKind::MissingHostRole,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrphanKind::TextChannel => "text_channel",
+            OrphanKind::VoiceChannel => "voice_channel",
+            OrphanKind::Role => "role",
+            OrphanKind::MissingTextChannel => "missing_text_channel",
+            OrphanKind::MissingVoiceChannel => "missing_voice_channel",
+            OrphanKind::MissingRole => "missing_role",
+            OrphanKind::MissingHostRole => "missing_host_role",
+        }
+    }
+
+    pub fn from_name(kind: &str) -> Option<OrphanKind> {
+        OrphanKind::ALL
+            .iter()
+            .copied()
+            .find(|candidate| candidate.as_str() == kind)
+    }
+
+    // Whether the object exists on Discord (as opposed to only in the database)
+    pub fn is_on_discord(&self) -> bool {
+        matches!(
+            self,
+            OrphanKind::TextChannel | OrphanKind::VoiceChannel | OrphanKind::Role
+        )
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            OrphanKind::TextChannel => "text channel unknown to the bot",
+            OrphanKind::VoiceChannel => "voice channel unknown to the bot",
+            OrphanKind::Role => "channel role unknown to the bot",
+            OrphanKind::MissingTextChannel => "text channel missing on Discord",
+            OrphanKind::MissingVoiceChannel => "voice channel missing on Discord",
+            OrphanKind::MissingRole => "channel role missing on Discord",
+            OrphanKind::MissingHostRole => "host role missing on Discord",
+        }
+    }
+
+    // The kind of Discord object that can replace a missing one
+    fn replacement(&self) -> Option<OrphanKind> {
+        match self {
+            OrphanKind::MissingTextChannel => Some(OrphanKind::TextChannel),
+            OrphanKind::MissingVoiceChannel => Some(OrphanKind::VoiceChannel),
+            OrphanKind::MissingRole | OrphanKind::MissingHostRole => Some(OrphanKind::Role),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Orphan {
+    pub discord_id: u64,
+    pub kind: OrphanKind,
+    pub name: Option<String>,
+    pub series_id: Option<db::EventSeriesId>,
+}
+
+impl std::fmt::Display for Orphan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            OrphanKind::TextChannel | OrphanKind::VoiceChannel => {
+                write!(f, "<#{}>", self.discord_id)?
+            }
+            OrphanKind::Role => write!(f, "<@&{}>", self.discord_id)?,
+            _ => write!(f, "`{}`", self.discord_id)?,
+        }
+        if let Some(name) = &self.name {
+            write!(f, " \"{}\"", name)?;
+        }
+        write!(f, " (`{}`): {}", self.discord_id, self.kind.description())?;
+        if let Some(series_id) = self.series_id {
+            write!(f, " (series {})", series_id.0)?;
+        }
+        Ok(())
+    }
+}
+
+// Lists the Discord channels in managed categories and the channel roles that
+// no event series knows about, as well as the event series channels and roles
+// that are gone from Discord
+pub async fn find_orphans(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<Vec<Orphan>, crate::meetup::Error> {
+    let guild_id = guild_config.guild_id.get() as i64;
+    let is_default_guild = guild_config.is_default;
+    let existing_channels = discord_api.guild_channels(guild_config.guild_id).await?;
+    let existing_roles = discord_api.guild_roles(guild_config.guild_id).await?;
+    let mut orphans = vec![];
+
+    // Channels and roles which the bot already knows about
+    let known_channel_ids: HashSet<ChannelId> = sqlx::query!(
+        r#"SELECT discord_id AS "discord_id!" FROM event_series_text_channel WHERE deleted IS NULL
+        UNION SELECT discord_id FROM event_series_voice_channel WHERE deleted IS NULL
+        UNION SELECT discord_id FROM managed_channel"#
+    )
+    .map(|row| ChannelId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?
+    .into_iter()
+    .chain(guild_config.bot_alerts_channel_id)
+    .chain(guild_config.free_spots_channel_id)
+    .chain(guild_config.user_topic_voice_channel_id)
+    .collect();
+    let known_role_ids: HashSet<RoleId> = sqlx::query!(
+        r#"SELECT discord_id AS "discord_id!" FROM event_series_role WHERE deleted IS NULL
+        UNION SELECT discord_id FROM event_series_host_role WHERE deleted IS NULL"#
+    )
+    .map(|row| RoleId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut managed_category_ids: HashSet<ChannelId> = guild_config
+        .one_shot_category_ids
+        .iter()
+        .chain(&guild_config.campaign_category_ids)
+        .chain(&guild_config.voice_channel_category_ids)
+        .copied()
+        .collect();
+    managed_category_ids.extend(
+        super::categories::overflow_categories(guild_config.guild_id, db_connection).await?,
+    );
+    for channel in existing_channels.values() {
+        let is_in_managed_category = channel
+            .parent_id
+            .is_some_and(|parent_id| managed_category_ids.contains(&parent_id));
+        if !is_in_managed_category || known_channel_ids.contains(&channel.id) {
+            continue;
+        }
+        let kind = match channel.channel_type {
+            Some(ChannelType::Text) => OrphanKind::TextChannel,
+            Some(ChannelType::Voice) => OrphanKind::VoiceChannel,
+            None => continue,
+        };
+        orphans.push(Orphan {
+            discord_id: channel.id.get(),
+            kind,
+            name: Some(channel.name.clone()),
+            series_id: None,
+        });
+    }
+    for role in existing_roles.values() {
+        if known_role_ids.contains(&role.id) || !SERIES_ROLE_NAME_REGEX.is_match(&role.name) {
+            continue;
+        }
+        orphans.push(Orphan {
+            discord_id: role.id.get(),
+            kind: OrphanKind::Role,
+            name: Some(role.name.clone()),
+            series_id: None,
+        });
+    }
+
+    // Channels and roles which are gone from Discord. Rows which are not (or
+    // no longer) associated with a guild are handled as part of the default
+    // guild, like in the end of game task.
+    let text_channels = sqlx::query!(
+        r#"SELECT event_series_text_channel.discord_id, event_series.id AS "series_id?"
+        FROM event_series_text_channel
+        LEFT JOIN event_series ON event_series.discord_text_channel_id = event_series_text_channel.discord_id
+        WHERE event_series_text_channel.deleted IS NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
+    )
+    .map(|row| (row.discord_id as u64, row.series_id))
+    .fetch_all(db_connection)
+    .await?;
+    let voice_channels = sqlx::query!(
+        r#"SELECT event_series_voice_channel.discord_id, event_series.id AS "series_id?"
+        FROM event_series_voice_channel
+        LEFT JOIN event_series ON event_series.discord_voice_channel_id = event_series_voice_channel.discord_id
+        WHERE event_series_voice_channel.deleted IS NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
+    )
+    .map(|row| (row.discord_id as u64, row.series_id))
+    .fetch_all(db_connection)
+    .await?;
+    let roles = sqlx::query!(
+        r#"SELECT event_series_role.discord_id, event_series.id AS "series_id?"
+        FROM event_series_role
+        LEFT JOIN event_series ON event_series.discord_role_id = event_series_role.discord_id
+        WHERE event_series_role.deleted IS NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
+    )
+    .map(|row| (row.discord_id as u64, row.series_id))
+    .fetch_all(db_connection)
+    .await?;
+    let host_roles = sqlx::query!(
+        r#"SELECT event_series_host_role.discord_id, event_series.id AS "series_id?"
+        FROM event_series_host_role
+        LEFT JOIN event_series ON event_series.discord_host_role_id = event_series_host_role.discord_id
+        WHERE event_series_host_role.deleted IS NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
+    )
+    .map(|row| (row.discord_id as u64, row.series_id))
+    .fetch_all(db_connection)
+    .await?;
+    let missing_channels = text_channels
+        .into_iter()
+        .map(|row| (row, OrphanKind::MissingTextChannel))
+        .chain(
+            voice_channels
+                .into_iter()
+                .map(|row| (row, OrphanKind::MissingVoiceChannel)),
+        )
+        .filter(|((discord_id, _), _)| {
+            !existing_channels.contains_key(&ChannelId::new(*discord_id))
+        });
+    let missing_roles = roles
+        .into_iter()
+        .map(|row| (row, OrphanKind::MissingRole))
+        .chain(
+            host_roles
+                .into_iter()
+                .map(|row| (row, OrphanKind::MissingHostRole)),
+        )
+        .filter(|((discord_id, _), _)| !existing_roles.contains_key(&RoleId::new(*discord_id)));
+    for ((discord_id, series_id), kind) in missing_channels.chain(missing_roles) {
+        orphans.push(Orphan {
+            discord_id,
+            kind,
+            name: None,
+            series_id: series_id.map(db::EventSeriesId),
+        });
+    }
+    orphans.sort_unstable_by_key(|orphan| (orphan.kind.as_str(), orphan.discord_id));
+    Ok(orphans)
+}
+
+// Runs the reconciliation pass in all guilds
+pub async fn reconcile_orphans(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+) -> Result<(), crate::meetup::Error> {
+    let mut some_failed = false;
+    for guild_config in guild_configs.iter() {
+        if let Err(err) = reconcile_guild_orphans(db_connection, discord_api, guild_config).await {
+            some_failed = true;
+            eprintln!(
+                "Orphan reconciliation in guild {} failed: {:#}",
+                guild_config.guild_id, err
+            );
+        }
+    }
+    if some_failed {
+        Err(SimpleError::new("One or more orphan reconciliations failed").into())
+    } else {
+        Ok(())
+    }
+}
+
+// Stores the guild's current orphans and reports the ones which weren't known
+// yet to the bot alerts channel. Returns all current orphans.
+pub async fn reconcile_guild_orphans(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<Vec<Orphan>, crate::meetup::Error> {
+    let orphans = find_orphans(db_connection, discord_api, guild_config).await?;
+    let guild_id = guild_config.guild_id.get() as i64;
+    let discord_ids: Vec<i64> = orphans
+        .iter()
+        .map(|orphan| orphan.discord_id as i64)
+        .collect();
+    let kinds: Vec<String> = orphans
+        .iter()
+        .map(|orphan| orphan.kind.as_str().to_string())
+        .collect();
+    let mut tx = db_connection.begin().await?;
+    // Forget about orphans which have been resolved in the meantime
+    sqlx::query!(
+        r#"DELETE FROM discord_orphan
+        WHERE guild_id = $1
+        AND (discord_id, kind) NOT IN (SELECT * FROM UNNEST($2::bigint[], $3::text[]))"#,
+        guild_id,
+        &discord_ids,
+        &kinds
+    )
+    .execute(&mut *tx)
+    .await?;
+    let mut new_orphans = vec![];
+    for orphan in &orphans {
+        let is_new = sqlx::query!(
+            r#"INSERT INTO discord_orphan (discord_id, kind, guild_id, name, event_series_id)
+            VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (discord_id, kind) DO UPDATE SET name = $4, event_series_id = $5
+            RETURNING (xmax = 0) AS "is_new!""#,
+            orphan.discord_id as i64,
+            orphan.kind.as_str(),
+            guild_id,
+            orphan.name,
+            orphan.series_id.map(|id| id.0)
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .is_new;
+        if is_new {
+            new_orphans.push(orphan);
+        }
+    }
+    tx.commit().await?;
+    if !new_orphans.is_empty() {
+        let mut report = format!(
+            "Found {} new orphaned channel(s) or role(s). Use _adopt orphan_, _relink orphan_ \
+             or _delete orphan_ to resolve them:\n",
+            new_orphans.len()
+        );
+        for orphan in new_orphans {
+            writeln!(&mut report, "• {}", orphan).ok();
+        }
+        println!("{}", report);
+        if let Some(channel_id) = guild_config.bot_alerts_channel_id {
+            discord_api
+                .send_message(channel_id, CreateMessage::new().content(report))
+                .await?;
+        }
+    }
+    Ok(orphans)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolveOutcome {
+    Resolved,
+    // The ID is not (or no longer) listed as an orphan of the right kind
+    NotAnOrphan,
+    SeriesNotFound,
+    // The series already has a working channel or role of this kind
+    SeriesHasOne,
+}
+
+async fn stored_orphan(
+    discord_id: u64,
+    guild_config: &GuildConfig,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<Orphan>, crate::meetup::Error> {
+    let row = sqlx::query!(
+        r#"SELECT kind, name, event_series_id FROM discord_orphan WHERE discord_id = $1 AND guild_id = $2"#,
+        discord_id as i64,
+        guild_config.guild_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(row.and_then(|row| {
+        Some(Orphan {
+            discord_id,
+            kind: OrphanKind::from_name(&row.kind)?,
+            name: row.name,
+            series_id: row.event_series_id.map(db::EventSeriesId),
+        })
+    }))
+}
+
+async fn remove_stored_orphan(
+    orphan: &Orphan,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"DELETE FROM discord_orphan WHERE discord_id = $1 AND kind = $2"#,
+        orphan.discord_id as i64,
+        orphan.kind.as_str()
+    )
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
+
+// Locks the stored orphan until the end of the transaction. Returns false if
+// it has been resolved in the meantime.
+async fn lock_stored_orphan(
+    orphan: &Orphan,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<bool, crate::meetup::Error> {
+    let row = sqlx::query!(
+        r#"SELECT discord_id FROM discord_orphan WHERE discord_id = $1 AND kind = $2 FOR UPDATE"#,
+        orphan.discord_id as i64,
+        orphan.kind.as_str()
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    Ok(row.is_some())
+}
+
+// Whether a Discord channel or role has become known to the bot since it was
+// stored as an orphan, for example because it was linked to a series
+async fn is_referenced(
+    orphan: &Orphan,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<bool, crate::meetup::Error> {
+    let discord_id = orphan.discord_id as i64;
+    let is_referenced = match orphan.kind {
+        OrphanKind::TextChannel | OrphanKind::VoiceChannel => {
+            sqlx::query!(
+                r#"SELECT EXISTS (
+                    SELECT 1 FROM event_series_text_channel WHERE discord_id = $1 AND deleted IS NULL
+                    UNION ALL SELECT 1 FROM event_series_voice_channel WHERE discord_id = $1 AND deleted IS NULL
+                    UNION ALL SELECT 1 FROM managed_channel WHERE discord_id = $1
+                ) AS "is_referenced!""#,
+                discord_id
+            )
+            .fetch_one(&mut **tx)
+            .await?
+            .is_referenced
+        }
+        OrphanKind::Role => {
+            sqlx::query!(
+                r#"SELECT EXISTS (
+                    SELECT 1 FROM event_series_role WHERE discord_id = $1 AND deleted IS NULL
+                    UNION ALL SELECT 1 FROM event_series_host_role WHERE discord_id = $1 AND deleted IS NULL
+                ) AS "is_referenced!""#,
+                discord_id
+            )
+            .fetch_one(&mut **tx)
+            .await?
+            .is_referenced
+        }
+        // Objects which are gone from Discord only get forgotten
+        _ => false,
+    };
+    Ok(is_referenced)
+}
+
+// Makes an orphaned Discord channel or role the series' channel or role.
+// `slot` is the kind of the missing object it takes the place of.
+async fn link_to_series(
+    discord_id: u64,
+    slot: OrphanKind,
+    series_id: db::EventSeriesId,
+    guild_config: &GuildConfig,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<ResolveOutcome, crate::meetup::Error> {
+    let guild_id = guild_config.guild_id.get() as i64;
+    let series = sqlx::query!(
+        r#"SELECT event_series.guild_id,
+            (SELECT discord_id FROM event_series_text_channel WHERE discord_id = event_series.discord_text_channel_id AND deleted IS NULL) AS text_channel_id,
+            (SELECT discord_id FROM event_series_voice_channel WHERE discord_id = event_series.discord_voice_channel_id AND deleted IS NULL) AS voice_channel_id,
+            (SELECT discord_id FROM event_series_role WHERE discord_id = event_series.discord_role_id AND deleted IS NULL) AS role_id,
+            (SELECT discord_id FROM event_series_host_role WHERE discord_id = event_series.discord_host_role_id AND deleted IS NULL) AS host_role_id
+        FROM event_series WHERE id = $1 FOR UPDATE"#,
+        series_id.0
+    )
+    .fetch_optional(&mut **tx)
+    .await?;
+    let series = match series {
+        Some(series) if series.guild_id.is_none() || series.guild_id == Some(guild_id) => series,
+        _ => return Ok(ResolveOutcome::SeriesNotFound),
+    };
+    let current = match slot {
+        OrphanKind::MissingTextChannel => series.text_channel_id,
+        OrphanKind::MissingVoiceChannel => series.voice_channel_id,
+        OrphanKind::MissingRole => series.role_id,
+        OrphanKind::MissingHostRole => series.host_role_id,
+        _ => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    if current.is_some() {
+        return Ok(ResolveOutcome::SeriesHasOne);
+    }
+    let discord_id = discord_id as i64;
+    match slot {
+        OrphanKind::MissingTextChannel => {
+            sqlx::query!(
+                r#"INSERT INTO event_series_text_channel (discord_id) VALUES ($1)
+                ON CONFLICT (discord_id) DO UPDATE SET deletion_time = NULL, deleted = NULL"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_text_channel_id = NULL WHERE discord_text_channel_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_text_channel_id = $2, discord_info_message_id = NULL, discord_info_message_synced = NULL, guild_id = $3 WHERE id = $1"#,
+                series_id.0,
+                discord_id,
+                guild_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        OrphanKind::MissingVoiceChannel => {
+            sqlx::query!(
+                r#"INSERT INTO event_series_voice_channel (discord_id) VALUES ($1)
+                ON CONFLICT (discord_id) DO UPDATE SET deletion_time = NULL, deleted = NULL"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_voice_channel_id = NULL WHERE discord_voice_channel_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_voice_channel_id = $2, guild_id = $3 WHERE id = $1"#,
+                series_id.0,
+                discord_id,
+                guild_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        OrphanKind::MissingRole => {
+            sqlx::query!(
+                r#"INSERT INTO event_series_role (discord_id) VALUES ($1)
+                ON CONFLICT (discord_id) DO UPDATE SET deletion_time = NULL, deleted = NULL"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_role_id = NULL WHERE discord_role_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_role_id = $2, guild_id = $3 WHERE id = $1"#,
+                series_id.0,
+                discord_id,
+                guild_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        _ => {
+            sqlx::query!(
+                r#"INSERT INTO event_series_host_role (discord_id) VALUES ($1)
+                ON CONFLICT (discord_id) DO UPDATE SET deletion_time = NULL, deleted = NULL"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_host_role_id = NULL WHERE discord_host_role_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_host_role_id = $2, guild_id = $3 WHERE id = $1"#,
+                series_id.0,
+                discord_id,
+                guild_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+    }
+    Ok(ResolveOutcome::Resolved)
+}
+
+// Marks a channel or role which is gone from Discord as deleted and unlinks it
+// from its series, so that the Discord sync creates a new one if the series
+// has upcoming events
+async fn forget_missing(
+    orphan: &Orphan,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), crate::meetup::Error> {
+    let discord_id = orphan.discord_id as i64;
+    match orphan.kind {
+        OrphanKind::MissingTextChannel => {
+            sqlx::query!(
+                r#"UPDATE event_series_text_channel SET deleted = NOW() WHERE discord_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_text_channel_id = NULL, discord_info_message_id = NULL, discord_info_message_synced = NULL WHERE discord_text_channel_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        OrphanKind::MissingVoiceChannel => {
+            sqlx::query!(
+                r#"UPDATE event_series_voice_channel SET deleted = NOW() WHERE discord_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_voice_channel_id = NULL WHERE discord_voice_channel_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        OrphanKind::MissingRole => {
+            sqlx::query!(
+                r#"UPDATE event_series_role SET deleted = NOW() WHERE discord_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_role_id = NULL WHERE discord_role_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        OrphanKind::MissingHostRole => {
+            sqlx::query!(
+                r#"UPDATE event_series_host_role SET deleted = NOW() WHERE discord_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_host_role_id = NULL WHERE discord_host_role_id = $1"#,
+                discord_id
+            )
+            .execute(&mut **tx)
+            .await?;
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+// Adopts an orphaned Discord channel or role as the channel or role of a series.
+// Roles become the series' channel role, or its host role if `as_host_role` is set.
+pub async fn adopt(
+    discord_id: u64,
+    series_id: db::EventSeriesId,
+    as_host_role: bool,
+    actor: UserId,
+    db_connection: &sqlx::PgPool,
+    guild_config: &GuildConfig,
+) -> Result<ResolveOutcome, crate::meetup::Error> {
+    let orphan = match stored_orphan(discord_id, guild_config, db_connection).await? {
+        Some(orphan) if orphan.kind.is_on_discord() => orphan,
+        _ => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    let slot = match orphan.kind {
+        OrphanKind::TextChannel if !as_host_role => OrphanKind::MissingTextChannel,
+        OrphanKind::VoiceChannel if !as_host_role => OrphanKind::MissingVoiceChannel,
+        OrphanKind::Role if as_host_role => OrphanKind::MissingHostRole,
+        OrphanKind::Role => OrphanKind::MissingRole,
+        _ => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    let mut tx = db_connection.begin().await?;
+    if !lock_stored_orphan(&orphan, &mut tx).await? {
+        return Ok(ResolveOutcome::NotAnOrphan);
+    }
+    let outcome = link_to_series(discord_id, slot, series_id, guild_config, &mut tx).await?;
+    if outcome != ResolveOutcome::Resolved {
+        return Ok(outcome);
+    }
+    remove_stored_orphan(&orphan, &mut tx).await?;
+    tx.commit().await?;
+    crate::audit::record(
+        crate::audit::Entry::new(crate::audit::Action::OrphanAdopted, orphan_target(&orphan))
+            .actor(actor)
+            .after(serde_json::json!({
+                "series_id": series_id.0,
+                "name": orphan.name,
+            })),
+        db_connection,
+    )
+    .await;
+    Ok(ResolveOutcome::Resolved)
+}
+
+// Replaces a series' channel or role which is gone from Discord with an
+// orphaned one
+pub async fn relink(
+    missing_id: u64,
+    discord_id: u64,
+    actor: UserId,
+    db_connection: &sqlx::PgPool,
+    guild_config: &GuildConfig,
+) -> Result<ResolveOutcome, crate::meetup::Error> {
+    let missing = match stored_orphan(missing_id, guild_config, db_connection).await? {
+        Some(orphan) if !orphan.kind.is_on_discord() => orphan,
+        _ => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    let replacement = match stored_orphan(discord_id, guild_config, db_connection).await? {
+        Some(orphan) if Some(orphan.kind) == missing.kind.replacement() => orphan,
+        _ => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    let series_id = match missing.series_id {
+        Some(series_id) => series_id,
+        None => return Ok(ResolveOutcome::SeriesNotFound),
+    };
+    let mut tx = db_connection.begin().await?;
+    if !lock_stored_orphan(&missing, &mut tx).await?
+        || !lock_stored_orphan(&replacement, &mut tx).await?
+    {
+        return Ok(ResolveOutcome::NotAnOrphan);
+    }
+    forget_missing(&missing, &mut tx).await?;
+    let outcome =
+        link_to_series(discord_id, missing.kind, series_id, guild_config, &mut tx).await?;
+    if outcome != ResolveOutcome::Resolved {
+        return Ok(outcome);
+    }
+    remove_stored_orphan(&missing, &mut tx).await?;
+    remove_stored_orphan(&replacement, &mut tx).await?;
+    tx.commit().await?;
+    crate::audit::record(
+        crate::audit::Entry::new(
+            crate::audit::Action::OrphanRelinked,
+            orphan_target(&replacement),
+        )
+        .actor(actor)
+        .before(serde_json::json!({ "discord_id": missing_id.to_string() }))
+        .after(serde_json::json!({
+            "series_id": series_id.0,
+            "name": replacement.name,
+        })),
+        db_connection,
+    )
+    .await;
+    Ok(ResolveOutcome::Resolved)
+}
+
+// Deletes an orphaned channel or role from Discord, or forgets about a series'
+// channel or role which is gone from Discord
+pub async fn delete(
+    discord_id: u64,
+    actor: UserId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<ResolveOutcome, crate::meetup::Error> {
+    let orphan = match stored_orphan(discord_id, guild_config, db_connection).await? {
+        Some(orphan) => orphan,
+        None => return Ok(ResolveOutcome::NotAnOrphan),
+    };
+    // Keep the orphan locked while deleting it, so that it can't be adopted or
+    // relinked in the meantime
+    let mut tx = db_connection.begin().await?;
+    if !lock_stored_orphan(&orphan, &mut tx).await? || is_referenced(&orphan, &mut tx).await? {
+        return Ok(ResolveOutcome::NotAnOrphan);
+    }
+    // Objects which are already gone from Discord count as deleted
+    let action = match orphan.kind {
+        OrphanKind::TextChannel | OrphanKind::VoiceChannel => {
+            discord_api
+                .delete_channel(ChannelId::new(discord_id), Some(DELETION_REASON))
+                .await?;
+            crate::audit::Action::ChannelDeleted
+        }
+        OrphanKind::Role => {
+            discord_api
+                .delete_role(
+                    guild_config.guild_id,
+                    RoleId::new(discord_id),
+                    Some(DELETION_REASON),
+                )
+                .await?;
+            crate::audit::Action::RoleDeleted
+        }
+        _ => crate::audit::Action::OrphanForgotten,
+    };
+    forget_missing(&orphan, &mut tx).await?;
+    remove_stored_orphan(&orphan, &mut tx).await?;
+    tx.commit().await?;
+    crate::audit::record(
+        crate::audit::Entry::new(action, orphan_target(&orphan))
+            .actor(actor)
+            .before(serde_json::json!({
+                "name": orphan.name,
+                "orphan": orphan.kind.as_str(),
+                "series_id": orphan.series_id.map(|id| id.0),
+            })),
+        db_connection,
+    )
+    .await;
+    Ok(ResolveOutcome::Resolved)
+}
+
+fn orphan_target(orphan: &Orphan) -> crate::audit::Target {
+    match orphan.kind {
+        OrphanKind::TextChannel
+        | OrphanKind::VoiceChannel
+        | OrphanKind::MissingTextChannel
+        | OrphanKind::MissingVoiceChannel => {
+            crate::audit::Target::Channel(ChannelId::new(orphan.discord_id))
+        }
+        _ => crate::audit::Target::Role(RoleId::new(orphan.discord_id)),
+    }
+}
@@ -0,0 +1,351 @@
+use std::fmt::Write;
+
+use serenity::{
+    all::Mentionable,
+    builder::{
+        CreateActionRow, CreateButton, CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage,
+    },
+    model::{
+        application::ButtonStyle,
+        id::{ChannelId, MessageId, RoleId, UserId},
+    },
+};
+
+use crate::db;
+
+const GOING_PREFIX: &str = "rsvp_going:";
+const NOT_GOING_PREFIX: &str = "rsvp_not_going:";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RsvpOutcome {
+    Going,
+    AlreadyGoing,
+    Full,
+    NotGoing,
+    NotRsvped,
+    // The RSVP was made on Meetup and has to be cancelled there
+    RsvpedOnMeetup,
+    EventOver,
+}
+
+// Returns the event and whether the user clicked "Going" if the custom ID
+// belongs to one of our RSVP buttons
+pub fn parse_custom_id(custom_id: &str) -> Option<(db::EventId, bool)> {
+    let (event_id, going) = if let Some(event_id) = custom_id.strip_prefix(GOING_PREFIX) {
+        (event_id, true)
+    } else if let Some(event_id) = custom_id.strip_prefix(NOT_GOING_PREFIX) {
+        (event_id, false)
+    } else {
+        return None;
+    };
+    event_id.parse().ok().map(|id| (db::EventId(id), going))
+}
+
+fn buttons(event_id: db::EventId) -> Vec<CreateActionRow> {
+    vec![CreateActionRow::Buttons(vec![
+        CreateButton::new(format!("{}{}", GOING_PREFIX, event_id.0))
+            .label("Going")
+            .style(ButtonStyle::Success),
+        CreateButton::new(format!("{}{}", NOT_GOING_PREFIX, event_id.0))
+            .label("Not going")
+            .style(ButtonStyle::Secondary),
+    ])]
+}
+
+// Records a Discord RSVP for the specified event
+pub async fn rsvp(
+    event_id: db::EventId,
+    user_id: UserId,
+    going: bool,
+    db_connection: &sqlx::PgPool,
+) -> Result<RsvpOutcome, crate::meetup::Error> {
+    let mut tx = db_connection.begin().await?;
+    // Lock the event so that concurrent RSVPs can't exceed the guest limit
+    let event = sqlx::query!(
+        r#"SELECT start_time, deleted, guest_limit FROM event WHERE id = $1 FOR UPDATE"#,
+        event_id.0
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+    if event.deleted.is_some() || event.start_time < chrono::Utc::now() {
+        return Ok(RsvpOutcome::EventOver);
+    }
+    let member_id = db::get_or_create_member_for_discord_id(&mut tx, user_id).await?;
+    let existing_origin = sqlx::query_scalar!(
+        r#"SELECT origin FROM event_participant WHERE event_id = $1 AND member_id = $2"#,
+        event_id.0,
+        member_id.0
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    let outcome = match (going, existing_origin.as_deref()) {
+        (true, Some(_)) => RsvpOutcome::AlreadyGoing,
+        (true, None) => {
+            let num_participants = sqlx::query_scalar!(
+                r#"SELECT COUNT(*) AS "count!" FROM event_participant WHERE event_id = $1"#,
+                event_id.0
+            )
+            .fetch_one(&mut *tx)
+            .await?;
+            match event.guest_limit {
+                Some(guest_limit) if num_participants >= guest_limit as i64 => RsvpOutcome::Full,
+                _ => {
+                    sqlx::query!(
+                        r#"INSERT INTO event_participant (event_id, member_id, origin) VALUES ($1, $2, 'discord')"#,
+                        event_id.0,
+                        member_id.0
+                    )
+                    .execute(&mut *tx)
+                    .await?;
+                    RsvpOutcome::Going
+                }
+            }
+        }
+        (false, None) => RsvpOutcome::NotRsvped,
+        (false, Some("discord")) => {
+            sqlx::query!(
+                r#"DELETE FROM event_participant WHERE event_id = $1 AND member_id = $2"#,
+                event_id.0,
+                member_id.0
+            )
+            .execute(&mut *tx)
+            .await?;
+            RsvpOutcome::NotGoing
+        }
+        (false, Some(_)) => RsvpOutcome::RsvpedOnMeetup,
+    };
+    tx.commit().await?;
+    Ok(outcome)
+}
+
+// Posts or updates the announcement of an upcoming event in its series'
+// channel, listing everybody who is attending
+pub async fn sync_event_announcement(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl super::api::DiscordApi,
+) -> Result<(), crate::meetup::Error> {
+    let event = sqlx::query!(
+        r#"SELECT event.title, event.start_time, event.guest_limit, event.discord_announcement_message_id, event.discord_announcement_synced, event_series.discord_text_channel_id, meetup_event.url AS "meetup_url?"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.id = $1"#,
+        event_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    let channel_id = match event.discord_text_channel_id {
+        Some(id) => ChannelId::new(id as u64),
+        // The series has no channel (yet)
+        None => return Ok(()),
+    };
+    let participants = sqlx::query!(
+        r#"SELECT member.discord_id
+        FROM event_participant
+        INNER JOIN member ON event_participant.member_id = member.id
+        WHERE event_participant.event_id = $1"#,
+        event_id.0
+    )
+    .map(|row| row.discord_id.map(|id| UserId::new(id as u64)))
+    .fetch_all(db_connection)
+    .await?;
+    let hosts = db::get_events_participants(&[event_id], /*hosts*/ true, db_connection).await?;
+
+    let mut description = format!("<t:{}:F>\n", event.start_time.timestamp());
+    match event.guest_limit {
+        Some(guest_limit) => write!(
+            &mut description,
+            "{} / {} spots taken",
+            participants.len(),
+            guest_limit
+        )
+        .ok(),
+        None => write!(&mut description, "{} going", participants.len()).ok(),
+    };
+    if let Some(meetup_url) = &event.meetup_url {
+        write!(&mut description, "\n[Also on Meetup](<{}>)", meetup_url).ok();
+    }
+    let host_list = itertools::join(
+        hosts
+            .iter()
+            .filter_map(|host| host.discord_id)
+            .map(|id| id.mention().to_string()),
+        ", ",
+    );
+    let mut attendee_list = itertools::join(
+        participants
+            .iter()
+            .flatten()
+            .map(|id| id.mention().to_string()),
+        "\n",
+    );
+    let num_without_discord = participants.iter().filter(|id| id.is_none()).count();
+    if num_without_discord > 0 {
+        if !attendee_list.is_empty() {
+            attendee_list.push('\n');
+        }
+        write!(
+            &mut attendee_list,
+            "+{} without a linked Discord account",
+            num_without_discord
+        )
+        .ok();
+    }
+    if attendee_list.is_empty() {
+        attendee_list.push_str("Nobody yet");
+    }
+    let mut embed = CreateEmbed::new()
+        .title(event.title.as_str())
+        .description(description)
+        .field("Going", attendee_list, false)
+        .footer(CreateEmbedFooter::new(
+            "RSVPs made on Meetup can only be cancelled on Meetup",
+        ));
+    if !host_list.is_empty() {
+        embed = embed.field("Hosts", host_list, false);
+    }
+
+    let edit_builder = EditMessage::new()
+        .embed(embed.clone())
+        .components(buttons(event_id));
+    let synced = super::util::rendered_content(&edit_builder)?;
+    // Try to update the existing announcement first
+    if let Some(message_id) = event.discord_announcement_message_id {
+        if event.discord_announcement_synced.as_ref() == Some(&synced) {
+            // Nothing changed since the last sync
+            return Ok(());
+        }
+        let edited = discord_api
+            .edit_message(channel_id, MessageId::new(message_id as u64), edit_builder)
+            .await?;
+        if edited {
+            sqlx::query!(
+                r#"UPDATE event SET discord_announcement_synced = $2 WHERE id = $1"#,
+                event_id.0,
+                synced
+            )
+            .execute(db_connection)
+            .await?;
+            return Ok(());
+        }
+        eprintln!(
+            "The announcement of event {} is gone, posting a new one",
+            event_id.0
+        );
+    }
+    let message_builder = CreateMessage::new()
+        .embed(embed)
+        .components(buttons(event_id));
+    let message_id = discord_api
+        .send_message(channel_id, message_builder)
+        .await?;
+    sqlx::query!(
+        r#"UPDATE event SET discord_announcement_message_id = $2, discord_announcement_synced = $3 WHERE id = $1"#,
+        event_id.0,
+        message_id.get() as i64,
+        synced
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+// Announces the cancellation of an event in its series' channel and removes
+// the RSVP buttons from the event's announcement
+pub async fn announce_event_cancellation(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl super::api::DiscordApi,
+) -> Result<(), crate::meetup::Error> {
+    let event = sqlx::query!(
+        r#"SELECT event.title, event.start_time, event.discord_announcement_message_id, event_series.discord_role_id, event_series_text_channel.discord_id AS "discord_text_channel_id?"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        LEFT OUTER JOIN event_series_text_channel ON event_series.discord_text_channel_id = event_series_text_channel.discord_id AND event_series_text_channel.deleted IS NULL
+        WHERE event.id = $1"#,
+        event_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    // Without a channel there is nobody to tell
+    if let Some(channel_id) = event.discord_text_channel_id {
+        let channel_id = ChannelId::new(channel_id as u64);
+        if let Some(message_id) = event.discord_announcement_message_id {
+            let embed = CreateEmbed::new()
+                .title(event.title.as_str())
+                .description(format!(
+                    "~~<t:{}:F>~~\n{}",
+                    event.start_time.timestamp(),
+                    crate::strings::EVENT_ANNOUNCEMENT_CANCELLED
+                ));
+            let message_builder = EditMessage::new().embed(embed).components(vec![]);
+            // A deleted announcement doesn't need to be marked as cancelled
+            if let Err(err) = discord_api
+                .edit_message(
+                    channel_id,
+                    MessageId::new(message_id as u64),
+                    message_builder,
+                )
+                .await
+            {
+                eprintln!(
+                    "Could not mark the announcement of event {} as cancelled:\n{:#?}",
+                    event_id.0, err
+                );
+            }
+        }
+        let role_id = event.discord_role_id.map(|id| RoleId::new(id as u64));
+        let message_builder = CreateMessage::new().content(crate::strings::EVENT_CANCELLED(
+            role_id,
+            &event.title,
+            event.start_time.timestamp(),
+        ));
+        discord_api
+            .send_message(channel_id, message_builder)
+            .await?;
+    }
+    sqlx::query!(
+        r#"UPDATE event SET cancellation_announcement_pending = FALSE WHERE id = $1"#,
+        event_id.0
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+// Announces the new time of a rescheduled event in its series' channel and
+// updates the event's announcement
+pub async fn announce_event_rescheduled(
+    event_id: db::EventId,
+    old_start_time: chrono::DateTime<chrono::Utc>,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl super::api::DiscordApi,
+) -> Result<(), crate::meetup::Error> {
+    let event = sqlx::query!(
+        r#"SELECT event.title, event.start_time, event_series.discord_role_id, event_series_text_channel.discord_id AS "discord_text_channel_id?"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        LEFT OUTER JOIN event_series_text_channel ON event_series.discord_text_channel_id = event_series_text_channel.discord_id AND event_series_text_channel.deleted IS NULL
+        WHERE event.id = $1"#,
+        event_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    // Without a channel there is nobody to tell
+    if let Some(channel_id) = event.discord_text_channel_id {
+        let channel_id = ChannelId::new(channel_id as u64);
+        let role_id = event.discord_role_id.map(|id| RoleId::new(id as u64));
+        let message_builder = CreateMessage::new().content(crate::strings::EVENT_RESCHEDULED(
+            role_id,
+            &event.title,
+            old_start_time.timestamp(),
+            event.start_time.timestamp(),
+        ));
+        discord_api
+            .send_message(channel_id, message_builder)
+            .await?;
+        sync_event_announcement(event_id, db_connection, discord_api).await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,338 @@
+use serenity::model::id::{ChannelId, ScheduledEventId, UserId};
+
+use super::{
+    api::{DiscordApi, ScheduledEventInfo, ScheduledEventLocation},
+    config::GuildConfig,
+    sync_plan::{PlannedChange, SyncPlan},
+};
+use crate::{
+    db,
+    meetup::directives::{remove_shortcodes, ShortcodeKind},
+};
+
+// Limits imposed by Discord
+const MAX_NAME_LENGTH: usize = 100;
+const MAX_DESCRIPTION_LENGTH: usize = 1000;
+const MAX_LOCATION_LENGTH: usize = 100;
+
+// Sessions without an end time are assumed to take this long
+const DEFAULT_DURATION_HOURS: i64 = 4;
+
+const ALL_SHORTCODES: &[ShortcodeKind] = &[
+    ShortcodeKind::NewAdventure,
+    ShortcodeKind::NewCampaign,
+    ShortcodeKind::Campaign,
+    ShortcodeKind::Channel,
+    ShortcodeKind::Category,
+    ShortcodeKind::Role,
+    ShortcodeKind::Online,
+    ShortcodeKind::Closed,
+];
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max_chars.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+// Online sessions take place in the series' voice channel, all others are
+// linked to their Meetup event
+pub fn scheduled_event_info(
+    title: &str,
+    description: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+    end_time: Option<chrono::DateTime<chrono::Utc>>,
+    voice_channel_id: Option<ChannelId>,
+    meetup_url: Option<&str>,
+) -> ScheduledEventInfo {
+    let signup = match meetup_url {
+        Some(meetup_url) => format!("\n\nSign up on Meetup: {}", meetup_url),
+        None => String::new(),
+    };
+    let description = remove_shortcodes(description, ALL_SHORTCODES);
+    let description = truncate(
+        description.trim(),
+        MAX_DESCRIPTION_LENGTH - signup.chars().count(),
+    ) + &signup;
+    let location = match (voice_channel_id, meetup_url) {
+        (Some(voice_channel_id), _) => ScheduledEventLocation::Voice(voice_channel_id),
+        (None, Some(meetup_url)) => {
+            ScheduledEventLocation::External(truncate(meetup_url, MAX_LOCATION_LENGTH))
+        }
+        (None, None) => ScheduledEventLocation::External("See the game channel".to_string()),
+    };
+    ScheduledEventInfo {
+        name: truncate(title, MAX_NAME_LENGTH),
+        description,
+        start_time,
+        end_time: end_time
+            .filter(|&end_time| end_time > start_time)
+            .unwrap_or(start_time + chrono::Duration::hours(DEFAULT_DURATION_HOURS)),
+        location,
+    }
+}
+
+// Mirrors the guild's upcoming events as Discord scheduled events and removes
+// the scheduled events of cancelled and past events
+pub(crate) async fn sync_scheduled_events(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
+) -> Result<(), crate::meetup::Error> {
+    let guild_id = guild_config.guild_id;
+    let stale_events = sqlx::query!(
+        r#"SELECT event.id, event.discord_scheduled_event_id AS "discord_scheduled_event_id!"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        WHERE event_series.guild_id = $1 AND event.discord_scheduled_event_id IS NOT NULL
+        AND (event.deleted IS NOT NULL OR COALESCE(event.end_time, event.start_time + interval '4 hours') < NOW())"#,
+        guild_id.get() as i64
+    )
+    .fetch_all(db_connection)
+    .await?;
+    for stale_event in stale_events {
+        let event_id = db::EventId(stale_event.id);
+        let scheduled_event_id =
+            ScheduledEventId::new(stale_event.discord_scheduled_event_id as u64);
+        if plan.is_dry_run() {
+            plan.push(PlannedChange::DeleteScheduledEvent {
+                event_id,
+                scheduled_event_id,
+            });
+            continue;
+        }
+        discord_api
+            .delete_scheduled_event(guild_id, scheduled_event_id)
+            .await?;
+        sqlx::query!(
+            r#"UPDATE event SET discord_scheduled_event_id = NULL, discord_scheduled_event_synced = NULL WHERE id = $1"#,
+            event_id.0
+        )
+        .execute(db_connection)
+        .await?;
+    }
+    let upcoming_events = sqlx::query!(
+        r#"SELECT event.id, event.title, event.description, event.start_time, event.end_time, event.is_online, event.discord_scheduled_event_id, event.discord_scheduled_event_synced, meetup_event.url AS "meetup_url?", event_series_voice_channel.discord_id AS "voice_channel_id?"
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        LEFT OUTER JOIN event_series_voice_channel ON event_series.discord_voice_channel_id = event_series_voice_channel.discord_id AND event_series_voice_channel.deleted IS NULL
+        WHERE event_series.guild_id = $1 AND event.deleted IS NULL AND event.start_time > NOW()
+        ORDER BY event.start_time"#,
+        guild_id.get() as i64
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let mut some_failed = false;
+    for event in upcoming_events {
+        let event_id = db::EventId(event.id);
+        let info = scheduled_event_info(
+            &event.title,
+            &event.description,
+            event.start_time,
+            event.end_time,
+            event
+                .voice_channel_id
+                .filter(|_| event.is_online)
+                .map(|id| ChannelId::new(id as u64)),
+            event.meetup_url.as_deref(),
+        );
+        let synced = serde_json::to_string(&info).map_err(simple_error::SimpleError::from)?;
+        let scheduled_event_id = event
+            .discord_scheduled_event_id
+            .map(|id| ScheduledEventId::new(id as u64));
+        let result = match scheduled_event_id {
+            // Nothing changed since the last sync
+            Some(scheduled_event_id)
+                if event.discord_scheduled_event_synced.as_ref() == Some(&synced) =>
+            {
+                Ok(scheduled_event_id)
+            }
+            Some(scheduled_event_id) if plan.is_dry_run() => {
+                plan.push(PlannedChange::UpdateScheduledEvent {
+                    event_id,
+                    scheduled_event_id,
+                });
+                continue;
+            }
+            None if plan.is_dry_run() => {
+                plan.push(PlannedChange::CreateScheduledEvent {
+                    event_id,
+                    name: info.name,
+                });
+                continue;
+            }
+            Some(scheduled_event_id) => {
+                update_scheduled_event(
+                    event_id,
+                    scheduled_event_id,
+                    &info,
+                    &synced,
+                    db_connection,
+                    discord_api,
+                    guild_config,
+                )
+                .await
+            }
+            None => {
+                create_scheduled_event(
+                    event_id,
+                    &info,
+                    &synced,
+                    db_connection,
+                    discord_api,
+                    guild_config,
+                )
+                .await
+            }
+        };
+        let scheduled_event_id = match result {
+            Ok(scheduled_event_id) => scheduled_event_id,
+            Err(err) => {
+                some_failed = true;
+                eprintln!(
+                    "Could not sync the Discord scheduled event of event {}:\n{:#?}",
+                    event_id.0, err
+                );
+                continue;
+            }
+        };
+        if plan.is_dry_run() {
+            continue;
+        }
+        if let Err(err) = sync_interested_users(
+            event_id,
+            scheduled_event_id,
+            db_connection,
+            discord_api,
+            guild_config,
+        )
+        .await
+        {
+            some_failed = true;
+            eprintln!(
+                "Could not update the interested users of event {}:\n{:#?}",
+                event_id.0, err
+            );
+        }
+    }
+    if some_failed {
+        Err(simple_error::SimpleError::new("One or more scheduled event syncs failed").into())
+    } else {
+        Ok(())
+    }
+}
+
+async fn create_scheduled_event(
+    event_id: db::EventId,
+    info: &ScheduledEventInfo,
+    synced: &str,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<ScheduledEventId, crate::meetup::Error> {
+    let scheduled_event_id = discord_api
+        .create_scheduled_event(guild_config.guild_id, info)
+        .await?;
+    sqlx::query!(
+        r#"UPDATE event SET discord_scheduled_event_id = $2, discord_scheduled_event_synced = $3 WHERE id = $1"#,
+        event_id.0,
+        scheduled_event_id.get() as i64,
+        synced
+    )
+    .execute(db_connection)
+    .await?;
+    println!(
+        "Created Discord scheduled event {} for event {}",
+        scheduled_event_id, event_id.0
+    );
+    Ok(scheduled_event_id)
+}
+
+// Recreates the scheduled event if it was deleted on Discord
+async fn update_scheduled_event(
+    event_id: db::EventId,
+    scheduled_event_id: ScheduledEventId,
+    info: &ScheduledEventInfo,
+    synced: &str,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<ScheduledEventId, crate::meetup::Error> {
+    let exists = discord_api
+        .edit_scheduled_event(guild_config.guild_id, scheduled_event_id, info)
+        .await?;
+    if !exists {
+        return create_scheduled_event(
+            event_id,
+            info,
+            synced,
+            db_connection,
+            discord_api,
+            guild_config,
+        )
+        .await;
+    }
+    sqlx::query!(
+        r#"UPDATE event SET discord_scheduled_event_synced = $2 WHERE id = $1"#,
+        event_id.0,
+        synced
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(scheduled_event_id)
+}
+
+async fn sync_interested_users(
+    event_id: db::EventId,
+    scheduled_event_id: ScheduledEventId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<(), crate::meetup::Error> {
+    let user_ids: Vec<_> = discord_api
+        .scheduled_event_interested_users(guild_config.guild_id, scheduled_event_id)
+        .await?
+        .into_iter()
+        .map(|user_id| user_id.get() as i64)
+        .collect();
+    let mut tx = db_connection.begin().await?;
+    sqlx::query!(
+        r#"DELETE FROM event_discord_interested_user WHERE event_id = $1 AND discord_user_id <> ALL($2)"#,
+        event_id.0,
+        &user_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query!(
+        r#"INSERT INTO event_discord_interested_user (event_id, discord_user_id)
+        SELECT $1, discord_user_id FROM UNNEST($2::bigint[]) AS discord_user_id
+        ON CONFLICT DO NOTHING"#,
+        event_id.0,
+        &user_ids
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+// The Discord users interested in any of the events, as of the last sync
+pub async fn interested_users(
+    event_ids: &[db::EventId],
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<UserId>, crate::meetup::Error> {
+    let event_ids: Vec<i32> = event_ids.iter().map(|id| id.0).collect();
+    let user_ids = sqlx::query!(
+        r#"SELECT DISTINCT discord_user_id FROM event_discord_interested_user WHERE event_id = ANY($1)"#,
+        &event_ids
+    )
+    .map(|row| UserId::new(row.discord_user_id as u64))
+    .fetch_all(db_connection)
+    .await?;
+    Ok(user_ids)
+}
@@ -0,0 +1,194 @@
+use std::fmt::Write;
+
+use serenity::{
+    all::Mentionable,
+    builder::{CreateEmbed, CreateEmbedFooter, CreateMessage, EditMessage},
+    model::id::{ChannelId, MessageId, UserId},
+};
+
+use super::{
+    api::DiscordApi,
+    sync_plan::{PlannedChange, SyncPlan},
+};
+use crate::db;
+
+fn build_embed(
+    series_name: &str,
+    next_event: &db::Event,
+    hosts: &[UserId],
+    players: &[Option<UserId>],
+    voice_channel_id: Option<ChannelId>,
+    expiration_time: Option<chrono::DateTime<chrono::Utc>>,
+) -> CreateEmbed {
+    let timestamp = next_event.time.timestamp();
+    let mut description = format!("Next session: <t:{timestamp}:F> (<t:{timestamp}:R>)");
+    if let Some(meetup_event) = &next_event.meetup_event {
+        write!(
+            &mut description,
+            "\n[Sign up on Meetup](<{}>)",
+            meetup_event.url
+        )
+        .ok();
+    }
+    let host_list = itertools::join(hosts.iter().map(|id| id.mention().to_string()), ", ");
+    let mut player_list = itertools::join(
+        players.iter().flatten().map(|id| id.mention().to_string()),
+        "\n",
+    );
+    let num_without_discord = players.iter().filter(|id| id.is_none()).count();
+    if num_without_discord > 0 {
+        if !player_list.is_empty() {
+            player_list.push('\n');
+        }
+        write!(
+            &mut player_list,
+            "+{} without a linked Discord account",
+            num_without_discord
+        )
+        .ok();
+    }
+    if player_list.is_empty() {
+        player_list.push_str("Nobody yet");
+    }
+    let mut embed = CreateEmbed::new()
+        .title(series_name)
+        .description(description);
+    if !host_list.is_empty() {
+        embed = embed.field("Hosts", host_list, false);
+    }
+    embed = embed.field(format!("Players ({})", players.len()), player_list, false);
+    if let Some(voice_channel_id) = voice_channel_id {
+        embed = embed.field(
+            "Voice channel",
+            voice_channel_id.mention().to_string(),
+            false,
+        );
+    }
+    if let Some(expiration_time) = expiration_time {
+        embed = embed.field(
+            "Channel expires",
+            format!("<t:{}:D>", expiration_time.timestamp()),
+            false,
+        );
+    }
+    embed.footer(CreateEmbedFooter::new("Kept up to date by the bot"))
+}
+
+// The series whose info message is synced
+pub(crate) struct SeriesInfo<'a> {
+    pub series_id: db::EventSeriesId,
+    pub series_name: &'a str,
+    pub next_event: &'a db::Event,
+    pub channel_id: ChannelId,
+    pub voice_channel_id: Option<ChannelId>,
+}
+
+// Keeps one pinned message in the series' text channel up to date which shows
+// the next session, the hosts, the players, the voice channel and when the
+// channel expires
+pub(crate) async fn sync_series_info(
+    series: &SeriesInfo<'_>,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    plan: &mut SyncPlan,
+) -> Result<(), crate::meetup::Error> {
+    let SeriesInfo {
+        series_id,
+        series_name,
+        next_event,
+        channel_id,
+        voice_channel_id,
+    } = *series;
+    let info_message = sqlx::query!(
+        r#"SELECT discord_info_message_id, discord_info_message_synced FROM event_series WHERE id = $1"#,
+        series_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    let info_message_id = info_message
+        .discord_info_message_id
+        .map(|id| MessageId::new(id as u64));
+    if plan.is_dry_run() {
+        // Existing info messages are only refreshed, so only new ones are planned
+        if info_message_id.is_none() {
+            plan.push(PlannedChange::PostSeriesInfo {
+                series_id,
+                channel_id,
+            });
+        }
+        return Ok(());
+    }
+    let hosts: Vec<_> =
+        db::get_events_participants(&[next_event.id], /*hosts*/ true, db_connection)
+            .await?
+            .into_iter()
+            .filter_map(|host| host.discord_id)
+            .collect();
+    let players = sqlx::query!(
+        r#"SELECT member.discord_id
+        FROM event_participant
+        INNER JOIN member ON event_participant.member_id = member.id
+        WHERE event_participant.event_id = $1"#,
+        next_event.id.0
+    )
+    .map(|row| row.discord_id.map(|id| UserId::new(id as u64)))
+    .fetch_all(db_connection)
+    .await?;
+    let expiration_time = sqlx::query_scalar!(
+        r#"SELECT expiration_time FROM event_series_text_channel WHERE discord_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
+    let embed = build_embed(
+        series_name,
+        next_event,
+        &hosts,
+        &players,
+        voice_channel_id,
+        expiration_time,
+    );
+
+    let edit_builder = EditMessage::new().embed(embed.clone());
+    let synced = super::util::rendered_content(&edit_builder)?;
+
+    // Try to update the existing message first. Only post a new one if the
+    // old one is gone, other errors would pile up duplicate pinned messages.
+    if let Some(message_id) = info_message_id {
+        if info_message.discord_info_message_synced.as_ref() == Some(&synced) {
+            // Nothing changed since the last sync
+            return Ok(());
+        }
+        let edited = discord_api
+            .edit_message(channel_id, message_id, edit_builder)
+            .await?;
+        if edited {
+            sqlx::query!(
+                r#"UPDATE event_series SET discord_info_message_synced = $2 WHERE id = $1"#,
+                series_id.0,
+                synced
+            )
+            .execute(db_connection)
+            .await?;
+            return Ok(());
+        }
+        eprintln!(
+            "The info message of series {} is gone, posting a new one",
+            series_id.0
+        );
+    }
+    let message_id = discord_api
+        .send_message(channel_id, CreateMessage::new().embed(embed))
+        .await?;
+    sqlx::query!(
+        r#"UPDATE event_series SET discord_info_message_id = $2, discord_info_message_synced = $3 WHERE id = $1"#,
+        series_id.0,
+        message_id.get() as i64,
+        synced
+    )
+    .execute(db_connection)
+    .await?;
+    discord_api.pin_message(channel_id, message_id).await?;
+    Ok(())
+}
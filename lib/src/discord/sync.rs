@@ -2,63 +2,23 @@ use futures::{stream, StreamExt};
 use lazy_static::lazy_static;
 use redis::{self, AsyncCommands};
 use serenity::{
-    builder::{CreateChannel, CreateMessage, EditChannel, EditRole},
-    http::CacheHttp,
+    builder::CreateMessage,
     model::{
         channel::{PermissionOverwrite, PermissionOverwriteType},
+        colour::Colour,
         id::{ChannelId, GuildId, RoleId, UserId},
         permissions::Permissions,
     },
 };
 use simple_error::SimpleError;
 
-#[cfg(feature = "bottest")]
-pub mod ids {
-    use super::*;
-    // Test server:
-    pub const GUILD_ID: GuildId = GuildId::new(601070848446824509);
-    pub const BOT_ADMIN_ID: RoleId = RoleId::new(606829075226689536);
-    pub const ORGANISER_ID: RoleId = RoleId::new(689914933357314090);
-    pub const GAME_MASTER_ID: RoleId = RoleId::new(606913167439822987);
-    pub const DICE_ROLLER_BOT_ID: Option<RoleId> = None;
-    pub const ONE_SHOT_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(607561808429056042)];
-    pub const CAMPAIGN_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(607561949651402772)];
-    pub const VOICE_CHANNELS_CATEGORY_IDS: &'static [ChannelId] =
-        &[ChannelId::new(601070848446824512)];
-    pub const BOT_ALERTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(650656330390175764));
-    pub const FREE_SPOTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(704988201038643270));
-    pub const USER_TOPIC_VOICE_CHANNEL_ID: Option<ChannelId> =
-        Some(ChannelId::new(807270405672140831));
-    pub const ADMIN_ROLE_ID: Option<RoleId> = None;
-}
-
-#[cfg(not(feature = "bottest"))]
-pub mod ids {
-    use super::*;
-    // SwissRPG server:
-    pub const GUILD_ID: GuildId = GuildId::new(401856510709202945);
-    pub const BOT_ADMIN_ID: RoleId = RoleId::new(610541498852966436);
-    pub const ORGANISER_ID: RoleId = RoleId::new(539447673988841492);
-    pub const GAME_MASTER_ID: RoleId = RoleId::new(412946716892069888);
-    pub const DICE_ROLLER_BOT_ID: Option<RoleId> = Some(RoleId::new(600612886368223274));
-    pub const ONE_SHOT_CATEGORY_IDS: &'static [ChannelId] = &[ChannelId::new(562607292176924694)];
-    pub const CAMPAIGN_CATEGORY_IDS: &'static [ChannelId] = &[
-        ChannelId::new(414074722259828736),
-        ChannelId::new(651006290998329354),
-    ];
-    pub const VOICE_CHANNELS_CATEGORY_IDS: &'static [ChannelId] = &[
-        ChannelId::new(401856511233753110),
-        ChannelId::new(831140794952843324),
-    ];
-    pub const BOT_ALERTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(650660608705822723));
-    pub const FREE_SPOTS_CHANNEL_ID: Option<ChannelId> = Some(ChannelId::new(706131908102324345));
-    pub const USER_TOPIC_VOICE_CHANNEL_ID: Option<ChannelId> =
-        Some(ChannelId::new(811601700736729129));
-    pub const ADMIN_ROLE_ID: Option<RoleId> = Some(RoleId::new(412927099855437825));
-}
-
-use ids::*;
-
+use super::{
+    api::DiscordApi,
+    categories::{with_overflow_categories, CategoryTracker},
+    config::{GuildConfig, GuildConfigs},
+    scheduled_events::sync_scheduled_events,
+    sync_plan::{PlannedChange, SyncPlan},
+};
 use crate::db;
 
 lazy_static! {
@@ -66,37 +26,439 @@ lazy_static! {
         regex::Regex::new(r"^\s*(?P<name>[^\[\(]+[^\s\[\(])").unwrap();
 }
 
+const HOST_TEXT_CHANNEL_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::MENTION_EVERYONE)
+    .union(Permissions::MANAGE_MESSAGES);
+const HOST_VOICE_CHANNEL_PERMISSIONS: Permissions = Permissions::VIEW_CHANNEL
+    .union(Permissions::CONNECT)
+    .union(Permissions::MUTE_MEMBERS)
+    .union(Permissions::DEAFEN_MEMBERS)
+    .union(Permissions::MOVE_MEMBERS)
+    .union(Permissions::PRIORITY_SPEAKER);
+
+// Event series are synced concurrently, but only this many at a time to stay
+// within Discord's rate limits and the database connection pool
+const MAX_CONCURRENT_SERIES_SYNCS: usize = 3;
+
+// Advisory lock keys are (namespace, ID) pairs
+const SERIES_LOCK_NAMESPACE: i32 = 1;
+
+// What a Discord sync run did
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    pub num_synced: usize,
+    pub num_failed: usize,
+    // Event series which another sync was syncing at the same time
+    pub num_skipped: usize,
+    // Steps other than the event series syncs which failed, e.g. the scheduled
+    // events sync
+    pub num_failed_steps: usize,
+    pub duration: std::time::Duration,
+}
+
+impl SyncSummary {
+    pub fn has_failures(&self) -> bool {
+        self.num_failed > 0 || self.num_failed_steps > 0
+    }
+}
+
+impl std::fmt::Display for SyncSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Synced {} event series in {:.1}s. {} failed, {} skipped because another sync was \
+             running.",
+            self.num_synced,
+            self.duration.as_secs_f64(),
+            self.num_failed,
+            self.num_skipped
+        )?;
+        if self.num_failed_steps > 0 {
+            write!(f, " {} other sync steps failed.", self.num_failed_steps)?;
+        }
+        Ok(())
+    }
+}
+
 // Syncs Discord with the state of the database
 pub async fn sync_discord(
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+) -> Result<(), crate::meetup::Error> {
+    let summary = sync_discord_with_summary(
+        redis_connection,
+        db_connection,
+        discord_api,
+        guild_configs,
+        bot_id,
+    )
+    .await?;
+    if summary.has_failures() {
+        Err(SimpleError::new(format!(
+            "{} discord event series syncs and {} other sync steps failed",
+            summary.num_failed, summary.num_failed_steps
+        ))
+        .into())
+    } else {
+        Ok(())
+    }
+}
+
+// Like sync_discord, but failed event series syncs are only counted in the
+// summary instead of failing the whole sync
+pub async fn sync_discord_with_summary(
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+) -> Result<SyncSummary, crate::meetup::Error> {
+    sync_discord_impl(
+        redis_connection,
+        db_connection,
+        discord_api,
+        guild_configs,
+        bot_id,
+        &mut SyncPlan::apply(),
+    )
+    .await
+}
+
+// Computes the changes that sync_discord would make without performing them
+pub async fn plan_sync_discord(
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+) -> Result<SyncPlan, crate::meetup::Error> {
+    let mut plan = SyncPlan::dry_run();
+    sync_discord_impl(
+        redis_connection,
+        db_connection,
+        discord_api,
+        guild_configs,
+        bot_id,
+        &mut plan,
+    )
+    .await?;
+    Ok(plan)
+}
+
+// Syncs a single event series with Discord, e.g. right after it changed. If
+// another sync is syncing the same series, this waits for it to finish.
+pub async fn sync_discord_series(
+    series_id: db::EventSeriesId,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
     bot_id: UserId,
 ) -> Result<(), crate::meetup::Error> {
-    let event_series_ids = sqlx::query!("SELECT id FROM event_series")
+    let mut plan = SyncPlan::apply();
+    assign_event_series_guilds(db_connection, discord_api, guild_configs, &mut plan).await?;
+    let guild_id = sqlx::query_scalar!(
+        "SELECT guild_id FROM event_series WHERE id = $1",
+        series_id.0
+    )
+    .fetch_optional(db_connection)
+    .await?
+    .flatten();
+    let guild_config = match guild_id.and_then(|id| guild_configs.get(GuildId::new(id as u64))) {
+        Some(guild_config) => guild_config,
+        None => {
+            return Err(SimpleError::new(format!(
+                "Event series {} does not exist or belongs to an unknown guild",
+                series_id.0
+            ))
+            .into())
+        }
+    };
+    let category_tracker = match CategoryTracker::load(guild_config.guild_id, discord_api).await {
+        Ok(category_tracker) => tokio::sync::Mutex::new(category_tracker),
+        Err(err) => {
+            return Err(SimpleError::new(format!(
+                "Could not sync event series {} since the channels of guild {} could not be \
+                 loaded: {}",
+                series_id.0, guild_config.guild_id, err
+            ))
+            .into())
+        }
+    };
+    let series_locks = SeriesLocks::connect(db_connection).await?;
+    series_locks.lock(series_id).await?;
+    let result = sync_event_series(
+        series_id,
+        redis_connection,
+        db_connection,
+        discord_api,
+        guild_config,
+        &category_tracker,
+        bot_id,
+        &mut plan,
+    )
+    .await;
+    series_locks.unlock(series_id).await?;
+    result
+}
+
+// Advisory locks which keep concurrent syncs from syncing the same event
+// series. They are held on a connection which is detached from the pool, so
+// that holding or waiting for them doesn't tie up the pool's connections which
+// the syncs need, and so that a lock which failed to be released never ends up
+// back in the pool. All locks are released when the connection closes at the
+// latest.
+struct SeriesLocks {
+    connection: tokio::sync::Mutex<sqlx::PgConnection>,
+}
+
+impl SeriesLocks {
+    async fn connect(db_connection: &sqlx::PgPool) -> Result<Self, crate::meetup::Error> {
+        let connection = db_connection.acquire().await?.detach();
+        Ok(SeriesLocks {
+            connection: tokio::sync::Mutex::new(connection),
+        })
+    }
+
+    // Returns false if another sync holds the lock already
+    async fn try_lock(&self, series_id: db::EventSeriesId) -> Result<bool, crate::meetup::Error> {
+        let is_locked = sqlx::query_scalar!(
+            r#"SELECT pg_try_advisory_lock($1, $2) AS "is_locked!""#,
+            SERIES_LOCK_NAMESPACE,
+            series_id.0
+        )
+        .fetch_one(&mut *self.connection.lock().await)
+        .await?;
+        Ok(is_locked)
+    }
+
+    // Waits until no other sync holds the lock. Nobody else can use these
+    // locks in the meantime.
+    async fn lock(&self, series_id: db::EventSeriesId) -> Result<(), crate::meetup::Error> {
+        sqlx::query!(
+            "SELECT pg_advisory_lock($1, $2)",
+            SERIES_LOCK_NAMESPACE,
+            series_id.0
+        )
+        .execute(&mut *self.connection.lock().await)
+        .await?;
+        Ok(())
+    }
+
+    async fn unlock(&self, series_id: db::EventSeriesId) -> Result<(), crate::meetup::Error> {
+        let was_locked = sqlx::query_scalar!(
+            r#"SELECT pg_advisory_unlock($1, $2) AS "was_locked!""#,
+            SERIES_LOCK_NAMESPACE,
+            series_id.0
+        )
+        .fetch_one(&mut *self.connection.lock().await)
+        .await?;
+        if !was_locked {
+            eprintln!("The lock on event series {} was not held", series_id.0);
+        }
+        Ok(())
+    }
+}
+
+async fn sync_discord_impl(
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+    plan: &mut SyncPlan,
+) -> Result<SyncSummary, crate::meetup::Error> {
+    let start_time = std::time::Instant::now();
+    let mut summary = SyncSummary::default();
+    assign_event_series_guilds(db_connection, discord_api, guild_configs, plan).await?;
+    let is_dry_run = plan.is_dry_run();
+    // Dry runs don't change anything, so they don't need the locks
+    let series_locks = if is_dry_run {
+        None
+    } else {
+        Some(SeriesLocks::connect(db_connection).await?)
+    };
+    let redis_db = redis_connection.get_db();
+    let shared_redis_connection = tokio::sync::Mutex::new(redis_connection);
+    for guild_config in guild_configs.iter() {
+        if let Err(err) =
+            announce_event_cancellations(db_connection, discord_api, guild_config, plan).await
+        {
+            summary.num_failed_steps += 1;
+            eprintln!("Announcing event cancellations failed: {}", err);
+        }
+        // Without the guild's channels, none of its series can be synced, but
+        // the other guilds still can
+        let category_tracker = match CategoryTracker::load(guild_config.guild_id, discord_api).await
+        {
+            Ok(category_tracker) => tokio::sync::Mutex::new(category_tracker),
+            Err(err) => {
+                summary.num_failed_steps += 1;
+                eprintln!(
+                    "Could not load the channels of guild {}, skipping its event series: {}",
+                    guild_config.guild_id, err
+                );
+                continue;
+            }
+        };
+        let event_series_ids = sqlx::query!(
+            "SELECT id FROM event_series WHERE guild_id = $1",
+            guild_config.guild_id.get() as i64
+        )
         .map(|row| db::EventSeriesId(row.id))
         .fetch_all(db_connection)
         .await?;
-    let mut some_failed = false;
-    for series_id in event_series_ids {
-        if let Err(err) = sync_event_series(
-            series_id,
-            redis_connection,
-            db_connection,
-            discord_api,
-            bot_id,
-        )
-        .await
+        // Each series gets its own plan, which are merged in order afterwards
+        let results: Vec<_> = stream::iter(event_series_ids)
+            .map(|series_id| {
+                let shared_redis_connection = &shared_redis_connection;
+                let category_tracker = &category_tracker;
+                let series_locks = series_locks.as_ref();
+                async move {
+                    let mut series_plan = if is_dry_run {
+                        SyncPlan::dry_run()
+                    } else {
+                        SyncPlan::apply()
+                    };
+                    if let Some(series_locks) = series_locks {
+                        match series_locks.try_lock(series_id).await {
+                            Ok(true) => (),
+                            Ok(false) => return (series_id, Ok(false), series_plan),
+                            Err(err) => return (series_id, Err(err), series_plan),
+                        }
+                    }
+                    let mut redis_connection =
+                        crate::redis::SharedConnection::new(shared_redis_connection, redis_db);
+                    let mut result = sync_event_series(
+                        series_id,
+                        &mut redis_connection,
+                        db_connection,
+                        discord_api,
+                        guild_config,
+                        category_tracker,
+                        bot_id,
+                        &mut series_plan,
+                    )
+                    .await
+                    .map(|()| true);
+                    if let Some(series_locks) = series_locks {
+                        if let Err(err) = series_locks.unlock(series_id).await {
+                            result = result.and(Err(err));
+                        }
+                    }
+                    (series_id, result, series_plan)
+                }
+            })
+            .buffered(MAX_CONCURRENT_SERIES_SYNCS)
+            .collect()
+            .await;
+        for (series_id, result, series_plan) in results {
+            plan.extend(series_plan);
+            match result {
+                Ok(true) => summary.num_synced += 1,
+                Ok(false) => {
+                    summary.num_skipped += 1;
+                    println!(
+                        "Skipped event series {} since another sync is syncing it",
+                        series_id.0
+                    );
+                }
+                Err(err) => {
+                    summary.num_failed += 1;
+                    if is_dry_run {
+                        // Don't let a single broken series spoil the whole plan
+                        plan.push_error(series_id, err.to_string());
+                    } else {
+                        eprintln!("Discord event series syncing task failed: {}", err);
+                    }
+                }
+            }
+        }
+        // After the series, so that new voice channels are already there
+        if let Err(err) =
+            sync_scheduled_events(db_connection, discord_api, guild_config, plan).await
         {
-            some_failed = true;
-            eprintln!("Discord event series syncing task failed: {}", err);
+            summary.num_failed_steps += 1;
+            eprintln!("Syncing Discord scheduled events failed: {}", err);
         }
     }
-    if some_failed {
-        Err(SimpleError::new("One or more discord event series syncs failed").into())
-    } else {
-        Ok(())
+    summary.duration = start_time.elapsed();
+    Ok(summary)
+}
+
+// Assigns a guild to all event series which don't have one yet. Series with an
+// existing text channel get the channel's guild, all others the default guild.
+// In a dry run, these series are not planned any further.
+async fn assign_event_series_guilds(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    plan: &mut SyncPlan,
+) -> Result<(), crate::meetup::Error> {
+    let unassigned_series =
+        sqlx::query!("SELECT id, discord_text_channel_id FROM event_series WHERE guild_id IS NULL")
+            .fetch_all(db_connection)
+            .await?;
+    for series in unassigned_series {
+        let channel_guild_id = match series.discord_text_channel_id {
+            Some(channel_id) => discord_api
+                .channel(ChannelId::new(channel_id as u64))
+                .await
+                .ok()
+                .flatten()
+                .map(|channel| channel.guild_id),
+            None => None,
+        };
+        let guild_id = match channel_guild_id {
+            Some(guild_id) if guild_configs.contains(guild_id) => guild_id,
+            _ => guild_configs.default_guild().guild_id,
+        };
+        if plan.is_dry_run() {
+            plan.push(PlannedChange::AssignGuild {
+                series_id: db::EventSeriesId(series.id),
+                guild_id,
+            });
+            continue;
+        }
+        sqlx::query!(
+            "UPDATE event_series SET guild_id = $2 WHERE id = $1 AND guild_id IS NULL",
+            series.id,
+            guild_id.get() as i64
+        )
+        .execute(db_connection)
+        .await?;
+    }
+    Ok(())
+}
+
+// Announces the events which were cancelled on Meetup in their series' channels
+async fn announce_event_cancellations(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
+) -> Result<(), crate::meetup::Error> {
+    let cancelled_event_ids = sqlx::query!(
+        r#"SELECT event.id
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        WHERE event.cancellation_announcement_pending AND event_series.guild_id = $1"#,
+        guild_config.guild_id.get() as i64
+    )
+    .map(|row| db::EventId(row.id))
+    .fetch_all(db_connection)
+    .await?;
+    for event_id in cancelled_event_ids {
+        if plan.is_dry_run() {
+            plan.push(PlannedChange::AnnounceEventCancellation { event_id });
+        } else {
+            super::rsvp::announce_event_cancellation(event_id, db_connection, discord_api).await?;
+        }
     }
+    Ok(())
 }
 
 /*
@@ -115,10 +477,13 @@ For each event series:
 */
 async fn sync_event_series(
     series_id: db::EventSeriesId,
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    category_tracker: &tokio::sync::Mutex<CategoryTracker>,
     bot_id: UserId,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Only sync event series that have events in the future
     let next_event = match db::get_next_event_in_series(db_connection, series_id).await? {
@@ -134,29 +499,56 @@ async fn sync_event_series(
     };
     // Upgrade this event series to a campaign if there is more than one event
     let num_events = sqlx::query_scalar!(
-        r#"SELECT COUNT(*) as "count!" FROM event WHERE event_series_id = $1"#,
+        r#"SELECT COUNT(*) as "count!" FROM event WHERE event_series_id = $1 AND deleted IS NULL"#,
         series_id.0
     )
     .fetch_one(db_connection)
     .await?;
     if num_events > 1 {
-        sqlx::query!(
-            r#"UPDATE event_series SET "type" = 'campaign' WHERE id = $1 AND "type" <> 'campaign'"#,
-            series_id.0
-        )
-        .execute(db_connection)
-        .await?;
+        if plan.is_dry_run() {
+            let series_type = sqlx::query_scalar!(
+                r#"SELECT "type" FROM event_series WHERE id = $1"#,
+                series_id.0
+            )
+            .fetch_one(db_connection)
+            .await?;
+            if series_type != "campaign" {
+                plan.push(PlannedChange::UpgradeToCampaign { series_id });
+            }
+        } else {
+            sqlx::query!(
+                r#"UPDATE event_series SET "type" = 'campaign' WHERE id = $1 AND "type" <> 'campaign'"#,
+                series_id.0
+            )
+            .execute(db_connection)
+            .await?;
+        }
     }
 
     // Update this series' Discord category to match the next upcoming event's (if any)
     if let Some(discord_category) = next_event.discord_category {
-        sqlx::query!(
+        if plan.is_dry_run() {
+            let current_category = sqlx::query_scalar!(
+                r#"SELECT discord_category_id FROM event_series WHERE id = $1"#,
+                series_id.0
+            )
+            .fetch_one(db_connection)
+            .await?;
+            if current_category != Some(discord_category.get() as i64) {
+                plan.push(PlannedChange::SetSeriesCategory {
+                    series_id,
+                    category_id: discord_category,
+                });
+            }
+        } else {
+            sqlx::query!(
             r#"UPDATE event_series SET discord_category_id = $2 WHERE id = $1 AND discord_category_id IS DISTINCT FROM $2"#,
-            series_id.0,
-            discord_category.get() as i64
-        )
-        .execute(db_connection)
-        .await?;
+                series_id.0,
+                discord_category.get() as i64
+            )
+            .execute(db_connection)
+            .await?;
+        }
     }
 
     // Figure out the title of this event series
@@ -207,18 +599,15 @@ async fn sync_event_series(
     .await?;
 
     // Step 0: Make sure that event hosts have the guild's game master role
-    sync_game_master_role(series_id, db_connection, discord_api).await?;
-    // Convert host IDs to user objects
-    let discord_hosts: Vec<_> = stream::iter(&discord_host_ids)
-        .then(|&host_id| host_id.to_user(discord_api))
+    sync_game_master_role(series_id, db_connection, discord_api, guild_config, plan).await?;
+    // Look up the hosts' names
+    let discord_host_names: Vec<_> = stream::iter(&discord_host_ids)
+        .then(|&host_id| discord_api.user_name(host_id))
         .filter_map(|res| async {
             match res {
-                Ok(user) => Some(user),
+                Ok(name) => Some(name),
                 Err(err) => {
-                    eprintln!(
-                        "Error converting Discord host ID to Discord user object: {}",
-                        err
-                    );
+                    eprintln!("Error looking up the name of a Discord host: {}", err);
                     None
                 }
             }
@@ -234,13 +623,15 @@ async fn sync_event_series(
         redis_connection,
         db_connection,
         discord_api,
+        guild_config,
+        plan,
     )
     .await?;
     // Step 2: Sync the channel's associated role
-    let guest_tag = if discord_hosts.is_empty() {
+    let guest_tag = if discord_host_names.is_empty() {
         "Player".to_string()
     } else {
-        itertools::join(discord_hosts.iter().map(|host| &host.name), ", ")
+        discord_host_names.join(", ")
     };
     let guest_role_name = format!("[{}] {}", guest_tag, series_name);
     let channel_role_id = sync_role(
@@ -250,8 +641,31 @@ async fn sync_event_series(
         redis_connection,
         db_connection,
         discord_api,
+        guild_config,
+        plan,
     )
     .await?;
+    let (channel_id, channel_role_id) = match (channel_id, channel_role_id) {
+        (Some(channel_id), Some(channel_role_id)) => (channel_id, channel_role_id),
+        (channel_id, channel_role_id) => {
+            // Only happens in a dry run: the channel or its role would be
+            // newly created, so all players and hosts would be added anew
+            for &user_id in &discord_guest_ids {
+                plan.push(PlannedChange::AssignRole {
+                    user_id,
+                    role_id: channel_role_id,
+                });
+            }
+            for &host_id in &discord_host_ids {
+                plan.push(PlannedChange::AddChannelUserPermissions {
+                    channel_id,
+                    user_id: host_id,
+                    permissions: HOST_TEXT_CHANNEL_PERMISSIONS,
+                });
+            }
+            return Ok(());
+        }
+    };
     // Step 3: Sync the channel's associated host role
     // let host_role_name = format!("[Host] {}", series_name);
     // let channel_host_role_id = sync_role(
@@ -269,6 +683,8 @@ async fn sync_event_series(
         &discord_host_ids,
         bot_id,
         discord_api,
+        guild_config,
+        plan,
     )
     .await
     {
@@ -287,6 +703,8 @@ async fn sync_event_series(
             redis_connection,
             db_connection,
             discord_api,
+            guild_config,
+            plan,
         )
         .await
         {
@@ -294,7 +712,9 @@ async fn sync_event_series(
                 eprintln!("Error in sync_channel (for voice channel):\n{:#?}", err);
                 None
             }
-            Ok(voice_channel_id) => {
+            // The voice channel would be newly created (dry run only)
+            Ok(None) => None,
+            Ok(Some(voice_channel_id)) => {
                 if let Err(err) = sync_channel_permissions(
                     voice_channel_id,
                     ChannelType::Voice,
@@ -302,6 +722,8 @@ async fn sync_event_series(
                     &discord_host_ids,
                     bot_id,
                     discord_api,
+                    guild_config,
+                    plan,
                 )
                 .await
                 {
@@ -334,17 +756,22 @@ async fn sync_event_series(
         channel_role_id,
         db_connection,
         discord_api,
+        guild_config,
+        plan,
     )
     .await?;
     // Step 6: Keep the channel's topic up-to-date
-    sync_channel_topic(channel_id, &next_event, discord_api).await?;
+    sync_channel_topic(channel_id, &next_event, discord_api, plan).await?;
     sync_channel_category(
         series_id,
         ChannelType::Text,
         &next_event,
         channel_id,
+        category_tracker,
         db_connection,
         discord_api,
+        guild_config,
+        plan,
     )
     .await?;
     if let Some(voice_channel_id) = voice_channel_id {
@@ -353,22 +780,81 @@ async fn sync_event_series(
             ChannelType::Voice,
             &next_event,
             voice_channel_id,
+            category_tracker,
             db_connection,
             discord_api,
+            guild_config,
+            plan,
         )
         .await?;
     }
+    // Step 7: Keep the pinned series info message up to date
+    let series_info = super::series_info::SeriesInfo {
+        series_id,
+        series_name,
+        next_event: &next_event,
+        channel_id,
+        voice_channel_id,
+    };
+    if let Err(err) =
+        super::series_info::sync_series_info(&series_info, db_connection, discord_api, plan).await
+    {
+        eprintln!(
+            "Error in sync_series_info (for series {}):\n{:#?}",
+            series_id.0, err
+        );
+    }
+    // Step 8: Announce the upcoming events with RSVP buttons
+    if plan.is_dry_run() {
+        // Existing announcements are only refreshed, so only new ones are planned
+        let unannounced_event_ids = sqlx::query!(
+            r#"SELECT id FROM event WHERE event_series_id = $1 AND start_time > NOW() AND deleted IS NULL AND discord_announcement_message_id IS NULL"#,
+            series_id.0
+        )
+        .map(|row| db::EventId(row.id))
+        .fetch_all(db_connection)
+        .await?;
+        for event_id in unannounced_event_ids {
+            plan.push(PlannedChange::PostEventAnnouncement { event_id });
+        }
+        return Ok(());
+    }
+    for event in db::get_upcoming_events_for_series(db_connection, series_id).await? {
+        if let Err(err) =
+            super::rsvp::sync_event_announcement(event.id, db_connection, discord_api).await
+        {
+            eprintln!(
+                "Error in sync_event_announcement (for event {}):\n{:#?}",
+                event.id.0, err
+            );
+        }
+    }
     Ok(())
 }
 
+// Returns None if the role would be created (only in a dry run)
 async fn sync_role(
     role_name: &str,
     is_host_role: bool,
     event_series: db::EventSeriesId,
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
-) -> Result<RoleId, crate::meetup::Error> {
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
+) -> Result<Option<RoleId>, crate::meetup::Error> {
+    if plan.is_dry_run() {
+        return plan_role(
+            role_name,
+            is_host_role,
+            event_series,
+            db_connection,
+            discord_api,
+            guild_config,
+            plan,
+        )
+        .await;
+    }
     let max_retries: u32 = 1;
     let mut current_num_try: u32 = 0;
     loop {
@@ -383,22 +869,11 @@ async fn sync_role(
             redis_connection,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await?;
         // Make sure that the role ID that was returned actually exists on Discord
-        // First, check the cache
-        let role_exists = match GUILD_ID.to_guild_cached(&discord_api.cache) {
-            Some(guild) => guild.roles.contains_key(&role),
-            None => false,
-        };
-        // If it was not in the cache, check Discord
-        let role_exists = if role_exists {
-            true
-        } else {
-            let guild_roles = discord_api.http().get_guild_roles(GUILD_ID).await?;
-            guild_roles.iter().any(|guild_role| guild_role.id == role)
-        };
-        if !role_exists {
+        if !role_exists(role, discord_api, guild_config).await? {
             // This role does not exist on Discord
             // Delete it from the DB and retry
             if is_host_role {
@@ -423,18 +898,69 @@ async fn sync_role(
             continue;
         } else {
             // The role exists on Discord, so everything is good
-            return Ok(role);
+            return Ok(Some(role));
+        }
+    }
+}
+
+async fn role_exists(
+    role: RoleId,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+) -> Result<bool, crate::meetup::Error> {
+    discord_api.role_exists(guild_config.guild_id, role).await
+}
+
+// Dry run version of sync_role
+async fn plan_role(
+    role_name: &str,
+    is_host_role: bool,
+    event_series: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
+) -> Result<Option<RoleId>, crate::meetup::Error> {
+    let role_id = if is_host_role {
+        sqlx::query_scalar!(
+            r#"SELECT discord_host_role_id FROM event_series WHERE id = $1"#,
+            event_series.0
+        )
+        .fetch_one(db_connection)
+        .await?
+    } else {
+        sqlx::query_scalar!(
+            r#"SELECT discord_role_id FROM event_series WHERE id = $1"#,
+            event_series.0
+        )
+        .fetch_one(db_connection)
+        .await?
+    };
+    if let Some(role_id) = role_id.map(|id| RoleId::new(id as u64)) {
+        if role_exists(role_id, discord_api, guild_config).await? {
+            return Ok(Some(role_id));
         }
+        plan.push(PlannedChange::ForgetMissingRole {
+            series_id: event_series,
+            role_id,
+        });
     }
+    plan.push(PlannedChange::CreateRole {
+        series_id: event_series,
+        name: role_name.to_string(),
+        is_host_role,
+    });
+    Ok(None)
 }
 
 async fn sync_role_impl(
     role_name: &str,
     is_host_role: bool,
     series_id: db::EventSeriesId,
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
 ) -> Result<RoleId, crate::meetup::Error> {
     let mut tx = db_connection.begin().await?;
     // Check if the role already exists
@@ -458,12 +984,8 @@ async fn sync_role_impl(
         return Ok(role_id);
     }
     // The role doesn't exist yet -> try to create it
-    let role_builder = EditRole::new()
-        .name(role_name)
-        .colour(serenity::all::Colour::BLUE)
-        .permissions(Permissions::empty());
-    let temp_channel_role = GUILD_ID
-        .create_role(discord_api.http(), role_builder)
+    let temp_channel_role = discord_api
+        .create_role(guild_config.guild_id, role_name, Colour::BLUE)
         .await?;
     println!(
         "Discord event sync: created new temporary channel role {} \"{}\"",
@@ -506,9 +1028,8 @@ async fn sync_role_impl(
         Some(err) => {
             println!("Trying to delete temporary channel role");
             match discord_api
-                .http()
                 .delete_role(
-                    GUILD_ID,
+                    guild_config.guild_id,
                     temp_channel_role.id,
                     Some("sync_role_impl transaction failed"),
                 )
@@ -542,6 +1063,19 @@ async fn sync_role_impl(
         }
         None => {
             println!("Persisted new channel role {}", temp_channel_role.id.get());
+            crate::audit::record(
+                crate::audit::Entry::new(
+                    crate::audit::Action::RoleCreated,
+                    crate::audit::Target::Role(temp_channel_role.id),
+                )
+                .after(serde_json::json!({
+                    "name": temp_channel_role.name,
+                    "event_series_id": series_id.0,
+                    "host_role": is_host_role,
+                })),
+                db_connection,
+            )
+            .await;
             // Return the new channel role
             Ok(temp_channel_role.id)
         }
@@ -549,7 +1083,7 @@ async fn sync_role_impl(
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
-pub(crate) enum ChannelType {
+pub enum ChannelType {
     Text,
     Voice,
 }
@@ -559,10 +1093,23 @@ async fn sync_channel(
     channel_name: &str,
     event_series_id: db::EventSeriesId,
     bot_id: UserId,
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
-) -> Result<ChannelId, crate::meetup::Error> {
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
+) -> Result<Option<ChannelId>, crate::meetup::Error> {
+    if plan.is_dry_run() {
+        return plan_channel(
+            channel_type,
+            channel_name,
+            event_series_id,
+            db_connection,
+            discord_api,
+            plan,
+        )
+        .await;
+    }
     let max_retries: u32 = 1;
     let mut current_num_try: u32 = 0;
     loop {
@@ -578,28 +1125,11 @@ async fn sync_channel(
             redis_connection,
             db_connection,
             discord_api,
+            guild_config,
         )
         .await?;
         // Make sure that the channel ID that was returned actually exists on Discord
-        let channel_exists = match channel.to_channel(discord_api).await {
-            Ok(_) => true,
-            Err(err) => {
-                if let serenity::Error::Http(http_err) = &err {
-                    if let serenity::http::HttpError::UnsuccessfulRequest(response) = http_err {
-                        if response.status_code == serenity::http::StatusCode::NOT_FOUND {
-                            false
-                        } else {
-                            return Err(err.into());
-                        }
-                    } else {
-                        return Err(err.into());
-                    }
-                } else {
-                    return Err(err.into());
-                }
-            }
-        };
-        if !channel_exists {
+        if !channel_exists(channel, discord_api).await? {
             // This channel does not exist on Discord
             // Delete it from the DB and retry
             match channel_type {
@@ -627,9 +1157,60 @@ async fn sync_channel(
             continue;
         } else {
             // The channel exists on Discord, so everything is good
-            return Ok(channel);
+            return Ok(Some(channel));
+        }
+    }
+}
+
+async fn channel_exists(
+    channel: ChannelId,
+    discord_api: &impl DiscordApi,
+) -> Result<bool, crate::meetup::Error> {
+    Ok(discord_api.channel(channel).await?.is_some())
+}
+
+// Dry run version of sync_channel
+async fn plan_channel(
+    channel_type: ChannelType,
+    channel_name: &str,
+    event_series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    plan: &mut SyncPlan,
+) -> Result<Option<ChannelId>, crate::meetup::Error> {
+    let channel_id = match channel_type {
+        ChannelType::Text => {
+            sqlx::query_scalar!(
+                "SELECT discord_text_channel_id FROM event_series WHERE id = $1",
+                event_series_id.0
+            )
+            .fetch_one(db_connection)
+            .await?
+        }
+        ChannelType::Voice => {
+            sqlx::query_scalar!(
+                "SELECT discord_voice_channel_id FROM event_series WHERE id = $1",
+                event_series_id.0
+            )
+            .fetch_one(db_connection)
+            .await?
+        }
+    };
+    if let Some(channel_id) = channel_id.map(|id| ChannelId::new(id as u64)) {
+        if channel_exists(channel_id, discord_api).await? {
+            return Ok(Some(channel_id));
         }
+        plan.push(PlannedChange::ForgetMissingChannel {
+            series_id: event_series_id,
+            channel_id,
+        });
     }
+    plan.push(PlannedChange::CreateChannel {
+        series_id: event_series_id,
+        channel_type,
+        name: channel_name.to_string(),
+    });
+    Ok(None)
 }
 
 async fn sync_channel_impl(
@@ -637,9 +1218,10 @@ async fn sync_channel_impl(
     channel_name: &str,
     event_series_id: db::EventSeriesId,
     bot_id: UserId,
-    redis_connection: &mut redis::aio::Connection,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
 ) -> Result<ChannelId, crate::meetup::Error> {
     let mut tx = db_connection.begin().await?;
     // Check if the channel already exists
@@ -672,7 +1254,7 @@ async fn sync_channel_impl(
             PermissionOverwrite {
                 allow: Permissions::empty(),
                 deny: Permissions::VIEW_CHANNEL,
-                kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
             },
             PermissionOverwrite {
                 allow: Permissions::VIEW_CHANNEL,
@@ -684,7 +1266,7 @@ async fn sync_channel_impl(
             PermissionOverwrite {
                 allow: Permissions::empty(),
                 deny: Permissions::CONNECT,
-                kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
             },
             PermissionOverwrite {
                 allow: Permissions::CONNECT,
@@ -693,14 +1275,13 @@ async fn sync_channel_impl(
             },
         ],
     };
-    let channel_builder = CreateChannel::new(channel_name)
-        .kind(match channel_type {
-            ChannelType::Text => serenity::model::channel::ChannelType::Text,
-            ChannelType::Voice => serenity::model::channel::ChannelType::Voice,
-        })
-        .permissions(permission_overwrites);
-    let temp_channel = GUILD_ID
-        .create_channel(discord_api.http(), channel_builder)
+    let temp_channel = discord_api
+        .create_channel(
+            guild_config.guild_id,
+            channel_name,
+            channel_type,
+            permission_overwrites,
+        )
         .await?;
     println!(
         "Discord event sync: created new temporary channel {} \"{}\"",
@@ -749,7 +1330,6 @@ async fn sync_channel_impl(
         Some(err) => {
             println!("Trying to delete temporary channel");
             match discord_api
-                .http()
                 .delete_channel(
                     temp_channel.id,
                     Some("sync_channel_impl transaction failed"),
@@ -785,6 +1365,19 @@ async fn sync_channel_impl(
         }
         None => {
             println!("Persisted new channel {}", temp_channel.id.get());
+            crate::audit::record(
+                crate::audit::Entry::new(
+                    crate::audit::Action::ChannelCreated,
+                    crate::audit::Target::Channel(temp_channel.id),
+                )
+                .after(serde_json::json!({
+                    "name": temp_channel.name,
+                    "event_series_id": event_series_id.0,
+                    "voice": channel_type == ChannelType::Voice,
+                })),
+                db_connection,
+            )
+            .await;
             // Return the new channel
             Ok(temp_channel.id)
         }
@@ -801,7 +1394,9 @@ async fn sync_channel_permissions(
     role_id: RoleId,
     discord_host_ids: &[UserId],
     bot_id: UserId,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Make this channel private.
     // This is achieved by denying @everyone the VIEW_CHANNEL permission
@@ -813,7 +1408,7 @@ async fn sync_channel_permissions(
                 PermissionOverwrite {
                     allow: Permissions::empty(),
                     deny: Permissions::VIEW_CHANNEL,
-                    kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                    kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
                 },
                 PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL,
@@ -830,10 +1425,10 @@ async fn sync_channel_permissions(
                         | Permissions::MENTION_EVERYONE
                         | Permissions::MANAGE_MESSAGES,
                     deny: Permissions::empty(),
-                    kind: PermissionOverwriteType::Role(ORGANISER_ID),
+                    kind: PermissionOverwriteType::Role(guild_config.organiser_role_id),
                 },
             ];
-            if let Some(dice_roller_bot_id) = DICE_ROLLER_BOT_ID {
+            if let Some(dice_roller_bot_id) = guild_config.dice_roller_bot_role_id {
                 permission_overwrites.push(PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL,
                     deny: Permissions::empty(),
@@ -842,9 +1437,7 @@ async fn sync_channel_permissions(
             }
             for &host_id in discord_host_ids {
                 permission_overwrites.push(PermissionOverwrite {
-                    allow: Permissions::VIEW_CHANNEL
-                        | Permissions::MENTION_EVERYONE
-                        | Permissions::MANAGE_MESSAGES,
+                    allow: HOST_TEXT_CHANNEL_PERMISSIONS,
                     deny: Permissions::empty(),
                     kind: PermissionOverwriteType::Member(host_id),
                 });
@@ -856,7 +1449,7 @@ async fn sync_channel_permissions(
                 PermissionOverwrite {
                     allow: Permissions::empty(),
                     deny: Permissions::VIEW_CHANNEL | Permissions::CONNECT,
-                    kind: PermissionOverwriteType::Role(GUILD_ID.everyone_role()),
+                    kind: PermissionOverwriteType::Role(guild_config.guild_id.everyone_role()),
                 },
                 PermissionOverwrite {
                     allow: Permissions::VIEW_CHANNEL | Permissions::CONNECT,
@@ -875,7 +1468,7 @@ async fn sync_channel_permissions(
                         | Permissions::MUTE_MEMBERS
                         | Permissions::DEAFEN_MEMBERS,
                     deny: Permissions::empty(),
-                    kind: PermissionOverwriteType::Role(ORGANISER_ID),
+                    kind: PermissionOverwriteType::Role(guild_config.organiser_role_id),
                 },
             ];
             for &host_id in discord_host_ids {
@@ -892,9 +1485,32 @@ async fn sync_channel_permissions(
             permission_overwrites
         }
     };
+    if plan.is_dry_run() {
+        // Only plan the overwrites which differ from the current ones
+        let current_overwrites = match discord_api.channel(channel_id).await? {
+            Some(channel) => channel.permission_overwrites,
+            None => vec![],
+        };
+        for permission_overwrite in permission_overwrites {
+            let is_current = current_overwrites.iter().any(|current| {
+                current.kind == permission_overwrite.kind
+                    && current.allow == permission_overwrite.allow
+                    && current.deny == permission_overwrite.deny
+            });
+            if !is_current {
+                plan.push(PlannedChange::SetPermissionOverwrite {
+                    channel_id: Some(channel_id),
+                    kind: permission_overwrite.kind,
+                    allow: permission_overwrite.allow,
+                    deny: permission_overwrite.deny,
+                });
+            }
+        }
+        return Ok(());
+    }
     for permission_overwrite in permission_overwrites {
-        channel_id
-            .create_permission(discord_api.http(), permission_overwrite)
+        discord_api
+            .create_permission(channel_id, permission_overwrite)
             .await?;
     }
     Ok(())
@@ -908,7 +1524,9 @@ async fn sync_role_assignments_permissions(
     voice_channel_id: Option<ChannelId>,
     user_role: RoleId,
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Check whether any users have manually removed roles and don't add them back
     // Don't automatically assign the user role to user that have been
@@ -935,37 +1553,51 @@ async fn sync_role_assignments_permissions(
         if ignore_discord_user_ids.contains(&user_id) {
             continue;
         }
-        match user_id.to_user(discord_api).await {
-            Ok(user) => match user.has_role(discord_api, GUILD_ID, user_role).await {
-                Ok(has_role) => {
-                    if !has_role {
-                        match discord_api
-                            .http()
-                            .add_member_role(
-                                GUILD_ID,
-                                user_id,
-                                user_role,
-                                Some("Automatic role assignment due to event participation"),
+        match discord_api
+            .member_has_role(guild_config.guild_id, user_id, user_role)
+            .await
+        {
+            Ok(has_role) => {
+                if !has_role && plan.is_dry_run() {
+                    plan.push(PlannedChange::AssignRole {
+                        user_id,
+                        role_id: Some(user_role),
+                    });
+                } else if !has_role {
+                    match discord_api
+                        .add_member_role(
+                            guild_config.guild_id,
+                            user_id,
+                            user_role,
+                            Some("Automatic role assignment due to event participation"),
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            println!("Assigned user {} to role {}", user_id, user_role);
+                            newly_added_user_ids.push(user_id);
+                            crate::audit::record(
+                                crate::audit::Entry::new(
+                                    crate::audit::Action::RoleAssigned,
+                                    crate::audit::Target::User(user_id),
+                                )
+                                .channel(channel_id)
+                                .after(serde_json::json!({ "role_id": user_role.get() })),
+                                db_connection,
                             )
-                            .await
-                        {
-                            Ok(_) => {
-                                println!("Assigned user {} to role {}", user_id, user_role);
-                                newly_added_user_ids.push(user_id);
-                            }
-                            Err(err) => eprintln!(
-                                "Could not assign user {} to role {}: {}",
-                                user_id, user_role, err
-                            ),
+                            .await;
                         }
+                        Err(err) => eprintln!(
+                            "Could not assign user {} to role {}: {}",
+                            user_id, user_role, err
+                        ),
                     }
                 }
-                Err(err) => eprintln!(
-                    "Could not figure out whether the user {} already has role {}: {}",
-                    user.id, user_role, err
-                ),
-            },
-            Err(err) => eprintln!("Could not find the user {}: {}", user_id, err),
+            }
+            Err(err) => eprintln!(
+                "Could not figure out whether the user {} already has role {}: {}",
+                user_id, user_role, err
+            ),
         }
     }
     // Assign direct permissions to hosts
@@ -975,9 +1607,22 @@ async fn sync_role_assignments_permissions(
             continue;
         }
         // Assign text channel permissions
-        let new_permissions = Permissions::VIEW_CHANNEL
-            | Permissions::MENTION_EVERYONE
-            | Permissions::MANAGE_MESSAGES;
+        let new_permissions = HOST_TEXT_CHANNEL_PERMISSIONS;
+        if plan.is_dry_run() {
+            plan_channel_user_permissions(channel_id, host_id, new_permissions, discord_api, plan)
+                .await?;
+            if let Some(voice_channel_id) = voice_channel_id {
+                plan_channel_user_permissions(
+                    voice_channel_id,
+                    host_id,
+                    HOST_VOICE_CHANNEL_PERMISSIONS,
+                    discord_api,
+                    plan,
+                )
+                .await?;
+            }
+            continue;
+        }
         match crate::discord::add_channel_user_permissions(
             discord_api,
             channel_id,
@@ -989,6 +1634,16 @@ async fn sync_role_assignments_permissions(
             Ok(true) => {
                 println!("Assigned user {} host permissions in text channel", host_id);
                 newly_added_host_ids.push(host_id);
+                crate::audit::record(
+                    crate::audit::Entry::new(
+                        crate::audit::Action::HostPermissionsAdded,
+                        crate::audit::Target::User(host_id),
+                    )
+                    .channel(channel_id)
+                    .after(serde_json::json!({ "permissions": new_permissions.bits() })),
+                    db_connection,
+                )
+                .await;
             }
             Err(err) => eprintln!(
                 "Could not assign user {} host permissions in text channel:\n{:#?}",
@@ -998,17 +1653,11 @@ async fn sync_role_assignments_permissions(
         }
         // Also assign rights in the possibly existing voice channel
         if let Some(voice_channel_id) = voice_channel_id {
-            let new_permissions = Permissions::VIEW_CHANNEL
-                | Permissions::CONNECT
-                | Permissions::MUTE_MEMBERS
-                | Permissions::DEAFEN_MEMBERS
-                | Permissions::MOVE_MEMBERS
-                | Permissions::PRIORITY_SPEAKER;
             match crate::discord::add_channel_user_permissions(
                 discord_api,
                 voice_channel_id,
                 host_id,
-                new_permissions,
+                HOST_VOICE_CHANNEL_PERMISSIONS,
             )
             .await
             {
@@ -1030,10 +1679,7 @@ async fn sync_role_assignments_permissions(
     if !newly_added_host_ids.is_empty() {
         let message_builder = CreateMessage::new()
             .content(crate::strings::CHANNEL_ADDED_HOSTS(&newly_added_host_ids));
-        if let Err(err) = channel_id
-            .send_message(&discord_api.http, message_builder)
-            .await
-        {
+        if let Err(err) = discord_api.send_message(channel_id, message_builder).await {
             eprintln!(
                 "Could not announce new hosts in channel {}:\n{:#?}",
                 channel_id, err
@@ -1043,10 +1689,7 @@ async fn sync_role_assignments_permissions(
     if !newly_added_user_ids.is_empty() {
         let message_builder = CreateMessage::new()
             .content(crate::strings::CHANNEL_ADDED_PLAYERS(&newly_added_user_ids));
-        if let Err(err) = channel_id
-            .send_message(&discord_api.http, message_builder)
-            .await
-        {
+        if let Err(err) = discord_api.send_message(channel_id, message_builder).await {
             eprintln!(
                 "Could not announce new players in channel {}:\n{:#?}",
                 channel_id, err
@@ -1056,10 +1699,37 @@ async fn sync_role_assignments_permissions(
     Ok(())
 }
 
+// Dry run version of add_channel_user_permissions
+async fn plan_channel_user_permissions(
+    channel_id: ChannelId,
+    user_id: UserId,
+    permissions: Permissions,
+    discord_api: &impl DiscordApi,
+    plan: &mut SyncPlan,
+) -> Result<(), crate::meetup::Error> {
+    let missing_permissions = crate::discord::missing_channel_user_permissions(
+        discord_api,
+        channel_id,
+        user_id,
+        permissions,
+    )
+    .await?;
+    if !missing_permissions.is_empty() {
+        plan.push(PlannedChange::AddChannelUserPermissions {
+            channel_id: Some(channel_id),
+            user_id,
+            permissions: missing_permissions,
+        });
+    }
+    Ok(())
+}
+
 async fn sync_game_master_role(
     event_series_id: db::EventSeriesId,
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Find all Discord users that are a host for any of the events in this series
     let discord_host_ids = sqlx::query!(
@@ -1077,34 +1747,55 @@ async fn sync_game_master_role(
     .await?;
     // Assign the Game Master role to the hosts
     for host_id in discord_host_ids {
-        match host_id.to_user(discord_api).await {
-            Ok(user) => match user.has_role(discord_api, GUILD_ID, GAME_MASTER_ID).await {
-                Ok(has_role) => {
-                    if !has_role {
-                        match discord_api
-                            .http()
-                            .add_member_role(
-                                GUILD_ID,
-                                host_id,
-                                GAME_MASTER_ID,
-                                Some("Automatic role assignment due to being a game master"),
+        match discord_api
+            .member_has_role(
+                guild_config.guild_id,
+                host_id,
+                guild_config.game_master_role_id,
+            )
+            .await
+        {
+            Ok(has_role) => {
+                if !has_role && plan.is_dry_run() {
+                    plan.push(PlannedChange::AssignRole {
+                        user_id: host_id,
+                        role_id: Some(guild_config.game_master_role_id),
+                    });
+                } else if !has_role {
+                    match discord_api
+                        .add_member_role(
+                            guild_config.guild_id,
+                            host_id,
+                            guild_config.game_master_role_id,
+                            Some("Automatic role assignment due to being a game master"),
+                        )
+                        .await
+                    {
+                        Ok(_) => {
+                            println!("Assigned user {} to the game master role", host_id);
+                            crate::audit::record(
+                                crate::audit::Entry::new(
+                                    crate::audit::Action::RoleAssigned,
+                                    crate::audit::Target::User(host_id),
+                                )
+                                .after(serde_json::json!({
+                                    "role_id": guild_config.game_master_role_id.get()
+                                })),
+                                db_connection,
                             )
-                            .await
-                        {
-                            Ok(_) => println!("Assigned user {} to the game master role", host_id),
-                            Err(err) => eprintln!(
-                                "Could not assign user {} to the game master role: {}",
-                                host_id, err
-                            ),
+                            .await;
                         }
+                        Err(err) => eprintln!(
+                            "Could not assign user {} to the game master role: {}",
+                            host_id, err
+                        ),
                     }
                 }
-                Err(err) => eprintln!(
-                    "Could not figure out whether the user {} already has the game master role: {}",
-                    user.id, err
-                ),
-            },
-            Err(err) => eprintln!("Could not find the host user {}: {}", host_id, err),
+            }
+            Err(err) => eprintln!(
+                "Could not figure out whether the user {} already has the game master role: {}",
+                host_id, err
+            ),
         }
     }
     Ok(())
@@ -1113,7 +1804,8 @@ async fn sync_game_master_role(
 async fn sync_channel_topic(
     channel_id: ChannelId,
     next_event: &db::Event,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Sync the topic
     let topic = match &next_event.meetup_event {
@@ -1126,17 +1818,16 @@ async fn sync_channel_topic(
                 .format("%d.%m.%Y %H:%M")
         ),
     };
-    let channel = channel_id.to_channel(discord_api).await?;
-    if let serenity::model::channel::Channel::Guild(channel) = channel {
+    if let Some(channel) = discord_api.channel(channel_id).await? {
         let topic_needs_update = if let Some(current_topic) = channel.topic {
             current_topic != topic
         } else {
             true
         };
-        if topic_needs_update {
-            channel_id
-                .edit(&discord_api.http, EditChannel::new().topic(topic))
-                .await?;
+        if topic_needs_update && plan.is_dry_run() {
+            plan.push(PlannedChange::SetTopic { channel_id, topic });
+        } else if topic_needs_update {
+            discord_api.set_channel_topic(channel_id, &topic).await?;
         }
     }
     Ok(())
@@ -1147,8 +1838,11 @@ async fn sync_channel_category(
     channel_type: ChannelType,
     next_event: &db::Event,
     channel_id: ChannelId,
+    category_tracker: &tokio::sync::Mutex<CategoryTracker>,
     db_connection: &sqlx::PgPool,
-    discord_api: &super::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    plan: &mut SyncPlan,
 ) -> Result<(), crate::meetup::Error> {
     // Sync the category
     let event_series_type = sqlx::query_scalar!(
@@ -1164,45 +1858,58 @@ async fn sync_channel_category(
     };
     match channel_type {
         ChannelType::Text => match event_series_type.as_str() {
-            "campaign" => categories.extend_from_slice(CAMPAIGN_CATEGORY_IDS),
-            "adventure" => categories.extend_from_slice(ONE_SHOT_CATEGORY_IDS),
+            "campaign" => categories.extend_from_slice(&guild_config.campaign_category_ids),
+            "adventure" => categories.extend_from_slice(&guild_config.one_shot_category_ids),
             _ => {
                 eprintln!(
                     "Event series {} does not have a type of 'campaign' or 'adventure'",
                     series_id.0
                 );
-                categories.extend_from_slice(CAMPAIGN_CATEGORY_IDS)
+                categories.extend_from_slice(&guild_config.campaign_category_ids)
             }
         },
-        ChannelType::Voice => categories.extend_from_slice(VOICE_CHANNELS_CATEGORY_IDS),
+        ChannelType::Voice => {
+            categories.extend_from_slice(&guild_config.voice_channel_category_ids)
+        }
     }
-    let channel = channel_id.to_channel(discord_api).await?;
-    if let serenity::model::channel::Channel::Guild(channel) = channel {
+    // Full categories overflow into numbered overflow categories
+    let categories = with_overflow_categories(&categories, db_connection).await?;
+    if let Some(channel) = discord_api.channel(channel_id).await? {
         let category_needs_update = match channel.parent_id {
             Some(channel_category) => {
                 if let Some(special_category) = next_event.discord_category {
                     special_category != channel_category
+                        && !with_overflow_categories(&[special_category], db_connection)
+                            .await?
+                            .contains(&channel_category)
                 } else {
                     !categories.contains(&channel_category)
                 }
             }
             None => true,
         };
-        if category_needs_update {
-            // Try the categories in order and put the channel in the first
-            // one that works. Meetup has an undocumented limit of 50 channels
-            // per category, so an error will be returned if the category is full.
-            for category in categories {
-                if let Ok(_) = channel_id
-                    .edit(
-                        &discord_api.http,
-                        EditChannel::new().category(Some(category)),
-                    )
-                    .await
-                {
-                    break;
+        // Concurrent series syncs must not fill the same free category slot
+        let mut category_tracker = category_tracker.lock().await;
+        if category_needs_update && plan.is_dry_run() {
+            if let Some(&category_id) = categories.first() {
+                if category_tracker.find_free(&categories).is_none() {
+                    plan.push(PlannedChange::CreateOverflowCategory { category_id });
                 }
             }
+            plan.push(PlannedChange::MoveToCategory {
+                channel_id,
+                category_ids: categories,
+            });
+        } else if category_needs_update && !categories.is_empty() {
+            // Put the channel in the first category that still has room.
+            // Discord allows at most 50 channels per category.
+            let category_id = category_tracker
+                .find_or_create_free(&categories, db_connection, discord_api)
+                .await?;
+            discord_api
+                .set_channel_category(channel_id, category_id)
+                .await?;
+            category_tracker.moved(channel_id, category_id);
         }
     }
     Ok(())
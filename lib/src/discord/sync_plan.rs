@@ -0,0 +1,320 @@
+use std::fmt;
+
+use serenity::model::{
+    channel::PermissionOverwriteType,
+    id::{ChannelId, GuildId, RoleId, ScheduledEventId, UserId},
+    permissions::Permissions,
+};
+
+use super::sync::ChannelType;
+use crate::db;
+
+// A change that the Discord sync would make. Channels and roles that don't
+// exist yet (and would be created by the sync) have no ID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PlannedChange {
+    AssignGuild {
+        series_id: db::EventSeriesId,
+        guild_id: GuildId,
+    },
+    UpgradeToCampaign {
+        series_id: db::EventSeriesId,
+    },
+    SetSeriesCategory {
+        series_id: db::EventSeriesId,
+        category_id: ChannelId,
+    },
+    // The channel is stored in the database but doesn't exist on Discord anymore
+    ForgetMissingChannel {
+        series_id: db::EventSeriesId,
+        channel_id: ChannelId,
+    },
+    CreateChannel {
+        series_id: db::EventSeriesId,
+        channel_type: ChannelType,
+        name: String,
+    },
+    // The role is stored in the database but doesn't exist on Discord anymore
+    ForgetMissingRole {
+        series_id: db::EventSeriesId,
+        role_id: RoleId,
+    },
+    CreateRole {
+        series_id: db::EventSeriesId,
+        name: String,
+        is_host_role: bool,
+    },
+    SetPermissionOverwrite {
+        channel_id: Option<ChannelId>,
+        kind: PermissionOverwriteType,
+        allow: Permissions,
+        deny: Permissions,
+    },
+    AssignRole {
+        user_id: UserId,
+        role_id: Option<RoleId>,
+    },
+    AddChannelUserPermissions {
+        channel_id: Option<ChannelId>,
+        user_id: UserId,
+        permissions: Permissions,
+    },
+    SetTopic {
+        channel_id: ChannelId,
+        topic: String,
+    },
+    MoveToCategory {
+        channel_id: ChannelId,
+        category_ids: Vec<ChannelId>,
+    },
+    CreateOverflowCategory {
+        category_id: ChannelId,
+    },
+    PostEventAnnouncement {
+        event_id: db::EventId,
+    },
+    AnnounceEventCancellation {
+        event_id: db::EventId,
+    },
+    PostSeriesInfo {
+        series_id: db::EventSeriesId,
+        channel_id: ChannelId,
+    },
+    CreateScheduledEvent {
+        event_id: db::EventId,
+        name: String,
+    },
+    UpdateScheduledEvent {
+        event_id: db::EventId,
+        scheduled_event_id: ScheduledEventId,
+    },
+    DeleteScheduledEvent {
+        event_id: db::EventId,
+        scheduled_event_id: ScheduledEventId,
+    },
+}
+
+fn format_channel(channel_id: &Option<ChannelId>) -> String {
+    match channel_id {
+        Some(channel_id) => format!("channel {}", channel_id),
+        None => "the new channel".to_string(),
+    }
+}
+
+fn format_permissions(permissions: &Permissions) -> String {
+    if permissions.is_empty() {
+        "nothing".to_string()
+    } else {
+        permissions.get_permission_names().join(", ")
+    }
+}
+
+fn format_overwrite_kind(kind: &PermissionOverwriteType) -> String {
+    match kind {
+        PermissionOverwriteType::Member(user_id) => format!("user {}", user_id),
+        PermissionOverwriteType::Role(role_id) => format!("role {}", role_id),
+        _ => "unknown target".to_string(),
+    }
+}
+
+impl fmt::Display for PlannedChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PlannedChange::AssignGuild {
+                series_id,
+                guild_id,
+            } => write!(
+                f,
+                "Assign event series {} to guild {}",
+                series_id.0, guild_id
+            ),
+            PlannedChange::UpgradeToCampaign { series_id } => {
+                write!(f, "Upgrade event series {} to a campaign", series_id.0)
+            }
+            PlannedChange::SetSeriesCategory {
+                series_id,
+                category_id,
+            } => write!(
+                f,
+                "Set the category of event series {} to {}",
+                series_id.0, category_id
+            ),
+            PlannedChange::ForgetMissingChannel {
+                series_id,
+                channel_id,
+            } => write!(
+                f,
+                "Forget channel {} of event series {} which doesn't exist on Discord anymore",
+                channel_id, series_id.0
+            ),
+            PlannedChange::CreateChannel {
+                series_id,
+                channel_type,
+                name,
+            } => write!(
+                f,
+                "Create {} channel \"{}\" for event series {}",
+                match channel_type {
+                    ChannelType::Text => "text",
+                    ChannelType::Voice => "voice",
+                },
+                name,
+                series_id.0
+            ),
+            PlannedChange::ForgetMissingRole { series_id, role_id } => write!(
+                f,
+                "Forget role {} of event series {} which doesn't exist on Discord anymore",
+                role_id, series_id.0
+            ),
+            PlannedChange::CreateRole {
+                series_id,
+                name,
+                is_host_role,
+            } => write!(
+                f,
+                "Create {}role \"{}\" for event series {}",
+                if *is_host_role { "host " } else { "" },
+                name,
+                series_id.0
+            ),
+            PlannedChange::SetPermissionOverwrite {
+                channel_id,
+                kind,
+                allow,
+                deny,
+            } => write!(
+                f,
+                "Set the permissions of {} in {} to allow {} and deny {}",
+                format_overwrite_kind(kind),
+                format_channel(channel_id),
+                format_permissions(allow),
+                format_permissions(deny)
+            ),
+            PlannedChange::AssignRole { user_id, role_id } => match role_id {
+                Some(role_id) => write!(f, "Assign role {} to user {}", role_id, user_id),
+                None => write!(f, "Assign the new role to user {}", user_id),
+            },
+            PlannedChange::AddChannelUserPermissions {
+                channel_id,
+                user_id,
+                permissions,
+            } => write!(
+                f,
+                "Grant user {} {} in {}",
+                user_id,
+                format_permissions(permissions),
+                format_channel(channel_id)
+            ),
+            PlannedChange::SetTopic { channel_id, topic } => {
+                write!(
+                    f,
+                    "Set the topic of channel {} to \"{}\"",
+                    channel_id, topic
+                )
+            }
+            PlannedChange::MoveToCategory {
+                channel_id,
+                category_ids,
+            } => write!(
+                f,
+                "Move channel {} to the first available category of {}",
+                channel_id,
+                itertools::join(category_ids, ", ")
+            ),
+            PlannedChange::CreateOverflowCategory { category_id } => write!(
+                f,
+                "Create an overflow category for the full category {}",
+                category_id
+            ),
+            PlannedChange::PostEventAnnouncement { event_id } => {
+                write!(f, "Post the announcement of event {}", event_id.0)
+            }
+            PlannedChange::AnnounceEventCancellation { event_id } => {
+                write!(f, "Announce the cancellation of event {}", event_id.0)
+            }
+            PlannedChange::PostSeriesInfo {
+                series_id,
+                channel_id,
+            } => write!(
+                f,
+                "Post and pin the info message of series {} in channel {}",
+                series_id.0, channel_id
+            ),
+            PlannedChange::CreateScheduledEvent { event_id, name } => write!(
+                f,
+                "Create the Discord scheduled event \"{}\" for event {}",
+                name, event_id.0
+            ),
+            PlannedChange::UpdateScheduledEvent {
+                event_id,
+                scheduled_event_id,
+            } => write!(
+                f,
+                "Update the Discord scheduled event {} of event {}",
+                scheduled_event_id, event_id.0
+            ),
+            PlannedChange::DeleteScheduledEvent {
+                event_id,
+                scheduled_event_id,
+            } => write!(
+                f,
+                "Delete the Discord scheduled event {} of event {}",
+                scheduled_event_id, event_id.0
+            ),
+        }
+    }
+}
+
+// In a dry run, the Discord sync collects the changes it would make in a plan
+// instead of performing them
+#[derive(Debug)]
+pub struct SyncPlan {
+    dry_run: bool,
+    changes: Vec<PlannedChange>,
+    // Event series which could not be planned and the reason why
+    errors: Vec<(db::EventSeriesId, String)>,
+}
+
+impl SyncPlan {
+    pub fn apply() -> Self {
+        SyncPlan {
+            dry_run: false,
+            changes: vec![],
+            errors: vec![],
+        }
+    }
+
+    pub fn dry_run() -> Self {
+        SyncPlan {
+            dry_run: true,
+            changes: vec![],
+            errors: vec![],
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    pub(crate) fn push(&mut self, change: PlannedChange) {
+        self.changes.push(change);
+    }
+
+    pub(crate) fn push_error(&mut self, series_id: db::EventSeriesId, error: String) {
+        self.errors.push((series_id, error));
+    }
+
+    // Appends the changes and errors of another plan
+    pub(crate) fn extend(&mut self, other: SyncPlan) {
+        self.changes.extend(other.changes);
+        self.errors.extend(other.errors);
+    }
+
+    pub fn changes(&self) -> &[PlannedChange] {
+        &self.changes
+    }
+
+    pub fn errors(&self) -> &[(db::EventSeriesId, String)] {
+        &self.errors
+    }
+}
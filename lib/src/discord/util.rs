@@ -45,8 +45,9 @@ pub async fn say_in_event_channel(
 pub async fn say_in_bot_alerts_channel(
     message: &str,
     discord_cache_http: &super::CacheAndHttp,
+    guild_config: &super::config::GuildConfig,
 ) -> Result<(), crate::BoxedError> {
-    if let Some(channel_id) = super::sync::ids::BOT_ALERTS_CHANNEL_ID {
+    if let Some(channel_id) = guild_config.bot_alerts_channel_id {
         channel_id
             .say(&discord_cache_http.http, message)
             .await
@@ -84,3 +85,12 @@ pub async fn say_in_bot_alerts_channel(
 //     while !remaining.is_empty() {}
 //     parts
 // }
+
+// The message as it is sent to Discord, to compare it with what was last
+// posted. Unlike a hash, this stays comparable across Rust versions.
+pub(crate) fn rendered_content(
+    message: &serenity::builder::EditMessage,
+) -> Result<String, crate::meetup::Error> {
+    let json = serde_json::to_string(message).map_err(simple_error::SimpleError::from)?;
+    Ok(json)
+}
@@ -1,5 +1,5 @@
 use chrono::DateTime;
-use serenity::all::ChannelId;
+use serenity::all::{ChannelId, UserId};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum EndAdventureResult {
@@ -12,6 +12,7 @@ pub enum EndAdventureResult {
 
 pub async fn end_adventure(
     channel_id: ChannelId,
+    actor: UserId,
     db_connection: &mut sqlx::Transaction<'_, sqlx::Postgres>,
 ) -> Result<EndAdventureResult, crate::meetup::Error> {
     // Check whether this is a game channel
@@ -34,8 +35,9 @@ pub async fn end_adventure(
     if expiration_time > chrono::Utc::now() {
         return Ok(EndAdventureResult::NotYetExpired);
     }
-    // Schedule this channel for deletion
-    let new_deletion_time = chrono::Utc::now() + chrono::Duration::hours(8);
+    // Schedule this channel for deletion after the grace period of its series
+    let policy = crate::expiration_policy::for_text_channel(channel_id, db_connection).await?;
+    let new_deletion_time = chrono::Utc::now() + policy.deletion_grace();
     let current_deletion_time = sqlx::query_scalar!(
         r#"SELECT deletion_time FROM event_series_text_channel WHERE discord_id = $1"#,
         channel_id.get() as i64
@@ -87,6 +89,22 @@ pub async fn end_adventure(
             .await?;
         }
     }
+    crate::audit::record(
+        crate::audit::Entry::new(
+            crate::audit::Action::AdventureEnded,
+            crate::audit::Target::Channel(channel_id),
+        )
+        .actor(actor)
+        .before(serde_json::json!({
+            "deletion_time": current_deletion_time.map(|time| time.to_rfc3339()),
+        }))
+        .after(serde_json::json!({
+            "deletion_time": new_deletion_time.to_rfc3339(),
+            "voice_channel_id": voice_channel_id.map(|id| id.get()),
+        })),
+        &mut **db_connection,
+    )
+    .await;
     Ok(EndAdventureResult::NewlyMarkedForDeletion(
         new_deletion_time,
     ))
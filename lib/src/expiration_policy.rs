@@ -0,0 +1,187 @@
+use serenity::model::id::ChannelId;
+
+use crate::db;
+
+// When the game channels of an event series type expire, how often their hosts
+// get reminded to end the game and how long the channels are kept afterwards
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExpirationPolicy {
+    pub event_series_type: String,
+    // The channel expires this long after the last session
+    pub expiration_delay_hours: i32,
+    // The first reminder is sent this long after expiration
+    pub first_reminder_delay_hours: i32,
+    pub reminder_interval_hours: i32,
+    // None to keep reminding until the channel is deleted
+    pub max_reminders: Option<i32>,
+    // How long the channels are kept after "end adventure"
+    pub deletion_grace_hours: i32,
+    // Whether the text channel's messages are archived before deleting it
+    pub archive: bool,
+}
+
+impl ExpirationPolicy {
+    // Used for event series types without a policy of their own. These are
+    // the adventure settings.
+    pub fn default_for(event_series_type: &str) -> Self {
+        ExpirationPolicy {
+            event_series_type: event_series_type.to_string(),
+            expiration_delay_hours: 0,
+            first_reminder_delay_hours: 24,
+            reminder_interval_hours: 46,
+            max_reminders: None,
+            deletion_grace_hours: 8,
+            archive: true,
+        }
+    }
+
+    pub fn expiration_delay(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.expiration_delay_hours.into())
+    }
+
+    pub fn first_reminder_delay(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.first_reminder_delay_hours.into())
+    }
+
+    pub fn reminder_interval(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.reminder_interval_hours.into())
+    }
+
+    pub fn deletion_grace(&self) -> chrono::Duration {
+        chrono::Duration::hours(self.deletion_grace_hours.into())
+    }
+
+    // Whether another reminder may be sent after `num_reminders` reminders
+    pub fn may_remind(&self, num_reminders: i32) -> bool {
+        match self.max_reminders {
+            Some(max_reminders) => num_reminders < max_reminders,
+            None => true,
+        }
+    }
+}
+
+pub async fn for_series<'c>(
+    series_id: db::EventSeriesId,
+    db_connection: impl sqlx::PgExecutor<'c>,
+) -> Result<ExpirationPolicy, crate::meetup::Error> {
+    let row = sqlx::query!(
+        r#"SELECT event_series."type", policy.event_series_type AS "policy_type?", policy.expiration_delay_hours AS "expiration_delay_hours?", policy.first_reminder_delay_hours AS "first_reminder_delay_hours?", policy.reminder_interval_hours AS "reminder_interval_hours?", policy.max_reminders, policy.deletion_grace_hours AS "deletion_grace_hours?", policy.archive AS "archive?"
+        FROM event_series
+        LEFT JOIN channel_expiration_policy AS policy ON event_series."type" = policy.event_series_type
+        WHERE event_series.id = $1"#,
+        series_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(
+        match (
+            row.policy_type,
+            row.expiration_delay_hours,
+            row.first_reminder_delay_hours,
+            row.reminder_interval_hours,
+            row.deletion_grace_hours,
+            row.archive,
+        ) {
+            (
+                Some(event_series_type),
+                Some(expiration_delay_hours),
+                Some(first_reminder_delay_hours),
+                Some(reminder_interval_hours),
+                Some(deletion_grace_hours),
+                Some(archive),
+            ) => ExpirationPolicy {
+                event_series_type,
+                expiration_delay_hours,
+                first_reminder_delay_hours,
+                reminder_interval_hours,
+                max_reminders: row.max_reminders,
+                deletion_grace_hours,
+                archive,
+            },
+            _ => ExpirationPolicy::default_for(&row.r#type),
+        },
+    )
+}
+
+// The policy of the event series which the text channel belongs to. Channels
+// which were detached from their series get the adventure defaults.
+pub async fn for_text_channel(
+    channel_id: ChannelId,
+    db_connection: &mut sqlx::PgConnection,
+) -> Result<ExpirationPolicy, crate::meetup::Error> {
+    let series_id = sqlx::query_scalar!(
+        r#"SELECT id FROM event_series WHERE discord_text_channel_id = $1"#,
+        channel_id.get() as i64
+    )
+    .fetch_optional(&mut *db_connection)
+    .await?;
+    match series_id {
+        Some(series_id) => for_series(db::EventSeriesId(series_id), db_connection).await,
+        None => Ok(ExpirationPolicy::default_for("adventure")),
+    }
+}
+
+// The policies of all event series types, including the defaults of the types
+// without a policy of their own
+pub async fn list(
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<ExpirationPolicy>, crate::meetup::Error> {
+    let policies = sqlx::query!(
+        r#"SELECT event_series_type."type", policy.expiration_delay_hours AS "expiration_delay_hours?", policy.first_reminder_delay_hours AS "first_reminder_delay_hours?", policy.reminder_interval_hours AS "reminder_interval_hours?", policy.max_reminders, policy.deletion_grace_hours AS "deletion_grace_hours?", policy.archive AS "archive?"
+        FROM event_series_type
+        LEFT JOIN channel_expiration_policy AS policy ON event_series_type."type" = policy.event_series_type
+        ORDER BY event_series_type."type""#
+    )
+    .map(|row| {
+        let default = ExpirationPolicy::default_for(&row.r#type);
+        ExpirationPolicy {
+            expiration_delay_hours: row
+                .expiration_delay_hours
+                .unwrap_or(default.expiration_delay_hours),
+            first_reminder_delay_hours: row
+                .first_reminder_delay_hours
+                .unwrap_or(default.first_reminder_delay_hours),
+            reminder_interval_hours: row
+                .reminder_interval_hours
+                .unwrap_or(default.reminder_interval_hours),
+            max_reminders: row.max_reminders,
+            deletion_grace_hours: row
+                .deletion_grace_hours
+                .unwrap_or(default.deletion_grace_hours),
+            archive: row.archive.unwrap_or(default.archive),
+            event_series_type: row.r#type,
+        }
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(policies)
+}
+
+// Creates or replaces the policy of an event series type. Channel expiration
+// times are updated on the next run of the end of game task.
+pub async fn save(
+    policy: &ExpirationPolicy,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"INSERT INTO channel_expiration_policy (event_series_type, expiration_delay_hours, first_reminder_delay_hours, reminder_interval_hours, max_reminders, deletion_grace_hours, archive)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        ON CONFLICT (event_series_type) DO UPDATE SET
+            expiration_delay_hours = EXCLUDED.expiration_delay_hours,
+            first_reminder_delay_hours = EXCLUDED.first_reminder_delay_hours,
+            reminder_interval_hours = EXCLUDED.reminder_interval_hours,
+            max_reminders = EXCLUDED.max_reminders,
+            deletion_grace_hours = EXCLUDED.deletion_grace_hours,
+            archive = EXCLUDED.archive"#,
+        policy.event_series_type,
+        policy.expiration_delay_hours,
+        policy.first_reminder_delay_hours,
+        policy.reminder_interval_hours,
+        policy.max_reminders,
+        policy.deletion_grace_hours,
+        policy.archive
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
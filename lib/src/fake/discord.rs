@@ -0,0 +1,630 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use serenity::{
+    builder::{CreateMessage, EditMessage},
+    model::{
+        channel::{PermissionOverwrite, PermissionOverwriteType},
+        colour::Colour,
+        id::{ChannelId, GuildId, MessageId, RoleId, ScheduledEventId, UserId},
+    },
+};
+
+use super::not_found;
+use crate::discord::{
+    api::{
+        ChannelInfo, DiscordApi, MemberInfo, MessageInfo, RoleInfo, ScheduledEventInfo,
+        MESSAGES_PAGE_SIZE,
+    },
+    categories::MAX_CHANNELS_PER_CATEGORY,
+    sync::ChannelType,
+};
+
+// IDs of objects created by the fake start here, such that they don't clash
+// with the small IDs that tests like to use
+const FIRST_FAKE_ID: u64 = 1_000_000;
+
+#[derive(Debug, Clone)]
+pub struct FakeChannel {
+    pub info: ChannelInfo,
+    pub channel_type: ChannelType,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeMessage {
+    pub id: MessageId,
+    pub channel_id: ChannelId,
+    // None if the message was sent by the bot
+    pub author_id: Option<UserId>,
+    // The message as it would have been sent to Discord
+    pub json: serde_json::Value,
+    pub pinned: bool,
+}
+
+impl FakeMessage {
+    pub fn content(&self) -> Option<&str> {
+        self.json
+            .get("content")
+            .and_then(|content| content.as_str())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeScheduledEvent {
+    pub guild_id: GuildId,
+    pub info: ScheduledEventInfo,
+    pub interested_users: Vec<UserId>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeDiscordState {
+    pub channels: HashMap<ChannelId, FakeChannel>,
+    // Roles and members are not tracked per guild
+    pub roles: HashMap<RoleId, RoleInfo>,
+    pub members: HashMap<UserId, MemberInfo>,
+    pub messages: Vec<FakeMessage>,
+    pub scheduled_events: HashMap<ScheduledEventId, FakeScheduledEvent>,
+    next_id: u64,
+}
+
+impl FakeDiscordState {
+    fn next_id(&mut self) -> u64 {
+        self.next_id = self.next_id.max(FIRST_FAKE_ID) + 1;
+        self.next_id
+    }
+
+    fn channel_mut(
+        &mut self,
+        channel_id: ChannelId,
+    ) -> Result<&mut FakeChannel, crate::meetup::Error> {
+        self.channels
+            .get_mut(&channel_id)
+            .ok_or_else(|| not_found("channel", channel_id))
+    }
+
+    fn member_mut(&mut self, user_id: UserId) -> Result<&mut MemberInfo, crate::meetup::Error> {
+        self.members
+            .get_mut(&user_id)
+            .ok_or_else(|| not_found("member", user_id))
+    }
+
+    fn next_category_position(&self, guild_id: GuildId) -> u16 {
+        self.channels
+            .values()
+            .filter(|channel| channel.info.is_category && channel.info.guild_id == guild_id)
+            .count() as u16
+    }
+
+    pub fn channel_by_name(&self, name: &str) -> Option<&FakeChannel> {
+        self.channels
+            .values()
+            .find(|channel| channel.info.name == name)
+    }
+
+    pub fn role_by_name(&self, name: &str) -> Option<&RoleInfo> {
+        self.roles.values().find(|role| role.name == name)
+    }
+
+    pub fn messages_in(&self, channel_id: ChannelId) -> Vec<&FakeMessage> {
+        self.messages
+            .iter()
+            .filter(|message| message.channel_id == channel_id)
+            .collect()
+    }
+}
+
+// An in-memory Discord guild
+#[derive(Debug, Default)]
+pub struct FakeDiscord {
+    state: Mutex<FakeDiscordState>,
+}
+
+impl FakeDiscord {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A copy of the current state for assertions
+    pub fn state(&self) -> FakeDiscordState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn add_member(&self, user_id: UserId, name: &str) {
+        self.state.lock().unwrap().members.insert(
+            user_id,
+            MemberInfo {
+                user_id,
+                name: name.to_string(),
+                discriminator: None,
+                roles: vec![],
+            },
+        );
+    }
+
+    pub fn add_role(&self, role_id: RoleId, name: &str) {
+        self.state.lock().unwrap().roles.insert(
+            role_id,
+            RoleInfo {
+                id: role_id,
+                name: name.to_string(),
+            },
+        );
+    }
+
+    // A message posted by one of the members
+    pub fn add_message(
+        &self,
+        channel_id: ChannelId,
+        author_id: UserId,
+        content: &str,
+        pinned: bool,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let message = FakeMessage {
+            id: MessageId::new(state.next_id()),
+            channel_id,
+            author_id: Some(author_id),
+            json: serde_json::json!({ "content": content }),
+            pinned,
+        };
+        state.messages.push(message);
+    }
+
+    // A member clicking "Interested" on a scheduled event
+    pub fn add_interested_user(&self, scheduled_event_id: ScheduledEventId, user_id: UserId) {
+        if let Some(scheduled_event) = self
+            .state
+            .lock()
+            .unwrap()
+            .scheduled_events
+            .get_mut(&scheduled_event_id)
+        {
+            scheduled_event.interested_users.push(user_id);
+        }
+    }
+
+    pub fn add_category(&self, guild_id: GuildId, category_id: ChannelId, name: &str) {
+        let mut state = self.state.lock().unwrap();
+        let position = state.next_category_position(guild_id);
+        state.channels.insert(
+            category_id,
+            FakeChannel {
+                info: ChannelInfo {
+                    id: category_id,
+                    guild_id,
+                    name: name.to_string(),
+                    topic: None,
+                    parent_id: None,
+                    permission_overwrites: vec![],
+                    channel_type: None,
+                    is_category: true,
+                    position,
+                },
+                // Categories are never synced, so their type doesn't matter
+                channel_type: ChannelType::Text,
+            },
+        );
+    }
+}
+
+#[async_trait]
+impl DiscordApi for FakeDiscord {
+    async fn channel(
+        &self,
+        channel_id: ChannelId,
+    ) -> Result<Option<ChannelInfo>, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .channels
+            .get(&channel_id)
+            .map(|channel| channel.info.clone()))
+    }
+
+    async fn guild_channels(
+        &self,
+        guild_id: GuildId,
+    ) -> Result<HashMap<ChannelId, ChannelInfo>, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .channels
+            .values()
+            .filter(|channel| channel.info.guild_id == guild_id)
+            .map(|channel| (channel.info.id, channel.info.clone()))
+            .collect())
+    }
+
+    async fn create_channel(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        channel_type: ChannelType,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let info = ChannelInfo {
+            id: ChannelId::new(state.next_id()),
+            guild_id,
+            name: name.to_string(),
+            topic: None,
+            parent_id: None,
+            permission_overwrites,
+            channel_type: Some(channel_type),
+            is_category: false,
+            position: 0,
+        };
+        state.channels.insert(
+            info.id,
+            FakeChannel {
+                info: info.clone(),
+                channel_type,
+            },
+        );
+        Ok(info)
+    }
+
+    async fn create_category(
+        &self,
+        guild_id: GuildId,
+        name: &str,
+        permission_overwrites: Vec<PermissionOverwrite>,
+    ) -> Result<ChannelInfo, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        // Like Discord, put new categories at the end
+        let position = state.next_category_position(guild_id);
+        let info = ChannelInfo {
+            id: ChannelId::new(state.next_id()),
+            guild_id,
+            name: name.to_string(),
+            topic: None,
+            parent_id: None,
+            permission_overwrites,
+            channel_type: None,
+            is_category: true,
+            position,
+        };
+        state.channels.insert(
+            info.id,
+            FakeChannel {
+                info: info.clone(),
+                // Categories are never synced, so their type doesn't matter
+                channel_type: ChannelType::Text,
+            },
+        );
+        Ok(info)
+    }
+
+    async fn delete_channel(
+        &self,
+        channel_id: ChannelId,
+        _audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.channels.remove(&channel_id);
+        Ok(())
+    }
+
+    async fn set_channel_topic(
+        &self,
+        channel_id: ChannelId,
+        topic: &str,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.channel_mut(channel_id)?.info.topic = Some(topic.to_string());
+        Ok(())
+    }
+
+    async fn set_channel_category(
+        &self,
+        channel_id: ChannelId,
+        category_id: ChannelId,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        if !state.channels.contains_key(&category_id) {
+            return Err(not_found("category", category_id));
+        }
+        let num_channels = state
+            .channels
+            .values()
+            .filter(|channel| channel.info.parent_id == Some(category_id))
+            .count();
+        if num_channels >= MAX_CHANNELS_PER_CATEGORY {
+            return Err(simple_error::SimpleError::new(format!(
+                "Fake: category {} is full",
+                category_id
+            ))
+            .into());
+        }
+        state.channel_mut(channel_id)?.info.parent_id = Some(category_id);
+        Ok(())
+    }
+
+    async fn set_channel_positions(
+        &self,
+        _guild_id: GuildId,
+        positions: Vec<(ChannelId, u16)>,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        for (channel_id, position) in positions {
+            state.channel_mut(channel_id)?.info.position = position;
+        }
+        Ok(())
+    }
+
+    async fn create_permission(
+        &self,
+        channel_id: ChannelId,
+        permission_overwrite: PermissionOverwrite,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let overwrites = &mut state.channel_mut(channel_id)?.info.permission_overwrites;
+        overwrites.retain(|overwrite| overwrite.kind != permission_overwrite.kind);
+        overwrites.push(permission_overwrite);
+        Ok(())
+    }
+
+    async fn delete_permission(
+        &self,
+        channel_id: ChannelId,
+        kind: PermissionOverwriteType,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let overwrites = &mut state.channel_mut(channel_id)?.info.permission_overwrites;
+        overwrites.retain(|overwrite| overwrite.kind != kind);
+        Ok(())
+    }
+
+    async fn role_exists(
+        &self,
+        _guild_id: GuildId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error> {
+        Ok(self.state.lock().unwrap().roles.contains_key(&role_id))
+    }
+
+    async fn guild_roles(
+        &self,
+        _guild_id: GuildId,
+    ) -> Result<HashMap<RoleId, RoleInfo>, crate::meetup::Error> {
+        Ok(self.state.lock().unwrap().roles.clone())
+    }
+
+    async fn create_role(
+        &self,
+        _guild_id: GuildId,
+        name: &str,
+        _colour: Colour,
+    ) -> Result<RoleInfo, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let role = RoleInfo {
+            id: RoleId::new(state.next_id()),
+            name: name.to_string(),
+        };
+        state.roles.insert(role.id, role.clone());
+        Ok(role)
+    }
+
+    async fn delete_role(
+        &self,
+        _guild_id: GuildId,
+        role_id: RoleId,
+        _audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.roles.remove(&role_id);
+        for member in state.members.values_mut() {
+            member.roles.retain(|&id| id != role_id);
+        }
+        Ok(())
+    }
+
+    async fn user_name(&self, user_id: UserId) -> Result<String, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(state.member_mut(user_id)?.name.clone())
+    }
+
+    async fn guild_members(
+        &self,
+        _guild_id: GuildId,
+    ) -> Result<Vec<MemberInfo>, crate::meetup::Error> {
+        Ok(self
+            .state
+            .lock()
+            .unwrap()
+            .members
+            .values()
+            .cloned()
+            .collect())
+    }
+
+    async fn member_has_role(
+        &self,
+        _guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+    ) -> Result<bool, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        Ok(state.member_mut(user_id)?.roles.contains(&role_id))
+    }
+
+    async fn add_member_role(
+        &self,
+        _guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        _audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        if !state.roles.contains_key(&role_id) {
+            return Err(not_found("role", role_id));
+        }
+        let member = state.member_mut(user_id)?;
+        if !member.roles.contains(&role_id) {
+            member.roles.push(role_id);
+        }
+        Ok(())
+    }
+
+    async fn remove_member_role(
+        &self,
+        _guild_id: GuildId,
+        user_id: UserId,
+        role_id: RoleId,
+        _audit_log_reason: Option<&str>,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.member_mut(user_id)?.roles.retain(|&id| id != role_id);
+        Ok(())
+    }
+
+    async fn send_message(
+        &self,
+        channel_id: ChannelId,
+        message: CreateMessage,
+    ) -> Result<MessageId, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.channel_mut(channel_id)?;
+        let message = FakeMessage {
+            id: MessageId::new(state.next_id()),
+            channel_id,
+            author_id: None,
+            json: serde_json::to_value(&message).map_err(simple_error::SimpleError::from)?,
+            pinned: false,
+        };
+        let message_id = message.id;
+        state.messages.push(message);
+        Ok(message_id)
+    }
+
+    async fn edit_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+        message: EditMessage,
+    ) -> Result<bool, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let json = serde_json::to_value(&message).map_err(simple_error::SimpleError::from)?;
+        let existing_message = state
+            .messages
+            .iter_mut()
+            .find(|message| message.id == message_id && message.channel_id == channel_id);
+        match existing_message {
+            Some(existing_message) => {
+                existing_message.json = json;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    async fn pin_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let message = state
+            .messages
+            .iter_mut()
+            .find(|message| message.id == message_id && message.channel_id == channel_id)
+            .ok_or_else(|| not_found("message", message_id))?;
+        message.pinned = true;
+        Ok(())
+    }
+
+    async fn channel_messages_after(
+        &self,
+        channel_id: ChannelId,
+        after: Option<MessageId>,
+    ) -> Result<Vec<MessageInfo>, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.channel_mut(channel_id)?;
+        // Messages are stored in the order they were posted, and None sorts
+        // before all of them
+        Ok(state
+            .messages_in(channel_id)
+            .into_iter()
+            .filter(|message| after < Some(message.id))
+            .take(MESSAGES_PAGE_SIZE as usize)
+            .map(|message| {
+                let (author_id, author_name) = match message.author_id {
+                    Some(author_id) => (
+                        author_id,
+                        state
+                            .members
+                            .get(&author_id)
+                            .map(|member| member.name.clone())
+                            .unwrap_or_default(),
+                    ),
+                    None => (UserId::new(FIRST_FAKE_ID), "Bot".to_string()),
+                };
+                MessageInfo {
+                    id: message.id,
+                    author_id,
+                    author_name,
+                    timestamp: chrono::Utc::now(),
+                    edited_timestamp: None,
+                    content: message.content().unwrap_or_default().to_string(),
+                    attachments: vec![],
+                    pinned: message.pinned,
+                }
+            })
+            .collect())
+    }
+
+    async fn create_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<ScheduledEventId, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let id = ScheduledEventId::new(state.next_id());
+        state.scheduled_events.insert(
+            id,
+            FakeScheduledEvent {
+                guild_id,
+                info: scheduled_event.clone(),
+                interested_users: vec![],
+            },
+        );
+        Ok(id)
+    }
+
+    async fn edit_scheduled_event(
+        &self,
+        guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+        scheduled_event: &ScheduledEventInfo,
+    ) -> Result<bool, crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        match state.scheduled_events.get_mut(&scheduled_event_id) {
+            Some(existing_event) if existing_event.guild_id == guild_id => {
+                existing_event.info = scheduled_event.clone();
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_scheduled_event(
+        &self,
+        _guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        state.scheduled_events.remove(&scheduled_event_id);
+        Ok(())
+    }
+
+    async fn scheduled_event_interested_users(
+        &self,
+        _guild_id: GuildId,
+        scheduled_event_id: ScheduledEventId,
+    ) -> Result<Vec<UserId>, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        let scheduled_event = state
+            .scheduled_events
+            .get(&scheduled_event_id)
+            .ok_or_else(|| not_found("scheduled event", scheduled_event_id))?;
+        Ok(scheduled_event.interested_users.clone())
+    }
+}
@@ -0,0 +1,143 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+use futures::stream::{self, BoxStream, StreamExt};
+
+use crate::meetup::{
+    api::MeetupApi,
+    newapi::{
+        event_tickets_query, upcoming_events_query, AlphaNumericId, Error, EventStatus, NumericId,
+        Ticket, UpcomingEventDetails, ZonedDateTime,
+    },
+};
+
+#[derive(Debug, Default)]
+struct FakeMeetupState {
+    upcoming_events: Vec<UpcomingEventDetails>,
+    // Meetup user IDs of the attendees, by event ID
+    rsvps: HashMap<String, Vec<u64>>,
+}
+
+// A set of in-memory Meetup groups
+#[derive(Debug, Default)]
+pub struct FakeMeetup {
+    state: Mutex<FakeMeetupState>,
+}
+
+impl FakeMeetup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_upcoming_events(&self, upcoming_events: Vec<UpcomingEventDetails>) {
+        self.state.lock().unwrap().upcoming_events = upcoming_events;
+    }
+
+    pub fn set_rsvps(&self, event_id: &str, meetup_user_ids: &[u64]) {
+        self.state
+            .lock()
+            .unwrap()
+            .rsvps
+            .insert(event_id.to_string(), meetup_user_ids.to_vec());
+    }
+
+    // Cancelled events are still listed as upcoming events
+    pub fn cancel_event(&self, event_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        for event in &mut state.upcoming_events {
+            if event.id.0 == event_id {
+                event.status = Some(upcoming_events_query::EventStatus::CANCELLED);
+            }
+        }
+    }
+
+    pub fn delete_event(&self, event_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.upcoming_events.retain(|event| event.id.0 != event_id);
+        state.rsvps.remove(event_id);
+    }
+}
+
+// An online event in the specified group with the minimal set of details
+pub fn upcoming_event(
+    event_id: &str,
+    urlname: &str,
+    title: &str,
+    description: &str,
+    date_time: chrono::DateTime<chrono::Utc>,
+    host_meetup_ids: &[u64],
+) -> UpcomingEventDetails {
+    UpcomingEventDetails {
+        id: AlphaNumericId(event_id.to_string()),
+        title: Some(title.to_string()),
+        event_url: format!("https://www.meetup.com/{}/events/{}/", urlname, event_id),
+        short_url: format!("https://meetu.ps/e/{}", event_id),
+        description: Some(description.to_string()),
+        hosts: Some(
+            host_meetup_ids
+                .iter()
+                .map(|&id| {
+                    upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNodeHosts {
+                        id: NumericId(id),
+                    }
+                })
+                .collect(),
+        ),
+        date_time: ZonedDateTime(date_time),
+        max_tickets: 0,
+        going: 0,
+        is_online: true,
+        status: Some(upcoming_events_query::EventStatus::PUBLISHED),
+        rsvp_settings: None,
+        venue: None,
+        group: Some(
+            upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNodeGroup {
+                urlname: Some(urlname.to_string()),
+            },
+        ),
+    }
+}
+
+#[async_trait]
+impl MeetupApi for FakeMeetup {
+    fn get_upcoming_events_all_groups(&self) -> BoxStream<'_, Result<UpcomingEventDetails, Error>> {
+        let upcoming_events = self.state.lock().unwrap().upcoming_events.clone();
+        stream::iter(upcoming_events.into_iter().map(Ok)).boxed()
+    }
+
+    async fn get_tickets_vec(&self, event_id: String) -> Result<Vec<Ticket>, Error> {
+        let state = self.state.lock().unwrap();
+        if let Some(meetup_user_ids) = state.rsvps.get(&event_id) {
+            Ok(meetup_user_ids
+                .iter()
+                .map(|&id| Ticket {
+                    user: event_tickets_query::EventTicketsQueryEventTicketsEdgesNodeUser {
+                        id: NumericId(id),
+                    },
+                })
+                .collect())
+        } else if state
+            .upcoming_events
+            .iter()
+            .any(|event| event.id.0 == event_id)
+        {
+            Ok(vec![])
+        } else {
+            Err(Error::ResourceNotFound)
+        }
+    }
+
+    async fn get_event_status(&self, event_id: String) -> Result<Option<EventStatus>, Error> {
+        let state = self.state.lock().unwrap();
+        let event = state
+            .upcoming_events
+            .iter()
+            .find(|event| event.id.0 == event_id)
+            .ok_or(Error::ResourceNotFound)?;
+        if event.is_cancelled() {
+            Ok(Some(EventStatus::CANCELLED))
+        } else {
+            Ok(Some(EventStatus::PUBLISHED))
+        }
+    }
+}
@@ -0,0 +1,14 @@
+// In-memory fakes of Discord, Meetup, Stripe and Redis which allow running
+// the syncing tasks offline, e.g. in integration tests
+pub mod discord;
+pub mod meetup;
+pub mod redis;
+pub mod stripe;
+
+pub use self::{
+    discord::FakeDiscord, meetup::FakeMeetup, redis::FakeRedisConnection, stripe::FakeStripe,
+};
+
+fn not_found(what: &str, id: impl std::fmt::Display) -> crate::meetup::Error {
+    simple_error::SimpleError::new(format!("Fake: unknown {} {}", what, id)).into()
+}
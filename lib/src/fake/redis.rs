@@ -0,0 +1,92 @@
+use std::collections::{BTreeSet, HashMap};
+
+use ::redis::{aio::ConnectionLike, Arg, Cmd, ErrorKind, Pipeline, RedisFuture, Value};
+use futures::FutureExt;
+
+// An in-memory Redis connection which supports the set commands used by the
+// Discord sync to record orphaned channels and roles
+#[derive(Debug, Default, Clone)]
+pub struct FakeRedisConnection {
+    pub sets: HashMap<Vec<u8>, BTreeSet<Vec<u8>>>,
+}
+
+impl FakeRedisConnection {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // The members of the specified set as strings
+    pub fn set_members(&self, key: &str) -> Vec<String> {
+        self.sets
+            .get(key.as_bytes())
+            .map(|members| {
+                members
+                    .iter()
+                    .map(|member| String::from_utf8_lossy(member).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn execute(&mut self, cmd: &Cmd) -> ::redis::RedisResult<Value> {
+        let args: Vec<&[u8]> = cmd
+            .args_iter()
+            .filter_map(|arg| match arg {
+                Arg::Simple(arg) => Some(arg),
+                Arg::Cursor => None,
+            })
+            .collect();
+        let (name, key, values) = match args.as_slice() {
+            [name, key, values @ ..] => (name.to_ascii_uppercase(), key.to_vec(), values),
+            _ => return Err((ErrorKind::ClientError, "Fake: unsupported command").into()),
+        };
+        match name.as_slice() {
+            b"SADD" => {
+                let set = self.sets.entry(key).or_default();
+                let added = values
+                    .iter()
+                    .filter(|value| set.insert(value.to_vec()))
+                    .count();
+                Ok(Value::Int(added as i64))
+            }
+            b"SREM" => {
+                let set = self.sets.entry(key).or_default();
+                let removed = values.iter().filter(|value| set.remove(**value)).count();
+                Ok(Value::Int(removed as i64))
+            }
+            b"SMEMBERS" => Ok(Value::Array(
+                self.sets
+                    .get(&key)
+                    .into_iter()
+                    .flatten()
+                    .map(|member| Value::BulkString(member.clone()))
+                    .collect(),
+            )),
+            b"DEL" => Ok(Value::Int(self.sets.remove(&key).is_some() as i64)),
+            _ => Err((ErrorKind::ClientError, "Fake: unsupported command").into()),
+        }
+    }
+}
+
+impl ConnectionLike for FakeRedisConnection {
+    fn req_packed_command<'a>(&'a mut self, cmd: &'a Cmd) -> RedisFuture<'a, Value> {
+        let result = self.execute(cmd);
+        async move { result }.boxed()
+    }
+
+    fn req_packed_commands<'a>(
+        &'a mut self,
+        pipeline: &'a Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> RedisFuture<'a, Vec<Value>> {
+        let results: ::redis::RedisResult<Vec<Value>> =
+            pipeline.cmd_iter().map(|cmd| self.execute(cmd)).collect();
+        let result = results.map(|results| results.into_iter().skip(offset).take(count).collect());
+        async move { result }.boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        0
+    }
+}
@@ -0,0 +1,106 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use async_trait::async_trait;
+
+use super::not_found;
+use crate::stripe::StripeApi;
+
+#[derive(Debug, Clone, Default)]
+pub struct FakeStripeState {
+    pub subscriptions: Vec<stripe::Subscription>,
+    pub products: HashMap<String, stripe::Product>,
+    pub customers: HashMap<String, stripe::Customer>,
+}
+
+// An in-memory Stripe account
+#[derive(Debug, Default)]
+pub struct FakeStripe {
+    state: Mutex<FakeStripeState>,
+}
+
+impl FakeStripe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A copy of the current state for assertions
+    pub fn state(&self) -> FakeStripeState {
+        self.state.lock().unwrap().clone()
+    }
+
+    pub fn add_subscription(&self, subscription: stripe::Subscription) {
+        self.state.lock().unwrap().subscriptions.push(subscription);
+    }
+
+    pub fn add_product(&self, product: stripe::Product) {
+        self.state
+            .lock()
+            .unwrap()
+            .products
+            .insert(product.id.to_string(), product);
+    }
+
+    pub fn add_customer(&self, customer: stripe::Customer) {
+        self.state
+            .lock()
+            .unwrap()
+            .customers
+            .insert(customer.id.to_string(), customer);
+    }
+}
+
+#[async_trait]
+impl StripeApi for FakeStripe {
+    async fn list_active_subscriptions(
+        &self,
+    ) -> Result<Vec<stripe::Subscription>, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        Ok(state
+            .subscriptions
+            .iter()
+            .filter(|subscription| subscription.status == stripe::SubscriptionStatus::Active)
+            .cloned()
+            .collect())
+    }
+
+    async fn retrieve_product(
+        &self,
+        product_id: &stripe::ProductId,
+    ) -> Result<stripe::Product, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        state
+            .products
+            .get(product_id.as_str())
+            .cloned()
+            .ok_or_else(|| not_found("product", product_id))
+    }
+
+    async fn retrieve_customer(
+        &self,
+        customer_id: &stripe::CustomerId,
+    ) -> Result<stripe::Customer, crate::meetup::Error> {
+        let state = self.state.lock().unwrap();
+        state
+            .customers
+            .get(customer_id.as_str())
+            .cloned()
+            .ok_or_else(|| not_found("customer", customer_id))
+    }
+
+    async fn update_customer_metadata(
+        &self,
+        customer_id: &stripe::CustomerId,
+        metadata: stripe::Metadata,
+    ) -> Result<(), crate::meetup::Error> {
+        let mut state = self.state.lock().unwrap();
+        let customer = state
+            .customers
+            .get_mut(customer_id.as_str())
+            .ok_or_else(|| not_found("customer", customer_id))?;
+        customer
+            .metadata
+            .get_or_insert_with(Default::default)
+            .extend(metadata);
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use serenity::model::id::UserId;
+
+use crate::{db, meetup::connection::MeetupConnection};
+
+// Lets a host move or cancel one of the upcoming sessions of their series
+pub struct ManageSessionFlow {
+    pub id: u64,
+    pub event_series_id: db::EventSeriesId,
+    // The host who requested the flow
+    pub discord_id: UserId,
+}
+
+pub struct ManagedSession {
+    pub event_id: db::EventId,
+    pub title: String,
+    pub old_start_time: chrono::DateTime<chrono::Utc>,
+    // Only set if the session is published on Meetup
+    pub meetup_url: Option<String>,
+}
+
+impl ManageSessionFlow {
+    pub async fn new(
+        redis_connection: &mut redis::aio::Connection,
+        event_series_id: db::EventSeriesId,
+        discord_id: UserId,
+    ) -> Result<Self, crate::meetup::Error> {
+        let id = super::create_flow(
+            redis_connection,
+            "manage_session",
+            &[
+                ("event_series_id", event_series_id.0.into()),
+                ("discord_id", discord_id.get() as i64),
+            ],
+        )
+        .await?;
+        Ok(ManageSessionFlow {
+            id,
+            event_series_id,
+            discord_id,
+        })
+    }
+
+    pub async fn retrieve(
+        redis_connection: &mut redis::aio::Connection,
+        id: u64,
+    ) -> Result<Option<Self>, crate::meetup::Error> {
+        let (event_series_id, discord_id): (Option<i32>, Option<u64>) = super::retrieve_flow(
+            redis_connection,
+            "manage_session",
+            id,
+            &["event_series_id", "discord_id"],
+        )
+        .await?;
+        let flow = match (event_series_id, discord_id) {
+            (Some(event_series_id), Some(discord_id)) => Some(ManageSessionFlow {
+                id,
+                event_series_id: db::EventSeriesId(event_series_id),
+                discord_id: UserId::new(discord_id),
+            }),
+            _ => None,
+        };
+        Ok(flow)
+    }
+
+    pub async fn delete(
+        self,
+        redis_connection: &mut redis::aio::Connection,
+    ) -> Result<(), crate::meetup::Error> {
+        super::delete_flow(redis_connection, "manage_session", self.id).await?;
+        Ok(())
+    }
+
+    // Moves the session to a new time, on Meetup too if it is published there.
+    // Nothing is changed if Meetup refuses the change, since the next sync
+    // would revert it otherwise.
+    pub async fn reschedule(
+        self,
+        db_connection: &sqlx::PgPool,
+        redis_connection: &mut redis::aio::Connection,
+        meetup_connection: &MeetupConnection,
+        event_id: db::EventId,
+        date_time: chrono::DateTime<chrono::Utc>,
+        duration: chrono::Duration,
+    ) -> Result<ManagedSession, crate::meetup::Error> {
+        let (session, meetup_id) = self.session(event_id, db_connection).await?;
+        if let Some(meetup_id) = meetup_id {
+            match meetup_connection
+                .reschedule_event(meetup_id.clone(), date_time, duration)
+                .await
+            {
+                Ok(()) => (),
+                Err(crate::meetup::newapi::Error::ResourceNotFound) => {
+                    eprintln!(
+                        "Meetup event {} of session {} does not exist anymore, only rescheduling \
+                         it on Discord",
+                        meetup_id, event_id.0
+                    );
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let mut tx = db_connection.begin().await?;
+        sqlx::query!(
+            r#"UPDATE event SET start_time = $2, end_time = $3 WHERE id = $1"#,
+            event_id.0,
+            date_time,
+            date_time + duration
+        )
+        .execute(&mut *tx)
+        .await?;
+        // Remind the players again, based on the new time
+        sqlx::query!(
+            r#"DELETE FROM session_reminder_log WHERE event_id = $1"#,
+            event_id.0
+        )
+        .execute(&mut *tx)
+        .await?;
+        crate::audit::record(
+            crate::audit::Entry::new(
+                crate::audit::Action::SessionRescheduled,
+                crate::audit::Target::Event(event_id),
+            )
+            .actor(self.discord_id)
+            .before(serde_json::json!({
+                "start_time": session.old_start_time.to_rfc3339(),
+            }))
+            .after(serde_json::json!({
+                "start_time": date_time.to_rfc3339(),
+                "end_time": (date_time + duration).to_rfc3339(),
+            })),
+            &mut *tx,
+        )
+        .await;
+        tx.commit().await?;
+        self.delete(redis_connection).await?;
+        Ok(session)
+    }
+
+    // Cancels the session, on Meetup too if it is published there. The
+    // cancellation still needs to be announced on Discord.
+    pub async fn cancel(
+        self,
+        db_connection: &sqlx::PgPool,
+        redis_connection: &mut redis::aio::Connection,
+        meetup_connection: &MeetupConnection,
+        event_id: db::EventId,
+    ) -> Result<ManagedSession, crate::meetup::Error> {
+        let (session, meetup_id) = self.session(event_id, db_connection).await?;
+        if let Some(meetup_id) = meetup_id {
+            match meetup_connection.cancel_event(meetup_id.clone()).await {
+                Ok(()) => (),
+                Err(crate::meetup::newapi::Error::ResourceNotFound) => {
+                    eprintln!(
+                        "Meetup event {} of session {} does not exist anymore, only cancelling \
+                         it on Discord",
+                        meetup_id, event_id.0
+                    );
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let mut tx = db_connection.begin().await?;
+        sqlx::query!(
+            r#"UPDATE event SET deleted = NOW(), cancellation_announcement_pending = TRUE WHERE id = $1"#,
+            event_id.0
+        )
+        .execute(&mut *tx)
+        .await?;
+        crate::audit::record(
+            crate::audit::Entry::new(
+                crate::audit::Action::SessionCancelled,
+                crate::audit::Target::Event(event_id),
+            )
+            .actor(self.discord_id)
+            .before(serde_json::json!({
+                "start_time": session.old_start_time.to_rfc3339(),
+            })),
+            &mut *tx,
+        )
+        .await;
+        tx.commit().await?;
+        self.delete(redis_connection).await?;
+        Ok(session)
+    }
+
+    // The session has to be an upcoming one of this flow's series. Also
+    // returns the ID of its Meetup event, if any.
+    async fn session(
+        &self,
+        event_id: db::EventId,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<(ManagedSession, Option<String>), crate::meetup::Error> {
+        let session = sqlx::query!(
+            r#"SELECT event.title, event.start_time, meetup_event.meetup_id AS "meetup_id?", meetup_event.url AS "meetup_url?"
+            FROM event
+            LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+            WHERE event.id = $1 AND event.event_series_id = $2 AND event.start_time > NOW() AND event.deleted IS NULL"#,
+            event_id.0,
+            self.event_series_id.0
+        )
+        .fetch_optional(db_connection)
+        .await?;
+        match session {
+            Some(session) => Ok((
+                ManagedSession {
+                    event_id,
+                    title: session.title,
+                    old_start_time: session.start_time,
+                    meetup_url: session.meetup_url,
+                },
+                session.meetup_id,
+            )),
+            None => Err(simple_error::SimpleError::new(
+                "This session does not exist or is not upcoming anymore",
+            )
+            .into()),
+        }
+    }
+}
@@ -1,28 +1,115 @@
+mod manage_session;
+mod poll_dates;
+pub mod recurrence;
+
 use futures_util::FutureExt;
 use rand::Rng;
 use redis::AsyncCommands;
 
-use crate::{db, meetup::newapi::create_event_mutation::CreateEventInput};
+pub use self::{
+    manage_session::{ManageSessionFlow, ManagedSession},
+    poll_dates::PollDatesFlow,
+};
+use crate::{
+    db,
+    meetup::{
+        directives::{remove_shortcodes, EventDirectives, ShortcodeKind},
+        newapi::create_event_mutation::{CreateEventInput, PublishStatus},
+    },
+};
 
 pub struct ScheduleSessionFlow {
     pub id: u64,
     pub event_series_id: db::EventSeriesId,
 }
 
+// How the follow-up sessions are scheduled
+#[derive(Debug, Clone, Copy)]
+pub struct SessionOptions {
+    pub duration: chrono::Duration,
+    // Open sessions are published on Meetup
+    pub is_open_event: bool,
+    pub transfer_rsvps: bool,
+}
+
+pub struct ScheduledSession {
+    pub event_id: db::EventId,
+    pub title: String,
+    // Only set if the session was published on Meetup
+    pub meetup_event: Option<crate::meetup::newapi::NewEventResponse>,
+    // Set if the session is open but could not be published on Meetup. The
+    // session itself was still scheduled.
+    pub meetup_error: Option<crate::meetup::Error>,
+}
+
+// Scheduling stopped at a session that could not be created. The sessions
+// before it were created and stay scheduled.
+pub struct PartiallyScheduled {
+    pub sessions: Vec<ScheduledSession>,
+    pub error: crate::meetup::Error,
+}
+
+// The flows keep their state in a redis hash under "flow:<kind>:<id>", which
+// expires after ten minutes
+fn flow_redis_key(kind: &str, id: u64) -> String {
+    format!("flow:{}:{}", kind, id)
+}
+
+// Stores the fields of a new flow under a random ID and returns the ID
+async fn create_flow(
+    redis_connection: &mut redis::aio::Connection,
+    kind: &str,
+    fields: &[(&str, i64)],
+) -> Result<u64, crate::meetup::Error> {
+    let id: u64 = rand::thread_rng().gen();
+    let redis_key = flow_redis_key(kind, id);
+    let mut pipe = redis::pipe();
+    for &(field, value) in fields {
+        pipe.hset(&redis_key, field, value).ignore();
+    }
+    let _: () = pipe
+        .expire(&redis_key, 10 * 60)
+        .query_async(redis_connection)
+        .await?;
+    Ok(id)
+}
+
+// Loads the specified fields of a flow as a tuple of options, which are None
+// if the flow doesn't exist (anymore)
+async fn retrieve_flow<T: redis::FromRedisValue>(
+    redis_connection: &mut redis::aio::Connection,
+    kind: &str,
+    id: u64,
+    fields: &[&str],
+) -> Result<T, crate::meetup::Error> {
+    let redis_key = flow_redis_key(kind, id);
+    let mut pipe = redis::pipe();
+    for &field in fields {
+        pipe.hget(&redis_key, field);
+    }
+    let values = pipe.query_async(redis_connection).await?;
+    Ok(values)
+}
+
+async fn delete_flow(
+    redis_connection: &mut redis::aio::Connection,
+    kind: &str,
+    id: u64,
+) -> redis::RedisResult<()> {
+    redis_connection.del(flow_redis_key(kind, id)).await
+}
+
 impl ScheduleSessionFlow {
     pub async fn new(
         redis_connection: &mut redis::aio::Connection,
         event_series_id: db::EventSeriesId,
     ) -> Result<Self, crate::meetup::Error> {
-        let id: u64 = rand::thread_rng().gen();
-        let redis_key = format!("flow:schedule_session:{}", id);
-        let mut pipe = redis::pipe();
-        let _: () = pipe
-            .hset(&redis_key, "event_series_id", event_series_id.0)
-            .ignore()
-            .expire(&redis_key, 10 * 60)
-            .query_async(redis_connection)
-            .await?;
+        let id = create_flow(
+            redis_connection,
+            "schedule_session",
+            &[("event_series_id", event_series_id.0.into())],
+        )
+        .await?;
         Ok(ScheduleSessionFlow {
             id,
             event_series_id,
@@ -33,9 +120,13 @@ impl ScheduleSessionFlow {
         redis_connection: &mut redis::aio::Connection,
         id: u64,
     ) -> Result<Option<Self>, crate::meetup::Error> {
-        let redis_key = format!("flow:schedule_session:{}", id);
-        let event_series_id: Option<i32> =
-            redis_connection.hget(&redis_key, "event_series_id").await?;
+        let (event_series_id,): (Option<i32>,) = retrieve_flow(
+            redis_connection,
+            "schedule_session",
+            id,
+            &["event_series_id"],
+        )
+        .await?;
         let flow = event_series_id.map(|event_series_id| ScheduleSessionFlow {
             id: id,
             event_series_id: db::EventSeriesId(event_series_id),
@@ -43,16 +134,96 @@ impl ScheduleSessionFlow {
         Ok(flow)
     }
 
+    // Creates the follow-up session in the database. Open sessions are also
+    // published on Meetup, cloning the series' latest Meetup event if there is
+    // one or using a default template otherwise.
     pub async fn schedule<'a>(
+        self,
+        db_connection: sqlx::PgPool,
+        redis_connection: redis::aio::Connection,
+        meetup_connection: &'a crate::meetup::connection::MeetupConnection,
+        date_time: chrono::DateTime<chrono::Utc>,
+        options: SessionOptions,
+    ) -> Result<ScheduledSession, crate::meetup::Error> {
+        let mut sessions = self
+            .schedule_recurring(
+                db_connection,
+                redis_connection,
+                meetup_connection,
+                &[date_time],
+                options,
+            )
+            .await
+            .map_err(|partially_scheduled| partially_scheduled.error)?;
+        sessions
+            .pop()
+            .ok_or_else(|| simple_error::SimpleError::new("No session was scheduled").into())
+    }
+
+    // Creates a follow-up session for each of the specified times (in
+    // chronological order), each one continuing the previous one's session
+    // number and players. If one of the sessions can't be created, the ones
+    // scheduled before it are returned along with the error.
+    pub async fn schedule_recurring<'a>(
         self,
         db_connection: sqlx::PgPool,
         mut redis_connection: redis::aio::Connection,
-        meetup_client: &'a crate::meetup::newapi::AsyncClient,
-        // oauth2_consumer: &'a crate::meetup::oauth2::OAuth2Consumer,
+        meetup_connection: &'a crate::meetup::connection::MeetupConnection,
+        date_times: &[chrono::DateTime<chrono::Utc>],
+        options: SessionOptions,
+    ) -> Result<Vec<ScheduledSession>, PartiallyScheduled> {
+        let mut sessions = Vec::with_capacity(date_times.len());
+        let mut error = None;
+        for &date_time in date_times {
+            let session = match self
+                .schedule_one(&db_connection, meetup_connection, date_time, options)
+                .await
+            {
+                Ok(session) => session,
+                Err(err) => {
+                    error = Some(err);
+                    break;
+                }
+            };
+            if sessions.is_empty() {
+                let _ = delete_flow(&mut redis_connection, "schedule_session", self.id).await;
+            }
+            sessions.push(session);
+        }
+        if sessions.is_empty() {
+            if let Some(error) = error {
+                return Err(PartiallyScheduled { sessions, error });
+            }
+        }
+        // Create the channels and roles for the new sessions right away
+        if let Err(err) = crate::sync_queue::enqueue(
+            crate::sync_queue::SyncJob::EventSeries(self.event_series_id),
+            "schedule session",
+            &db_connection,
+        )
+        .await
+        {
+            eprintln!("Could not enqueue the event series sync:\n{:#?}", err);
+        }
+        match error {
+            None => Ok(sessions),
+            Some(error) => Err(PartiallyScheduled { sessions, error }),
+        }
+    }
+
+    async fn schedule_one(
+        &self,
+        db_connection: &sqlx::PgPool,
+        meetup_connection: &crate::meetup::connection::MeetupConnection,
         date_time: chrono::DateTime<chrono::Utc>,
-        is_open_event: bool,
-    ) -> Result<crate::meetup::newapi::NewEventResponse, crate::meetup::Error> {
-        let events = db::get_events_for_series(&db_connection, self.event_series_id).await?;
+        options: SessionOptions,
+    ) -> Result<ScheduledSession, crate::meetup::Error> {
+        let SessionOptions {
+            duration,
+            is_open_event,
+            transfer_rsvps,
+        } = options;
+        let events = db::get_events_for_series(db_connection, self.event_series_id).await?;
         let latest_event = if let Some(event) = events.first() {
             event
         } else {
@@ -61,85 +232,246 @@ impl ScheduleSessionFlow {
             )
             .into());
         };
-        // Find the latest event in the series which was published on Meetup
-        let latest_meetup_event = events.iter().find_map(|event| {
-            if let Some(meetup_event) = &event.meetup_event {
-                Some(meetup_event)
-            } else {
-                None
-            }
-        });
-        let latest_meetup_event = if let Some(event) = latest_meetup_event {
-            event
+        let title = Self::follow_up_title(&latest_event.title)?;
+        let description = Self::follow_up_description(&latest_event.description, is_open_event);
+        // Create the new event in the database
+        let mut tx = db_connection.begin().await?;
+        let event_id = sqlx::query!(
+            r#"INSERT INTO event (event_series_id, start_time, end_time, title, description, is_online, discord_category_id, guest_limit)
+            SELECT event_series_id, $2, $3, $4, $5, is_online, discord_category_id, guest_limit
+            FROM event
+            WHERE id = $1
+            RETURNING id"#,
+            latest_event.id.0,
+            date_time,
+            date_time + duration,
+            title,
+            description
+        )
+        .map(|row| db::EventId(row.id))
+        .fetch_one(&mut *tx)
+        .await?;
+        // TODO: hosts from latest session?
+        sqlx::query!(
+            r#"INSERT INTO event_host (event_id, member_id)
+            SELECT $2, member_id FROM event_host WHERE event_id = $1"#,
+            latest_event.id.0,
+            event_id.0
+        )
+        .execute(&mut *tx)
+        .await?;
+        // The transferred RSVPs don't exist on Meetup, so we don't mark them
+        // as Meetup RSVPs
+        if transfer_rsvps {
+            sqlx::query!(
+                r#"INSERT INTO event_participant (event_id, member_id, origin)
+                SELECT $2, member_id, 'discord' FROM event_participant WHERE event_id = $1"#,
+                latest_event.id.0,
+                event_id.0
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        let (meetup_event, meetup_error) = if !is_open_event {
+            (None, None)
         } else {
-            return Err(
-                simple_error::SimpleError::new("Could not find a Meetup event to clone").into(),
-            );
-        };
-        // Clone the Meetup event
-        let new_event_hook = Box::new(|mut new_event: CreateEventInput| {
-            new_event.title = latest_event.title.clone();
-            new_event.description = latest_event.description.clone();
-            // TODO: hosts from latest session?
-            Self::new_event_hook(
-                new_event,
+            match Self::publish_on_meetup(
+                &events,
+                event_id,
+                &title,
                 date_time,
-                &latest_meetup_event.meetup_id,
-                is_open_event,
+                duration,
+                db_connection,
+                meetup_connection,
             )
-        }) as _;
-        let new_event = crate::meetup::util::clone_event(
-            &latest_meetup_event.urlname,
-            &latest_meetup_event.meetup_id,
-            meetup_client,
-            Some(new_event_hook),
-        )
-        .await?;
-        // // Try to transfer the RSVPs to the new event
-        // let rsvp_result = match crate::meetup::util::clone_rsvps(
-        //     &latest_event.urlname,
-        //     &latest_event.id,
-        //     &new_event.id,
-        //     &mut redis_connection,
-        //     meetup_client,
-        //     oauth2_consumer,
-        // )
-        // .await
-        // {
-        //     Ok(result) => Some(result),
-        //     Err(err) => {
-        //         eprintln!("Could not transfer all RSVPs to the new event.\n{:#?}", err);
-        //         None
-        //     }
-        // };
-        let redis_key = format!("flow:schedule_session:{}", self.id);
-        let _: redis::RedisResult<()> = redis_connection.del(&redis_key).await;
-        let sync_future = {
-            let new_event = new_event.clone();
-            // let rsvps = rsvp_result.as_ref().map(|res| res.cloned_rsvps.clone());
-            async move {
-                crate::meetup::sync::sync_event(new_event.into(), &db_connection).await?;
-                // if let Some(rsvps) = rsvps {
-                //     crate::meetup::sync::sync_rsvps(&event_id, rsvps, &mut redis_connection)
-                //         .await?;
-                // }
-                Ok::<_, crate::meetup::Error>(())
+            .await
+            {
+                Ok(new_event) => (Some(new_event), None),
+                Err(err) => {
+                    eprintln!(
+                        "Could not publish the newly scheduled event on Meetup:\n{:#?}",
+                        err
+                    );
+                    (None, Some(err))
+                }
             }
         };
-        tokio::spawn(sync_future.map(|res| {
-            if let Err(err) = res {
-                eprintln!("Could not sync the newly scheduled event:\n{:#?}", err);
+        if let Some(new_event) = &meetup_event {
+            let sync_future = {
+                let new_event = new_event.clone();
+                let db_connection = db_connection.clone();
+                async move {
+                    crate::meetup::sync::sync_event(
+                        new_event.into(),
+                        chrono::Utc::now(),
+                        &db_connection,
+                    )
+                    .await?;
+                    Ok::<_, crate::meetup::Error>(())
+                }
+            };
+            tokio::spawn(sync_future.map(|res| {
+                if let Err(err) = res {
+                    eprintln!("Could not sync the newly scheduled event:\n{:#?}", err);
+                }
+            }));
+        }
+        Ok(ScheduledSession {
+            event_id,
+            title,
+            meetup_event,
+            meetup_error,
+        })
+    }
+
+    // Publishes the already created event on Meetup and links the two
+    async fn publish_on_meetup(
+        events: &[db::Event],
+        event_id: db::EventId,
+        title: &str,
+        date_time: chrono::DateTime<chrono::Utc>,
+        duration: chrono::Duration,
+        db_connection: &sqlx::PgPool,
+        meetup_connection: &crate::meetup::connection::MeetupConnection,
+    ) -> Result<crate::meetup::newapi::NewEventResponse, crate::meetup::Error> {
+        let description =
+            sqlx::query_scalar!(r#"SELECT description FROM event WHERE id = $1"#, event_id.0)
+                .fetch_one(db_connection)
+                .await?;
+        let mut new_event = None;
+        // We go from the latest event to the oldest event until we find one
+        // that has not been deleted on Meetup to use as a template
+        for meetup_event in events
+            .iter()
+            .filter_map(|event| event.meetup_event.as_ref())
+        {
+            let new_event_hook = Box::new(|mut new_event: CreateEventInput| {
+                new_event.title = title.to_string();
+                new_event.description = description.clone();
+                new_event.duration = Some(duration.into());
+                new_event.publish_status = Some(PublishStatus::PUBLISHED);
+                Self::new_event_hook(new_event, date_time, &meetup_event.meetup_id, true)
+            }) as _;
+            match crate::meetup::util::clone_event(
+                &meetup_event.urlname,
+                &meetup_event.meetup_id,
+                meetup_connection,
+                Some(new_event_hook),
+            )
+            .await
+            {
+                Err(crate::meetup::Error::NewAPIError(
+                    crate::meetup::newapi::Error::ResourceNotFound,
+                )) => {
+                    // Event was deleted, try the next one
+                    continue;
+                }
+                Err(err) => return Err(err),
+                Ok(event) => {
+                    new_event = Some((event, meetup_event.urlname.clone()));
+                    break;
+                }
             }
-        }));
+        }
+        let (new_event, urlname) = match new_event {
+            Some(new_event) => new_event,
+            None => {
+                // There is no Meetup event to clone, use a default template
+                let urlname = crate::meetup::newapi::URLNAMES[0];
+                let new_event = Self::default_meetup_event(
+                    urlname,
+                    event_id,
+                    title,
+                    description,
+                    date_time,
+                    duration,
+                    db_connection,
+                )
+                .await?;
+                println!(
+                    "Trying to create a Meetup event with the following details:\n{:#?}",
+                    new_event
+                );
+                (
+                    meetup_connection.create_event(new_event).await?,
+                    urlname.to_string(),
+                )
+            }
+        };
+        // Link the Meetup event to our event so that syncing Meetup updates
+        // it instead of creating a new one
+        sqlx::query!(
+            r#"INSERT INTO meetup_event (event_id, meetup_id, url, urlname) VALUES ($1, $2, $3, $4)"#,
+            event_id.0,
+            new_event.id.0,
+            new_event.event_url,
+            urlname
+        )
+        .execute(db_connection)
+        .await?;
         Ok(new_event)
     }
 
+    // A Meetup event for sessions without a previous Meetup event: online
+    // sessions are published as online events, all others without a venue
+    async fn default_meetup_event(
+        urlname: &str,
+        event_id: db::EventId,
+        title: &str,
+        description: String,
+        date_time: chrono::DateTime<chrono::Utc>,
+        duration: chrono::Duration,
+        db_connection: &sqlx::PgPool,
+    ) -> Result<crate::meetup::newapi::NewEvent, crate::meetup::Error> {
+        let event = sqlx::query!(
+            r#"SELECT is_online, guest_limit FROM event WHERE id = $1"#,
+            event_id.0
+        )
+        .fetch_one(db_connection)
+        .await?;
+        let host_meetup_ids = sqlx::query_scalar!(
+            r#"SELECT "member".meetup_id AS "meetup_id!"
+            FROM event_host
+            INNER JOIN "member" ON event_host.member_id = "member".id
+            WHERE event_host.event_id = $1 AND "member".meetup_id IS NOT NULL"#,
+            event_id.0
+        )
+        .fetch_all(db_connection)
+        .await?;
+        Ok(crate::meetup::newapi::NewEvent {
+            group_urlname: urlname.into(),
+            title: title.into(),
+            description,
+            start_date_time: crate::meetup::newapi::DateTime(date_time),
+            duration: Some(duration.into()),
+            rsvp_settings: Some(crate::meetup::newapi::NewEventRsvpSettings {
+                rsvp_limit: event.guest_limit.map(|limit| limit as i64),
+                guest_limit: Some(0),
+                rsvp_open_time: None,
+                rsvp_close_time: None,
+                rsvp_open_duration: None,
+                rsvp_close_duration: None,
+            }),
+            event_hosts: Some(host_meetup_ids),
+            venue_id: if event.is_online {
+                Some("online".into())
+            } else {
+                None
+            },
+            self_rsvp: Some(false),
+            how_to_find_us: None,
+            question: None,
+            featured_photo_id: None,
+            publish_status: Some(PublishStatus::PUBLISHED),
+        })
+    }
+
     pub async fn delete(
         self,
         redis_connection: &mut redis::aio::Connection,
     ) -> Result<(), crate::meetup::Error> {
-        let redis_key = format!("flow:schedule_session:{}", self.id);
-        let () = redis_connection.del(&redis_key).await?;
+        delete_flow(redis_connection, "schedule_session", self.id).await?;
         Ok(())
     }
 
@@ -149,32 +481,43 @@ impl ScheduleSessionFlow {
         old_event_id: &str,
         is_open_event: bool,
     ) -> Result<crate::meetup::newapi::NewEvent, crate::meetup::Error> {
+        let mut description = Self::follow_up_description(&new_event.description, is_open_event);
+        // Add an event series shortcode if there is none yet
+        if EventDirectives::parse(&description)
+            .series_event_id
+            .is_none()
+        {
+            description.push_str(&format!("\n[campaign {}]", old_event_id));
+        }
+        new_event.title = Self::follow_up_title(&new_event.title)?;
+        new_event.description = description;
+        new_event.start_date_time = crate::meetup::newapi::DateTime(new_date_time);
+        Ok(new_event)
+    }
+
+    fn follow_up_description(description: &str, is_open_event: bool) -> String {
         // Remove unnecessary shortcodes from follow-up sessions
-        let description = new_event.description;
-        let description = crate::meetup::sync::NEW_ADVENTURE_REGEX.replace_all(&description, "");
-        let description = crate::meetup::sync::NEW_CAMPAIGN_REGEX.replace_all(&description, "");
         // We don't remove the [online] shortcode from descriptions anymore,
         // such that the "free game spots" feature has an easy way to tell
         // whether an event is online or not. This is mostly due to the fact
         // that at the time of this writing, we can not use the official Meetup
         // feature (yet?) for marking events as being online.
-        // let description = crate::meetup::sync::ONLINE_REGEX.replace_all(&description, "");
-        let mut description = crate::meetup::sync::CHANNEL_REGEX
-            .replace_all(&description, "")
-            .into_owned();
+        let mut removed_shortcodes = vec![
+            ShortcodeKind::NewAdventure,
+            ShortcodeKind::NewCampaign,
+            ShortcodeKind::Channel,
+        ];
         // If this event is an "open event", make sure that there is no [closed] shortcode.
         // (We don't add it automatically here for closed events though)
         if is_open_event {
-            description = crate::free_spots::CLOSED_REGEX
-                .replace_all(&description, "")
-                .into_owned()
-        }
-        // Add an event series shortcode if there is none yet
-        if !crate::meetup::sync::EVENT_SERIES_REGEX.is_match(&description) {
-            description.push_str(&format!("\n[campaign {}]", old_event_id));
+            removed_shortcodes.push(ShortcodeKind::Closed);
         }
-        // Increase the Session number
-        let title_captures = crate::meetup::sync::SESSION_REGEX.captures_iter(&new_event.title);
+        remove_shortcodes(description, &removed_shortcodes)
+    }
+
+    // Increases the session number in the title
+    fn follow_up_title(title: &str) -> Result<String, crate::meetup::Error> {
+        let title_captures = crate::meetup::sync::SESSION_REGEX.captures_iter(title);
         // Match the rightmost occurence of " Session X" in the event name.
         // Returns the event name without the session number (title_only) and
         // the current session number
@@ -186,12 +529,12 @@ impl ScheduleSessionFlow {
             let session_number = session_number.parse::<i32>()?;
             // Find the range of the " Session X" match and remove it from the string
             let session_x_match = capture.get(0).unwrap();
-            let mut title_only = new_event.title.clone();
+            let mut title_only = title.to_string();
             title_only.truncate(session_x_match.start());
             (title_only, session_number)
         } else {
             // If there is no match, return the whole name and Session number 1
-            (new_event.title.clone(), 1)
+            (title.to_string(), 1)
         };
         // Create a new " Session X+1" suffix
         let new_session_suffix = format!(" Session {}", session_number + 1);
@@ -213,9 +556,6 @@ impl ScheduleSessionFlow {
                 crate::meetup::util::truncate_str(title_only, max_title_utf16_len);
             shortened_title + ellipsis + &new_session_suffix
         };
-        new_event.title = new_event_title;
-        new_event.description = description;
-        new_event.start_date_time = crate::meetup::newapi::DateTime(new_date_time);
-        Ok(new_event)
+        Ok(new_event_title)
     }
 }
@@ -0,0 +1,64 @@
+use serenity::model::id::UserId;
+
+use crate::db;
+
+// Lets a host propose candidate slots for the next session of their series
+pub struct PollDatesFlow {
+    pub id: u64,
+    pub event_series_id: db::EventSeriesId,
+    // The host who requested the flow
+    pub discord_id: UserId,
+}
+
+impl PollDatesFlow {
+    pub async fn new(
+        redis_connection: &mut redis::aio::Connection,
+        event_series_id: db::EventSeriesId,
+        discord_id: UserId,
+    ) -> Result<Self, crate::meetup::Error> {
+        let id = super::create_flow(
+            redis_connection,
+            "poll_dates",
+            &[
+                ("event_series_id", event_series_id.0.into()),
+                ("discord_id", discord_id.get() as i64),
+            ],
+        )
+        .await?;
+        Ok(PollDatesFlow {
+            id,
+            event_series_id,
+            discord_id,
+        })
+    }
+
+    pub async fn retrieve(
+        redis_connection: &mut redis::aio::Connection,
+        id: u64,
+    ) -> Result<Option<Self>, crate::meetup::Error> {
+        let (event_series_id, discord_id): (Option<i32>, Option<u64>) = super::retrieve_flow(
+            redis_connection,
+            "poll_dates",
+            id,
+            &["event_series_id", "discord_id"],
+        )
+        .await?;
+        let flow = match (event_series_id, discord_id) {
+            (Some(event_series_id), Some(discord_id)) => Some(PollDatesFlow {
+                id,
+                event_series_id: db::EventSeriesId(event_series_id),
+                discord_id: UserId::new(discord_id),
+            }),
+            _ => None,
+        };
+        Ok(flow)
+    }
+
+    pub async fn delete(
+        self,
+        redis_connection: &mut redis::aio::Connection,
+    ) -> Result<(), crate::meetup::Error> {
+        super::delete_flow(redis_connection, "poll_dates", self.id).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+use chrono::{offset::TimeZone, Datelike, NaiveDate};
+
+// Upper limit for the number of sessions scheduled at once, so that a typo in
+// the end date doesn't flood Meetup and Discord with events
+pub const MAX_SESSIONS: usize = 26;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Weekly,
+    Biweekly,
+    // Same day of the month (or the last day of shorter months)
+    Monthly,
+}
+
+impl Frequency {
+    // The name used in forms
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Frequency::Weekly => "weekly",
+            Frequency::Biweekly => "biweekly",
+            Frequency::Monthly => "monthly",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Frequency> {
+        [Frequency::Weekly, Frequency::Biweekly, Frequency::Monthly]
+            .into_iter()
+            .find(|frequency| frequency.as_str() == name)
+    }
+
+    // The date of the nth session after the first one
+    fn nth_date(&self, first: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self {
+            Frequency::Weekly => first.checked_add_days(chrono::Days::new(7 * n as u64)),
+            Frequency::Biweekly => first.checked_add_days(chrono::Days::new(14 * n as u64)),
+            Frequency::Monthly => first.checked_add_months(chrono::Months::new(n)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecurrenceEnd {
+    // Number of sessions to schedule, not counting skipped holidays
+    Count(u32),
+    // Date of the last possible session (inclusive)
+    Until(NaiveDate),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub end: RecurrenceEnd,
+    pub skip_holidays: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedSession {
+    pub date_time: chrono::DateTime<chrono_tz::Tz>,
+    // Set if the session falls on a public holiday and will not be scheduled
+    pub skipped_holiday: Option<&'static str>,
+}
+
+impl Recurrence {
+    // Lists the sessions starting with the first one, keeping the local time
+    // of day across daylight saving time changes
+    pub fn plan(&self, first: chrono::DateTime<chrono_tz::Tz>) -> Vec<PlannedSession> {
+        let timezone = first.timezone();
+        let mut sessions = vec![];
+        let mut num_scheduled = 0;
+        for n in 0.. {
+            let date = match self.frequency.nth_date(first.date_naive(), n) {
+                Some(date) => date,
+                None => break,
+            };
+            let done = match self.end {
+                RecurrenceEnd::Count(count) => num_scheduled >= count as usize,
+                RecurrenceEnd::Until(until) => date > until,
+            };
+            if done || num_scheduled >= MAX_SESSIONS || sessions.len() >= 2 * MAX_SESSIONS {
+                break;
+            }
+            // Local times which don't exist on this day are skipped
+            let date_time = match timezone
+                .from_local_datetime(&date.and_time(first.time()))
+                .earliest()
+            {
+                Some(date_time) => date_time,
+                None => continue,
+            };
+            let skipped_holiday = if self.skip_holidays {
+                swiss_public_holiday(date)
+            } else {
+                None
+            };
+            if skipped_holiday.is_none() {
+                num_scheduled += 1;
+            }
+            sessions.push(PlannedSession {
+                date_time,
+                skipped_holiday,
+            });
+        }
+        sessions
+    }
+}
+
+// The public holidays observed in (almost) all of Switzerland
+pub fn swiss_public_holiday(date: NaiveDate) -> Option<&'static str> {
+    match (date.month(), date.day()) {
+        (1, 1) => return Some("New Year's Day"),
+        (1, 2) => return Some("Berchtold's Day"),
+        (8, 1) => return Some("Swiss National Day"),
+        (12, 25) => return Some("Christmas Day"),
+        (12, 26) => return Some("St. Stephen's Day"),
+        _ => (),
+    }
+    let easter = easter_sunday(date.year())?;
+    match (date - easter).num_days() {
+        -2 => Some("Good Friday"),
+        1 => Some("Easter Monday"),
+        39 => Some("Ascension Day"),
+        50 => Some("Whit Monday"),
+        _ => None,
+    }
+}
+
+// Anonymous Gregorian algorithm
+fn easter_sunday(year: i32) -> Option<NaiveDate> {
+    let a = year % 19;
+    let b = year / 100;
+    let c = year % 100;
+    let d = b / 4;
+    let e = b % 4;
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let i = c / 4;
+    let k = c % 4;
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32)
+}
@@ -1,9 +1,11 @@
 // While syncing upcoming Meetup events, the code in this file is used to build
 // a list of events with free spots and post those to Discord.
 
-use crate::{meetup::newapi::UpcomingEventDetails, DefaultStr};
+use crate::{
+    meetup::{directives::EventDirectives, newapi::UpcomingEventDetails},
+    DefaultStr,
+};
 use geo::{euclidean_distance::EuclideanDistance, Point};
-use lazy_static::lazy_static;
 use serenity::{
     builder::{
         CreateEmbed, CreateEmbedAuthor, CreateEmbedFooter, CreateMessage, EditMessage, GetMessages,
@@ -12,12 +14,6 @@ use serenity::{
 };
 use std::{collections::HashMap, fmt::Write};
 
-pub const CLOSED_PATTERN: &'static str = r"(?i)\\?\[\s*closed\s*\\?\]";
-
-lazy_static! {
-    pub static ref CLOSED_REGEX: regex::Regex = regex::Regex::new(CLOSED_PATTERN).unwrap();
-}
-
 #[derive(Debug, Clone)]
 pub struct EventCollector {
     // List of upcoming events and the number of free spots
@@ -207,7 +203,7 @@ impl EventCollector {
                     .as_ref()
                     .and_then(|rsvp_settings| rsvp_settings.rsvps_closed)
                     .unwrap_or(false)
-                    || CLOSED_REGEX.is_match(event.description.unwrap_or_str(""));
+                    || EventDirectives::parse(event.description.unwrap_or_str("")).closed;
                 !is_closed_event
             })
             // Discard events which are too far in the future
@@ -332,9 +328,7 @@ impl EventCollector {
             }
         };
         // Is this event online?
-        if event.is_online
-            || crate::meetup::sync::ONLINE_REGEX.is_match(event.description.unwrap_or_str(""))
-        {
+        if event.is_online || EventDirectives::parse(event.description.unwrap_or_str("")).online {
             return Some(Location::Online);
         }
         // Doesn't seem to be an online event.
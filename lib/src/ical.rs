@@ -0,0 +1,203 @@
+use std::fmt::Write;
+
+use crate::db;
+
+// Events that ended longer ago than this are not included in the feeds
+const PAST_DAYS: i64 = 90;
+// Assumed duration of events without an end time
+const DEFAULT_DURATION_HOURS: i64 = 4;
+
+struct CalendarEvent {
+    id: i32,
+    title: String,
+    start_time: chrono::DateTime<chrono::Utc>,
+    end_time: Option<chrono::DateTime<chrono::Utc>>,
+    deleted: Option<chrono::DateTime<chrono::Utc>>,
+    meetup_url: Option<String>,
+}
+
+// Returns the member's calendar token, creating one if necessary
+pub async fn get_or_create_calendar_token(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<String, crate::meetup::Error> {
+    let new_token = crate::new_random_id(24);
+    let token = sqlx::query_scalar!(
+        r#"UPDATE "member" SET calendar_token = COALESCE(calendar_token, $2) WHERE id = $1 RETURNING calendar_token AS "calendar_token!""#,
+        member_id.0,
+        new_token
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(token)
+}
+
+pub async fn member_for_calendar_token(
+    token: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<db::MemberId>, crate::meetup::Error> {
+    let member_id = sqlx::query_scalar!(
+        r#"SELECT id FROM "member" WHERE calendar_token = $1"#,
+        token
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(member_id.map(db::MemberId))
+}
+
+// Whether the member hosts or plays in any of the series' events
+pub async fn is_series_member(
+    member_id: db::MemberId,
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<bool, crate::meetup::Error> {
+    let is_member = sqlx::query_scalar!(
+        r#"SELECT EXISTS (
+            SELECT 1 FROM event
+            LEFT OUTER JOIN event_host ON event.id = event_host.event_id AND event_host.member_id = $2
+            LEFT OUTER JOIN event_participant ON event.id = event_participant.event_id AND event_participant.member_id = $2
+            WHERE event.event_series_id = $1 AND (event_host.member_id IS NOT NULL OR event_participant.member_id IS NOT NULL)
+        ) AS "is_member!""#,
+        series_id.0,
+        member_id.0
+    )
+    .fetch_one(db_connection)
+    .await?;
+    Ok(is_member)
+}
+
+// All sessions the member hosts or plays in
+pub async fn member_feed(
+    member_id: db::MemberId,
+    db_connection: &sqlx::PgPool,
+) -> Result<String, crate::meetup::Error> {
+    let events = sqlx::query_as!(
+        CalendarEvent,
+        r#"SELECT event.id, event.title, event.start_time, event.end_time, event.deleted, meetup_event.url AS "meetup_url?"
+        FROM event
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.start_time > NOW() - $2 * INTERVAL '1 day'
+        AND (
+            EXISTS (SELECT 1 FROM event_host WHERE event_host.event_id = event.id AND event_host.member_id = $1)
+            OR EXISTS (SELECT 1 FROM event_participant WHERE event_participant.event_id = event.id AND event_participant.member_id = $1)
+        )
+        ORDER BY event.start_time"#,
+        member_id.0,
+        PAST_DAYS as f64
+    )
+    .fetch_all(db_connection)
+    .await?;
+    Ok(render_calendar("My SwissRPG sessions", &events))
+}
+
+// All sessions of an event series
+pub async fn series_feed(
+    series_id: db::EventSeriesId,
+    db_connection: &sqlx::PgPool,
+) -> Result<String, crate::meetup::Error> {
+    let events = sqlx::query_as!(
+        CalendarEvent,
+        r#"SELECT event.id, event.title, event.start_time, event.end_time, event.deleted, meetup_event.url AS "meetup_url?"
+        FROM event
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.event_series_id = $1 AND event.start_time > NOW() - $2 * INTERVAL '1 day'
+        ORDER BY event.start_time"#,
+        series_id.0,
+        PAST_DAYS as f64
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let name = match events.last() {
+        Some(event) => event.title.clone(),
+        None => "SwissRPG sessions".to_string(),
+    };
+    Ok(render_calendar(&name, &events))
+}
+
+// All sessions of the club
+pub async fn club_feed(db_connection: &sqlx::PgPool) -> Result<String, crate::meetup::Error> {
+    let events = sqlx::query_as!(
+        CalendarEvent,
+        r#"SELECT event.id, event.title, event.start_time, event.end_time, event.deleted, meetup_event.url AS "meetup_url?"
+        FROM event
+        LEFT OUTER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.start_time > NOW() - $1 * INTERVAL '1 day'
+        ORDER BY event.start_time"#,
+        PAST_DAYS as f64
+    )
+    .fetch_all(db_connection)
+    .await?;
+    Ok(render_calendar("SwissRPG", &events))
+}
+
+fn render_calendar(name: &str, events: &[CalendarEvent]) -> String {
+    let now = format_date_time(chrono::Utc::now());
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//SwissRPG//Hyperion//EN".to_string(),
+        "CALSCALE:GREGORIAN".to_string(),
+        "METHOD:PUBLISH".to_string(),
+        format!("X-WR-CALNAME:{}", escape_text(name)),
+    ];
+    for event in events {
+        let end_time = event
+            .end_time
+            .unwrap_or(event.start_time + chrono::Duration::hours(DEFAULT_DURATION_HOURS));
+        lines.push("BEGIN:VEVENT".to_string());
+        // The UID only depends on the event ID such that calendar clients
+        // update existing entries instead of creating duplicates
+        lines.push(format!("UID:event-{}@swissrpg.ch", event.id));
+        lines.push(format!("DTSTAMP:{}", now));
+        lines.push(format!("DTSTART:{}", format_date_time(event.start_time)));
+        lines.push(format!("DTEND:{}", format_date_time(end_time)));
+        lines.push(format!("SUMMARY:{}", escape_text(&event.title)));
+        if let Some(meetup_url) = &event.meetup_url {
+            lines.push(format!("URL:{}", meetup_url));
+        }
+        if event.deleted.is_some() {
+            // Clients only apply the cancellation if the sequence number
+            // increased
+            lines.push("STATUS:CANCELLED".to_string());
+            lines.push("SEQUENCE:1".to_string());
+        } else {
+            lines.push("STATUS:CONFIRMED".to_string());
+            lines.push("SEQUENCE:0".to_string());
+        }
+        lines.push("END:VEVENT".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+    let mut calendar = String::new();
+    for line in lines {
+        write_folded_line(&mut calendar, &line);
+    }
+    calendar
+}
+
+fn format_date_time(date_time: chrono::DateTime<chrono::Utc>) -> String {
+    date_time.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+// See RFC 5545, section 3.3.11
+fn escape_text(text: &str) -> String {
+    text.replace('\\', r"\\")
+        .replace(';', r"\;")
+        .replace(',', r"\,")
+        .replace("\r\n", r"\n")
+        .replace('\n', r"\n")
+}
+
+// Lines should not be longer than 75 octets. Longer lines are split and
+// continued with a leading space (RFC 5545, section 3.1).
+fn write_folded_line(calendar: &mut String, line: &str) {
+    let mut line_length = 0;
+    for c in line.chars() {
+        if line_length + c.len_utf8() > 75 {
+            calendar.push_str("\r\n ");
+            line_length = 1;
+        }
+        calendar.push(c);
+        line_length += c.len_utf8();
+    }
+    write!(calendar, "\r\n").ok();
+}
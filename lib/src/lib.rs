@@ -1,16 +1,25 @@
 #![forbid(unsafe_code)]
 #![warn(rust_2018_idioms)]
+pub mod archive;
+pub mod audit;
+pub mod date_poll;
 pub mod db;
+pub mod directive_errors;
 pub mod discord;
 pub mod end_adventure;
 pub mod error;
+pub mod expiration_policy;
+#[cfg(any(test, feature = "test-util"))]
+pub mod fake;
 pub mod flow;
 mod free_spots;
+pub mod ical;
 pub mod meetup;
 pub mod redis;
 pub mod role_shortcode;
 pub mod strings;
 pub mod stripe;
+pub mod sync_queue;
 pub mod tasks;
 pub mod urls;
 
@@ -312,10 +321,10 @@ pub async fn link_discord_meetup(
                 .await?;
             }
             let attended_events_old_member = sqlx::query!(
-                r#"SELECT event_id FROM event_participant WHERE member_id = $1"#,
+                r#"SELECT event_id, origin FROM event_participant WHERE member_id = $1"#,
                 member_id_with_meetup.0
             )
-            .map(|row| db::EventId(row.event_id))
+            .map(|row| (db::EventId(row.event_id), row.origin))
             .fetch_all(&mut **db_connection)
             .await?;
             sqlx::query!(
@@ -324,11 +333,12 @@ pub async fn link_discord_meetup(
             )
             .execute(&mut **db_connection)
             .await?;
-            for attended_event_id in attended_events_old_member {
+            for (attended_event_id, origin) in attended_events_old_member {
                 sqlx::query!(
-                    r#"INSERT INTO event_participant (event_id, member_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+                    r#"INSERT INTO event_participant (event_id, member_id, origin) VALUES ($1, $2, $3) ON CONFLICT DO NOTHING"#,
                     attended_event_id.0,
-                    member_id_with_discord.0
+                    member_id_with_discord.0,
+                    origin
                 )
                 .execute(&mut **db_connection)
                 .await?;
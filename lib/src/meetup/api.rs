@@ -0,0 +1,31 @@
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+
+use super::newapi::{AsyncClient, Error, EventStatus, Ticket, UpcomingEventDetails};
+
+// The Meetup operations used by the Meetup sync. Implemented by AsyncClient
+// and by an in-memory fake for tests (see crate::fake).
+#[async_trait]
+pub trait MeetupApi: Send + Sync {
+    fn get_upcoming_events_all_groups(&self) -> BoxStream<'_, Result<UpcomingEventDetails, Error>>;
+
+    async fn get_tickets_vec(&self, event_id: String) -> Result<Vec<Ticket>, Error>;
+
+    // Returns Error::ResourceNotFound if the event was deleted
+    async fn get_event_status(&self, event_id: String) -> Result<Option<EventStatus>, Error>;
+}
+
+#[async_trait]
+impl MeetupApi for AsyncClient {
+    fn get_upcoming_events_all_groups(&self) -> BoxStream<'_, Result<UpcomingEventDetails, Error>> {
+        AsyncClient::get_upcoming_events_all_groups(self).boxed()
+    }
+
+    async fn get_tickets_vec(&self, event_id: String) -> Result<Vec<Ticket>, Error> {
+        AsyncClient::get_tickets_vec(self, event_id).await
+    }
+
+    async fn get_event_status(&self, event_id: String) -> Result<Option<EventStatus>, Error> {
+        Ok(self.get_event(event_id).await?.status)
+    }
+}
@@ -0,0 +1,296 @@
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+
+use async_trait::async_trait;
+use futures::stream::{BoxStream, StreamExt};
+use reqwest::StatusCode;
+use serde::Serialize;
+use tokio::sync::Notify;
+
+use super::{
+    api::MeetupApi,
+    newapi::{
+        event_query, self_query, AsyncClient, Error, EventStatus, GroupMembership, NewEvent,
+        NewEventResponse, Ticket, UpcomingEventDetails,
+    },
+};
+use crate::discord::config::GuildConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionHealth {
+    Ok,
+    // Meetup rejected the organizer's token (or there is none yet)
+    TokenExpired,
+    // Meetup could not be reached or responded with server errors
+    Unreachable,
+}
+
+// How many requests in a row have to agree on a new health state before it is
+// taken over, so that a flapping connection doesn't flood the alerts channel
+const HEALTH_CHANGE_THRESHOLD: u32 = 3;
+
+// GraphQL error codes with which Meetup rejects the organizer's token
+const AUTH_ERROR_CODES: &[&str] = &[
+    "UNAUTHENTICATED",
+    "UNAUTHORIZED",
+    "NOT_AUTHORIZED",
+    "AUTHENTICATION_ERROR",
+];
+
+struct HealthState {
+    current: ConnectionHealth,
+    // A different health state and how many requests in a row reported it
+    pending: Option<(ConnectionHealth, u32)>,
+}
+
+// The bot's connection to the Meetup API using the organizer's token.
+// Cloning it is cheap and all clones share the same token and health state.
+#[derive(Clone)]
+pub struct MeetupConnection {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    client: RwLock<Option<Arc<AsyncClient>>>,
+    health: Mutex<HealthState>,
+    // Where to report health changes, set once Discord is ready
+    alerts: OnceLock<(crate::discord::CacheAndHttp, Arc<GuildConfig>)>,
+    token_refresh_requested: Notify,
+}
+
+impl MeetupConnection {
+    pub fn new(access_token: Option<&str>) -> Self {
+        let client = access_token.map(|access_token| Arc::new(AsyncClient::new(access_token)));
+        let health = if client.is_some() {
+            ConnectionHealth::Ok
+        } else {
+            ConnectionHealth::TokenExpired
+        };
+        MeetupConnection {
+            inner: Arc::new(Inner {
+                client: RwLock::new(client),
+                health: Mutex::new(HealthState {
+                    current: health,
+                    pending: None,
+                }),
+                alerts: OnceLock::new(),
+                token_refresh_requested: Notify::new(),
+            }),
+        }
+    }
+
+    // Swaps in a new (e.g. refreshed) organizer token
+    pub fn set_access_token(&self, access_token: &str) {
+        *self.inner.client.write().unwrap() = Some(Arc::new(AsyncClient::new(access_token)));
+    }
+
+    // Health changes will be announced in the guild's bot alerts channel
+    pub fn set_alerts_channel(
+        &self,
+        discord_api: crate::discord::CacheAndHttp,
+        guild_config: Arc<GuildConfig>,
+    ) {
+        self.inner.alerts.set((discord_api, guild_config)).ok();
+    }
+
+    pub fn health(&self) -> ConnectionHealth {
+        self.inner.health.lock().unwrap().current
+    }
+
+    // Resolves once Meetup rejected the organizer's token, so that the token
+    // refresh task can refresh it early
+    pub async fn token_refresh_requested(&self) {
+        self.inner.token_refresh_requested.notified().await
+    }
+
+    fn client(&self) -> Result<Arc<AsyncClient>, Error> {
+        self.inner
+            .client
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or(Error::NotConnected)
+    }
+
+    // Updates the health state according to the outcome of a request
+    async fn record<T>(&self, result: &Result<T, Error>) {
+        let health = match result {
+            Ok(_) => ConnectionHealth::Ok,
+            Err(Error::NotConnected) => ConnectionHealth::TokenExpired,
+            Err(Error::Reqwest(err)) if err.status() == Some(StatusCode::UNAUTHORIZED) => {
+                self.inner.token_refresh_requested.notify_one();
+                ConnectionHealth::TokenExpired
+            }
+            Err(Error::GraphQL(errors)) if is_auth_error(errors) => {
+                self.inner.token_refresh_requested.notify_one();
+                ConnectionHealth::TokenExpired
+            }
+            Err(Error::Reqwest(err))
+                if err.is_connect()
+                    || err.is_timeout()
+                    || err.status().is_some_and(|status| {
+                        status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+                    }) =>
+            {
+                ConnectionHealth::Unreachable
+            }
+            // Meetup responded, so the connection itself is fine
+            Err(Error::GraphQL(..) | Error::ResourceNotFound | Error::Payload(..)) => {
+                ConnectionHealth::Ok
+            }
+            Err(Error::Reqwest(..)) => return,
+        };
+        let previous_health = {
+            let mut state = self.inner.health.lock().unwrap();
+            if state.current == health {
+                state.pending = None;
+                return;
+            }
+            let count = match state.pending {
+                Some((pending_health, count)) if pending_health == health => count + 1,
+                _ => 1,
+            };
+            if count < HEALTH_CHANGE_THRESHOLD {
+                state.pending = Some((health, count));
+                return;
+            }
+            state.pending = None;
+            std::mem::replace(&mut state.current, health)
+        };
+        println!(
+            "Meetup connection health changed from {:?} to {:?}",
+            previous_health, health
+        );
+        if let Some((discord_api, guild_config)) = self.inner.alerts.get() {
+            let message = if health == ConnectionHealth::Ok {
+                crate::strings::MEETUP_CONNECTION_RECOVERED.to_string()
+            } else {
+                crate::strings::MEETUP_CONNECTION_DEGRADED(health)
+            };
+            if let Err(err) =
+                crate::discord::util::say_in_bot_alerts_channel(&message, discord_api, guild_config)
+                    .await
+            {
+                eprintln!("Could not report the Meetup connection health:\n{:#?}", err);
+            }
+        }
+    }
+
+    pub async fn get_event(&self, id: String) -> Result<event_query::EventQueryEvent, Error> {
+        let result = match self.client() {
+            Ok(client) => client.get_event(id).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn get_self(&self) -> Result<self_query::SelfQuerySelf, Error> {
+        let result = match self.client() {
+            Ok(client) => client.get_self().await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn create_event(&self, new_event: NewEvent) -> Result<NewEventResponse, Error> {
+        let result = match self.client() {
+            Ok(client) => client.create_event(new_event).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn get_group_membership(&self, urlname: String) -> Result<GroupMembership, Error> {
+        let result = match self.client() {
+            Ok(client) => client.get_group_membership(urlname).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn close_rsvps(&self, event_id: String) -> Result<(), Error> {
+        let result = match self.client() {
+            Ok(client) => client.close_rsvps(event_id).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn reschedule_event(
+        &self,
+        event_id: String,
+        start_date_time: chrono::DateTime<chrono::Utc>,
+        duration: chrono::Duration,
+    ) -> Result<(), Error> {
+        let result = match self.client() {
+            Ok(client) => {
+                client
+                    .reschedule_event(event_id, start_date_time, duration)
+                    .await
+            }
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    pub async fn cancel_event(&self, event_id: String) -> Result<(), Error> {
+        let result = match self.client() {
+            Ok(client) => client.cancel_event(event_id).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+}
+
+// Whether Meetup responded with an authentication error instead of an HTTP 401
+fn is_auth_error(errors: &[graphql_client::Error]) -> bool {
+    errors.iter().any(|error| {
+        error
+            .extensions
+            .as_ref()
+            .and_then(|extensions| extensions.get("code"))
+            .and_then(|code| code.as_str())
+            .is_some_and(|code| AUTH_ERROR_CODES.contains(&code))
+    })
+}
+
+#[async_trait]
+impl MeetupApi for MeetupConnection {
+    fn get_upcoming_events_all_groups(&self) -> BoxStream<'_, Result<UpcomingEventDetails, Error>> {
+        let events = match self.client() {
+            Ok(client) => client.get_upcoming_events_all_groups().boxed(),
+            Err(err) => futures::stream::once(async { Err(err) }).boxed(),
+        };
+        events
+            .then(move |result| async move {
+                self.record(&result).await;
+                result
+            })
+            .boxed()
+    }
+
+    async fn get_tickets_vec(&self, event_id: String) -> Result<Vec<Ticket>, Error> {
+        let result = match self.client() {
+            Ok(client) => client.get_tickets_vec(event_id).await,
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+
+    async fn get_event_status(&self, event_id: String) -> Result<Option<EventStatus>, Error> {
+        let result = match self.client() {
+            Ok(client) => client.get_event(event_id).await.map(|event| event.status),
+            Err(err) => Err(err),
+        };
+        self.record(&result).await;
+        result
+    }
+}
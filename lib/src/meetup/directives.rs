@@ -0,0 +1,399 @@
+use std::ops::Range;
+
+use lazy_static::lazy_static;
+use serenity::model::{
+    channel::ChannelType,
+    id::{ChannelId, RoleId},
+};
+
+// Matches every shortcode, including ones with malformed arguments, so that
+// these can be reported to the host. Markdown might escape the brackets.
+pub const SHORTCODE_PATTERN: &'static str =
+    r"(?i)\\?\[\s*(?P<name>new\s*adventure|new\s*campaign|campaign|channel|category|role|online|closed)\s*(?P<argument>[^\s\[\]\\]*)\s*\\?\]";
+
+lazy_static! {
+    static ref SHORTCODE_REGEX: regex::Regex = regex::Regex::new(SHORTCODE_PATTERN).unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcodeKind {
+    NewAdventure,
+    NewCampaign,
+    // [campaign X] links an event to the series of Meetup event X
+    Campaign,
+    Channel,
+    Category,
+    Role,
+    Online,
+    Closed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeriesType {
+    Adventure,
+    Campaign,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Spanned<T> {
+    pub value: T,
+    // Byte range of the shortcode in the description
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirectiveErrorKind {
+    MissingArgument { expected: &'static str },
+    InvalidArgument { expected: &'static str },
+    UnexpectedArgument,
+    // Contradicts the shortcode quoted here
+    Conflict { with: String },
+    ChannelWithoutNewSeries,
+    DoesNotExist { what: &'static str },
+    NotACategory,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectiveError {
+    // The shortcode as written in the description
+    pub shortcode: String,
+    pub span: Range<usize>,
+    pub kind: DirectiveErrorKind,
+}
+
+// The shortcodes of a Meetup event description. Invalid shortcodes are left
+// out and described in `errors` instead.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventDirectives {
+    pub new_series: Option<Spanned<SeriesType>>,
+    // The Meetup ID of an event in the series this event belongs to
+    pub series_event_id: Option<Spanned<String>>,
+    pub channel_id: Option<Spanned<ChannelId>>,
+    pub category_id: Option<Spanned<ChannelId>>,
+    pub role_ids: Vec<Spanned<RoleId>>,
+    pub online: bool,
+    pub closed: bool,
+    pub errors: Vec<DirectiveError>,
+}
+
+struct Shortcode<'a> {
+    kind: ShortcodeKind,
+    text: &'a str,
+    argument: &'a str,
+    span: Range<usize>,
+}
+
+fn shortcodes(description: &str) -> impl Iterator<Item = Shortcode<'_>> {
+    SHORTCODE_REGEX
+        .captures_iter(description)
+        .filter_map(|captures| {
+            let full_match = captures.get(0).unwrap();
+            let name = captures.name("name").unwrap();
+            let argument = captures.name("argument").unwrap();
+            // Something like "[roleplay]" is not a shortcode, but "[channel123]" is
+            if argument.start() == name.end()
+                && argument.as_str().starts_with(|c: char| !c.is_ascii_digit())
+            {
+                return None;
+            }
+            let name: String = name
+                .as_str()
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_lowercase();
+            let kind = match name.as_str() {
+                "newadventure" => ShortcodeKind::NewAdventure,
+                "newcampaign" => ShortcodeKind::NewCampaign,
+                "campaign" => ShortcodeKind::Campaign,
+                "channel" => ShortcodeKind::Channel,
+                "category" => ShortcodeKind::Category,
+                "role" => ShortcodeKind::Role,
+                "online" => ShortcodeKind::Online,
+                _ => ShortcodeKind::Closed,
+            };
+            Some(Shortcode {
+                kind,
+                text: full_match.as_str(),
+                argument: argument.as_str(),
+                span: full_match.range(),
+            })
+        })
+}
+
+// Removes the shortcodes of the specified kinds from the description
+pub fn remove_shortcodes(description: &str, kinds: &[ShortcodeKind]) -> String {
+    let mut result = String::with_capacity(description.len());
+    let mut last_end = 0;
+    for shortcode in shortcodes(description).filter(|shortcode| kinds.contains(&shortcode.kind)) {
+        result.push_str(&description[last_end..shortcode.span.start]);
+        last_end = shortcode.span.end;
+    }
+    result.push_str(&description[last_end..]);
+    result
+}
+
+impl DirectiveError {
+    fn new(shortcode: &Shortcode<'_>, kind: DirectiveErrorKind) -> Self {
+        DirectiveError {
+            shortcode: shortcode.text.to_string(),
+            span: shortcode.span.clone(),
+            kind,
+        }
+    }
+}
+
+// Parses a Discord ID, which is never zero
+fn parse_id(argument: &str) -> Option<u64> {
+    argument.parse::<u64>().ok().filter(|&id| id != 0)
+}
+
+impl EventDirectives {
+    pub fn parse(description: &str) -> Self {
+        let mut directives = EventDirectives::default();
+        for shortcode in shortcodes(description) {
+            if let Err(kind) = directives.add(&shortcode, description) {
+                directives
+                    .errors
+                    .push(DirectiveError::new(&shortcode, kind));
+            }
+        }
+        // Checks which involve several shortcodes
+        if let Some(series_event_id) = &directives.series_event_id {
+            let conflicting_span = directives
+                .new_series
+                .as_ref()
+                .map(|new_series| &new_series.span)
+                .or(directives.channel_id.as_ref().map(|channel| &channel.span));
+            if let Some(span) = conflicting_span {
+                directives.errors.push(DirectiveError {
+                    shortcode: description[series_event_id.span.clone()].to_string(),
+                    span: series_event_id.span.clone(),
+                    kind: DirectiveErrorKind::Conflict {
+                        with: description[span.clone()].to_string(),
+                    },
+                });
+            }
+        } else if let (Some(channel_id), None) = (&directives.channel_id, &directives.new_series) {
+            directives.errors.push(DirectiveError {
+                shortcode: description[channel_id.span.clone()].to_string(),
+                span: channel_id.span.clone(),
+                kind: DirectiveErrorKind::ChannelWithoutNewSeries,
+            });
+        }
+        directives.errors.sort_by_key(|error| error.span.start);
+        directives
+    }
+
+    fn add(
+        &mut self,
+        shortcode: &Shortcode<'_>,
+        description: &str,
+    ) -> Result<(), DirectiveErrorKind> {
+        let span = shortcode.span.clone();
+        match shortcode.kind {
+            ShortcodeKind::NewAdventure | ShortcodeKind::NewCampaign => {
+                let series_type = if shortcode.kind == ShortcodeKind::NewAdventure {
+                    SeriesType::Adventure
+                } else {
+                    SeriesType::Campaign
+                };
+                if !shortcode.argument.is_empty() {
+                    return Err(DirectiveErrorKind::UnexpectedArgument);
+                }
+                set_once(&mut self.new_series, series_type, shortcode, description)
+            }
+            ShortcodeKind::Campaign => {
+                let expected = "Meetup event ID";
+                if shortcode.argument.is_empty() {
+                    return Err(DirectiveErrorKind::MissingArgument { expected });
+                }
+                if !shortcode
+                    .argument
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric())
+                {
+                    return Err(DirectiveErrorKind::InvalidArgument { expected });
+                }
+                set_once(
+                    &mut self.series_event_id,
+                    shortcode.argument.to_string(),
+                    shortcode,
+                    description,
+                )
+            }
+            ShortcodeKind::Channel | ShortcodeKind::Category | ShortcodeKind::Role => {
+                let expected = match shortcode.kind {
+                    ShortcodeKind::Channel => "Discord channel ID",
+                    ShortcodeKind::Category => "Discord category ID",
+                    _ => "Discord role ID",
+                };
+                if shortcode.argument.is_empty() {
+                    return Err(DirectiveErrorKind::MissingArgument { expected });
+                }
+                let id = parse_id(shortcode.argument)
+                    .ok_or(DirectiveErrorKind::InvalidArgument { expected })?;
+                match shortcode.kind {
+                    ShortcodeKind::Channel => set_once(
+                        &mut self.channel_id,
+                        ChannelId::new(id),
+                        shortcode,
+                        description,
+                    ),
+                    ShortcodeKind::Category => set_once(
+                        &mut self.category_id,
+                        ChannelId::new(id),
+                        shortcode,
+                        description,
+                    ),
+                    _ => {
+                        let role_id = RoleId::new(id);
+                        if !self.role_ids.iter().any(|role| role.value == role_id) {
+                            self.role_ids.push(Spanned {
+                                value: role_id,
+                                span,
+                            });
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            ShortcodeKind::Online | ShortcodeKind::Closed => {
+                if !shortcode.argument.is_empty() {
+                    return Err(DirectiveErrorKind::UnexpectedArgument);
+                }
+                if shortcode.kind == ShortcodeKind::Online {
+                    self.online = true;
+                } else {
+                    self.closed = true;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    // Checks the referenced channels, categories and roles against the
+    // Discord cache. Channels and categories may be in any of the guilds,
+    // roles have to be in the specified guild.
+    pub fn validate(
+        &self,
+        description: &str,
+        cache: &serenity::cache::Cache,
+        guild_configs: &crate::discord::config::GuildConfigs,
+        role_guild_id: serenity::model::id::GuildId,
+    ) -> Vec<DirectiveError> {
+        let find_channel = |channel_id: ChannelId| {
+            guild_configs.iter().find_map(|guild_config| {
+                cache
+                    .guild(guild_config.guild_id)
+                    .and_then(|guild| guild.channels.get(&channel_id).map(|channel| channel.kind))
+            })
+        };
+        let mut errors = vec![];
+        let error = |span: &Range<usize>, kind| DirectiveError {
+            shortcode: description[span.clone()].to_string(),
+            span: span.clone(),
+            kind,
+        };
+        if let Some(channel_id) = &self.channel_id {
+            match find_channel(channel_id.value) {
+                Some(ChannelType::Text) => (),
+                Some(_) | None => errors.push(error(
+                    &channel_id.span,
+                    DirectiveErrorKind::DoesNotExist {
+                        what: "text channel",
+                    },
+                )),
+            }
+        }
+        if let Some(category_id) = &self.category_id {
+            match find_channel(category_id.value) {
+                Some(ChannelType::Category) => (),
+                Some(_) => errors.push(error(&category_id.span, DirectiveErrorKind::NotACategory)),
+                None => errors.push(error(
+                    &category_id.span,
+                    DirectiveErrorKind::DoesNotExist { what: "category" },
+                )),
+            }
+        }
+        let guild_roles: Option<Vec<RoleId>> = cache
+            .guild(role_guild_id)
+            .map(|guild| guild.roles.keys().copied().collect());
+        if let Some(guild_roles) = guild_roles {
+            for role_id in &self.role_ids {
+                if !guild_roles.contains(&role_id.value) {
+                    errors.push(error(
+                        &role_id.span,
+                        DirectiveErrorKind::DoesNotExist { what: "role" },
+                    ));
+                }
+            }
+        }
+        errors
+    }
+}
+
+// Sets a single-valued directive. Repeating a shortcode is fine as long as
+// it doesn't contradict the first one.
+fn set_once<T: PartialEq>(
+    field: &mut Option<Spanned<T>>,
+    value: T,
+    shortcode: &Shortcode<'_>,
+    description: &str,
+) -> Result<(), DirectiveErrorKind> {
+    match field {
+        Some(existing) if existing.value == value => Ok(()),
+        Some(existing) => Err(DirectiveErrorKind::Conflict {
+            with: description[existing.span.clone()].to_string(),
+        }),
+        None => {
+            *field = Some(Spanned {
+                value,
+                span: shortcode.span.clone(),
+            });
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for DirectiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            DirectiveErrorKind::MissingArgument { expected } => {
+                write!(f, "`{}` is missing a {}", self.shortcode, expected)
+            }
+            DirectiveErrorKind::InvalidArgument { expected } => {
+                write!(
+                    f,
+                    "`{}` doesn't contain a valid {}",
+                    self.shortcode, expected
+                )
+            }
+            DirectiveErrorKind::UnexpectedArgument => {
+                write!(f, "`{}` doesn't take an argument", self.shortcode)
+            }
+            DirectiveErrorKind::Conflict { with } => {
+                write!(f, "`{}` contradicts `{}`", self.shortcode, with)
+            }
+            DirectiveErrorKind::ChannelWithoutNewSeries => write!(
+                f,
+                "`{}` only works together with `[new adventure]` or `[new campaign]`",
+                self.shortcode
+            ),
+            DirectiveErrorKind::DoesNotExist { what } => {
+                write!(
+                    f,
+                    "`{}` refers to a {} which doesn't exist",
+                    self.shortcode, what
+                )
+            }
+            DirectiveErrorKind::NotACategory => {
+                write!(
+                    f,
+                    "`{}` refers to a channel which is not a category",
+                    self.shortcode
+                )
+            }
+        }
+    }
+}
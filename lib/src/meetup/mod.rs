@@ -1,6 +1,10 @@
+pub mod api;
+pub mod connection;
+pub mod directives;
 pub mod error;
 pub mod newapi;
 pub mod oauth2;
+pub mod rate_limit;
 pub mod sync;
 pub mod util;
 
@@ -1,10 +1,19 @@
 use chrono::{NaiveDate, TimeZone};
 use futures::{stream, Stream, StreamExt};
 use graphql_client::{GraphQLQuery, Response};
-use reqwest::header::{HeaderMap, AUTHORIZATION};
-use serde::{de::Error as _, Deserialize, Serialize, Serializer};
+use rand::Rng;
+use reqwest::{
+    header::{HeaderMap, AUTHORIZATION, RETRY_AFTER},
+    StatusCode,
+};
+use serde::{de::DeserializeOwned, de::Error as _, Deserialize, Serialize, Serializer};
+
+use super::rate_limit::{RATE_LIMITER, STATS};
 
 const API_ENDPOINT: &'static str = "https://api.meetup.com/gql";
+const MAX_ATTEMPTS: u32 = 5;
+const INITIAL_BACKOFF: std::time::Duration = std::time::Duration::from_millis(500);
+const MAX_BACKOFF: std::time::Duration = std::time::Duration::from_secs(30);
 pub const URLNAMES: [&'static str; 3] =
     ["SwissRPG-Zurich", "SwissRPG-Central", "SwissRPG-Romandie"];
 
@@ -80,11 +89,29 @@ pub struct CreateEventMutation;
 )]
 pub struct CloseEventRsvpsMutation;
 
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/meetup/schema.graphql",
+    query_path = "src/meetup/queries.graphql",
+    response_derives = "Debug,Clone"
+)]
+pub struct EditEventMutation;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "src/meetup/schema.graphql",
+    query_path = "src/meetup/queries.graphql",
+    response_derives = "Debug,Clone"
+)]
+pub struct CancelEventMutation;
+
 pub type UpcomingEventDetails =
     upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNode;
 
 pub type Ticket = event_tickets_query::EventTicketsQueryEventTicketsEdgesNode;
 
+pub type EventStatus = event_query::EventStatus;
+
 pub type TicketStatus = String;
 
 pub type NewEventResponse = create_event_mutation::CreateEventMutationCreateEventEvent;
@@ -100,6 +127,14 @@ pub struct AsyncClient {
     client: reqwest::Client,
 }
 
+// Mutations are only retried if they cannot have reached Meetup (or Meetup
+// refused them), so that e.g. events don't get created twice
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum RequestKind {
+    Query,
+    Mutation,
+}
+
 #[derive(Debug, Clone)]
 pub struct PayloadError {
     pub code: String,
@@ -113,6 +148,8 @@ pub enum Error {
     GraphQL(Vec<graphql_client::Error>),
     ResourceNotFound,
     Payload(Vec<PayloadError>),
+    // There is no organizer token to access the Meetup API with
+    NotConnected,
 }
 
 // TODO: using a GraphQL crate like "cynic" several queries could share the same type and we wouldn't need these conversions
@@ -129,6 +166,8 @@ impl From<NewEventResponse> for UpcomingEventDetails {
             max_tickets: event.max_tickets,
             going: event.going,
             is_online: event.is_online,
+            // Not part of the mutation's response
+            status: None,
             rsvp_settings: event.rsvp_settings.map(|rsvp_settings| upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNodeRsvpSettings { rsvps_closed: rsvp_settings.rsvps_closed }),
             venue: event.venue.map(|venue| upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNodeVenue { lat: venue.lat, lng: venue.lng, city: venue.city }),
             group: event.group.map(|group| upcoming_events_query::UpcomingEventsQueryGroupByUrlnameUpcomingEventsEdgesNodeGroup { urlname: group.urlname }),
@@ -146,6 +185,26 @@ impl From<create_event_mutation::CreateEventMutationCreateEventErrors> for Paylo
     }
 }
 
+impl From<edit_event_mutation::EditEventMutationEditEventErrors> for PayloadError {
+    fn from(error: edit_event_mutation::EditEventMutationEditEventErrors) -> Self {
+        PayloadError {
+            code: error.code,
+            message: error.message,
+            field: error.field,
+        }
+    }
+}
+
+impl From<cancel_event_mutation::CancelEventMutationCancelEventErrors> for PayloadError {
+    fn from(error: cancel_event_mutation::CancelEventMutationCancelEventErrors) -> Self {
+        PayloadError {
+            code: error.code,
+            message: error.message,
+            field: error.field,
+        }
+    }
+}
+
 impl From<chrono::Duration> for Duration {
     fn from(duration: chrono::Duration) -> Self {
         Duration(duration)
@@ -307,6 +366,24 @@ impl UpcomingEventDetails {
             .map(|hosts| hosts.iter().map(|user| user.id.0).collect())
             .unwrap_or(vec![])
     }
+
+    pub fn is_cancelled(&self) -> bool {
+        matches!(
+            self.status,
+            Some(upcoming_events_query::EventStatus::CANCELLED)
+        )
+    }
+
+    // Whether Meetup explicitly reported the event as taking place
+    pub fn is_live(&self) -> bool {
+        matches!(
+            self.status,
+            Some(
+                upcoming_events_query::EventStatus::PUBLISHED
+                    | upcoming_events_query::EventStatus::ACTIVE
+            )
+        )
+    }
 }
 
 impl std::fmt::Display for Error {
@@ -320,6 +397,7 @@ impl std::fmt::Display for Error {
             Error::GraphQL(error) => write!(f, "Meetup GraphQL Client Error:\n{:#?}", error),
             Error::ResourceNotFound => write!(f, "Resource not found"),
             Error::Payload(errors) => write!(f, "Payload errors:\n{:#?}", errors),
+            Error::NotConnected => write!(f, "Meetup API unavailable"),
         }
     }
 }
@@ -343,6 +421,7 @@ impl std::error::Error for Error {
             Error::GraphQL(..) => None,
             Error::ResourceNotFound => None,
             Error::Payload(..) => None,
+            Error::NotConnected => None,
         }
     }
 }
@@ -353,6 +432,28 @@ impl From<reqwest::Error> for Error {
     }
 }
 
+// Whether a failed request should be retried
+fn is_transient(error: &reqwest::Error, kind: RequestKind) -> bool {
+    match error.status() {
+        Some(StatusCode::TOO_MANY_REQUESTS) => true,
+        Some(status) => status.is_server_error() && kind == RequestKind::Query,
+        None => error.is_connect() || (error.is_timeout() && kind == RequestKind::Query),
+    }
+}
+
+// The delay requested by a "Retry-After" header in seconds
+fn retry_after(http_response: &reqwest::Response) -> Option<std::time::Duration> {
+    http_response
+        .headers()
+        .get(RETRY_AFTER)?
+        .to_str()
+        .ok()?
+        .trim()
+        .parse()
+        .ok()
+        .map(std::time::Duration::from_secs)
+}
+
 impl self_query::SelfQuerySelfMemberPhoto {
     pub fn url_for_size(&self, width: u16, height: u16) -> Option<String> {
         if let Some(base_url) = &self.base_url {
@@ -378,12 +479,72 @@ impl AsyncClient {
         }
     }
 
+    // Sends a GraphQL request once the rate limiter allows it and retries it
+    // with exponential backoff on rate limiting, server and network errors
+    async fn post<Q: Serialize, R: DeserializeOwned>(
+        &self,
+        body: &Q,
+        kind: RequestKind,
+    ) -> Result<Response<R>, Error> {
+        let mut backoff = INITIAL_BACKOFF;
+        let mut attempt = 1;
+        loop {
+            RATE_LIMITER.acquire().await;
+            STATS.record_request();
+            let (error, delay) = match self.client.post(API_ENDPOINT).json(body).send().await {
+                Ok(http_response) => match http_response.error_for_status_ref().err() {
+                    Some(error) if error.status() == Some(StatusCode::UNAUTHORIZED) => {
+                        // The token expired or was revoked
+                        STATS.record_failure();
+                        return Err(error.into());
+                    }
+                    Some(error) if is_transient(&error, kind) => {
+                        if error.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
+                            STATS.record_rate_limited();
+                            // Slow down all other requests as well
+                            let delay = retry_after(&http_response).unwrap_or(backoff);
+                            RATE_LIMITER.pause(delay);
+                            (error, delay)
+                        } else {
+                            (error, backoff)
+                        }
+                    }
+                    _ => {
+                        // GraphQL errors are part of the response body
+                        return http_response.json().await.map_err(|error| {
+                            STATS.record_failure();
+                            error.into()
+                        });
+                    }
+                },
+                Err(error) if is_transient(&error, kind) => (error, backoff),
+                Err(error) => {
+                    STATS.record_failure();
+                    return Err(error.into());
+                }
+            };
+            if attempt >= MAX_ATTEMPTS {
+                STATS.record_failure();
+                return Err(error.into());
+            }
+            let jitter = rand::thread_rng().gen_range(0..=delay.as_millis() as u64 / 2);
+            let delay = delay + std::time::Duration::from_millis(jitter);
+            eprintln!(
+                "Meetup request failed (attempt {}/{}), retrying in {:?}:\n{:#?}",
+                attempt, MAX_ATTEMPTS, delay, error
+            );
+            STATS.record_retry();
+            tokio::time::sleep(delay).await;
+            backoff = (backoff * 2).min(MAX_BACKOFF);
+            attempt += 1;
+        }
+    }
+
     pub async fn get_event(&self, id: String) -> Result<event_query::EventQueryEvent, Error> {
         use event_query::*;
         let query_variables = Variables { id };
         let query = EventQuery::build_query(query_variables);
-        let http_response = self.client.post(API_ENDPOINT).json(&query).send().await?;
-        let response: Response<ResponseData> = http_response.json().await?;
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Query).await?;
         match response.data {
             Some(ResponseData { event: Some(event) }) => Ok(event),
             Some(ResponseData { event: None }) => Err(Error::ResourceNotFound),
@@ -391,10 +552,10 @@ impl AsyncClient {
         }
     }
 
-    pub fn get_upcoming_events<'a>(
-        &'a self,
+    pub fn get_upcoming_events(
+        &self,
         urlname: String,
-    ) -> impl Stream<Item = Result<UpcomingEventDetails, Error>> + 'a {
+    ) -> impl Stream<Item = Result<UpcomingEventDetails, Error>> + 'static {
         use upcoming_events_query::*;
         enum States {
             QueryPage {
@@ -408,8 +569,10 @@ impl AsyncClient {
         }
         const NUM_EVENTS_PER_QUERY: i64 = 20;
         let state = States::QueryPage { cursor: None };
+        let client = self.clone();
         futures::stream::unfold(state, move |mut state| {
             let urlname = urlname.clone();
+            let client = client.clone();
             async move {
                 loop {
                     state = match state {
@@ -444,16 +607,11 @@ impl AsyncClient {
                                 after: cursor,
                             };
                             let query = UpcomingEventsQuery::build_query(query_variables);
-                            let http_response =
-                                match self.client.post(API_ENDPOINT).json(&query).send().await {
-                                    Err(error) => return Some((Err(error.into()), States::End)),
+                            let response: Response<ResponseData> =
+                                match client.post(&query, RequestKind::Query).await {
+                                    Err(error) => return Some((Err(error), States::End)),
                                     Ok(response) => response,
                                 };
-                            let response: Response<ResponseData> = match http_response.json().await
-                            {
-                                Err(error) => return Some((Err(error.into()), States::End)),
-                                Ok(response) => response,
-                            };
                             match response.data {
                                 Some(ResponseData {
                                     group_by_urlname: None,
@@ -494,16 +652,17 @@ impl AsyncClient {
         })
     }
 
-    pub fn get_upcoming_events_all_groups<'a>(
-        &'a self,
-    ) -> impl Stream<Item = Result<UpcomingEventDetails, Error>> + 'a {
-        stream::iter(URLNAMES).flat_map(|urlname| self.get_upcoming_events(urlname.into()))
+    pub fn get_upcoming_events_all_groups(
+        &self,
+    ) -> impl Stream<Item = Result<UpcomingEventDetails, Error>> + 'static {
+        let client = self.clone();
+        stream::iter(URLNAMES).flat_map(move |urlname| client.get_upcoming_events(urlname.into()))
     }
 
-    pub fn get_tickets<'a>(
-        &'a self,
+    pub fn get_tickets(
+        &self,
         event_id: String,
-    ) -> impl Stream<Item = Result<Ticket, Error>> + 'a {
+    ) -> impl Stream<Item = Result<Ticket, Error>> + 'static {
         use event_tickets_query::*;
         enum States {
             QueryPage {
@@ -517,8 +676,10 @@ impl AsyncClient {
         }
         const NUM_TICKETS_PER_QUERY: i64 = 10;
         let state = States::QueryPage { cursor: None };
+        let client = self.clone();
         futures::stream::unfold(state, move |mut state| {
             let event_id = event_id.clone();
+            let client = client.clone();
             async move {
                 loop {
                     state = match state {
@@ -553,16 +714,11 @@ impl AsyncClient {
                                 after: cursor,
                             };
                             let query = EventTicketsQuery::build_query(query_variables);
-                            let http_response =
-                                match self.client.post(API_ENDPOINT).json(&query).send().await {
-                                    Err(error) => return Some((Err(error.into()), States::End)),
+                            let response: Response<ResponseData> =
+                                match client.post(&query, RequestKind::Query).await {
+                                    Err(error) => return Some((Err(error), States::End)),
                                     Ok(response) => response,
                                 };
-                            let response: Response<ResponseData> = match http_response.json().await
-                            {
-                                Err(error) => return Some((Err(error.into()), States::End)),
-                                Ok(response) => response,
-                            };
                             match response.data {
                                 Some(ResponseData { event: None }) => {
                                     return Some((Err(Error::ResourceNotFound), States::End))
@@ -614,8 +770,7 @@ impl AsyncClient {
     pub async fn get_self(&self) -> Result<self_query::SelfQuerySelf, Error> {
         use self_query::*;
         let query = SelfQuery::build_query(Variables {});
-        let http_response = self.client.post(API_ENDPOINT).json(&query).send().await?;
-        let response: Response<ResponseData> = http_response.json().await?;
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Query).await?;
         match response.data {
             Some(ResponseData { self_: Some(self_) }) => Ok(self_),
             Some(ResponseData { self_: None }) => Err(Error::ResourceNotFound),
@@ -627,8 +782,7 @@ impl AsyncClient {
         use create_event_mutation::*;
         let query_variables = Variables { input: new_event };
         let query = CreateEventMutation::build_query(query_variables);
-        let http_response = self.client.post(API_ENDPOINT).json(&query).send().await?;
-        let response: Response<ResponseData> = http_response.json().await?;
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Mutation).await?;
         match response.data {
             Some(ResponseData {
                 create_event:
@@ -651,8 +805,7 @@ impl AsyncClient {
         use group_membership_query::*;
         let query_variables = Variables { urlname: urlname };
         let query = GroupMembershipQuery::build_query(query_variables);
-        let http_response = self.client.post(API_ENDPOINT).json(&query).send().await?;
-        let response: Response<ResponseData> = http_response.json().await?;
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Query).await?;
         match response.data {
             Some(ResponseData {
                 group_by_urlname: Some(group_membership),
@@ -670,8 +823,7 @@ impl AsyncClient {
             input: CloseEventRsvpsInput { event_id: event_id },
         };
         let query = CloseEventRsvpsMutation::build_query(query_variables);
-        let http_response = self.client.post(API_ENDPOINT).json(&query).send().await?;
-        let response: Response<ResponseData> = http_response.json().await?;
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Mutation).await?;
         match response.data {
             Some(ResponseData {
                 close_event_rsvps:
@@ -685,4 +837,74 @@ impl AsyncClient {
             _ => Err(Error::GraphQL(response.errors.unwrap_or(vec![]))),
         }
     }
+
+    // Moves the event to a new start time and duration
+    pub async fn reschedule_event(
+        &self,
+        event_id: String,
+        start_date_time: chrono::DateTime<chrono::Utc>,
+        duration: chrono::Duration,
+    ) -> Result<(), Error> {
+        use edit_event_mutation::*;
+        let query_variables = Variables {
+            input: EditEventInput {
+                event_id: event_id,
+                start_date_time: Some(DateTime(start_date_time)),
+                duration: Some(duration.into()),
+            },
+        };
+        let query = EditEventMutation::build_query(query_variables);
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Mutation).await?;
+        match response.data {
+            Some(ResponseData {
+                edit_event:
+                    EditEventMutationEditEvent {
+                        event: Some(..), ..
+                    },
+            }) => Ok(()),
+            Some(ResponseData {
+                edit_event:
+                    EditEventMutationEditEvent {
+                        event: None,
+                        errors: Some(errors),
+                    },
+            }) if !errors.is_empty() => {
+                Err(Error::Payload(errors.into_iter().map(Into::into).collect()))
+            }
+            Some(ResponseData {
+                edit_event: EditEventMutationEditEvent { event: None, .. },
+            }) => Err(Error::ResourceNotFound),
+            _ => Err(Error::GraphQL(response.errors.unwrap_or(vec![]))),
+        }
+    }
+
+    pub async fn cancel_event(&self, event_id: String) -> Result<(), Error> {
+        use cancel_event_mutation::*;
+        let query_variables = Variables {
+            input: CancelEventInput { event_id: event_id },
+        };
+        let query = CancelEventMutation::build_query(query_variables);
+        let response: Response<ResponseData> = self.post(&query, RequestKind::Mutation).await?;
+        match response.data {
+            Some(ResponseData {
+                cancel_event:
+                    CancelEventMutationCancelEvent {
+                        event: Some(..), ..
+                    },
+            }) => Ok(()),
+            Some(ResponseData {
+                cancel_event:
+                    CancelEventMutationCancelEvent {
+                        event: None,
+                        errors: Some(errors),
+                    },
+            }) if !errors.is_empty() => {
+                Err(Error::Payload(errors.into_iter().map(Into::into).collect()))
+            }
+            Some(ResponseData {
+                cancel_event: CancelEventMutationCancelEvent { event: None, .. },
+            }) => Err(Error::ResourceNotFound),
+            _ => Err(Error::GraphQL(response.errors.unwrap_or(vec![]))),
+        }
+    }
 }
@@ -0,0 +1,148 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use lazy_static::lazy_static;
+use serde::Serialize;
+use tokio::time::Instant;
+
+const REQUESTS_PER_MINUTE_VAR: &str = "MEETUP_REQUESTS_PER_MINUTE";
+const DEFAULT_REQUESTS_PER_MINUTE: f64 = 120.0;
+const BURST_SIZE: f64 = 10.0;
+
+lazy_static! {
+    // Shared by all Meetup clients (the organizer's and the users' ones), since
+    // Meetup's rate limit applies to the bot's OAuth consumer as a whole
+    pub static ref RATE_LIMITER: RateLimiter = RateLimiter::new(
+        std::env::var(REQUESTS_PER_MINUTE_VAR)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|&requests_per_minute: &f64| requests_per_minute > 0.0)
+            .unwrap_or(DEFAULT_REQUESTS_PER_MINUTE),
+        BURST_SIZE,
+    );
+    pub static ref STATS: RequestStats = RequestStats::default();
+}
+
+// A token bucket which allows short bursts of requests while keeping the
+// average request rate below the configured limit
+#[derive(Debug)]
+pub struct RateLimiter {
+    capacity: f64,
+    tokens_per_second: f64,
+    state: Mutex<RateLimiterState>,
+}
+
+#[derive(Debug)]
+struct RateLimiterState {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+impl RateLimiter {
+    pub fn new(requests_per_minute: f64, capacity: f64) -> Self {
+        RateLimiter {
+            capacity,
+            tokens_per_second: requests_per_minute / 60.0,
+            state: Mutex::new(RateLimiterState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+        }
+    }
+
+    // Waits until a request may be sent
+    pub async fn acquire(&self) {
+        while let Some(wait) = self.try_acquire() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Takes a token if one is available, otherwise returns how long to wait
+    fn try_acquire(&self) -> Option<Duration> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        if let Some(paused_until) = state.paused_until {
+            if paused_until > now {
+                return Some(paused_until - now);
+            }
+            state.paused_until = None;
+        }
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens = (state.tokens + elapsed * self.tokens_per_second).min(self.capacity);
+        state.last_refill = now;
+        if state.tokens >= 1.0 {
+            state.tokens -= 1.0;
+            None
+        } else {
+            Some(Duration::from_secs_f64(
+                (1.0 - state.tokens) / self.tokens_per_second,
+            ))
+        }
+    }
+
+    // Holds back all requests for the specified duration, e.g. after Meetup
+    // responded with "429 Too Many Requests"
+    pub fn pause(&self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        let paused_until = Instant::now() + duration;
+        state.tokens = 0.0;
+        state.paused_until = Some(match state.paused_until {
+            Some(previous) if previous > paused_until => previous,
+            _ => paused_until,
+        });
+    }
+}
+
+// Counters for monitoring the requests sent to the Meetup API
+#[derive(Debug, Default)]
+pub struct RequestStats {
+    requests: AtomicU64,
+    retries: AtomicU64,
+    rate_limited: AtomicU64,
+    failures: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct RequestStatsSnapshot {
+    // HTTP requests sent, including retries
+    pub requests: u64,
+    pub retries: u64,
+    // Responses with status "429 Too Many Requests"
+    pub rate_limited: u64,
+    // Requests which failed for good, i.e. were not (or no longer) retried
+    pub failures: u64,
+}
+
+impl RequestStats {
+    pub fn record_request(&self) {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_retry(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_rate_limited(&self) {
+        self.rate_limited.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_failure(&self) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> RequestStatsSnapshot {
+        RequestStatsSnapshot {
+            requests: self.requests.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            rate_limited: self.rate_limited.load(Ordering::Relaxed),
+            failures: self.failures.load(Ordering::Relaxed),
+        }
+    }
+}
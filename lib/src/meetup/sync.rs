@@ -1,51 +1,25 @@
-use futures_util::{lock::Mutex, stream::StreamExt};
+use futures_util::stream::StreamExt;
 use lazy_static::lazy_static;
 use simple_error::SimpleError;
-use std::sync::Arc;
+use std::collections::HashSet;
 
+use super::{
+    api::MeetupApi,
+    directives::{EventDirectives, SeriesType},
+};
 use crate::{db, DefaultStr};
 
-pub const NEW_ADVENTURE_PATTERN: &'static str = r"(?i)\\?\[\s*new\s*adventure\s*\\?\]";
-pub const NEW_CAMPAIGN_PATTERN: &'static str = r"(?i)\\?\[\s*new\s*campaign\s*\\?\]";
-pub const EVENT_SERIES_PATTERN: &'static str =
-    r"(?i)\\?\[\s*campaign\s*(?P<event_id>[a-zA-Z0-9]+)\s*\\?\]";
-pub const CHANNEL_PATTERN: &'static str = r"(?i)\\?\[\s*channel\s*(?P<channel_id>[0-9]+)\s*\\?\]";
 pub const SESSION_PATTERN: &'static str = r"(?i)\s*session\s*(?P<number>[0-9]+)";
-pub const ONLINE_PATTERN: &'static str = r"(?i)\\?\[\s*online\s*\\?\]";
-pub const ROLE_PATTERN: &'static str = r"(?i)\\?\[\s*role\s*(?P<role_id>[0-9]+)\s*\\?\]";
-pub const CATEGORY_PATTERN: &'static str =
-    r"(?i)\\?\[\s*category\s*(?P<category_id>[0-9]+)\s*\\?\]";
 
 lazy_static! {
-    pub static ref NEW_ADVENTURE_REGEX: regex::Regex =
-        regex::Regex::new(NEW_ADVENTURE_PATTERN).unwrap();
-    pub static ref NEW_CAMPAIGN_REGEX: regex::Regex =
-        regex::Regex::new(NEW_CAMPAIGN_PATTERN).unwrap();
-    pub static ref EVENT_SERIES_REGEX: regex::Regex =
-        regex::Regex::new(EVENT_SERIES_PATTERN).unwrap();
-    pub static ref CHANNEL_REGEX: regex::Regex = regex::Regex::new(CHANNEL_PATTERN).unwrap();
     pub static ref SESSION_REGEX: regex::Regex = regex::Regex::new(SESSION_PATTERN).unwrap();
-    pub static ref ONLINE_REGEX: regex::Regex = regex::Regex::new(ONLINE_PATTERN).unwrap();
-    pub static ref ROLE_REGEX: regex::Regex = regex::Regex::new(ROLE_PATTERN).unwrap();
-    pub static ref CATEGORY_REGEX: regex::Regex = regex::Regex::new(CATEGORY_PATTERN).unwrap();
 }
 
-// TODO: Introduce a type like "Meetup connection" that contains
-// an Arc<Mutex<Option<MeetupClient>>> internally and has the same
-// methods as MeetupClient (so we don't need to match on the Option
-// every time we want to use the client)
 pub async fn sync_task(
-    meetup_client: Arc<Mutex<Option<Arc<super::newapi::AsyncClient>>>>,
+    meetup_client: &impl MeetupApi,
     db_connection: &sqlx::PgPool,
 ) -> Result<crate::free_spots::EventCollector, super::Error> {
-    let meetup_client = {
-        let guard = meetup_client.lock().await;
-        match *guard {
-            Some(ref meetup_client) => meetup_client.clone(),
-            None => return Err(SimpleError::new("Meetup API unavailable").into()),
-        }
-        // The Mutex guard will be dropped here
-    };
+    let listed_at = chrono::Utc::now();
     let upcoming_events = meetup_client.get_upcoming_events_all_groups();
     futures::pin_mut!(upcoming_events);
     // Sync events
@@ -53,18 +27,34 @@ pub async fn sync_task(
     // While looping over the upcoming events, we also keep information about
     // free spots. This information will be posted to Discord.
     let mut event_collector = crate::free_spots::EventCollector::new();
+    // Tracked events missing from a complete listing were cancelled or deleted
+    let mut listed_meetup_ids = HashSet::new();
+    let mut listing_complete = true;
     while let Some(event) = upcoming_events.next().await {
         match event {
-            Err(err) => eprintln!("Couldn't query upcoming event: {}", err),
+            Err(err) => {
+                listing_complete = false;
+                eprintln!("Couldn't query upcoming event: {}", err)
+            }
             Ok(event) => {
-                event_collector.add_event(event.clone());
-                match sync_event(event, db_connection).await {
+                listed_meetup_ids.insert(event.id.0.clone());
+                if !event.is_cancelled() {
+                    event_collector.add_event(event.clone());
+                }
+                match sync_event(event, listed_at, db_connection).await {
                     Err(err) => eprintln!("Event sync failed: {}", err),
                     _ => (),
                 }
             }
         }
     }
+    if listing_complete {
+        if let Err(err) =
+            sync_unlisted_events(&listed_meetup_ids, meetup_client, db_connection).await
+        {
+            eprintln!("Syncing unlisted events failed: {}", err);
+        }
+    }
     // Sync event series with upcoming Meetup events
     let meetup_active_event_series = sqlx::query!(
         r#"SELECT event_series.id
@@ -78,60 +68,45 @@ pub async fn sync_task(
     .fetch_all(db_connection)
     .await?;
     for series_id in meetup_active_event_series {
-        match sync_event_series(series_id, meetup_client.as_ref(), db_connection).await {
+        match sync_event_series(series_id, meetup_client, db_connection).await {
             Err(err) => eprintln!("Series sync failed: {}", err),
             _ => (),
         };
-        // Add a 250ms delay between each item as a naive rate limit for the Meetup API
-        tokio::time::sleep(std::time::Duration::from_millis(250)).await;
     }
     Ok(event_collector)
 }
 
 // This function is supposed to be idempotent, so calling it with the same
-// event is fine.
+// event is fine. `listed_at` is when Meetup was asked for the event.
 pub async fn sync_event(
     event: super::newapi::UpcomingEventDetails,
+    listed_at: chrono::DateTime<chrono::Utc>,
     db_connection: &sqlx::PgPool,
 ) -> Result<(), super::Error> {
+    if event.is_cancelled() {
+        return mark_meetup_event_cancelled(&event.id.0, db_connection).await;
+    }
+    let is_live = event.is_live();
     let description = event.description.unwrap_or_str("");
     let title = event.title.unwrap_or_str("No title");
-    let is_new_adventure = NEW_ADVENTURE_REGEX.is_match(description);
-    let is_new_campaign = NEW_CAMPAIGN_REGEX.is_match(description);
-    let is_online = event.is_online || ONLINE_REGEX.is_match(description);
-    let event_series_captures = EVENT_SERIES_REGEX.captures(description);
-    let channel_captures = CHANNEL_REGEX.captures(description);
-    let category_captures = CATEGORY_REGEX.captures(description);
-    let indicated_channel_id = match channel_captures {
-        Some(captures) => match captures.name("channel_id") {
-            Some(id) => match id.as_str().parse::<u64>() {
-                Ok(id) => Some(id),
-                _ => return Err(SimpleError::new("Invalid channel id").into()),
-            },
-            _ => return Err(SimpleError::new("Internal error parsing channel id").into()),
-        },
-        _ => None,
-    };
-    let category_id = match category_captures {
-        Some(captures) => match captures.name("category_id") {
-            Some(id) => match id.as_str().parse::<u64>() {
-                Ok(id) => Some(id),
-                _ => {
-                    eprintln!(
-                        "Event {} specifies invalid category ID {}",
-                        title,
-                        id.as_str()
-                    );
-                    None
-                }
-            },
-            _ => {
-                eprintln!("Internal error parsing category ID");
-                None
-            }
-        },
-        _ => None,
-    };
+    // Problems with the shortcodes are reported to the hosts separately (see
+    // EventCollector::report_directive_errors), invalid ones are ignored here
+    let directives = EventDirectives::parse(description);
+    let new_series = directives
+        .new_series
+        .as_ref()
+        .map(|new_series| new_series.value);
+    let is_new_adventure = new_series == Some(SeriesType::Adventure);
+    let is_new_campaign = new_series == Some(SeriesType::Campaign);
+    let is_online = event.is_online || directives.online;
+    let indicated_channel_id = directives
+        .channel_id
+        .as_ref()
+        .map(|channel_id| channel_id.value.get());
+    let category_id = directives
+        .category_id
+        .as_ref()
+        .map(|category_id| category_id.value.get());
     let urlname = if let Some(urlname) = event
         .group
         .as_ref()
@@ -150,14 +125,14 @@ pub async fn sync_event(
         ))
         .into());
     }
-    // Either: new adventure, new campaign, or continuation (event series)
-    if !(is_new_adventure || is_new_campaign || event_series_captures.is_some()) {
-        println!("Syncing task: Ignoring event \"{}\"", title);
-        return Ok(());
-    } else {
+    // Either: new adventure, new campaign, or continuation (event series).
+    // Events without shortcodes are still synced below if they are already
+    // linked to an event in the database (e.g. sessions scheduled in Discord
+    // and then published on Meetup).
+    if is_new_adventure || is_new_campaign || directives.series_event_id.is_some() {
         println!("Syncing task: found event \"{}\"", title);
     }
-    if event_series_captures.is_some()
+    if directives.series_event_id.is_some()
         && (is_new_adventure || is_new_campaign || indicated_channel_id.is_some())
     {
         eprintln!(
@@ -183,15 +158,9 @@ pub async fn sync_event(
     let existing_series_id = row.as_ref().map(|row| row.event_series_id);
 
     // If this is part of an event series, figure out which
-    let indicated_event_series_id = if let Some(event_series_captures) = event_series_captures {
+    let indicated_event_series_id = if let Some(series_event_id) = &directives.series_event_id {
         // This is the event ID of an event that belongs to this series
-        let series_event_id = match event_series_captures.name("event_id") {
-            Some(id) => id.as_str(),
-            None => {
-                eprintln!("Syncing task: error capturing event_id");
-                return Ok(());
-            }
-        };
+        let series_event_id = series_event_id.value.as_str();
         // Look up that event's series ID
         let event_series_id = sqlx::query_scalar!(
             r#"SELECT event.event_series_id
@@ -345,11 +314,21 @@ pub async fn sync_event(
         }
     };
 
-    // Create or update the event and corresponding Meetup event in the database
+    // Meetup uses a ticket limit of 0 for events without a limit
+    let guest_limit = if event.max_tickets > 0 {
+        Some(event.max_tickets as i32)
+    } else {
+        None
+    };
+    // Create or update the event and corresponding Meetup event in the database.
+    // A deleted event is only restored if Meetup says it's live and it was
+    // deleted before it was listed (deletion times are rounded to seconds),
+    // since the listing might predate a cancellation. A pending cancellation
+    // announcement is left to the Discord sync.
     let db_event_id = if let Some(db_event_id) = db_event_id {
         sqlx::query_scalar!(
             r#"UPDATE event
-            SET event_series_id = $1, start_time = $2, title = $3, description = $4, is_online = $5, discord_category_id = $6
+            SET event_series_id = $1, start_time = $2, title = $3, description = $4, is_online = $5, discord_category_id = $6, guest_limit = $8, deleted = CASE WHEN $9 AND deleted + INTERVAL '1 second' < $10 THEN NULL ELSE deleted END
             WHERE id = $7
             RETURNING id"#,
             series_id,
@@ -358,19 +337,23 @@ pub async fn sync_event(
             description,
             is_online,
             category_id.map(|id| id as i64),
-            db_event_id
+            db_event_id,
+            guest_limit,
+            is_live,
+            listed_at
         ).fetch_one(&mut *tx).await?
     } else {
         sqlx::query_scalar!(
-            r#"INSERT INTO event (event_series_id, start_time, title, description, is_online, discord_category_id)
-            VALUES ($1, $2, $3, $4, $5, $6)
+            r#"INSERT INTO event (event_series_id, start_time, title, description, is_online, discord_category_id, guest_limit)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
             RETURNING id"#,
             series_id,
             event.date_time.0,
             title,
             description,
             is_online,
-            category_id.map(|id| id as i64)
+            category_id.map(|id| id as i64),
+            guest_limit
         ).fetch_one(&mut *tx).await?
     };
     let _db_meetup_event_id = if let Some(db_meetup_event_id) = db_meetup_event_id {
@@ -418,7 +401,7 @@ pub async fn sync_event(
 
 async fn sync_event_series(
     series_id: db::EventSeriesId,
-    meetup_client: &super::newapi::AsyncClient,
+    meetup_client: &impl MeetupApi,
     db_connection: &sqlx::PgPool,
 ) -> Result<(), super::Error> {
     // Get all events belonging to this event series
@@ -445,12 +428,7 @@ async fn sync_event_series(
                     "Event {} was deleted from Meetup, removing from database...",
                     next_event.id.0
                 );
-                sqlx::query!(
-                    "UPDATE event SET deleted = NOW() WHERE id = $1",
-                    next_event.id.0
-                )
-                .execute(db_connection)
-                .await?;
+                mark_event_cancelled(next_event.id, db_connection).await?;
                 eprintln!("Removed event {} from database", next_event.id.0);
                 continue;
             }
@@ -464,6 +442,89 @@ async fn sync_event_series(
     Ok(())
 }
 
+// Checks the tracked upcoming events which were not listed by Meetup and
+// marks those as deleted which were cancelled or deleted on Meetup
+async fn sync_unlisted_events(
+    listed_meetup_ids: &HashSet<String>,
+    meetup_client: &impl MeetupApi,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), super::Error> {
+    let tracked_events = sqlx::query!(
+        r#"SELECT event.id, meetup_event.meetup_id
+        FROM event
+        INNER JOIN meetup_event ON event.id = meetup_event.event_id
+        WHERE event.start_time > NOW() AND event.deleted IS NULL"#
+    )
+    .fetch_all(db_connection)
+    .await?;
+    for tracked_event in tracked_events {
+        if listed_meetup_ids.contains(&tracked_event.meetup_id) {
+            continue;
+        }
+        let is_cancelled = match meetup_client
+            .get_event_status(tracked_event.meetup_id.clone())
+            .await
+        {
+            Err(super::newapi::Error::ResourceNotFound) => true,
+            Ok(Some(super::newapi::EventStatus::CANCELLED)) => true,
+            // E.g. drafts, which are not listed as upcoming events
+            Ok(_) => false,
+            Err(err) => {
+                eprintln!(
+                    "Could not query the status of Meetup event {}: {}",
+                    tracked_event.meetup_id, err
+                );
+                false
+            }
+        };
+        if is_cancelled {
+            println!(
+                "Meetup event {} was cancelled or deleted, removing from database",
+                tracked_event.meetup_id
+            );
+            mark_event_cancelled(db::EventId(tracked_event.id), db_connection).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn mark_meetup_event_cancelled(
+    meetup_id: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), super::Error> {
+    let event_id = sqlx::query_scalar!(
+        r#"SELECT event_id FROM meetup_event WHERE meetup_id = $1"#,
+        meetup_id
+    )
+    .fetch_optional(db_connection)
+    .await?;
+    if let Some(event_id) = event_id {
+        mark_event_cancelled(db::EventId(event_id), db_connection).await?;
+    }
+    Ok(())
+}
+
+// Marks the event as deleted. The Discord sync announces the cancellation of
+// upcoming events in the series channel.
+async fn mark_event_cancelled(
+    event_id: db::EventId,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), super::Error> {
+    let newly_cancelled = sqlx::query!(
+        r#"UPDATE event SET deleted = NOW(), cancellation_announcement_pending = start_time > NOW()
+        WHERE id = $1 AND deleted IS NULL"#,
+        event_id.0
+    )
+    .execute(db_connection)
+    .await?
+    .rows_affected()
+        > 0;
+    if newly_cancelled {
+        println!("Marked event {} as cancelled", event_id.0);
+    }
+    Ok(())
+}
+
 pub async fn sync_rsvps(
     event_id: db::EventId,
     tickets: Vec<super::newapi::Ticket>,
@@ -486,9 +547,10 @@ pub async fn sync_rsvps(
         let member_id = db::get_or_create_member_for_meetup_id(&mut tx, user_id).await?;
         rsvp_yes_member_ids.push(member_id);
     }
-    // Remove all users which are not attending
+    // Remove all users which are not attending anymore. RSVPs made in Discord
+    // are not known to Meetup and are kept.
     sqlx::query!(
-        r#"DELETE FROM event_participant WHERE event_id = $1 AND NOT (member_id = ANY($2))"#,
+        r#"DELETE FROM event_participant WHERE event_id = $1 AND origin = 'meetup' AND NOT (member_id = ANY($2))"#,
         event_id.0,
         &rsvp_yes_member_ids
             .iter()
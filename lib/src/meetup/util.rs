@@ -3,7 +3,7 @@ use unicode_segmentation::UnicodeSegmentation;
 pub async fn clone_event<'a>(
     urlname: &'a str,
     event_id: &'a str,
-    meetup_client: &'a super::newapi::AsyncClient,
+    meetup_client: &'a super::connection::MeetupConnection,
     hook: Option<
         Box<
             dyn FnOnce(super::newapi::NewEvent) -> Result<super::newapi::NewEvent, super::Error>
@@ -177,3 +177,47 @@ impl<'a> AsyncLock<'a> {
         self.redis_connection
     }
 }
+
+// Lets concurrent tasks share one Redis connection. Each command waits until
+// the connection is free.
+pub struct SharedConnection<'a, 'c, C> {
+    connection: &'a tokio::sync::Mutex<&'c mut C>,
+    db: i64,
+}
+
+impl<'a, 'c, C: redis::aio::ConnectionLike> SharedConnection<'a, 'c, C> {
+    pub fn new(connection: &'a tokio::sync::Mutex<&'c mut C>, db: i64) -> Self {
+        SharedConnection { connection, db }
+    }
+}
+
+impl<C: redis::aio::ConnectionLike + Send> redis::aio::ConnectionLike
+    for SharedConnection<'_, '_, C>
+{
+    fn req_packed_command<'b>(
+        &'b mut self,
+        cmd: &'b redis::Cmd,
+    ) -> redis::RedisFuture<'b, redis::Value> {
+        async move { self.connection.lock().await.req_packed_command(cmd).await }.boxed()
+    }
+
+    fn req_packed_commands<'b>(
+        &'b mut self,
+        pipeline: &'b redis::Pipeline,
+        offset: usize,
+        count: usize,
+    ) -> redis::RedisFuture<'b, Vec<redis::Value>> {
+        async move {
+            self.connection
+                .lock()
+                .await
+                .req_packed_commands(pipeline, offset, count)
+                .await
+        }
+        .boxed()
+    }
+
+    fn get_db(&self) -> i64 {
+        self.db
+    }
+}
@@ -1,48 +1,29 @@
-use std::sync::Arc;
-
-use futures_util::lock::Mutex;
 use serenity::{all::Mentionable, builder::CreateMessage, model::id::RoleId};
-use simple_error::SimpleError;
 
 use super::free_spots::EventCollector;
-use crate::{db, DefaultStr};
+use crate::{
+    db,
+    discord::config::GuildConfig,
+    meetup::{api::MeetupApi, connection::MeetupConnection, directives::EventDirectives},
+    DefaultStr,
+};
 
 impl EventCollector {
     pub async fn assign_roles(
         &self,
-        meetup_client: Arc<Mutex<Option<Arc<super::meetup::newapi::AsyncClient>>>>,
+        meetup_client: &MeetupConnection,
         db_connection: &sqlx::PgPool,
         discord_api: &crate::discord::CacheAndHttp,
+        guild_config: &GuildConfig,
     ) -> Result<(), crate::meetup::Error> {
-        let meetup_client = {
-            let guard = meetup_client.lock().await;
-            match *guard {
-                Some(ref meetup_client) => meetup_client.clone(),
-                None => return Err(SimpleError::new("Meetup API unavailable").into()),
-            }
-            // The Mutex guard will be dropped here
-        };
         println!("Role shortcode: Checking {} events", self.events.len());
         for event in &self.events {
             // Check whether this event uses the role shortcode
-            let role_captures =
-                crate::meetup::sync::ROLE_REGEX.captures_iter(event.description.unwrap_or_str(""));
-            let roles = {
-                let mut roles = vec![];
-                for captures in role_captures {
-                    if let Some(role_id) = captures.name("role_id") {
-                        match role_id.as_str().parse::<u64>() {
-                            Ok(id) => roles.push(RoleId::new(id)),
-                            _ => eprintln!(
-                                "Meetup event {} specifies invalid role id {}",
-                                event.id,
-                                role_id.as_str()
-                            ),
-                        }
-                    }
-                }
-                roles
-            };
+            let roles: Vec<RoleId> = EventDirectives::parse(event.description.unwrap_or_str(""))
+                .role_ids
+                .into_iter()
+                .map(|role_id| role_id.value)
+                .collect();
             let title = event.title.unwrap_or_str("No title");
             if roles.is_empty() {
                 println!("Role shortcode: skipping {}", title);
@@ -98,7 +79,8 @@ impl EventCollector {
                 } else {
                     continue;
                 };
-                let discord_member = match crate::discord::sync::ids::GUILD_ID
+                let discord_member = match guild_config
+                    .guild_id
                     .member(discord_api, discord_user_id)
                     .await
                 {
@@ -112,7 +94,9 @@ impl EventCollector {
                     if !discord_member.roles.contains(&role_id) {
                         // Assign the role
                         if let Ok(_) = crate::tasks::subscription_roles::add_member_role(
+                            db_connection,
                             discord_api,
+                            guild_config,
                             discord_user_id,
                             role_id,
                             Some(
@@ -160,6 +160,9 @@ pub const NOT_A_CHANNEL_ADMIN: &'static str =
 pub const CHANNEL_NOT_BOT_CONTROLLED: &'static str =
     "This channel does not seem to be under my control. But one day... one day :smiling_imp:";
 
+pub const CHANNEL_NO_UPCOMING_SESSIONS: &'static str =
+    "There are no upcoming sessions in this channel. Maybe schedule one first?";
+
 pub const CHANNEL_NOT_YET_CLOSEABLE: &'static str = "Too soon mate. Please wait for my request \
                                                      for deletion first. This is to avoid \
                                                      accidental deletion of channels :grimacing:";
@@ -231,6 +234,135 @@ pub fn CHANNEL_ADDED_NEW_HOST(discord_id: UserId) -> String {
 pub const CHANNEL_ADD_USER_INVALID_DISCORD: &'static str =
     "Seems like the specified Discord ID is invalid";
 
+// ** RSVPs **
+
+pub const RSVP_GOING: &'static str = "You're in! See you at the table :game_die:";
+
+pub const RSVP_ALREADY_GOING: &'static str = "You are already signed up for this session.";
+
+pub const RSVP_FULL: &'static str = "Sorry, this session is fully booked.";
+
+pub const RSVP_NOT_GOING: &'static str = "Got it, you are no longer signed up for this session.";
+
+pub const RSVP_NOT_RSVPED: &'static str = "You are not signed up for this session.";
+
+pub const RSVP_MEETUP_ORIGIN: &'static str =
+    "You signed up for this session on Meetup, please cancel your RSVP there.";
+
+pub const RSVP_EVENT_OVER: &'static str = "This session is not open for RSVPs anymore.";
+
+#[allow(non_snake_case)]
+pub fn EVENT_CANCELLED(
+    channel_role_id: Option<RoleId>,
+    title: &str,
+    start_timestamp: i64,
+) -> String {
+    match channel_role_id {
+        Some(role_id) => format!(
+            "Heads up, {role_mention}! **{title}** on <t:{timestamp}:F> has been cancelled.",
+            role_mention = role_id.mention(),
+            title = title,
+            timestamp = start_timestamp
+        ),
+        None => format!(
+            "Heads up, adventurers! **{title}** on <t:{timestamp}:F> has been cancelled.",
+            title = title,
+            timestamp = start_timestamp
+        ),
+    }
+}
+
+pub const EVENT_ANNOUNCEMENT_CANCELLED: &'static str = "This session has been cancelled.";
+
+#[allow(non_snake_case)]
+pub fn EVENT_RESCHEDULED(
+    channel_role_id: Option<RoleId>,
+    title: &str,
+    old_start_timestamp: i64,
+    new_start_timestamp: i64,
+) -> String {
+    match channel_role_id {
+        Some(role_id) => format!(
+            "Heads up, {role_mention}! **{title}** has been moved from <t:{old_timestamp}:F> to \
+             <t:{new_timestamp}:F>.",
+            role_mention = role_id.mention(),
+            title = title,
+            old_timestamp = old_start_timestamp,
+            new_timestamp = new_start_timestamp
+        ),
+        None => format!(
+            "Heads up, adventurers! **{title}** has been moved from <t:{old_timestamp}:F> to \
+             <t:{new_timestamp}:F>.",
+            title = title,
+            old_timestamp = old_start_timestamp,
+            new_timestamp = new_start_timestamp
+        ),
+    }
+}
+
+// ** Session reminders **
+
+#[allow(non_snake_case)]
+pub fn SESSION_REMINDER(
+    channel_role_id: Option<RoleId>,
+    title: &str,
+    start_timestamp: i64,
+) -> String {
+    match channel_role_id {
+        Some(role_id) => format!(
+            "Gather your gear, {role_mention}! **{title}** starts <t:{timestamp}:R> \
+             (<t:{timestamp}:F>).",
+            role_mention = role_id.mention(),
+            title = title,
+            timestamp = start_timestamp
+        ),
+        None => format!(
+            "Gather your gear, adventurers! **{title}** starts <t:{timestamp}:R> \
+             (<t:{timestamp}:F>).",
+            title = title,
+            timestamp = start_timestamp
+        ),
+    }
+}
+
+#[allow(non_snake_case)]
+pub fn SESSION_REMINDER_DM(title: &str, start_timestamp: i64) -> String {
+    format!(
+        "Just a friendly reminder that your session **{title}** starts <t:{timestamp}:R> \
+         (<t:{timestamp}:F>).\nDon't want these messages? Send me _reminders off_.",
+        title = title,
+        timestamp = start_timestamp
+    )
+}
+
+pub const SESSION_REMINDER_DMS_ENABLED: &'static str =
+    "Got it, I will remind you of your sessions via direct message.";
+
+pub const SESSION_REMINDER_DMS_DISABLED: &'static str =
+    "Got it, I won't send you session reminders via direct message anymore.";
+
+// ** Calendar **
+
+#[allow(non_snake_case)]
+pub fn CALENDAR_LINKS(member_url: &str, series_url: Option<&str>, club_url: &str) -> String {
+    let mut message = format!(
+        "Subscribe to these links in your calendar app to always have your sessions at hand.\n\n\
+         **Your sessions** (keep this link to yourself):\n<{member_url}>",
+        member_url = member_url
+    );
+    if let Some(series_url) = series_url {
+        message.push_str(&format!(
+            "\n\n**Sessions of this channel:**\n<{series_url}>",
+            series_url = series_url
+        ));
+    }
+    message.push_str(&format!(
+        "\n\n**All SwissRPG sessions:**\n<{club_url}>",
+        club_url = club_url
+    ));
+    message
+}
+
 // **************************************
 // *** Meetup linking webpage replies ***
 // **************************************
@@ -309,3 +441,67 @@ pub fn LOGIN_LINK_MESSAGE(linking_url: &str) -> String {
         linking_url
     )
 }
+
+// ** Audit log **
+
+#[allow(non_snake_case)]
+pub fn AUDIT_NO_ENTRIES(channel_id: ChannelId) -> String {
+    format!(
+        "I haven't recorded any actions concerning {} yet.",
+        channel_id.mention()
+    )
+}
+
+// ** Discord sync **
+
+pub const SYNC_DISCORD_DRY_RUN_NO_CHANGES: &'static str =
+    "Discord is in sync with the database, a sync would not change anything.";
+
+#[allow(non_snake_case)]
+pub fn SYNC_DISCORD_DRY_RUN_CHANGES(num_changes: usize) -> String {
+    format!(
+        "A Discord sync would make {} change(s). See also {}/sync_discord/dry_run",
+        num_changes,
+        crate::urls::BASE_URL
+    )
+}
+
+// ** Meetup connection **
+
+#[allow(non_snake_case)]
+pub fn MEETUP_CONNECTION_DEGRADED(health: crate::meetup::connection::ConnectionHealth) -> String {
+    use crate::meetup::connection::ConnectionHealth;
+    let reason = match health {
+        ConnectionHealth::Ok => "is fine",
+        ConnectionHealth::TokenExpired => {
+            "was rejected because the organizer's token expired. If a refresh doesn't fix \
+             this, please log in with an organizer account again"
+        }
+        ConnectionHealth::Unreachable => "failed because Meetup is unreachable",
+    };
+    format!(
+        "The connection to Meetup {}. Syncing with Meetup won't work until this is resolved.",
+        reason
+    )
+}
+
+pub const MEETUP_CONNECTION_RECOVERED: &'static str = "The connection to Meetup works again.";
+
+// ** Shortcodes **
+
+#[allow(non_snake_case)]
+pub fn EVENT_DIRECTIVE_ERRORS(title: &str, event_url: &str, problems: &str) -> String {
+    format!(
+        "I found some problems with the shortcodes in the description of **{}** ({}):\n{}\n\
+         I'm ignoring these shortcodes until they are fixed on Meetup.",
+        title, event_url, problems
+    )
+}
+
+// ** Date polls **
+
+pub const DATE_POLL_CREATED: &'static str =
+    "Time to find a date for the next session! Please let your GM know which of these slots work \
+     for you.";
+
+pub const DATE_POLL_LINK: &'static str = "Use the following link to set up a date poll";
@@ -0,0 +1,67 @@
+use async_trait::async_trait;
+
+// The Stripe operations used by the subscription role updates. Implemented by
+// stripe::Client and by an in-memory fake for tests (see crate::fake).
+#[async_trait]
+pub trait StripeApi: Send + Sync {
+    async fn list_active_subscriptions(
+        &self,
+    ) -> Result<Vec<stripe::Subscription>, crate::meetup::Error>;
+
+    async fn retrieve_product(
+        &self,
+        product_id: &stripe::ProductId,
+    ) -> Result<stripe::Product, crate::meetup::Error>;
+
+    async fn retrieve_customer(
+        &self,
+        customer_id: &stripe::CustomerId,
+    ) -> Result<stripe::Customer, crate::meetup::Error>;
+
+    // Adds the metadata to the customer's existing metadata
+    async fn update_customer_metadata(
+        &self,
+        customer_id: &stripe::CustomerId,
+        metadata: stripe::Metadata,
+    ) -> Result<(), crate::meetup::Error>;
+}
+
+#[async_trait]
+impl StripeApi for stripe::Client {
+    async fn list_active_subscriptions(
+        &self,
+    ) -> Result<Vec<stripe::Subscription>, crate::meetup::Error> {
+        super::list_active_subscriptions(self).await
+    }
+
+    async fn retrieve_product(
+        &self,
+        product_id: &stripe::ProductId,
+    ) -> Result<stripe::Product, crate::meetup::Error> {
+        Ok(stripe::Product::retrieve(self, product_id, &[]).await?)
+    }
+
+    async fn retrieve_customer(
+        &self,
+        customer_id: &stripe::CustomerId,
+    ) -> Result<stripe::Customer, crate::meetup::Error> {
+        Ok(stripe::Customer::retrieve(self, customer_id, &[]).await?)
+    }
+
+    async fn update_customer_metadata(
+        &self,
+        customer_id: &stripe::CustomerId,
+        metadata: stripe::Metadata,
+    ) -> Result<(), crate::meetup::Error> {
+        stripe::Customer::update(
+            self,
+            customer_id,
+            stripe::UpdateCustomer {
+                metadata: Some(metadata),
+                ..Default::default()
+            },
+        )
+        .await?;
+        Ok(())
+    }
+}
@@ -1,3 +1,7 @@
+pub mod api;
+
+pub use api::StripeApi;
+
 pub async fn list_active_subscriptions(
     client: &stripe::Client,
 ) -> Result<Vec<stripe::Subscription>, crate::meetup::Error> {
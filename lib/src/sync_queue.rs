@@ -0,0 +1,279 @@
+use serenity::model::id::UserId;
+use simple_error::SimpleError;
+
+use crate::{
+    db,
+    discord::{api::DiscordApi, config::GuildConfigs},
+};
+
+// The Postgres channel on which new jobs are announced
+pub const NOTIFICATION_CHANNEL: &str = "sync_job";
+
+// Finished jobs are kept this long so that they can be inspected
+const FINISHED_JOB_RETENTION_DAYS: i32 = 7;
+
+// A targeted sync which runs as soon as possible instead of waiting for the
+// next periodic full sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncJob {
+    // Syncs the channels, roles and permissions of one event series
+    EventSeries(db::EventSeriesId),
+    // Syncs all upcoming event series that one member hosts or plays in
+    MemberRoles(UserId),
+}
+
+impl SyncJob {
+    fn kind(&self) -> &'static str {
+        match self {
+            SyncJob::EventSeries(_) => "event_series",
+            SyncJob::MemberRoles(_) => "member_roles",
+        }
+    }
+
+    fn from_row(
+        kind: &str,
+        event_series_id: Option<i32>,
+        discord_user_id: Option<i64>,
+    ) -> Option<Self> {
+        match (kind, event_series_id, discord_user_id) {
+            ("event_series", Some(series_id), _) => {
+                Some(SyncJob::EventSeries(db::EventSeriesId(series_id)))
+            }
+            ("member_roles", _, Some(user_id)) if user_id > 0 => {
+                Some(SyncJob::MemberRoles(UserId::new(user_id as u64)))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for SyncJob {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyncJob::EventSeries(series_id) => write!(f, "sync event series {}", series_id.0),
+            SyncJob::MemberRoles(user_id) => write!(f, "sync the roles of <@{}>", user_id),
+        }
+    }
+}
+
+// A job as stored in the queue
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    pub id: i32,
+    // None if the job's kind is unknown
+    pub job: Option<SyncJob>,
+    pub reason: String,
+    pub created_time: chrono::DateTime<chrono::Utc>,
+    pub started_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub finished_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub error: Option<String>,
+}
+
+// Adds a job to the queue, unless the same job is pending already
+pub async fn enqueue(
+    job: SyncJob,
+    reason: &str,
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    let (event_series_id, discord_user_id) = match job {
+        SyncJob::EventSeries(series_id) => (Some(series_id.0), None),
+        SyncJob::MemberRoles(user_id) => (None, Some(user_id.get() as i64)),
+    };
+    sqlx::query!(
+        r#"INSERT INTO sync_job (kind, event_series_id, discord_user_id, reason)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (kind, COALESCE(event_series_id, 0), COALESCE(discord_user_id, 0)) WHERE started_time IS NULL
+        DO NOTHING"#,
+        job.kind(),
+        event_series_id,
+        discord_user_id,
+        reason
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+// The pending and running jobs, followed by the most recently finished ones
+pub async fn queue_status(
+    num_finished: i64,
+    db_connection: &sqlx::PgPool,
+) -> Result<Vec<QueuedJob>, crate::meetup::Error> {
+    let jobs = sqlx::query!(
+        r#"(SELECT id AS "id!", kind AS "kind!", event_series_id, discord_user_id, reason AS "reason!", created_time AS "created_time!", started_time, finished_time, error
+        FROM sync_job
+        WHERE finished_time IS NULL
+        ORDER BY id)
+        UNION ALL
+        (SELECT id, kind, event_series_id, discord_user_id, reason, created_time, started_time, finished_time, error
+        FROM sync_job
+        WHERE finished_time IS NOT NULL
+        ORDER BY finished_time DESC, id DESC
+        LIMIT $1)"#,
+        num_finished
+    )
+    .map(|row| QueuedJob {
+        id: row.id,
+        job: SyncJob::from_row(&row.kind, row.event_series_id, row.discord_user_id),
+        reason: row.reason,
+        created_time: row.created_time,
+        started_time: row.started_time,
+        finished_time: row.finished_time,
+        error: row.error,
+    })
+    .fetch_all(db_connection)
+    .await?;
+    Ok(jobs)
+}
+
+// Runs the pending jobs one after the other until the queue is empty and
+// returns how many jobs were run
+pub async fn run_pending_jobs(
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+) -> Result<usize, crate::meetup::Error> {
+    let mut num_jobs = 0;
+    while let Some(queued_job) = claim_next_job(db_connection).await? {
+        num_jobs += 1;
+        let result = match queued_job.job {
+            Some(job) => {
+                run_job(
+                    job,
+                    redis_connection,
+                    db_connection,
+                    discord_api,
+                    guild_configs,
+                    bot_id,
+                )
+                .await
+            }
+            None => Err(SimpleError::new("Unknown job kind").into()),
+        };
+        let error = match result {
+            Ok(()) => None,
+            Err(err) => {
+                eprintln!("Sync job {} failed:\n{:#?}", queued_job.id, err);
+                Some(err.to_string())
+            }
+        };
+        sqlx::query!(
+            r#"UPDATE sync_job SET finished_time = NOW(), error = $2 WHERE id = $1"#,
+            queued_job.id,
+            error
+        )
+        .execute(db_connection)
+        .await?;
+    }
+    Ok(num_jobs)
+}
+
+// Marks the oldest pending job as started. Concurrent workers skip the jobs
+// which are being claimed by someone else.
+async fn claim_next_job(
+    db_connection: &sqlx::PgPool,
+) -> Result<Option<QueuedJob>, crate::meetup::Error> {
+    let job = sqlx::query!(
+        r#"UPDATE sync_job SET started_time = NOW()
+        WHERE id = (
+            SELECT id FROM sync_job
+            WHERE started_time IS NULL
+            ORDER BY id
+            LIMIT 1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, kind, event_series_id, discord_user_id, reason, created_time, started_time"#
+    )
+    .map(|row| QueuedJob {
+        id: row.id,
+        job: SyncJob::from_row(&row.kind, row.event_series_id, row.discord_user_id),
+        reason: row.reason,
+        created_time: row.created_time,
+        started_time: row.started_time,
+        finished_time: None,
+        error: None,
+    })
+    .fetch_optional(db_connection)
+    .await?;
+    Ok(job)
+}
+
+async fn run_job(
+    job: SyncJob,
+    redis_connection: &mut (impl redis::aio::ConnectionLike + Send),
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
+    bot_id: UserId,
+) -> Result<(), crate::meetup::Error> {
+    let series_ids = match job {
+        SyncJob::EventSeries(series_id) => vec![series_id],
+        SyncJob::MemberRoles(user_id) => {
+            sqlx::query!(
+                r#"SELECT DISTINCT event.event_series_id
+                FROM event
+                INNER JOIN (
+                    SELECT event_id, member_id FROM event_participant
+                    UNION SELECT event_id, member_id FROM event_host
+                ) AS event_member ON event.id = event_member.event_id
+                INNER JOIN "member" ON event_member.member_id = "member".id
+                WHERE "member".discord_id = $1 AND event.deleted IS NULL AND event.start_time > NOW()"#,
+                user_id.get() as i64
+            )
+            .map(|row| db::EventSeriesId(row.event_series_id))
+            .fetch_all(db_connection)
+            .await?
+        }
+    };
+    // One failed series doesn't keep the others from being synced
+    let mut failed_series_ids = vec![];
+    for series_id in series_ids {
+        if let Err(err) = crate::discord::sync::sync_discord_series(
+            series_id,
+            redis_connection,
+            db_connection,
+            discord_api,
+            guild_configs,
+            bot_id,
+        )
+        .await
+        {
+            eprintln!("Sync job: Event series {} failed:\n{:#?}", series_id.0, err);
+            failed_series_ids.push(series_id.0.to_string());
+        }
+    }
+    if failed_series_ids.is_empty() {
+        Ok(())
+    } else {
+        Err(SimpleError::new(format!(
+            "Syncing event series {} failed",
+            failed_series_ids.join(", ")
+        ))
+        .into())
+    }
+}
+
+// Jobs which were running when the bot shut down won't ever finish
+pub async fn abandon_running_jobs(
+    db_connection: &sqlx::PgPool,
+) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"UPDATE sync_job SET finished_time = NOW(), error = 'Interrupted'
+        WHERE started_time IS NOT NULL AND finished_time IS NULL"#
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_old_jobs(db_connection: &sqlx::PgPool) -> Result<(), crate::meetup::Error> {
+    sqlx::query!(
+        r#"DELETE FROM sync_job WHERE finished_time < NOW() - make_interval(days => $1)"#,
+        FINISHED_JOB_RETENTION_DAYS
+    )
+    .execute(db_connection)
+    .await?;
+    Ok(())
+}
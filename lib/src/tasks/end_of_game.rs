@@ -1,20 +1,25 @@
-use crate::{db, discord::sync::ChannelType, strings};
+use crate::{
+    db,
+    discord::{
+        api::{ChannelInfo, DiscordApi, RoleInfo},
+        config::{GuildConfig, GuildConfigs},
+        sync::ChannelType,
+    },
+    strings,
+};
 use chrono::{NaiveTime, TimeZone};
 use serenity::{
     builder::CreateMessage,
-    model::{
-        channel::GuildChannel,
-        guild::Role,
-        id::{ChannelId, RoleId, UserId},
-    },
+    model::id::{ChannelId, GuildId, RoleId, UserId},
 };
 use simple_error::SimpleError;
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::Arc};
 
 // Sends channel deletion reminders to expired Discord channels
 pub async fn create_recurring_end_of_game_task(
     db_connection: sqlx::PgPool,
-    mut discord_api: crate::discord::CacheAndHttp,
+    discord_api: crate::discord::CacheAndHttp,
+    guild_configs: Arc<GuildConfigs>,
     bot_id: UserId,
 ) -> ! {
     let next_end_of_game_task_time = {
@@ -43,7 +48,9 @@ pub async fn create_recurring_end_of_game_task(
     loop {
         // Wait for the next interval tick
         interval_timer.tick().await;
-        if let Err(err) = end_of_game_task(&db_connection, &mut discord_api, bot_id).await {
+        if let Err(err) =
+            end_of_game_task(&db_connection, &discord_api, &guild_configs, bot_id).await
+        {
             eprintln!("End of game task failed:\n{:#?}", err);
         }
     }
@@ -51,7 +58,8 @@ pub async fn create_recurring_end_of_game_task(
 
 pub async fn end_of_game_task(
     db_connection: &sqlx::PgPool,
-    discord_api: &mut crate::discord::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_configs: &GuildConfigs,
     bot_id: UserId,
 ) -> Result<(), crate::meetup::Error> {
     let event_series = sqlx::query!(
@@ -68,14 +76,53 @@ pub async fn end_of_game_task(
             eprintln!("Series channel expiration update failed: {:#}", err);
         }
     }
-    let existing_channels = crate::discord::sync::ids::GUILD_ID
-        .channels(&discord_api.http)
-        .await?;
+    // Then, delete channels and roles and send reminders in each guild
+    for guild_config in guild_configs.iter() {
+        if let Err(err) =
+            end_of_game_guild_task(db_connection, discord_api, guild_config, bot_id).await
+        {
+            some_failed = true;
+            eprintln!(
+                "End of game task for guild {} failed: {:#}",
+                guild_config.guild_id, err
+            );
+        }
+    }
+    // Lastly, look for channels and roles which fell through the cracks
+    if let Err(err) =
+        crate::discord::orphans::reconcile_orphans(db_connection, discord_api, guild_configs).await
+    {
+        some_failed = true;
+        eprintln!("Orphan reconciliation failed: {:#}", err);
+    }
+    if some_failed {
+        Err(SimpleError::new("One or more end of game tasks failed").into())
+    } else {
+        Ok(())
+    }
+}
+
+// Channels and roles which are not (or not yet) associated with a guild are
+// handled as part of the default guild
+async fn end_of_game_guild_task(
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
+    bot_id: UserId,
+) -> Result<(), crate::meetup::Error> {
+    let guild_id = guild_config.guild_id.get() as i64;
+    let is_default_guild = guild_config.is_default;
+    let mut some_failed = false;
+    let existing_channels = discord_api.guild_channels(guild_config.guild_id).await?;
     let discord_channels = sqlx::query!(
         r#"
-        SELECT discord_id as "discord_text_channel_id!"
+        SELECT event_series_text_channel.discord_id as "discord_text_channel_id!"
         FROM event_series_text_channel
-        WHERE deleted IS NULL"#
+        LEFT JOIN event_series ON event_series.discord_text_channel_id = event_series_text_channel.discord_id
+        WHERE event_series_text_channel.deleted IS NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
     )
     .map(|row| ChannelId::new(row.discord_text_channel_id as u64))
     .fetch_all(db_connection)
@@ -114,9 +161,13 @@ pub async fn end_of_game_task(
         }
     }
     let discord_voice_channels = sqlx::query!(
-        r#"SELECT discord_id as "discord_voice_channel_id!"
+        r#"SELECT event_series_voice_channel.discord_id as "discord_voice_channel_id!"
         FROM event_series_voice_channel
-        WHERE deleted IS NULL AND deletion_time IS NOT NULL"#
+        LEFT JOIN event_series ON event_series.discord_voice_channel_id = event_series_voice_channel.discord_id
+        WHERE event_series_voice_channel.deleted IS NULL AND event_series_voice_channel.deletion_time IS NOT NULL
+        AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
     )
     .map(|row| ChannelId::new(row.discord_voice_channel_id as u64))
     .fetch_all(db_connection)
@@ -136,45 +187,80 @@ pub async fn end_of_game_task(
             eprintln!("Error during voice channel deletion: {:#}", err);
         }
     }
-    let existing_roles = crate::discord::sync::ids::GUILD_ID
-        .roles(&discord_api.http)
-        .await?;
+    let existing_roles = discord_api.guild_roles(guild_config.guild_id).await?;
     let discord_roles = sqlx::query!(
-        r#"SELECT discord_id as "discord_role_id!"
+        r#"SELECT event_series_role.discord_id as "discord_role_id!"
             FROM event_series_role
-            WHERE deleted IS NULL AND deletion_time IS NOT NULL"#
+            LEFT JOIN event_series ON event_series.discord_role_id = event_series_role.discord_id
+            WHERE event_series_role.deleted IS NULL AND event_series_role.deletion_time IS NOT NULL
+            AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
     )
     .map(|row| RoleId::new(row.discord_role_id as u64))
     .fetch_all(db_connection)
     .await?;
     for role in discord_roles {
         // Check if the role is due for deletion
-        if let Err(err) =
-            delete_marked_role(false, role, &existing_roles, db_connection, discord_api).await
+        if let Err(err) = delete_marked_role(
+            false,
+            role,
+            guild_config.guild_id,
+            &existing_roles,
+            db_connection,
+            discord_api,
+        )
+        .await
         {
             some_failed = true;
             eprintln!("Error during role deletion: {:#}", err);
         }
     }
     let discord_host_roles = sqlx::query!(
-        r#"SELECT discord_id as "discord_role_id!"
+        r#"SELECT event_series_host_role.discord_id as "discord_role_id!"
             FROM event_series_host_role
-            WHERE deleted IS NULL AND deletion_time IS NOT NULL"#
+            LEFT JOIN event_series ON event_series.discord_host_role_id = event_series_host_role.discord_id
+            WHERE event_series_host_role.deleted IS NULL AND event_series_host_role.deletion_time IS NOT NULL
+            AND (event_series.guild_id = $1 OR (event_series.guild_id IS NULL AND $2))"#,
+        guild_id,
+        is_default_guild
     )
     .map(|row| RoleId::new(row.discord_role_id as u64))
     .fetch_all(db_connection)
     .await?;
     for role in discord_host_roles {
         // Check if the host role is due for deletion
-        if let Err(err) =
-            delete_marked_role(true, role, &existing_roles, db_connection, discord_api).await
+        if let Err(err) = delete_marked_role(
+            true,
+            role,
+            guild_config.guild_id,
+            &existing_roles,
+            db_connection,
+            discord_api,
+        )
+        .await
         {
             some_failed = true;
             eprintln!("Error during host role deletion: {:#}", err);
         }
     }
+    // Lastly, remove the overflow categories which were emptied by the deletions
+    if let Err(err) = crate::discord::categories::collapse_empty_overflow_categories(
+        db_connection,
+        discord_api,
+        guild_config,
+    )
+    .await
+    {
+        some_failed = true;
+        eprintln!("Error during overflow category cleanup: {:#}", err);
+    }
     if some_failed {
-        Err(SimpleError::new("One or more end of game tasks failed").into())
+        Err(SimpleError::new(format!(
+            "One or more end of game tasks failed in guild {}",
+            guild_config.guild_id
+        ))
+        .into())
     } else {
         Ok(())
     }
@@ -202,6 +288,7 @@ async fn update_series_channel_expiration(
         );
         return Ok(());
     };
+    let policy = crate::expiration_policy::for_series(series_id, db_connection).await?;
     // Get last event in this series
     let last_event = db::get_last_event_in_series(db_connection, series_id).await?;
     // Query the channel's current expiration time
@@ -213,7 +300,7 @@ async fn update_series_channel_expiration(
     .await?;
     // The last element in this vector will be the last event in the series
     let (new_expiration_time, needs_update) = if let Some(last_event) = last_event {
-        let new_expiration_time = last_event.time;
+        let new_expiration_time = last_event.time + policy.expiration_delay();
         let needs_update = current_expiration_time
             .map(|current| current != new_expiration_time)
             .unwrap_or(true);
@@ -229,10 +316,11 @@ async fn update_series_channel_expiration(
     // Store the new expiration time
     if needs_update {
         // Also delete any possibly stored deletion times from the channel, the
-        // possibly associated voice channel and roles
+        // possibly associated voice channel and roles, and start counting the
+        // reminders anew
         let mut tx = db_connection.begin().await?;
         sqlx::query!(
-            r#"UPDATE event_series_text_channel SET expiration_time = $2, deletion_time = NULL WHERE discord_id = $1"#,
+            r#"UPDATE event_series_text_channel SET expiration_time = $2, deletion_time = NULL, num_expiration_reminders = 0 WHERE discord_id = $1"#,
             discord_text_channel_id.get() as i64,
             new_expiration_time
         )
@@ -274,11 +362,11 @@ async fn update_series_channel_expiration(
 async fn send_channel_expiration_reminder(
     channel_id: ChannelId,
     db_connection: &sqlx::PgPool,
-    discord_api: &mut crate::discord::CacheAndHttp,
+    discord_api: &impl DiscordApi,
     bot_id: UserId,
 ) -> Result<(), crate::meetup::Error> {
-    let (expiration_time, last_reminder_time, snooze_until, deletion_time) = sqlx::query!(
-        r#"SELECT expiration_time, last_expiration_reminder_time, snooze_until, deletion_time
+    let (expiration_time, last_reminder_time, num_reminders, snooze_until, deletion_time) = sqlx::query!(
+        r#"SELECT expiration_time, last_expiration_reminder_time, num_expiration_reminders, snooze_until, deletion_time
         FROM event_series_text_channel
         WHERE discord_id = $1"#,
         channel_id.get() as i64
@@ -287,6 +375,7 @@ async fn send_channel_expiration_reminder(
         (
             row.expiration_time,
             row.last_expiration_reminder_time,
+            row.num_expiration_reminders,
             row.snooze_until,
             row.deletion_time,
         )
@@ -305,12 +394,17 @@ async fn send_channel_expiration_reminder(
         )
         .fetch_one(db_connection)
         .await?;
+        let policy = crate::expiration_policy::for_text_channel(
+            channel_id,
+            &mut *db_connection.acquire().await?,
+        )
+        .await?;
+        if !policy.may_remind(num_reminders) {
+            // The hosts have been reminded often enough
+            return Ok(());
+        }
         // We only remind a certain time after expiration
-        let reminder_time = if is_campaign {
-            expiration_time + chrono::Duration::days(3)
-        } else {
-            expiration_time + chrono::Duration::days(1)
-        };
+        let reminder_time = expiration_time + policy.first_reminder_delay();
         let now = chrono::Utc::now();
         if reminder_time > now {
             // The reminder time hasn't come yet
@@ -323,14 +417,7 @@ async fn send_channel_expiration_reminder(
             }
         }
         if let Some(last_reminder_time) = last_reminder_time {
-            // Reminders will be sent with an interval of two days for
-            // one-shots and four days for campaign channels
-            let reminder_interval = if is_campaign {
-                chrono::Duration::days(4) - chrono::Duration::hours(2)
-            } else {
-                chrono::Duration::days(2) - chrono::Duration::hours(2)
-            };
-            if last_reminder_time + reminder_interval > chrono::Utc::now() {
+            if last_reminder_time + policy.reminder_interval() > chrono::Utc::now() {
                 // We already sent a reminder recently
                 return Ok(());
             }
@@ -345,10 +432,10 @@ async fn send_channel_expiration_reminder(
         } else {
             strings::END_OF_ADVENTURE_MESSAGE(bot_id, user_role)
         });
-        channel_id
-            .send_message(&discord_api.http, message_builder)
+        discord_api
+            .send_message(channel_id, message_builder)
             .await?;
-        sqlx::query!("UPDATE event_series_text_channel SET last_expiration_reminder_time = NOW() WHERE discord_id = $1", channel_id.get() as i64).execute(db_connection).await?;
+        sqlx::query!("UPDATE event_series_text_channel SET last_expiration_reminder_time = NOW(), num_expiration_reminders = num_expiration_reminders + 1 WHERE discord_id = $1", channel_id.get() as i64).execute(db_connection).await?;
         println!(
             "Updated channel's {} latest expiration reminder time",
             channel_id
@@ -367,9 +454,9 @@ enum DeletionStatus {
 async fn delete_marked_channel(
     channel_type: ChannelType,
     channel_id: ChannelId,
-    existing_channels: &HashMap<ChannelId, GuildChannel>,
+    existing_channels: &HashMap<ChannelId, ChannelInfo>,
     db_connection: &sqlx::PgPool,
-    discord_api: &crate::discord::CacheAndHttp,
+    discord_api: &impl DiscordApi,
 ) -> Result<DeletionStatus, crate::meetup::Error> {
     let mark_channel_as_deleted = || async {
         match channel_type {
@@ -414,8 +501,58 @@ async fn delete_marked_channel(
     if deletion_time > chrono::Utc::now() {
         return Ok(DeletionStatus::NotDeleted);
     }
+    // Archive the text channel's history first, unless the series' policy says
+    // otherwise. If that fails, the channel is kept and archiving is retried on
+    // the next run.
+    let archive_id = if channel_type == ChannelType::Text {
+        let event_series_id = sqlx::query_scalar!(
+            "SELECT id FROM event_series WHERE discord_text_channel_id = $1",
+            channel_id.get() as i64
+        )
+        .fetch_optional(db_connection)
+        .await?;
+        let event_series_id = match event_series_id {
+            Some(event_series_id) => {
+                let policy = crate::expiration_policy::for_series(
+                    db::EventSeriesId(event_series_id),
+                    db_connection,
+                )
+                .await?;
+                Some(event_series_id).filter(|_| policy.archive)
+            }
+            None => None,
+        };
+        match event_series_id {
+            Some(event_series_id) => Some(
+                crate::archive::archive_channel(
+                    db::EventSeriesId(event_series_id),
+                    channel,
+                    db_connection,
+                    discord_api,
+                )
+                .await?,
+            ),
+            None => None,
+        }
+    } else {
+        None
+    };
     // Delete the channel from Discord
-    channel.delete(discord_api).await?;
+    discord_api.delete_channel(channel_id, None).await?;
+    crate::audit::record(
+        crate::audit::Entry::new(
+            crate::audit::Action::ChannelDeleted,
+            crate::audit::Target::Channel(channel_id),
+        )
+        .before(serde_json::json!({
+            "name": channel.name,
+            "voice": channel_type == ChannelType::Voice,
+            "deletion_time": deletion_time.to_rfc3339(),
+            "archive_id": archive_id.map(|id| id.0),
+        })),
+        db_connection,
+    )
+    .await;
     // Mark the channel as deleted
     mark_channel_as_deleted().await?;
     Ok(DeletionStatus::Deleted)
@@ -424,9 +561,10 @@ async fn delete_marked_channel(
 async fn delete_marked_role(
     is_host_role: bool,
     role_id: RoleId,
-    existing_roles: &HashMap<RoleId, Role>,
+    guild_id: GuildId,
+    existing_roles: &HashMap<RoleId, RoleInfo>,
     db_connection: &sqlx::PgPool,
-    discord_api: &crate::discord::CacheAndHttp,
+    discord_api: &impl DiscordApi,
 ) -> Result<DeletionStatus, crate::meetup::Error> {
     let mark_role_as_deleted = || async {
         if is_host_role {
@@ -436,8 +574,8 @@ async fn delete_marked_role(
         }
     };
     // Check whether the role still exists on Discord
-    let mut role = if let Some(role) = existing_roles.get(&role_id) {
-        role.clone()
+    let role = if let Some(role) = existing_roles.get(&role_id) {
+        role
     } else {
         mark_role_as_deleted().await?;
         return Ok(DeletionStatus::AlreadyDeleted);
@@ -466,7 +604,20 @@ async fn delete_marked_role(
         return Ok(DeletionStatus::NotDeleted);
     }
     // Delete the role from Discord
-    role.delete(&discord_api.http).await?;
+    discord_api.delete_role(guild_id, role_id, None).await?;
+    crate::audit::record(
+        crate::audit::Entry::new(
+            crate::audit::Action::RoleDeleted,
+            crate::audit::Target::Role(role_id),
+        )
+        .before(serde_json::json!({
+            "name": role.name,
+            "host_role": is_host_role,
+            "deletion_time": deletion_time.to_rfc3339(),
+        })),
+        db_connection,
+    )
+    .await;
     // Mark the role as deleted
     mark_role_as_deleted().await?;
     Ok(DeletionStatus::Deleted)
@@ -1,5 +1,7 @@
 pub mod end_of_game;
+pub mod session_reminders;
 pub mod subscription_roles;
 pub mod sync;
+pub mod sync_queue;
 pub mod token_refresh;
 pub mod user_topic_voice_channel;
@@ -0,0 +1,158 @@
+use serenity::{builder::CreateMessage, model::id::UserId};
+
+use crate::{db, strings};
+
+// Reminder offsets used if none are configured
+pub const DEFAULT_REMINDER_OFFSETS_HOURS: [i64; 2] = [24, 1];
+
+// Reminds players of their upcoming sessions in the game channels and via DM.
+// Reminders are sent the specified number of hours before a session starts.
+pub async fn create_recurring_session_reminder_task(
+    db_connection: sqlx::PgPool,
+    discord_api: crate::discord::CacheAndHttp,
+    reminder_offsets_hours: Vec<i64>,
+) -> ! {
+    let reminder_offsets: Vec<_> = reminder_offsets_hours
+        .into_iter()
+        .map(chrono::Duration::hours)
+        .collect();
+    // Check for due reminders every five minutes
+    let mut interval_timer = tokio::time::interval_at(
+        tokio::time::Instant::now() + tokio::time::Duration::from_secs(60),
+        tokio::time::Duration::from_secs(5 * 60),
+    );
+    // Run forever
+    loop {
+        // Wait for the next interval tick
+        interval_timer.tick().await;
+        if let Err(err) =
+            session_reminder_task(&db_connection, &discord_api, &reminder_offsets).await
+        {
+            eprintln!("Session reminder task failed:\n{:#?}", err);
+        }
+    }
+}
+
+pub async fn session_reminder_task(
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+    reminder_offsets: &[chrono::Duration],
+) -> Result<(), crate::meetup::Error> {
+    let max_offset = match reminder_offsets.iter().max() {
+        Some(offset) => *offset,
+        None => return Ok(()),
+    };
+    // Find all upcoming events in channels which are due for a reminder
+    let events = sqlx::query!(
+        r#"SELECT event.id, event.event_series_id, event.title, event.start_time
+        FROM event
+        INNER JOIN event_series ON event.event_series_id = event_series.id
+        WHERE event.deleted IS NULL AND event.start_time > NOW() AND event.start_time <= $1
+        AND event_series.discord_text_channel_id IS NOT NULL"#,
+        chrono::Utc::now() + max_offset
+    )
+    .fetch_all(db_connection)
+    .await?;
+    let mut some_failed = false;
+    for event in events {
+        let time_until_start = event.start_time - chrono::Utc::now();
+        // All reminders whose time has come. If there are several (e.g. the
+        // event was scheduled on short notice), only one reminder is sent.
+        let due_offsets_minutes: Vec<i32> = reminder_offsets
+            .iter()
+            .filter(|offset| time_until_start <= **offset)
+            .map(|offset| offset.num_minutes() as i32)
+            .collect();
+        // Mark the reminders as sent before actually sending them, such that
+        // a failure (or a restart) never leads to duplicate reminders
+        let newly_logged_offsets = sqlx::query_scalar!(
+            r#"INSERT INTO session_reminder_log (event_id, offset_minutes)
+            SELECT $1, UNNEST($2::integer[])
+            ON CONFLICT DO NOTHING
+            RETURNING offset_minutes"#,
+            event.id,
+            &due_offsets_minutes
+        )
+        .fetch_all(db_connection)
+        .await?;
+        if newly_logged_offsets.is_empty() {
+            continue;
+        }
+        if let Err(err) = send_session_reminder(
+            db::EventId(event.id),
+            db::EventSeriesId(event.event_series_id),
+            &event.title,
+            event.start_time,
+            db_connection,
+            discord_api,
+        )
+        .await
+        {
+            some_failed = true;
+            eprintln!(
+                "Could not send the session reminder for event {}:\n{:#?}",
+                event.id, err
+            );
+        }
+    }
+    if some_failed {
+        Err(simple_error::SimpleError::new("One or more session reminders failed").into())
+    } else {
+        Ok(())
+    }
+}
+
+async fn send_session_reminder(
+    event_id: db::EventId,
+    series_id: db::EventSeriesId,
+    title: &str,
+    start_time: chrono::DateTime<chrono::Utc>,
+    db_connection: &sqlx::PgPool,
+    discord_api: &crate::discord::CacheAndHttp,
+) -> Result<(), crate::meetup::Error> {
+    let channel_roles =
+        crate::get_event_series_roles(series_id, &mut db_connection.begin().await?).await?;
+    let message = strings::SESSION_REMINDER(
+        channel_roles.map(|roles| roles.user),
+        title,
+        start_time.timestamp(),
+    );
+    crate::discord::util::say_in_event_series_channel(
+        series_id,
+        &message,
+        db_connection,
+        discord_api,
+    )
+    .await
+    .map_err(crate::meetup::Error::CommonError)?;
+    // Also remind the participants who did not opt out via DM
+    let participant_ids = sqlx::query!(
+        r#"SELECT "member".discord_id AS "discord_id!"
+        FROM event_participant
+        INNER JOIN "member" ON event_participant.member_id = "member".id
+        WHERE event_participant.event_id = $1 AND "member".discord_id IS NOT NULL
+        AND "member".session_reminder_dms"#,
+        event_id.0
+    )
+    .map(|row| UserId::new(row.discord_id as u64))
+    .fetch_all(db_connection)
+    .await?;
+    let dm = strings::SESSION_REMINDER_DM(title, start_time.timestamp());
+    for participant_id in participant_ids {
+        let result = async {
+            participant_id
+                .create_dm_channel(discord_api)
+                .await?
+                .send_message(&discord_api.http, CreateMessage::new().content(&dm))
+                .await
+        }
+        .await;
+        if let Err(err) = result {
+            eprintln!(
+                "Could not send a session reminder DM to {}:\n{:#?}",
+                participant_id, err
+            );
+        }
+    }
+    Ok(())
+}
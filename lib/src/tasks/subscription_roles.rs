@@ -3,12 +3,9 @@ use std::{
     sync::Arc,
 };
 
+use crate::{discord::api::DiscordApi, discord::config::GuildConfig, stripe::StripeApi};
 use lazy_static::lazy_static;
-use serenity::futures::StreamExt;
-use serenity::{
-    http::CacheHttp,
-    model::id::{RoleId, UserId},
-};
+use serenity::model::id::{RoleId, UserId};
 
 pub const CHAMPION_PRODUCT_PATTERN: &'static str =
     r"(?i).*(Novice|Apprentice|Adept|Master|Legendary).*";
@@ -21,27 +18,11 @@ lazy_static! {
         regex::Regex::new(INSIDER_PRODUCT_PATTERN).unwrap();
 }
 
-#[cfg(feature = "bottest")]
-pub mod ids {
-    use super::*;
-    // Test server:
-    pub const CHAMPION_ID: RoleId = RoleId::new(670250507436294144);
-    pub const INSIDER_ID: RoleId = RoleId::new(670250754422079488);
-    pub const GM_CHAMPION_ID: RoleId = RoleId::new(671107703703207940);
-}
-
-#[cfg(not(feature = "bottest"))]
-pub mod ids {
-    use super::*;
-    // SwissRPG server:
-    pub const CHAMPION_ID: RoleId = RoleId::new(670197555166052362);
-    pub const INSIDER_ID: RoleId = RoleId::new(670201953883783169);
-    pub const GM_CHAMPION_ID: RoleId = RoleId::new(671111220119470093);
-}
-
 pub async fn stripe_subscriptions_refresh_task(
+    db_connection: sqlx::PgPool,
     discord_api: crate::discord::CacheAndHttp,
     stripe_client: Arc<stripe::Client>,
+    guild_config: Arc<GuildConfig>,
 ) -> ! {
     // Sync every 8 hours, starting in an hour from now
     let mut interval_timer = tokio::time::interval_at(
@@ -54,9 +35,19 @@ pub async fn stripe_subscriptions_refresh_task(
         interval_timer.tick().await;
         println!("Refreshing Stripe subscription information");
         let join_handle = {
+            let db_connection = db_connection.clone();
             let discord_api = discord_api.clone();
             let stripe_client = stripe_client.clone();
-            tokio::spawn(async move { update_roles(&discord_api, &stripe_client).await })
+            let guild_config = guild_config.clone();
+            tokio::spawn(async move {
+                update_roles(
+                    &db_connection,
+                    &discord_api,
+                    stripe_client.as_ref(),
+                    &guild_config,
+                )
+                .await
+            })
         };
         match join_handle.await {
             Err(err) => {
@@ -73,11 +64,26 @@ pub async fn stripe_subscriptions_refresh_task(
 }
 
 pub async fn update_roles(
-    discord_api: &crate::discord::CacheAndHttp,
-    stripe_client: &stripe::Client,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    stripe_client: &impl StripeApi,
+    guild_config: &GuildConfig,
 ) -> Result<(), crate::meetup::Error> {
+    let (champion_id, insider_id, gm_champion_id) = match (
+        guild_config.champion_role_id,
+        guild_config.insider_role_id,
+        guild_config.gm_champion_role_id,
+    ) {
+        (Some(champion_id), Some(insider_id), Some(gm_champion_id)) => {
+            (champion_id, insider_id, gm_champion_id)
+        }
+        _ => {
+            eprintln!("Subscription roles are not configured, not updating them");
+            return Ok(());
+        }
+    };
     // Get all active subscriptions from Stripe
-    let subscriptions = crate::stripe::list_active_subscriptions(stripe_client).await?;
+    let subscriptions = stripe_client.list_active_subscriptions().await?;
     // For each subscription, find which product and customer are associated with it
     // and add the Discord name stored in Stripe to the appropriate list
     let mut new_champions = vec![];
@@ -95,17 +101,23 @@ pub async fn update_roles(
                 continue;
             }
         };
-        let discord_id =
-            match ensure_customer_has_discord_id(&customer, stripe_client, discord_api).await {
-                Ok(discord_id) => discord_id,
-                Err(err) => {
-                    eprintln!(
-                        "Error in update_roles ensure_customer_has_discord_id:\n{:#?}",
-                        err
-                    );
-                    continue;
-                }
-            };
+        let discord_id = match ensure_customer_has_discord_id(
+            &customer,
+            stripe_client,
+            discord_api,
+            guild_config,
+        )
+        .await
+        {
+            Ok(discord_id) => discord_id,
+            Err(err) => {
+                eprintln!(
+                    "Error in update_roles ensure_customer_has_discord_id:\n{:#?}",
+                    err
+                );
+                continue;
+            }
+        };
         if let Some(discord_id) = discord_id {
             let is_champion_product = product
                 .name
@@ -133,18 +145,13 @@ pub async fn update_roles(
     let mut current_gm_champions = vec![];
     let mut current_insiders = vec![];
     let mut current_gms = vec![];
-    let members = discord_api
-        .cache
-        .guild(crate::discord::sync::ids::GUILD_ID)
-        .map(|guild| guild.members.clone())
-        .ok_or_else(|| simple_error::SimpleError::new("Did not find guild in cache"))?;
-    for (&user_id, member) in &members {
-        let is_champion = member.roles.contains(&ids::CHAMPION_ID);
-        let is_gm_champion = member.roles.contains(&ids::GM_CHAMPION_ID);
-        let is_insider = member.roles.contains(&ids::INSIDER_ID);
-        let is_gm = member
-            .roles
-            .contains(&crate::discord::sync::ids::GAME_MASTER_ID);
+    let members = discord_api.guild_members(guild_config.guild_id).await?;
+    for member in &members {
+        let user_id = member.user_id;
+        let is_champion = member.roles.contains(&champion_id);
+        let is_gm_champion = member.roles.contains(&gm_champion_id);
+        let is_insider = member.roles.contains(&insider_id);
+        let is_gm = member.roles.contains(&guild_config.game_master_role_id);
         if is_champion {
             current_champions.push(user_id);
         }
@@ -171,9 +178,11 @@ pub async fn update_roles(
             if !current_gm_champions.contains(new_champion) {
                 // Assign GM champion role
                 if let Err(err) = add_member_role(
+                    db_connection,
                     discord_api,
+                    guild_config,
                     *new_champion,
-                    ids::GM_CHAMPION_ID,
+                    gm_champion_id,
                     Some("Automatic role assignment due to being a GM champion"),
                 )
                 .await
@@ -184,9 +193,11 @@ pub async fn update_roles(
             if current_champions.contains(new_champion) {
                 // Remove (non-GM) champion role
                 if let Err(err) = remove_member_role(
+                    db_connection,
                     discord_api,
+                    guild_config,
                     *new_champion,
-                    ids::CHAMPION_ID,
+                    champion_id,
                     Some("Automatic role removal due to being upgraded to a GM champion"),
                 )
                 .await
@@ -199,9 +210,11 @@ pub async fn update_roles(
             if !current_champions.contains(new_champion) {
                 // Assign champion role
                 if let Err(err) = add_member_role(
+                    db_connection,
                     discord_api,
+                    guild_config,
                     *new_champion,
-                    ids::CHAMPION_ID,
+                    champion_id,
                     Some("Automatic role assignment due to being a champion"),
                 )
                 .await
@@ -212,9 +225,11 @@ pub async fn update_roles(
             if current_gm_champions.contains(new_champion) {
                 // Remove GM champion role
                 if let Err(err) = remove_member_role(
+                    db_connection,
                     discord_api,
+                    guild_config,
                     *new_champion,
-                    ids::GM_CHAMPION_ID,
+                    gm_champion_id,
                     Some("Automatic role removal due to no longer being a GM champion"),
                 )
                 .await
@@ -228,9 +243,11 @@ pub async fn update_roles(
         if !current_insiders.contains(new_insider) {
             // Assign insider role
             if let Err(err) = add_member_role(
+                db_connection,
                 discord_api,
+                guild_config,
                 *new_insider,
-                ids::INSIDER_ID,
+                insider_id,
                 Some("Automatic role assignment due to being an insider"),
             )
             .await
@@ -243,9 +260,11 @@ pub async fn update_roles(
         if !new_champions.contains(current_champion) {
             // Remove champion role
             if let Err(err) = remove_member_role(
+                db_connection,
                 discord_api,
+                guild_config,
                 *current_champion,
-                ids::CHAMPION_ID,
+                champion_id,
                 Some("Automatic role removal due to no longer being a champion"),
             )
             .await
@@ -260,9 +279,11 @@ pub async fn update_roles(
         {
             // Remove GM champion role
             if let Err(err) = remove_member_role(
+                db_connection,
                 discord_api,
+                guild_config,
                 *current_gm_champion,
-                ids::GM_CHAMPION_ID,
+                gm_champion_id,
                 Some("Automatic role removal due to no longer being a GM or a champion"),
             )
             .await
@@ -275,9 +296,11 @@ pub async fn update_roles(
         if !new_insiders.contains(current_insider) {
             // Remove insider role
             if let Err(err) = remove_member_role(
+                db_connection,
                 discord_api,
+                guild_config,
                 *current_insider,
-                ids::INSIDER_ID,
+                insider_id,
                 Some("Automatic role removal due to no longer being an insider"),
             )
             .await
@@ -290,7 +313,7 @@ pub async fn update_roles(
 }
 
 pub async fn get_customer_and_product(
-    client: &stripe::Client,
+    client: &impl StripeApi,
     subscription: &stripe::Subscription,
 ) -> Result<(stripe::Customer, stripe::Product), crate::meetup::Error> {
     // First, figure out which product was bought
@@ -309,25 +332,21 @@ pub async fn get_customer_and_product(
         })?;
     let product = match product {
         stripe::Expandable::Object(product) => *product.clone(),
-        stripe::Expandable::Id(product_id) => {
-            stripe::Product::retrieve(client, &product_id, &[]).await?
-        }
+        stripe::Expandable::Id(product_id) => client.retrieve_product(product_id).await?,
     };
     // Now, figure out who the customer is
     let customer = match &subscription.customer {
         stripe::Expandable::Object(customer) => *customer.clone(),
-        stripe::Expandable::Id(customer_id) => {
-            let customer = stripe::Customer::retrieve(client, customer_id, &[]).await?;
-            customer
-        }
+        stripe::Expandable::Id(customer_id) => client.retrieve_customer(customer_id).await?,
     };
     Ok((customer, product))
 }
 
 async fn ensure_customer_has_discord_id(
     customer: &stripe::Customer,
-    client: &stripe::Client,
-    discord_api: &crate::discord::CacheAndHttp,
+    client: &impl StripeApi,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
 ) -> Result<Option<UserId>, crate::meetup::Error> {
     let discord_id = customer
         .metadata
@@ -350,16 +369,17 @@ async fn ensure_customer_has_discord_id(
             None => return Ok(None),
             Some(username) => username,
         };
-        let discord_id = match discord_username_to_id(discord_api, discord_username).await? {
-            Some(id) => id,
-            None => {
-                eprintln!(
-                    "Could not find Discord ID for username `{}`",
-                    discord_username
-                );
-                return Ok(None);
-            }
-        };
+        let discord_id =
+            match discord_username_to_id(discord_api, guild_config, discord_username).await? {
+                Some(id) => id,
+                None => {
+                    eprintln!(
+                        "Could not find Discord ID for username `{}`",
+                        discord_username
+                    );
+                    return Ok(None);
+                }
+            };
         // Try to store the Discord ID in Stripe.
         // Don't fail this method if it doesn't work, just log it.
         let mut new_metadata = HashMap::new();
@@ -367,15 +387,9 @@ async fn ensure_customer_has_discord_id(
             "_hyperion_discord_id".to_string(),
             format!("{}", discord_id),
         );
-        if let Err(err) = stripe::Customer::update(
-            client,
-            &customer.id,
-            stripe::UpdateCustomer {
-                metadata: Some(new_metadata),
-                ..Default::default()
-            },
-        )
-        .await
+        if let Err(err) = client
+            .update_customer_metadata(&customer.id, new_metadata)
+            .await
         {
             eprintln!(
                 "Could not store the Discord user ID in Stripe customer metadata.\nStripe \
@@ -389,22 +403,16 @@ async fn ensure_customer_has_discord_id(
 
 // TODO: move to discord utils
 pub async fn discord_username_to_id(
-    discord_api: &crate::discord::CacheAndHttp,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
     username: &str,
 ) -> Result<Option<UserId>, crate::meetup::Error> {
-    let mut members = crate::discord::sync::ids::GUILD_ID
-        .members_iter(discord_api.http())
-        .boxed();
-    while let Some(member_result) = members.next().await {
-        let member = member_result?;
-        if member.user.name == username
-            || &format!(
-                "{}#{:04}",
-                member.user.name,
-                member.user.discriminator.map(|d| d.get()).unwrap_or(0)
-            ) == username
+    let members = discord_api.guild_members(guild_config.guild_id).await?;
+    for member in members {
+        if member.name == username
+            || format!("{}#{:04}", member.name, member.discriminator.unwrap_or(0)) == username
         {
-            return Ok(Some(member.user.id));
+            return Ok(Some(member.user_id));
         }
     }
     eprintln!("Could not find a Discord ID for username {}", username);
@@ -413,23 +421,31 @@ pub async fn discord_username_to_id(
 
 // TODO: move to discord utils
 pub async fn add_member_role(
-    discord_api: &crate::discord::CacheAndHttp,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
     user_id: UserId,
     role_id: RoleId,
     audit_log_reason: Option<&str>,
 ) -> Result<(), crate::meetup::Error> {
     match discord_api
-        .http()
-        .add_member_role(
-            crate::discord::sync::ids::GUILD_ID,
-            user_id,
-            role_id,
-            audit_log_reason,
-        )
+        .add_member_role(guild_config.guild_id, user_id, role_id, audit_log_reason)
         .await
     {
         Ok(_) => {
             println!("Assigned user {} to role {}", user_id, role_id);
+            crate::audit::record(
+                crate::audit::Entry::new(
+                    crate::audit::Action::RoleAssigned,
+                    crate::audit::Target::User(user_id),
+                )
+                .after(serde_json::json!({
+                    "role_id": role_id.get(),
+                    "reason": audit_log_reason,
+                })),
+                db_connection,
+            )
+            .await;
             Ok(())
         }
         Err(err) => {
@@ -437,30 +453,38 @@ pub async fn add_member_role(
                 "Could not assign user {} to role {}:\n{:#?}",
                 user_id, role_id, err
             );
-            Err(err.into())
+            Err(err)
         }
     }
 }
 
 // TODO: move to discord utils
 async fn remove_member_role(
-    discord_api: &crate::discord::CacheAndHttp,
+    db_connection: &sqlx::PgPool,
+    discord_api: &impl DiscordApi,
+    guild_config: &GuildConfig,
     user_id: UserId,
     role_id: RoleId,
     audit_log_reason: Option<&str>,
 ) -> Result<(), crate::meetup::Error> {
     match discord_api
-        .http()
-        .remove_member_role(
-            crate::discord::sync::ids::GUILD_ID,
-            user_id,
-            role_id,
-            audit_log_reason,
-        )
+        .remove_member_role(guild_config.guild_id, user_id, role_id, audit_log_reason)
         .await
     {
         Ok(_) => {
             println!("Removed role {} from user {}", role_id, user_id);
+            crate::audit::record(
+                crate::audit::Entry::new(
+                    crate::audit::Action::RoleRemoved,
+                    crate::audit::Target::User(user_id),
+                )
+                .before(serde_json::json!({
+                    "role_id": role_id.get(),
+                    "reason": audit_log_reason,
+                })),
+                db_connection,
+            )
+            .await;
             Ok(())
         }
         Err(err) => {
@@ -468,7 +492,7 @@ async fn remove_member_role(
                 "Could not remove role {} from user {}:\n{:#?}",
                 role_id, user_id, err
             );
-            Err(err.into())
+            Err(err)
         }
     }
 }
@@ -1,5 +1,5 @@
+use crate::discord::config::GuildConfigs;
 use futures::future::TryFutureExt;
-use futures_util::lock::Mutex;
 use serenity::model::id::UserId;
 use std::sync::Arc;
 use tokio::time::{Duration, Instant};
@@ -7,8 +7,9 @@ use tokio::time::{Duration, Instant};
 pub async fn create_recurring_syncing_task(
     db_connection: sqlx::PgPool,
     redis_client: redis::Client,
-    meetup_client: Arc<Mutex<Option<Arc<crate::meetup::newapi::AsyncClient>>>>,
+    meetup_connection: crate::meetup::connection::MeetupConnection,
     discord_api: crate::discord::CacheAndHttp,
+    guild_configs: Arc<GuildConfigs>,
     bot_id: UserId,
     static_file_prefix: &'static str,
 ) -> ! {
@@ -23,18 +24,17 @@ pub async fn create_recurring_syncing_task(
         let db_connection = db_connection.clone();
         let redis_client = redis_client.clone();
         let discord_api = discord_api.clone();
-        let meetup_client = meetup_client.clone();
+        let meetup_connection = meetup_connection.clone();
+        let guild_configs = guild_configs.clone();
         tokio::spawn(async move {
             let mut redis_connection = redis_client.get_async_connection().await?;
             // Sync with Meetup
             let event_collector = tokio::time::timeout(
                 Duration::from_secs(360),
-                crate::meetup::sync::sync_task(meetup_client.clone(), &db_connection).map_err(
-                    |err| {
-                        eprintln!("Syncing task failed: {}", err);
-                        err
-                    },
-                ),
+                crate::meetup::sync::sync_task(&meetup_connection, &db_connection).map_err(|err| {
+                    eprintln!("Syncing task failed: {}", err);
+                    err
+                }),
             )
             .map_err(|err| {
                 eprintln!("Syncing task timed out: {}", err);
@@ -46,6 +46,7 @@ pub async fn create_recurring_syncing_task(
                 &mut redis_connection,
                 &db_connection,
                 &discord_api,
+                &guild_configs,
                 bot_id,
             )
             .await
@@ -53,7 +54,9 @@ pub async fn create_recurring_syncing_task(
                 eprintln!("Discord syncing task failed: {}", err);
             }
             // Finally, update Discord with the information on open spots.
-            if let Some(channel_id) = crate::discord::sync::ids::FREE_SPOTS_CHANNEL_ID {
+            // Open spots and Meetup roles are handled by the default guild.
+            let guild_config = guild_configs.default_guild();
+            if let Some(channel_id) = guild_config.free_spots_channel_id {
                 if let Err(err) = event_collector
                     .update_channel(&discord_api, channel_id, static_file_prefix)
                     .await
@@ -64,11 +67,25 @@ pub async fn create_recurring_syncing_task(
                 eprintln!("No channel configured for posting open game spots");
             }
             if let Err(err) = event_collector
-                .assign_roles(meetup_client.clone(), &db_connection, &discord_api)
+                .assign_roles(
+                    &meetup_connection,
+                    &db_connection,
+                    &discord_api,
+                    guild_config,
+                )
                 .await
             {
                 eprintln!("Error in EventCollector::assign_roles:\n{:#?}", err);
             }
+            if let Err(err) = event_collector
+                .report_directive_errors(&db_connection, &discord_api, &guild_configs)
+                .await
+            {
+                eprintln!(
+                    "Error in EventCollector::report_directive_errors:\n{:#?}",
+                    err
+                );
+            }
             Ok::<_, crate::BoxedError>(())
         });
     }
@@ -0,0 +1,85 @@
+use crate::{discord::config::GuildConfigs, sync_queue};
+use serenity::model::id::UserId;
+use sqlx::postgres::PgListener;
+use std::{sync::Arc, time::Duration};
+
+// Jobs are announced via Postgres notifications, but the queue is also checked
+// this often in case a notification got lost
+const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+// Runs the jobs in the sync queue as soon as they are enqueued
+pub async fn create_sync_queue_task(
+    db_connection: sqlx::PgPool,
+    redis_client: redis::Client,
+    discord_api: crate::discord::CacheAndHttp,
+    guild_configs: Arc<GuildConfigs>,
+    bot_id: UserId,
+) -> ! {
+    if let Err(err) = sync_queue::abandon_running_jobs(&db_connection).await {
+        eprintln!(
+            "Sync queue task: Could not abandon running jobs:\n{:#?}",
+            err
+        );
+    }
+    let mut listener = None;
+    // Run forever
+    loop {
+        if listener.is_none() {
+            listener = match listen(&db_connection).await {
+                Ok(new_listener) => Some(new_listener),
+                Err(err) => {
+                    eprintln!(
+                        "Sync queue task: Could not listen for new jobs:\n{:#?}",
+                        err
+                    );
+                    None
+                }
+            };
+        }
+        match redis_client.get_async_connection().await {
+            Ok(mut redis_connection) => {
+                if let Err(err) = sync_queue::run_pending_jobs(
+                    &mut redis_connection,
+                    &db_connection,
+                    &discord_api,
+                    &guild_configs,
+                    bot_id,
+                )
+                .await
+                {
+                    eprintln!("Sync queue task failed:\n{:#?}", err);
+                }
+            }
+            Err(err) => {
+                eprintln!(
+                    "Sync queue task: Could not acquire Redis connection:\n{:#?}",
+                    err
+                );
+            }
+        }
+        if let Err(err) = sync_queue::delete_old_jobs(&db_connection).await {
+            eprintln!("Sync queue task: Could not delete old jobs:\n{:#?}", err);
+        }
+        // Wait for the next job
+        match &mut listener {
+            Some(active_listener) => {
+                if let Ok(Err(err)) =
+                    tokio::time::timeout(POLL_INTERVAL, active_listener.recv()).await
+                {
+                    eprintln!(
+                        "Sync queue task: Lost the connection for notifications:\n{:#?}",
+                        err
+                    );
+                    listener = None;
+                }
+            }
+            None => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+}
+
+async fn listen(db_connection: &sqlx::PgPool) -> Result<PgListener, crate::meetup::Error> {
+    let mut listener = PgListener::connect_with(db_connection).await?;
+    listener.listen(sync_queue::NOTIFICATION_CHANNEL).await?;
+    Ok(listener)
+}
@@ -1,13 +1,12 @@
 use crate::{db, meetup::oauth2::TokenType};
 use chrono::{Datelike, Timelike};
-use futures_util::lock::Mutex;
-use std::sync::Arc;
+use futures::future::{self, Either};
 
-// Refreshes the authorization token
+// Refreshes the authorization token, early if Meetup rejects it
 pub async fn organizer_token_refresh_task(
     oauth2_consumer: crate::meetup::oauth2::OAuth2Consumer,
     pool: sqlx::PgPool,
-    async_meetup_client: Arc<Mutex<Option<Arc<crate::meetup::newapi::AsyncClient>>>>,
+    meetup_connection: crate::meetup::connection::MeetupConnection,
 ) -> ! {
     // Try to get the next scheduled refresh time from the database, otherwise
     // schedule a refresh immediately
@@ -30,10 +29,13 @@ pub async fn organizer_token_refresh_task(
         );
         let wait_duration_in_secs = (next_refresh_time - chrono::Utc::now()).num_seconds();
         if wait_duration_in_secs > 0 {
-            tokio::time::sleep(tokio::time::Duration::from_secs(
-                wait_duration_in_secs as u64,
-            ))
-            .await;
+            let scheduled_refresh = std::pin::pin!(tokio::time::sleep(
+                tokio::time::Duration::from_secs(wait_duration_in_secs as u64)
+            ));
+            let refresh_requested = std::pin::pin!(meetup_connection.token_refresh_requested());
+            if let Either::Right(_) = future::select(scheduled_refresh, refresh_requested).await {
+                println!("Meetup rejected the organizer token, refreshing it early");
+            }
         }
         println!("Starting organizer token refresh");
         // Try to refresh the organizer oauth tokens.
@@ -42,9 +44,9 @@ pub async fn organizer_token_refresh_task(
         let join_handle = {
             let oauth2_consumer = oauth2_consumer.clone();
             let pool = pool.clone();
-            let async_meetup_client = async_meetup_client.clone();
+            let meetup_connection = meetup_connection.clone();
             tokio::spawn(async move {
-                organizer_token_refresh_task_impl(oauth2_consumer, pool, async_meetup_client).await
+                organizer_token_refresh_task_impl(oauth2_consumer, pool, meetup_connection).await
             })
         };
         match join_handle.await {
@@ -75,13 +77,15 @@ pub async fn organizer_token_refresh_task(
                 .ok();
             }
         }
+        // Don't let rejected requests trigger refreshes in quick succession
+        tokio::time::sleep(tokio::time::Duration::from_secs(10 * 60)).await;
     }
 }
 
 async fn organizer_token_refresh_task_impl(
     oauth2_consumer: crate::meetup::oauth2::OAuth2Consumer,
     pool: sqlx::PgPool,
-    async_meetup_client: Arc<Mutex<Option<Arc<crate::meetup::newapi::AsyncClient>>>>,
+    meetup_connection: crate::meetup::connection::MeetupConnection,
 ) -> Result<(), crate::meetup::Error> {
     // Try to refresh the organizer oauth tokens
     let new_auth_token = crate::meetup::oauth2::refresh_oauth_tokens(
@@ -90,11 +94,7 @@ async fn organizer_token_refresh_task_impl(
         &pool,
     )
     .await?;
-    let mut async_meetup_guard = async_meetup_client.lock().await;
-    *async_meetup_guard = Some(Arc::new(crate::meetup::newapi::AsyncClient::new(
-        new_auth_token.secret(),
-    )));
-    drop(async_meetup_guard);
+    meetup_connection.set_access_token(new_auth_token.secret());
     Ok(())
 }
 
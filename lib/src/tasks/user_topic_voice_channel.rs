@@ -1,6 +1,7 @@
+use crate::discord::config::{GuildConfig, GuildConfigs};
 use redis::AsyncCommands;
 use serenity::{builder::EditChannel, model::channel::Channel};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 
 pub const DEFAULT_USER_TOPIC_VOICE_CHANNEL_NAME: &str = "Your topic (ask Hyperion)";
 
@@ -8,6 +9,7 @@ pub const DEFAULT_USER_TOPIC_VOICE_CHANNEL_NAME: &str = "Your topic (ask Hyperio
 pub async fn reset_user_topic_voice_channel_task(
     redis_client: redis::Client,
     mut discord_api: crate::discord::CacheAndHttp,
+    guild_configs: Arc<GuildConfigs>,
 ) -> ! {
     // Do this every minute
     let mut interval_timer = tokio::time::interval_at(
@@ -28,10 +30,19 @@ pub async fn reset_user_topic_voice_channel_task(
                 continue;
             }
         };
-        if let Err(err) =
-            reset_user_topic_voice_channel(&mut redis_connection, &mut discord_api).await
-        {
-            eprintln!("User topic voice channel reset task failed:\n{:#?}", err);
+        for guild_config in guild_configs.iter() {
+            if let Err(err) = reset_user_topic_voice_channel(
+                &mut redis_connection,
+                &mut discord_api,
+                guild_config,
+            )
+            .await
+            {
+                eprintln!(
+                    "User topic voice channel reset task failed in guild {}:\n{:#?}",
+                    guild_config.guild_id, err
+                );
+            }
         }
     }
 }
@@ -39,10 +50,10 @@ pub async fn reset_user_topic_voice_channel_task(
 async fn reset_user_topic_voice_channel(
     redis_connection: &mut redis::aio::Connection,
     discord_api: &crate::discord::CacheAndHttp,
+    guild_config: &GuildConfig,
 ) -> Result<(), crate::meetup::Error> {
     // Check if there is a user topic voice channel
-    let voice_channel_id = if let Some(id) = crate::discord::sync::ids::USER_TOPIC_VOICE_CHANNEL_ID
-    {
+    let voice_channel_id = if let Some(id) = guild_config.user_topic_voice_channel_id {
         id
     } else {
         return Ok(());
@@ -63,7 +74,10 @@ async fn reset_user_topic_voice_channel(
     }
     // Check if the voice channel has not been renamed very recently
     let topic_time = match redis_connection
-        .get::<_, Option<String>>("user_topic_voice_channel_topic_time")
+        .get::<_, Option<String>>(format!(
+            "user_topic_voice_channel:{}:topic_time",
+            voice_channel_id
+        ))
         .await?
     {
         Some(time) => match chrono::DateTime::parse_from_rfc3339(&time) {
@@ -0,0 +1,64 @@
+// Picks the winning slot of a date poll
+use chrono::TimeZone;
+use swissrpg_lib::{
+    date_poll::{Answer, DatePoll, DatePollId, DatePollSlot, DatePollSlotId},
+    db::{EventSeriesId, MemberId},
+};
+
+fn slot(id: i32, day: u32, votes: &[Answer]) -> DatePollSlot {
+    DatePollSlot {
+        id: DatePollSlotId(id),
+        start_time: chrono::Utc
+            .with_ymd_and_hms(2026, 11, day, 18, 0, 0)
+            .unwrap(),
+        votes: votes
+            .iter()
+            .enumerate()
+            .map(|(member, &answer)| (MemberId(member as i32), answer))
+            .collect(),
+    }
+}
+
+fn poll(slots: Vec<DatePollSlot>) -> DatePoll {
+    DatePoll {
+        id: DatePollId(1),
+        event_series_id: EventSeriesId(1),
+        created_by: MemberId(0),
+        duration: chrono::Duration::hours(4),
+        scheduled_event_id: None,
+        slots,
+    }
+}
+
+fn winner(poll: &DatePoll) -> Option<i32> {
+    poll.winning_slot().map(|slot| slot.id.0)
+}
+
+#[test]
+fn most_yes_votes_win() {
+    use Answer::*;
+    let poll = poll(vec![
+        slot(1, 2, &[Yes, No, Maybe]),
+        slot(2, 3, &[Yes, Yes, No]),
+    ]);
+    assert_eq!(winner(&poll), Some(2));
+    assert_eq!(poll.num_voters(), 3);
+}
+
+#[test]
+fn ties_prefer_fewer_no_votes_then_the_earlier_slot() {
+    use Answer::*;
+    let poll = poll(vec![
+        slot(1, 2, &[Yes, No]),
+        slot(2, 3, &[Yes, Maybe]),
+        slot(3, 4, &[Yes, Maybe]),
+    ]);
+    assert_eq!(winner(&poll), Some(2));
+}
+
+#[test]
+fn no_winner_without_yes_votes() {
+    use Answer::*;
+    let poll = poll(vec![slot(1, 2, &[Maybe, No]), slot(2, 3, &[])]);
+    assert_eq!(winner(&poll), None);
+}
@@ -0,0 +1,114 @@
+// Parses the shortcodes of Meetup event descriptions
+use serenity::model::id::{ChannelId, RoleId};
+use swissrpg_lib::meetup::directives::{
+    remove_shortcodes, DirectiveErrorKind, EventDirectives, SeriesType, ShortcodeKind,
+};
+
+#[test]
+fn valid_shortcodes_are_parsed() {
+    let description = "A one-shot! \\[new adventure\\] [channel 123] [ Category456 ] \
+                       [role 7] [role 8] [online] [closed] [roleplay] [campaigners]";
+    let directives = EventDirectives::parse(description);
+    assert_eq!(
+        directives.new_series.map(|new_series| new_series.value),
+        Some(SeriesType::Adventure)
+    );
+    let channel_id = directives.channel_id.unwrap();
+    assert_eq!(channel_id.value, ChannelId::new(123));
+    assert_eq!(&description[channel_id.span], "[channel 123]");
+    assert_eq!(
+        directives.category_id.map(|category_id| category_id.value),
+        Some(ChannelId::new(456))
+    );
+    let role_ids: Vec<_> = directives
+        .role_ids
+        .iter()
+        .map(|role_id| role_id.value)
+        .collect();
+    assert_eq!(role_ids, vec![RoleId::new(7), RoleId::new(8)]);
+    assert!(directives.online);
+    assert!(directives.closed);
+    assert_eq!(directives.series_event_id, None);
+    assert_eq!(directives.errors, vec![]);
+}
+
+#[test]
+fn malformed_and_conflicting_shortcodes_are_reported() {
+    let description = "[campaign 301] [new campaign] [channel abc] [role] [online now] \
+                       [category 1] [category 2]";
+    let directives = EventDirectives::parse(description);
+    let errors: Vec<_> = directives
+        .errors
+        .iter()
+        .map(|error| (error.shortcode.as_str(), error.kind.clone()))
+        .collect();
+    assert_eq!(
+        errors,
+        vec![
+            (
+                "[campaign 301]",
+                DirectiveErrorKind::Conflict {
+                    with: "[new campaign]".into()
+                }
+            ),
+            (
+                "[channel abc]",
+                DirectiveErrorKind::InvalidArgument {
+                    expected: "Discord channel ID"
+                }
+            ),
+            (
+                "[role]",
+                DirectiveErrorKind::MissingArgument {
+                    expected: "Discord role ID"
+                }
+            ),
+            ("[online now]", DirectiveErrorKind::UnexpectedArgument),
+            (
+                "[category 2]",
+                DirectiveErrorKind::Conflict {
+                    with: "[category 1]".into()
+                }
+            ),
+        ]
+    );
+    // The valid shortcodes are still available
+    assert_eq!(
+        directives
+            .series_event_id
+            .map(|series_event_id| series_event_id.value),
+        Some("301".to_string())
+    );
+    assert_eq!(
+        directives.category_id.map(|category_id| category_id.value),
+        Some(ChannelId::new(1))
+    );
+    assert_eq!(directives.channel_id, None);
+    assert!(!directives.online);
+    assert_eq!(
+        directives.errors[1].to_string(),
+        "`[channel abc]` doesn't contain a valid Discord channel ID"
+    );
+}
+
+#[test]
+fn channel_requires_a_new_series() {
+    let directives = EventDirectives::parse("[channel 5]");
+    assert_eq!(directives.errors.len(), 1);
+    assert_eq!(
+        directives.errors[0].kind,
+        DirectiveErrorKind::ChannelWithoutNewSeries
+    );
+}
+
+#[test]
+fn shortcodes_are_removed_by_kind() {
+    let description = "Intro [new campaign]\n[channel 9] [online] [closed]";
+    assert_eq!(
+        remove_shortcodes(
+            description,
+            &[ShortcodeKind::NewCampaign, ShortcodeKind::Channel]
+        ),
+        "Intro \n [online] [closed]"
+    );
+}
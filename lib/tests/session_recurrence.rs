@@ -0,0 +1,119 @@
+// Plans runs of recurring sessions
+use chrono::{NaiveDate, TimeZone};
+use chrono_tz::Europe;
+use swissrpg_lib::flow::recurrence::{
+    swiss_public_holiday, Frequency, PlannedSession, Recurrence, RecurrenceEnd, MAX_SESSIONS,
+};
+
+fn local_times(sessions: &[PlannedSession]) -> Vec<String> {
+    sessions
+        .iter()
+        .map(|session| session.date_time.format("%Y-%m-%d %H:%M %Z").to_string())
+        .collect()
+}
+
+#[test]
+fn biweekly_sessions_keep_their_local_time_across_dst() {
+    let first = Europe::Zurich
+        .with_ymd_and_hms(2026, 10, 14, 19, 0, 0)
+        .unwrap();
+    let recurrence = Recurrence {
+        frequency: Frequency::Biweekly,
+        end: RecurrenceEnd::Count(3),
+        skip_holidays: false,
+    };
+    assert_eq!(
+        local_times(&recurrence.plan(first)),
+        vec![
+            "2026-10-14 19:00 CEST",
+            "2026-10-28 19:00 CET",
+            "2026-11-11 19:00 CET",
+        ]
+    );
+}
+
+#[test]
+fn holidays_are_skipped_and_not_counted() {
+    // Fridays, including Christmas Day and New Year's Day
+    let first = Europe::Zurich
+        .with_ymd_and_hms(2026, 12, 18, 19, 0, 0)
+        .unwrap();
+    let recurrence = Recurrence {
+        frequency: Frequency::Weekly,
+        end: RecurrenceEnd::Count(3),
+        skip_holidays: true,
+    };
+    let sessions = recurrence.plan(first);
+    assert_eq!(
+        local_times(&sessions),
+        vec![
+            "2026-12-18 19:00 CET",
+            "2026-12-25 19:00 CET",
+            "2027-01-01 19:00 CET",
+            "2027-01-08 19:00 CET",
+            "2027-01-15 19:00 CET",
+        ]
+    );
+    let skipped: Vec<_> = sessions
+        .iter()
+        .map(|session| session.skipped_holiday)
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            None,
+            Some("Christmas Day"),
+            Some("New Year's Day"),
+            None,
+            None
+        ]
+    );
+}
+
+#[test]
+fn monthly_sessions_end_at_the_until_date() {
+    let first = Europe::Zurich
+        .with_ymd_and_hms(2027, 1, 31, 14, 30, 0)
+        .unwrap();
+    let recurrence = Recurrence {
+        frequency: Frequency::Monthly,
+        end: RecurrenceEnd::Until(NaiveDate::from_ymd_opt(2027, 4, 30).unwrap()),
+        skip_holidays: false,
+    };
+    assert_eq!(
+        local_times(&recurrence.plan(first)),
+        vec![
+            "2027-01-31 14:30 CET",
+            "2027-02-28 14:30 CET",
+            "2027-03-31 14:30 CEST",
+            "2027-04-30 14:30 CEST",
+        ]
+    );
+}
+
+#[test]
+fn runs_are_limited() {
+    let first = Europe::Zurich
+        .with_ymd_and_hms(2026, 1, 5, 18, 0, 0)
+        .unwrap();
+    let recurrence = Recurrence {
+        frequency: Frequency::Weekly,
+        end: RecurrenceEnd::Until(NaiveDate::from_ymd_opt(2030, 1, 1).unwrap()),
+        skip_holidays: false,
+    };
+    assert_eq!(recurrence.plan(first).len(), MAX_SESSIONS);
+}
+
+#[test]
+fn movable_holidays_follow_easter() {
+    let holiday =
+        |month, day| swiss_public_holiday(NaiveDate::from_ymd_opt(2027, month, day).unwrap());
+    // Easter Sunday 2027 is on March 28
+    assert_eq!(holiday(3, 26), Some("Good Friday"));
+    assert_eq!(holiday(3, 28), None);
+    assert_eq!(holiday(3, 29), Some("Easter Monday"));
+    assert_eq!(holiday(5, 6), Some("Ascension Day"));
+    assert_eq!(holiday(5, 17), Some("Whit Monday"));
+    assert_eq!(holiday(8, 1), Some("Swiss National Day"));
+    assert_eq!(holiday(8, 2), None);
+}
@@ -0,0 +1,287 @@
+// The parts of the syncs which don't need a database: the in-memory fakes the
+// syncs run against, the Discord sync's dry run plan and summary, and the
+// bookkeeping of channels per category
+use redis::AsyncCommands;
+use serenity::model::{
+    channel::PermissionOverwriteType,
+    id::{ChannelId, GuildId, MessageId, RoleId, UserId},
+    permissions::Permissions,
+};
+use swissrpg_lib::{
+    db::{EventId, EventSeriesId},
+    discord::{
+        api::DiscordApi,
+        categories::{CategoryTracker, MAX_CHANNELS_PER_CATEGORY},
+        sync::{ChannelType, SyncSummary},
+        sync_plan::{PlannedChange, SyncPlan},
+    },
+    fake::{meetup::upcoming_event, FakeDiscord, FakeMeetup, FakeRedisConnection},
+    meetup::api::MeetupApi,
+};
+
+const GUILD_ID: GuildId = GuildId::new(1);
+
+#[test]
+fn sync_plans_start_empty() {
+    let plan = SyncPlan::dry_run();
+    assert!(plan.is_dry_run());
+    assert!(plan.changes().is_empty());
+    assert!(plan.errors().is_empty());
+    assert!(!SyncPlan::apply().is_dry_run());
+}
+
+#[test]
+fn planned_changes_describe_new_objects() {
+    let series_id = EventSeriesId(7);
+    assert_eq!(
+        PlannedChange::CreateRole {
+            series_id,
+            name: "[Host] The Adventure".to_string(),
+            is_host_role: true,
+        }
+        .to_string(),
+        "Create host role \"[Host] The Adventure\" for event series 7"
+    );
+    assert_eq!(
+        PlannedChange::CreateChannel {
+            series_id,
+            channel_type: ChannelType::Voice,
+            name: "the-adventure".to_string(),
+        }
+        .to_string(),
+        "Create voice channel \"the-adventure\" for event series 7"
+    );
+    // Objects which the sync would create don't have an ID yet
+    assert_eq!(
+        PlannedChange::AssignRole {
+            user_id: UserId::new(3),
+            role_id: None,
+        }
+        .to_string(),
+        "Assign the new role to user 3"
+    );
+    assert_eq!(
+        PlannedChange::SetPermissionOverwrite {
+            channel_id: None,
+            kind: PermissionOverwriteType::Role(RoleId::new(4)),
+            allow: Permissions::VIEW_CHANNEL,
+            deny: Permissions::empty(),
+        }
+        .to_string(),
+        "Set the permissions of role 4 in the new channel to allow View Channel and deny nothing"
+    );
+    assert_eq!(
+        PlannedChange::MoveToCategory {
+            channel_id: ChannelId::new(5),
+            category_ids: vec![ChannelId::new(10), ChannelId::new(11)],
+        }
+        .to_string(),
+        "Move channel 5 to the first available category of 10, 11"
+    );
+    assert_eq!(
+        PlannedChange::AnnounceEventCancellation {
+            event_id: EventId(8)
+        }
+        .to_string(),
+        "Announce the cancellation of event 8"
+    );
+}
+
+#[test]
+fn sync_summaries_count_failed_steps() {
+    let mut summary = SyncSummary {
+        num_synced: 4,
+        num_skipped: 1,
+        duration: std::time::Duration::from_millis(2500),
+        ..Default::default()
+    };
+    assert!(!summary.has_failures());
+    assert_eq!(
+        summary.to_string(),
+        "Synced 4 event series in 2.5s. 0 failed, 1 skipped because another sync was running."
+    );
+    summary.num_failed_steps = 2;
+    assert!(summary.has_failures());
+    assert!(summary.to_string().ends_with(" 2 other sync steps failed."));
+}
+
+#[tokio::test]
+async fn category_trackers_find_categories_with_room() {
+    let discord = FakeDiscord::new();
+    let full_category_id = ChannelId::new(10);
+    let free_category_id = ChannelId::new(11);
+    let missing_category_id = ChannelId::new(12);
+    discord.add_category(GUILD_ID, full_category_id, "One-Shots");
+    discord.add_category(GUILD_ID, free_category_id, "One-Shots 2");
+    for i in 0..MAX_CHANNELS_PER_CATEGORY {
+        let channel = discord
+            .create_channel(
+                GUILD_ID,
+                &format!("filler-{}", i),
+                ChannelType::Text,
+                vec![],
+            )
+            .await
+            .unwrap();
+        discord
+            .set_channel_category(channel.id, full_category_id)
+            .await
+            .unwrap();
+    }
+    let mut tracker = CategoryTracker::load(GUILD_ID, &discord).await.unwrap();
+    assert_eq!(
+        tracker.num_channels(full_category_id),
+        MAX_CHANNELS_PER_CATEGORY
+    );
+    assert_eq!(tracker.num_channels(free_category_id), 0);
+    // Categories which don't exist on Discord are skipped, too
+    assert_eq!(
+        tracker.find_free(&[missing_category_id, full_category_id, free_category_id]),
+        Some(free_category_id)
+    );
+    // Moving a channel out of the full category makes room in it
+    let moved_channel_id = discord.state().channel_by_name("filler-0").unwrap().info.id;
+    tracker.moved(moved_channel_id, free_category_id);
+    assert_eq!(
+        tracker.num_channels(full_category_id),
+        MAX_CHANNELS_PER_CATEGORY - 1
+    );
+    assert_eq!(tracker.num_channels(free_category_id), 1);
+    assert_eq!(
+        tracker.find_free(&[full_category_id, free_category_id]),
+        Some(full_category_id)
+    );
+}
+
+#[tokio::test]
+async fn fake_discord_tracks_roles_and_messages() {
+    let discord = FakeDiscord::new();
+    let user_id = UserId::new(3);
+    discord.add_member(user_id, "player");
+    let role = discord
+        .create_role(GUILD_ID, "[Player] The Adventure", Default::default())
+        .await
+        .unwrap();
+    discord
+        .add_member_role(GUILD_ID, user_id, role.id, None)
+        .await
+        .unwrap();
+    assert!(discord
+        .member_has_role(GUILD_ID, user_id, role.id)
+        .await
+        .unwrap());
+    // Deleting a role also takes it away from its members
+    discord.delete_role(GUILD_ID, role.id, None).await.unwrap();
+    assert!(!discord
+        .member_has_role(GUILD_ID, user_id, role.id)
+        .await
+        .unwrap());
+    // Objects which are gone already count as deleted
+    discord.delete_role(GUILD_ID, role.id, None).await.unwrap();
+    discord
+        .delete_channel(ChannelId::new(99), None)
+        .await
+        .unwrap();
+    assert!(discord.channel(ChannelId::new(99)).await.unwrap().is_none());
+
+    let channel = discord
+        .create_channel(GUILD_ID, "the-adventure", ChannelType::Text, vec![])
+        .await
+        .unwrap();
+    discord.add_message(channel.id, user_id, "Hello", false);
+    let message_id = discord
+        .send_message(
+            channel.id,
+            serenity::builder::CreateMessage::new().content("Welcome"),
+        )
+        .await
+        .unwrap();
+    let messages = discord
+        .channel_messages_after(channel.id, None)
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[1].id, message_id);
+    // Pages continue after the last message of the previous one
+    let messages = discord
+        .channel_messages_after(channel.id, Some(messages[0].id))
+        .await
+        .unwrap();
+    assert_eq!(messages.len(), 1);
+    assert!(discord
+        .channel_messages_after(channel.id, Some(message_id))
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(discord
+        .edit_message(
+            channel.id,
+            message_id,
+            serenity::builder::EditMessage::new().content("Welcome!")
+        )
+        .await
+        .unwrap());
+    assert_eq!(
+        discord.state().messages_in(channel.id)[1].content(),
+        Some("Welcome!")
+    );
+    // Editing a message which doesn't exist (anymore) is not an error
+    assert!(!discord
+        .edit_message(
+            channel.id,
+            MessageId::new(98),
+            serenity::builder::EditMessage::new().content("Gone")
+        )
+        .await
+        .unwrap());
+}
+
+#[tokio::test]
+async fn fake_meetup_lists_cancelled_events() {
+    use futures::StreamExt;
+
+    let meetup = FakeMeetup::new();
+    let date_time = chrono::Utc::now() + chrono::Duration::days(1);
+    meetup.set_upcoming_events(vec![
+        upcoming_event("1", "group", "First", "", date_time, &[1]),
+        upcoming_event("2", "group", "Second", "", date_time, &[1]),
+    ]);
+    meetup.set_rsvps("1", &[1, 2]);
+    meetup.cancel_event("2");
+    let events: Vec<_> = meetup
+        .get_upcoming_events_all_groups()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+    assert_eq!(events.len(), 2);
+    assert!(!events[0].is_cancelled());
+    assert!(events[1].is_cancelled());
+    assert_eq!(meetup.get_tickets_vec("1".into()).await.unwrap().len(), 2);
+    assert!(meetup.get_tickets_vec("2".into()).await.unwrap().is_empty());
+    // Deleted events are gone completely
+    meetup.delete_event("1");
+    assert!(meetup.get_tickets_vec("1".into()).await.is_err());
+}
+
+#[tokio::test]
+async fn fake_redis_keeps_sets() {
+    let mut redis_connection = FakeRedisConnection::new();
+    let _: () = redis_connection
+        .sadd("orphaned_discord_roles", &[1u64, 2, 2])
+        .await
+        .unwrap();
+    let removed: u64 = redis_connection
+        .srem("orphaned_discord_roles", 1u64)
+        .await
+        .unwrap();
+    assert_eq!(removed, 1);
+    let members: Vec<u64> = redis_connection
+        .smembers("orphaned_discord_roles")
+        .await
+        .unwrap();
+    assert_eq!(members, vec![2]);
+    assert_eq!(
+        redis_connection.set_members("orphaned_discord_roles"),
+        vec!["2"]
+    );
+}
@@ -0,0 +1,21 @@
+BEGIN;
+
+CREATE TABLE guild_config (
+    guild_id bigint PRIMARY KEY,
+    bot_admin_role_id bigint NOT NULL,
+    organiser_role_id bigint NOT NULL,
+    game_master_role_id bigint NOT NULL,
+    dice_roller_bot_role_id bigint,
+    admin_role_id bigint,
+    champion_role_id bigint,
+    insider_role_id bigint,
+    gm_champion_role_id bigint,
+    one_shot_category_ids bigint[] NOT NULL DEFAULT '{}',
+    campaign_category_ids bigint[] NOT NULL DEFAULT '{}',
+    voice_channel_category_ids bigint[] NOT NULL DEFAULT '{}',
+    bot_alerts_channel_id bigint,
+    free_spots_channel_id bigint,
+    user_topic_voice_channel_id bigint
+);
+
+COMMIT;
@@ -0,0 +1,13 @@
+BEGIN;
+
+DROP TABLE guild_member;
+
+ALTER TABLE managed_channel DROP COLUMN guild_id;
+
+DROP INDEX event_series_guild_id_idx;
+ALTER TABLE event_series DROP COLUMN guild_id;
+
+DROP INDEX guild_config_is_default_idx;
+ALTER TABLE guild_config DROP COLUMN is_default;
+
+COMMIT;
@@ -0,0 +1,20 @@
+BEGIN;
+
+ALTER TABLE guild_config ADD COLUMN is_default boolean NOT NULL DEFAULT FALSE;
+CREATE UNIQUE INDEX guild_config_is_default_idx ON guild_config (is_default) WHERE is_default;
+
+-- NULL means that the series has not been assigned to a guild yet
+ALTER TABLE event_series ADD COLUMN guild_id bigint;
+CREATE INDEX event_series_guild_id_idx ON event_series USING btree (guild_id);
+
+ALTER TABLE managed_channel ADD COLUMN guild_id bigint;
+
+-- Per guild member information
+CREATE TABLE guild_member (
+    guild_id bigint NOT NULL,
+    member_id integer NOT NULL REFERENCES "member" (id),
+    discord_nick text,
+    PRIMARY KEY (guild_id, member_id)
+);
+
+COMMIT;
@@ -0,0 +1,9 @@
+BEGIN;
+
+ALTER TABLE event DROP COLUMN discord_announcement_message_id;
+ALTER TABLE event DROP COLUMN guest_limit;
+
+ALTER TABLE event_participant DROP COLUMN origin;
+DROP TABLE rsvp_origin;
+
+COMMIT;
@@ -0,0 +1,16 @@
+BEGIN;
+
+-- Where an RSVP was made. More flexible than an enum.
+CREATE TABLE rsvp_origin (
+    origin text PRIMARY KEY
+);
+INSERT INTO rsvp_origin (origin) VALUES ('meetup'), ('discord');
+
+ALTER TABLE event_participant ADD COLUMN origin text NOT NULL DEFAULT 'meetup' REFERENCES rsvp_origin (origin);
+
+-- Maximum number of participants (not counting hosts), NULL if unlimited
+ALTER TABLE event ADD COLUMN guest_limit integer;
+-- Message in the series channel announcing the event with RSVP buttons
+ALTER TABLE event ADD COLUMN discord_announcement_message_id bigint;
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+-- Secret token for the member's personal calendar feed
+ALTER TABLE "member" ADD COLUMN calendar_token text UNIQUE;
+
+COMMIT;
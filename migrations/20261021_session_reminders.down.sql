@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE session_reminder_log;
+ALTER TABLE "member" DROP COLUMN session_reminder_dms;
+
+COMMIT;
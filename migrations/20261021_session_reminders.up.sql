@@ -0,0 +1,14 @@
+BEGIN;
+
+-- Whether the member wants to receive session reminders via DM
+ALTER TABLE "member" ADD COLUMN session_reminder_dms boolean NOT NULL DEFAULT TRUE;
+
+-- Reminders that have already been sent, such that they are not sent twice
+CREATE TABLE session_reminder_log (
+    event_id integer NOT NULL REFERENCES event (id),
+    offset_minutes integer NOT NULL,
+    sent_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT session_reminder_log_pk PRIMARY KEY (event_id, offset_minutes)
+);
+
+COMMIT;
@@ -0,0 +1,23 @@
+BEGIN;
+
+-- Every action the bot takes on Discord (and a few in the database), such
+-- that it can be reconstructed later what happened and why
+CREATE SEQUENCE audit_log_id_seq START WITH 1000;
+CREATE TABLE audit_log (
+    id integer PRIMARY KEY DEFAULT nextval('audit_log_id_seq'),
+    action_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- The Discord user who triggered the action, NULL if the bot acted on its own
+    actor_discord_id bigint,
+    action text NOT NULL,
+    target_type text NOT NULL,
+    target_id bigint NOT NULL,
+    -- The channel the action concerns, if any
+    channel_id bigint,
+    before jsonb,
+    after jsonb
+);
+ALTER SEQUENCE audit_log_id_seq OWNED BY audit_log.id;
+CREATE INDEX audit_log_action_time_idx ON audit_log USING btree (action_time);
+CREATE INDEX audit_log_channel_id_idx ON audit_log USING btree (channel_id);
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE event DROP COLUMN cancellation_announcement_pending;
+
+COMMIT;
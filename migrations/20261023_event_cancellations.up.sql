@@ -0,0 +1,7 @@
+BEGIN;
+
+-- Set when an upcoming event was cancelled or deleted on Meetup, cleared once
+-- the Discord sync has announced the cancellation in the series channel
+ALTER TABLE event ADD COLUMN cancellation_announcement_pending boolean NOT NULL DEFAULT FALSE;
+
+COMMIT;
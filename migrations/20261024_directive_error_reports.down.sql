@@ -0,0 +1,5 @@
+BEGIN;
+
+DROP TABLE meetup_event_directive_report;
+
+COMMIT;
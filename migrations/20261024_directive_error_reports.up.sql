@@ -0,0 +1,11 @@
+BEGIN;
+
+-- The shortcode problems last reported to the hosts of an upcoming Meetup
+-- event, such that the same problems are only reported once
+CREATE TABLE meetup_event_directive_report (
+    meetup_id text PRIMARY KEY,
+    problems text NOT NULL,
+    reported_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TABLE date_poll_vote;
+DROP TABLE date_poll_slot;
+DROP TABLE date_poll;
+
+COMMIT;
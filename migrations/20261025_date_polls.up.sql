@@ -0,0 +1,37 @@
+BEGIN;
+
+-- Polls for finding the date of the next session of an event series. The
+-- players vote on candidate slots and the host schedules the winning one.
+CREATE SEQUENCE date_poll_id_seq START WITH 1000;
+CREATE TABLE date_poll (
+    id integer PRIMARY KEY DEFAULT nextval('date_poll_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    created_by_member_id integer NOT NULL REFERENCES "member" (id),
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    duration_minutes integer NOT NULL,
+    -- The live-updating summary in the series' channel
+    discord_summary_channel_id bigint,
+    discord_summary_message_id bigint,
+    -- Set once one of the slots has been scheduled
+    scheduled_event_id integer REFERENCES event (id),
+    closed_time timestamp (0) with time zone
+);
+ALTER SEQUENCE date_poll_id_seq OWNED BY date_poll.id;
+
+CREATE SEQUENCE date_poll_slot_id_seq START WITH 1000;
+CREATE TABLE date_poll_slot (
+    id integer PRIMARY KEY DEFAULT nextval('date_poll_slot_id_seq'),
+    date_poll_id integer NOT NULL REFERENCES date_poll (id) ON DELETE CASCADE,
+    start_time timestamp (0) with time zone NOT NULL
+);
+ALTER SEQUENCE date_poll_slot_id_seq OWNED BY date_poll_slot.id;
+CREATE INDEX date_poll_slot_date_poll_id_idx ON date_poll_slot USING btree (date_poll_id);
+
+CREATE TABLE date_poll_vote (
+    date_poll_slot_id integer NOT NULL REFERENCES date_poll_slot (id) ON DELETE CASCADE,
+    member_id integer NOT NULL REFERENCES "member" (id) ON DELETE CASCADE,
+    answer text NOT NULL CHECK (answer IN ('yes', 'maybe', 'no')),
+    CONSTRAINT date_poll_vote_pk PRIMARY KEY (date_poll_slot_id, member_id)
+);
+
+COMMIT;
@@ -0,0 +1,21 @@
+BEGIN;
+
+-- Snapshots of game channels, taken by the end of game task right before the
+-- channel is deleted from Discord
+CREATE SEQUENCE channel_archive_id_seq START WITH 1000;
+CREATE TABLE channel_archive (
+    id integer PRIMARY KEY DEFAULT nextval('channel_archive_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    discord_text_channel_id bigint UNIQUE NOT NULL REFERENCES event_series_text_channel (discord_id),
+    channel_name text NOT NULL,
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    num_messages integer NOT NULL,
+    -- The messages, their attachments' metadata and pins
+    content jsonb NOT NULL,
+    -- The same messages rendered as a static page
+    html text NOT NULL
+);
+ALTER SEQUENCE channel_archive_id_seq OWNED BY channel_archive.id;
+CREATE INDEX channel_archive_event_series_id_idx ON channel_archive USING btree (event_series_id);
+
+COMMIT;
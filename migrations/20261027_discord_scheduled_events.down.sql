@@ -0,0 +1,7 @@
+BEGIN;
+
+DROP TABLE event_discord_interested_user;
+ALTER TABLE event DROP COLUMN discord_scheduled_event_synced;
+ALTER TABLE event DROP COLUMN discord_scheduled_event_id;
+
+COMMIT;
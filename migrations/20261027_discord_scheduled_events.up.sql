@@ -0,0 +1,16 @@
+BEGIN;
+
+-- The Discord scheduled event mirroring an event, and what was last synced to
+-- it (as JSON) so that unchanged events are not edited on every sync
+ALTER TABLE event ADD COLUMN discord_scheduled_event_id bigint;
+ALTER TABLE event ADD COLUMN discord_scheduled_event_synced text;
+
+-- Discord users who marked themselves as interested in an event's Discord
+-- scheduled event, as of the last sync
+CREATE TABLE event_discord_interested_user (
+    event_id integer NOT NULL REFERENCES event (id) ON DELETE CASCADE,
+    discord_user_id bigint NOT NULL,
+    CONSTRAINT event_discord_interested_user_pk PRIMARY KEY (event_id, discord_user_id)
+);
+
+COMMIT;
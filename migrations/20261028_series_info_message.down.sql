@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE event_series DROP COLUMN discord_info_message_id;
+
+COMMIT;
@@ -0,0 +1,7 @@
+BEGIN;
+
+-- The pinned message in a series' text channel which shows the next session,
+-- the hosts and the players
+ALTER TABLE event_series ADD COLUMN discord_info_message_id bigint;
+
+COMMIT;
@@ -0,0 +1,16 @@
+BEGIN;
+
+-- Discord channels and roles in managed categories which no event series
+-- knows about, and event series channels and roles which are gone from
+-- Discord, as found by the last reconciliation pass
+CREATE TABLE discord_orphan (
+    discord_id bigint NOT NULL,
+    kind text NOT NULL,
+    guild_id bigint NOT NULL,
+    name text,
+    event_series_id integer REFERENCES event_series (id) ON DELETE SET NULL,
+    detected_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT discord_orphan_pk PRIMARY KEY (discord_id, kind)
+);
+
+COMMIT;
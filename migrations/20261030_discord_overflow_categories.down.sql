@@ -0,0 +1,5 @@
+BEGIN;
+
+DROP TABLE discord_overflow_category;
+
+COMMIT;
@@ -0,0 +1,14 @@
+BEGIN;
+
+-- Categories created by the Discord sync when all configured categories for a
+-- channel are full (Discord allows at most 50 channels per category)
+CREATE TABLE discord_overflow_category (
+    discord_id bigint PRIMARY KEY,
+    guild_id bigint NOT NULL,
+    primary_category_id bigint NOT NULL,
+    "number" integer NOT NULL,
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT discord_overflow_category_number_unique UNIQUE (primary_category_id, "number")
+);
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+DROP TABLE sync_job;
+DROP FUNCTION notify_sync_job();
+
+COMMIT;
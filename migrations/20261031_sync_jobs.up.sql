@@ -0,0 +1,33 @@
+BEGIN;
+
+-- Targeted sync jobs, e.g. syncing a single event series right after it got a
+-- new session. Jobs which have not been started yet are pending.
+CREATE SEQUENCE sync_job_id_seq START WITH 1000;
+CREATE TABLE sync_job (
+    id integer PRIMARY KEY DEFAULT nextval('sync_job_id_seq'),
+    -- See SyncJob
+    kind text NOT NULL,
+    event_series_id integer REFERENCES event_series (id) ON DELETE CASCADE,
+    discord_user_id bigint,
+    -- What caused the job, e.g. the command which enqueued it
+    reason text NOT NULL,
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_time timestamp (0) with time zone,
+    finished_time timestamp (0) with time zone,
+    error text
+);
+ALTER SEQUENCE sync_job_id_seq OWNED BY sync_job.id;
+-- The same job is only pending once
+CREATE UNIQUE INDEX sync_job_pending_idx ON sync_job USING btree (kind, COALESCE(event_series_id, 0), COALESCE(discord_user_id, 0)) WHERE started_time IS NULL;
+CREATE INDEX sync_job_finished_time_idx ON sync_job USING btree (finished_time);
+
+-- Wakes up the sync queue task whenever a job is enqueued
+CREATE FUNCTION notify_sync_job() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('sync_job', NEW.id::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER sync_job_notify AFTER INSERT ON sync_job FOR EACH ROW EXECUTE FUNCTION notify_sync_job();
+
+COMMIT;
@@ -0,0 +1,6 @@
+BEGIN;
+
+ALTER TABLE event_series_text_channel DROP COLUMN num_expiration_reminders;
+DROP TABLE channel_expiration_policy;
+
+COMMIT;
@@ -0,0 +1,24 @@
+BEGIN;
+
+-- When game channels of each event series type expire, how often their hosts
+-- get reminded and when they are deleted. Types without a policy use the
+-- adventure defaults.
+CREATE TABLE channel_expiration_policy (
+    event_series_type text PRIMARY KEY REFERENCES event_series_type ("type") ON DELETE CASCADE,
+    -- The channel expires this long after the last session
+    expiration_delay_hours integer NOT NULL DEFAULT 0 CHECK (expiration_delay_hours >= 0),
+    first_reminder_delay_hours integer NOT NULL CHECK (first_reminder_delay_hours >= 0),
+    reminder_interval_hours integer NOT NULL CHECK (reminder_interval_hours > 0),
+    -- NULL to keep reminding until the channel is deleted
+    max_reminders integer CHECK (max_reminders >= 0),
+    -- How long the channel is kept after "end adventure"
+    deletion_grace_hours integer NOT NULL DEFAULT 8 CHECK (deletion_grace_hours >= 0),
+    archive boolean NOT NULL DEFAULT true
+);
+INSERT INTO channel_expiration_policy (event_series_type, first_reminder_delay_hours, reminder_interval_hours)
+VALUES ('campaign', 72, 94), ('adventure', 24, 46);
+
+-- Reset whenever the expiration time changes
+ALTER TABLE event_series_text_channel ADD COLUMN num_expiration_reminders integer NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,5 @@
+BEGIN;
+
+ALTER TABLE event DROP COLUMN discord_announcement_synced;
+
+COMMIT;
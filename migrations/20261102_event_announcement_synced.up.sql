@@ -0,0 +1,7 @@
+BEGIN;
+
+-- A hash of what was last posted as the event's announcement, so that
+-- unchanged announcements are not edited on every sync
+ALTER TABLE event ADD COLUMN discord_announcement_synced text;
+
+COMMIT;
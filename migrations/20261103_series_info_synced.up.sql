@@ -0,0 +1,7 @@
+BEGIN;
+
+-- What was last posted as the series' info message, so that unchanged info
+-- messages are not edited on every sync
+ALTER TABLE event_series ADD COLUMN discord_info_message_synced text;
+
+COMMIT;
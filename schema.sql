@@ -1,11 +1,13 @@
 CREATE TABLE managed_channel (
-    discord_id bigint PRIMARY KEY
+    discord_id bigint PRIMARY KEY,
+    guild_id bigint
 );
 
 CREATE TABLE event_series_text_channel (
     discord_id bigint PRIMARY KEY,
     expiration_time timestamp (0) with time zone,
     last_expiration_reminder_time timestamp (0) with time zone,
+    num_expiration_reminders integer NOT NULL DEFAULT 0, -- Reset whenever the expiration time changes
     snooze_until timestamp (0) with time zone,
     deletion_time timestamp (0) with time zone, -- scheduled Discord channel deletion time
     deleted timestamp (0) with time zone -- set when Discord deletion is confirmed
@@ -35,6 +37,19 @@ CREATE TABLE event_series_type (
 );
 INSERT INTO event_series_type ("type") VALUES ('campaign'), ('adventure');
 
+-- Types without a policy use the adventure defaults
+CREATE TABLE channel_expiration_policy (
+    event_series_type text PRIMARY KEY REFERENCES event_series_type ("type") ON DELETE CASCADE,
+    expiration_delay_hours integer NOT NULL DEFAULT 0 CHECK (expiration_delay_hours >= 0), -- After the last session
+    first_reminder_delay_hours integer NOT NULL CHECK (first_reminder_delay_hours >= 0), -- After expiration
+    reminder_interval_hours integer NOT NULL CHECK (reminder_interval_hours > 0),
+    max_reminders integer CHECK (max_reminders >= 0), -- NULL for no limit
+    deletion_grace_hours integer NOT NULL DEFAULT 8 CHECK (deletion_grace_hours >= 0), -- After "end adventure"
+    archive boolean NOT NULL DEFAULT true
+);
+INSERT INTO channel_expiration_policy (event_series_type, first_reminder_delay_hours, reminder_interval_hours)
+VALUES ('campaign', 72, 94), ('adventure', 24, 46);
+
 CREATE SEQUENCE event_series_id_seq START WITH 1000;
 CREATE TABLE event_series (
     id integer PRIMARY KEY DEFAULT nextval('event_series_id_seq'),
@@ -44,9 +59,13 @@ CREATE TABLE event_series (
     discord_host_role_id bigint UNIQUE REFERENCES event_series_host_role (discord_id),
     discord_category_id bigint,
     "type" text NOT NULL REFERENCES event_series_type ("type"),
-    redis_series_id text UNIQUE
+    redis_series_id text UNIQUE,
+    guild_id bigint, -- NULL if not yet assigned to a guild
+    discord_info_message_id bigint, -- Pinned series info message in the text channel
+    discord_info_message_synced text -- What was last posted as the info message, as sent to Discord
 );
 ALTER SEQUENCE event_series_id_seq OWNED BY event_series.id;
+CREATE INDEX event_series_guild_id_idx ON event_series USING btree (guild_id);
 
 CREATE SEQUENCE event_id_seq START WITH 1000;
 CREATE TABLE event (
@@ -58,11 +77,23 @@ CREATE TABLE event (
     description text NOT NULL,
     is_online boolean NOT NULL DEFAULT FALSE,
     discord_category_id bigint,
-    deleted timestamp (0) with time zone
+    deleted timestamp (0) with time zone,
+    guest_limit integer, -- NULL if unlimited
+    discord_announcement_message_id bigint,
+    cancellation_announcement_pending boolean NOT NULL DEFAULT FALSE, -- Cancelled on Meetup but not yet announced
+    discord_scheduled_event_id bigint,
+    discord_scheduled_event_synced text, -- What was last synced to the Discord scheduled event (JSON)
+    discord_announcement_synced text -- What was last posted as the announcement, as sent to Discord
 );
 ALTER SEQUENCE event_id_seq OWNED BY event.id;
 CREATE INDEX event_start_time_idx ON event USING btree (start_time);
 
+CREATE TABLE event_discord_interested_user (
+    event_id integer NOT NULL REFERENCES event (id) ON DELETE CASCADE,
+    discord_user_id bigint NOT NULL,
+    CONSTRAINT event_discord_interested_user_pk PRIMARY KEY (event_id, discord_user_id)
+);
+
 CREATE SEQUENCE meetup_event_id_seq START WITH 1000;
 CREATE TABLE meetup_event (
     id integer PRIMARY KEY DEFAULT nextval('meetup_event_id_seq'),
@@ -82,6 +113,8 @@ CREATE TABLE "member" (
     meetup_oauth2_access_token text,
     meetup_oauth2_refresh_token text,
     meetup_oauth2_last_token_refresh_time timestamp (0) with time zone,
+    calendar_token text UNIQUE, -- Secret token for the personal calendar feed
+    session_reminder_dms boolean NOT NULL DEFAULT TRUE,
     CONSTRAINT is_identifiable CHECK (meetup_id IS NOT NULL or discord_id IS NOT NULL)
 );
 ALTER SEQUENCE member_id_seq OWNED BY "member".id;
@@ -106,9 +139,16 @@ CREATE TABLE event_host (
 CREATE INDEX event_hosts_event_id_idx ON event_host USING btree (event_id);
 CREATE INDEX event_hosts_member_id_idx ON event_host USING btree (member_id);
 
+-- More flexible than an enum
+CREATE TABLE rsvp_origin (
+    origin text PRIMARY KEY
+);
+INSERT INTO rsvp_origin (origin) VALUES ('meetup'), ('discord');
+
 CREATE TABLE event_participant (
 	event_id integer NOT NULL REFERENCES event (id),
 	member_id integer NOT NULL REFERENCES "member" (id),
+	origin text NOT NULL DEFAULT 'meetup' REFERENCES rsvp_origin (origin),
 	CONSTRAINT event_participants_pk PRIMARY KEY (event_id, member_id)
 );
 CREATE INDEX event_participants_event_id_idx ON event_participant USING btree (event_id);
@@ -135,4 +175,147 @@ CREATE TABLE web_session (
     member_id integer NOT NULL REFERENCES "member" (id),
     last_used timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
 );
-ALTER SEQUENCE web_session_id_seq OWNED BY web_session.id;
\ No newline at end of file
+ALTER SEQUENCE web_session_id_seq OWNED BY web_session.id;
+CREATE TABLE guild_config (
+    guild_id bigint PRIMARY KEY,
+    is_default boolean NOT NULL DEFAULT FALSE,
+    bot_admin_role_id bigint NOT NULL,
+    organiser_role_id bigint NOT NULL,
+    game_master_role_id bigint NOT NULL,
+    dice_roller_bot_role_id bigint,
+    admin_role_id bigint,
+    champion_role_id bigint,
+    insider_role_id bigint,
+    gm_champion_role_id bigint,
+    one_shot_category_ids bigint[] NOT NULL DEFAULT '{}',
+    campaign_category_ids bigint[] NOT NULL DEFAULT '{}',
+    voice_channel_category_ids bigint[] NOT NULL DEFAULT '{}',
+    bot_alerts_channel_id bigint,
+    free_spots_channel_id bigint,
+    user_topic_voice_channel_id bigint
+);
+CREATE UNIQUE INDEX guild_config_is_default_idx ON guild_config (is_default) WHERE is_default;
+
+CREATE TABLE guild_member (
+    guild_id bigint NOT NULL,
+    member_id integer NOT NULL REFERENCES "member" (id),
+    discord_nick text,
+    PRIMARY KEY (guild_id, member_id)
+);
+
+CREATE TABLE session_reminder_log (
+    event_id integer NOT NULL REFERENCES event (id),
+    offset_minutes integer NOT NULL,
+    sent_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT session_reminder_log_pk PRIMARY KEY (event_id, offset_minutes)
+);
+
+CREATE SEQUENCE audit_log_id_seq START WITH 1000;
+CREATE TABLE audit_log (
+    id integer PRIMARY KEY DEFAULT nextval('audit_log_id_seq'),
+    action_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    actor_discord_id bigint,
+    action text NOT NULL,
+    target_type text NOT NULL,
+    target_id bigint NOT NULL,
+    channel_id bigint,
+    before jsonb,
+    after jsonb
+);
+ALTER SEQUENCE audit_log_id_seq OWNED BY audit_log.id;
+CREATE INDEX audit_log_action_time_idx ON audit_log USING btree (action_time);
+CREATE INDEX audit_log_channel_id_idx ON audit_log USING btree (channel_id);
+
+CREATE TABLE meetup_event_directive_report (
+    meetup_id text PRIMARY KEY,
+    problems text NOT NULL, -- One problem per line
+    reported_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE SEQUENCE date_poll_id_seq START WITH 1000;
+CREATE TABLE date_poll (
+    id integer PRIMARY KEY DEFAULT nextval('date_poll_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    created_by_member_id integer NOT NULL REFERENCES "member" (id),
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    duration_minutes integer NOT NULL,
+    discord_summary_channel_id bigint,
+    discord_summary_message_id bigint,
+    scheduled_event_id integer REFERENCES event (id),
+    closed_time timestamp (0) with time zone -- Set once one of the slots has been scheduled
+);
+ALTER SEQUENCE date_poll_id_seq OWNED BY date_poll.id;
+
+CREATE SEQUENCE date_poll_slot_id_seq START WITH 1000;
+CREATE TABLE date_poll_slot (
+    id integer PRIMARY KEY DEFAULT nextval('date_poll_slot_id_seq'),
+    date_poll_id integer NOT NULL REFERENCES date_poll (id) ON DELETE CASCADE,
+    start_time timestamp (0) with time zone NOT NULL
+);
+ALTER SEQUENCE date_poll_slot_id_seq OWNED BY date_poll_slot.id;
+CREATE INDEX date_poll_slot_date_poll_id_idx ON date_poll_slot USING btree (date_poll_id);
+
+CREATE TABLE date_poll_vote (
+    date_poll_slot_id integer NOT NULL REFERENCES date_poll_slot (id) ON DELETE CASCADE,
+    member_id integer NOT NULL REFERENCES "member" (id) ON DELETE CASCADE,
+    answer text NOT NULL CHECK (answer IN ('yes', 'maybe', 'no')),
+    CONSTRAINT date_poll_vote_pk PRIMARY KEY (date_poll_slot_id, member_id)
+);
+
+CREATE SEQUENCE channel_archive_id_seq START WITH 1000;
+CREATE TABLE channel_archive (
+    id integer PRIMARY KEY DEFAULT nextval('channel_archive_id_seq'),
+    event_series_id integer NOT NULL REFERENCES event_series (id),
+    discord_text_channel_id bigint UNIQUE NOT NULL REFERENCES event_series_text_channel (discord_id),
+    channel_name text NOT NULL,
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    num_messages integer NOT NULL,
+    content jsonb NOT NULL, -- Messages, attachment metadata and pins
+    html text NOT NULL -- The messages rendered as a static page
+);
+ALTER SEQUENCE channel_archive_id_seq OWNED BY channel_archive.id;
+CREATE INDEX channel_archive_event_series_id_idx ON channel_archive USING btree (event_series_id);
+
+CREATE TABLE discord_orphan (
+    discord_id bigint NOT NULL,
+    kind text NOT NULL, -- See OrphanKind
+    guild_id bigint NOT NULL,
+    name text, -- NULL if the Discord object is gone
+    event_series_id integer REFERENCES event_series (id) ON DELETE SET NULL,
+    detected_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT discord_orphan_pk PRIMARY KEY (discord_id, kind)
+);
+
+CREATE TABLE discord_overflow_category (
+    discord_id bigint PRIMARY KEY,
+    guild_id bigint NOT NULL,
+    primary_category_id bigint NOT NULL, -- The configured category that was full
+    "number" integer NOT NULL, -- 2 for the first overflow category
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT discord_overflow_category_number_unique UNIQUE (primary_category_id, "number")
+);
+
+CREATE SEQUENCE sync_job_id_seq START WITH 1000;
+CREATE TABLE sync_job (
+    id integer PRIMARY KEY DEFAULT nextval('sync_job_id_seq'),
+    kind text NOT NULL, -- See SyncJob
+    event_series_id integer REFERENCES event_series (id) ON DELETE CASCADE,
+    discord_user_id bigint,
+    reason text NOT NULL, -- What caused the job, e.g. the command which enqueued it
+    created_time timestamp (0) with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_time timestamp (0) with time zone, -- NULL while the job is pending
+    finished_time timestamp (0) with time zone,
+    error text
+);
+ALTER SEQUENCE sync_job_id_seq OWNED BY sync_job.id;
+CREATE UNIQUE INDEX sync_job_pending_idx ON sync_job USING btree (kind, COALESCE(event_series_id, 0), COALESCE(discord_user_id, 0)) WHERE started_time IS NULL;
+CREATE INDEX sync_job_finished_time_idx ON sync_job USING btree (finished_time);
+
+-- Wakes up the sync queue task whenever a job is enqueued
+CREATE FUNCTION notify_sync_job() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('sync_job', NEW.id::text);
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+CREATE TRIGGER sync_job_notify AFTER INSERT ON sync_job FOR EACH ROW EXECUTE FUNCTION notify_sync_job();
@@ -3,14 +3,19 @@ use std::sync::{
     Arc,
 };
 
-use futures_util::lock::Mutex as AsyncMutex;
-use lib::strings;
+use lib::{
+    discord::{config::GuildConfigs, rsvp::RsvpOutcome},
+    strings,
+};
 use serenity::{
     all::{ApplicationId, GuildMemberUpdateEvent},
     async_trait,
-    builder::CreateMessage,
+    builder::{
+        CreateAutocompleteResponse, CreateInteractionResponse, CreateInteractionResponseMessage,
+        CreateMessage,
+    },
     model::{
-        application::Interaction,
+        application::{CommandInteraction, CommandOptionType, ComponentInteraction, Interaction},
         channel::Message,
         gateway::{GatewayIntents, Ready},
         guild::Member,
@@ -20,16 +25,17 @@ use serenity::{
     prelude::*,
 };
 
-use super::commands::{CommandContext, PreparedCommands};
+use super::commands::{Command, CommandContext, CommandLevel, PreparedCommands};
 
 pub async fn create_discord_client(
     discord_token: &str,
     application_id: ApplicationId,
     redis_client: redis::Client,
     pool: sqlx::PgPool,
-    async_meetup_client: Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
+    meetup_connection: lib::meetup::connection::MeetupConnection,
     oauth2_consumer: Arc<lib::meetup::oauth2::OAuth2Consumer>,
     stripe_client: Arc<stripe::Client>,
+    guild_configs: Arc<GuildConfigs>,
     shutdown_signal: Arc<AtomicBool>,
 ) -> Result<Client, lib::meetup::Error> {
     // Create a new instance of the Client, logging in as a bot. This will
@@ -67,11 +73,12 @@ pub async fn create_discord_client(
         let mut data = client.data.write().await;
         data.insert::<BotIdKey>(bot_id);
         data.insert::<BotNameKey>(bot_name);
-        data.insert::<AsyncMeetupClientKey>(async_meetup_client);
+        data.insert::<MeetupConnectionKey>(meetup_connection);
         data.insert::<RedisClientKey>(redis_client);
         data.insert::<PoolKey>(pool);
         data.insert::<OAuth2ConsumerKey>(oauth2_consumer);
         data.insert::<StripeClientKey>(stripe_client);
+        data.insert::<GuildConfigsKey>(guild_configs);
         data.insert::<ShutdownSignalKey>(shutdown_signal);
         data.insert::<PreparedCommandsKey>(prepared_commands);
     }
@@ -89,9 +96,9 @@ impl TypeMapKey for BotNameKey {
     type Value = String;
 }
 
-pub struct AsyncMeetupClientKey;
-impl TypeMapKey for AsyncMeetupClientKey {
-    type Value = Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>;
+pub struct MeetupConnectionKey;
+impl TypeMapKey for MeetupConnectionKey {
+    type Value = lib::meetup::connection::MeetupConnection;
 }
 
 pub struct RedisClientKey;
@@ -109,6 +116,11 @@ impl TypeMapKey for StripeClientKey {
     type Value = Arc<stripe::Client>;
 }
 
+pub struct GuildConfigsKey;
+impl TypeMapKey for GuildConfigsKey {
+    type Value = Arc<GuildConfigs>;
+}
+
 pub struct ShutdownSignalKey;
 impl TypeMapKey for ShutdownSignalKey {
     type Value = Arc<AtomicBool>;
@@ -183,37 +195,173 @@ impl Handler {
         };
         // Check whether the user has the required permissions
         let command = commands.commands[i];
+        if let Some(refusal) = Self::check_command_level(cmdctx, command).await? {
+            cmdctx.msg.channel_id.say(&cmdctx.ctx, refusal).await.ok();
+            return Ok(());
+        }
+        // Call the command
+        (command.fun)(cmdctx, captures).await?;
+        Ok(())
+    }
+
+    // Returns the message to reply with if the user is not allowed to use the
+    // command
+    async fn check_command_level(
+        cmdctx: &mut CommandContext,
+        command: &Command,
+    ) -> Result<Option<&'static str>, lib::meetup::Error> {
         match command.level {
-            super::commands::CommandLevel::Everybody => (),
-            super::commands::CommandLevel::AdminOnly => {
-                if !cmdctx.is_admin().await? {
-                    cmdctx
-                        .msg
-                        .channel_id
-                        .say(&cmdctx.ctx, strings::NOT_A_BOT_ADMIN)
-                        .await
-                        .ok();
-                    return Ok(());
+            CommandLevel::Everybody => Ok(None),
+            CommandLevel::AdminOnly => {
+                if cmdctx.is_admin().await? {
+                    Ok(None)
+                } else {
+                    Ok(Some(strings::NOT_A_BOT_ADMIN))
                 }
             }
-            super::commands::CommandLevel::HostAndAdminOnly => {
+            CommandLevel::HostAndAdminOnly => {
                 let is_admin = cmdctx.is_admin().await?;
                 let is_host = cmdctx.is_host().await?;
-                if !is_admin && !is_host {
-                    cmdctx
-                        .msg
-                        .channel_id
-                        .say(&cmdctx.ctx, strings::NOT_A_CHANNEL_ADMIN)
-                        .await
-                        .ok();
-                    return Ok(());
+                if is_admin || is_host {
+                    Ok(None)
+                } else {
+                    Ok(Some(strings::NOT_A_CHANNEL_ADMIN))
                 }
             }
         }
-        // Call the command
-        (command.fun)(cmdctx, captures).await?;
+    }
+
+    async fn handle_slash_command(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<(), lib::meetup::Error> {
+        let (commands, shutdown_signal) = {
+            let data = ctx.data.read().await;
+            let commands = data
+                .get::<PreparedCommandsKey>()
+                .cloned()
+                .expect("Prepared commands have not been set");
+            let shutdown_signal = data
+                .get::<ShutdownSignalKey>()
+                .expect("Shutdown signal was not set")
+                .load(Ordering::Acquire);
+            (commands, shutdown_signal)
+        };
+        let (slash, command, regex) = match commands
+            .slash_commands
+            .iter()
+            .find(|(slash, _, _)| slash.name == interaction.data.name)
+        {
+            Some(slash_command) => slash_command,
+            None => {
+                eprintln!("Unrecognized slash command: {}", interaction.data.name);
+                Self::respond_ephemeral(ctx, interaction, "Unknown command").await;
+                return Ok(());
+            }
+        };
+        if shutdown_signal {
+            Self::respond_ephemeral(
+                ctx,
+                interaction,
+                "Sorry, I can not help you right now. I am about to shut down!",
+            )
+            .await;
+            return Ok(());
+        }
+        // Acknowledge the interaction right away since Discord only waits a
+        // few seconds for a response and commands can take longer than that
+        interaction.defer_ephemeral(ctx).await?;
+        let message_content = super::slash::command_text(slash, interaction);
+        let mut cmdctx = CommandContext::from_interaction(
+            ctx.clone(),
+            interaction.clone(),
+            message_content.clone(),
+        );
+        let res: Result<Option<&str>, lib::meetup::Error> = async {
+            let captures = match regex.captures(&message_content) {
+                Some(captures) => captures,
+                None => {
+                    eprintln!("Unmatcheable slash command: {}", &message_content);
+                    return Ok(Some(
+                        "I can't parse your command. Please check its options.",
+                    ));
+                }
+            };
+            if let Some(refusal) = Self::check_command_level(&mut cmdctx, command).await? {
+                return Ok(Some(refusal));
+            }
+            (command.fun)(&mut cmdctx, captures).await?;
+            Ok(None)
+        }
+        .await;
+        let response = match res {
+            Ok(Some(response)) => Some(response),
+            // Confirm the command unless it already responded by itself
+            Ok(None) if cmdctx.has_responded() => None,
+            Ok(None) => Some("\u{2705}"),
+            Err(err) => {
+                eprintln!("Error in slash command handler:\n{:#?}", err);
+                Some(lib::strings::UNSPECIFIED_ERROR)
+            }
+        };
+        if let Some(response) = response {
+            cmdctx.reply(response).await?;
+        }
         Ok(())
     }
+
+    async fn handle_autocomplete(
+        ctx: &Context,
+        interaction: &CommandInteraction,
+    ) -> Result<(), lib::meetup::Error> {
+        let commands = ctx
+            .data
+            .read()
+            .await
+            .get::<PreparedCommandsKey>()
+            .cloned()
+            .expect("Prepared commands have not been set");
+        let autocomplete = commands
+            .slash_commands
+            .iter()
+            .find(|(slash, _, _)| slash.name == interaction.data.name)
+            .and_then(|(slash, _, _)| slash.autocomplete);
+        let (autocomplete, option) = match (autocomplete, interaction.data.autocomplete()) {
+            (Some(autocomplete), Some(option)) => (autocomplete, option),
+            _ => return Ok(()),
+        };
+        let is_integer = option.kind == CommandOptionType::Integer;
+        // Discord shows at most 25 choices
+        let choices = autocomplete(ctx, interaction, option)
+            .await?
+            .into_iter()
+            .take(25)
+            .fold(
+                CreateAutocompleteResponse::new(),
+                |response, choice| match choice.value.parse::<i64>() {
+                    Ok(value) if is_integer => response.add_int_choice(choice.name, value),
+                    _ => response.add_string_choice(choice.name, choice.value),
+                },
+            );
+        interaction
+            .create_response(ctx, CreateInteractionResponse::Autocomplete(choices))
+            .await?;
+        Ok(())
+    }
+
+    async fn respond_ephemeral(ctx: &Context, interaction: &CommandInteraction, content: &str) {
+        interaction
+            .create_response(
+                ctx,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(content)
+                        .ephemeral(true),
+                ),
+            )
+            .await
+            .ok();
+    }
 }
 
 #[async_trait]
@@ -224,23 +372,26 @@ impl EventHandler for Handler {
     // Event handlers are dispatched through a threadpool, and so multiple
     // events can be dispatched simultaneously.
     async fn message(&self, ctx: Context, msg: Message) {
-        let (bot_id, shutdown_signal) = {
+        let (bot_id, guild_configs, shutdown_signal) = {
             let data = ctx.data.read().await;
             let bot_id = data.get::<BotIdKey>().expect("Bot ID was not set").clone();
+            let guild_configs = data
+                .get::<GuildConfigsKey>()
+                .cloned()
+                .expect("Guild configs were not set");
             let shutdown_signal = data
                 .get::<ShutdownSignalKey>()
                 .expect("Shutdown signal was not set")
                 .load(Ordering::Acquire);
-            (bot_id, shutdown_signal)
+            (bot_id, guild_configs, shutdown_signal)
         };
         // Ignore all messages written by the bot itself
         if msg.author.id == bot_id {
             return;
         }
-        // Ignore all messages that might have come from another guild
-        // (shouldn't happen, but who knows)
-        if let Some(guild_id) = msg.guild_id {
-            if guild_id != lib::discord::sync::ids::GUILD_ID {
+        // Ignore all messages that come from a guild we don't manage
+        if let Some(msg_guild_id) = msg.guild_id {
+            if !guild_configs.contains(msg_guild_id) {
                 return;
             }
         }
@@ -307,12 +458,37 @@ impl EventHandler for Handler {
     // private channels, and more.
     //
     // In this case, just print what the current user's username is.
-    async fn ready(&self, _: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         println!("{} is connected!", ready.user.name);
+        let (commands, guild_configs) = {
+            let data = ctx.data.read().await;
+            let commands = data
+                .get::<PreparedCommandsKey>()
+                .cloned()
+                .expect("Prepared commands have not been set");
+            let guild_configs = data
+                .get::<GuildConfigsKey>()
+                .cloned()
+                .expect("Guild configs were not set");
+            (commands, guild_configs)
+        };
+        for guild_config in guild_configs.iter() {
+            if let Err(err) =
+                super::slash::register_slash_commands(&ctx, guild_config.guild_id, &commands).await
+            {
+                eprintln!(
+                    "Could not register the slash commands in guild {}:\n{:#?}",
+                    guild_config.guild_id, err
+                );
+            }
+        }
     }
 
     async fn guild_member_addition(&self, ctx: Context, new_member: Member) {
-        if new_member.guild_id != lib::discord::sync::ids::GUILD_ID {
+        if !Self::guild_configs(&ctx)
+            .await
+            .contains(new_member.guild_id)
+        {
             return;
         }
         Self::send_welcome_message(&ctx, &new_member.user).await;
@@ -320,7 +496,7 @@ impl EventHandler for Handler {
             .nick
             .as_deref()
             .unwrap_or(new_member.user.name.as_str());
-        Self::update_member_nick(&ctx, new_member.user.id, nick)
+        Self::update_member_nick(&ctx, new_member.guild_id, new_member.user.id, nick)
             .await
             .ok();
     }
@@ -332,106 +508,204 @@ impl EventHandler for Handler {
         _new: Option<Member>,
         event: GuildMemberUpdateEvent,
     ) {
+        if !Self::guild_configs(&ctx).await.contains(event.guild_id) {
+            return;
+        }
         let nick = event.nick.as_deref().unwrap_or(event.user.name.as_str());
-        Self::update_member_nick(&ctx, event.user.id, nick)
+        Self::update_member_nick(&ctx, event.guild_id, event.user.id, nick)
             .await
             .ok();
     }
 
     async fn cache_ready(&self, ctx: Context, guilds: Vec<GuildId>) {
-        let guild_id = match guilds.as_slice() {
-            [guild] => guild,
-            _ => {
-                eprintln!("cache_ready event received not exactly one guild");
-                return;
+        let guild_configs = Self::guild_configs(&ctx).await;
+        for guild_id in &guilds {
+            if !guild_configs.contains(*guild_id) {
+                eprintln!(
+                    "cache_ready event received guild {} which is not configured",
+                    guild_id
+                );
+            }
+        }
+        for guild_config in guild_configs.iter() {
+            // Check that the configured roles and channels actually exist
+            let problems = guild_config.validate(&ctx.cache);
+            if !problems.is_empty() {
+                let message = format!(
+                    "Found problems with the configuration of guild {}:\n{}",
+                    guild_config.guild_id,
+                    problems.join("\n")
+                );
+                eprintln!("{}", message);
+                lib::discord::util::say_in_bot_alerts_channel(
+                    &message,
+                    &(&ctx).into(),
+                    guild_config,
+                )
+                .await
+                .ok();
+            }
+            let members = match ctx
+                .cache
+                .guild(guild_config.guild_id)
+                .map(|guild| guild.members.clone())
+            {
+                Some(members) => members,
+                None => continue,
+            };
+            println!(
+                "Updating {} cached member nicks of guild {}",
+                members.len(),
+                guild_config.guild_id
+            );
+            for (user_id, member) in members {
+                let nick = member.nick.as_deref().unwrap_or(member.user.name.as_str());
+                Self::update_member_nick(&ctx, guild_config.guild_id, user_id, nick)
+                    .await
+                    .ok();
             }
-        };
-        let members = match ctx.cache.guild(guild_id).map(|guild| guild.members.clone()) {
-            Some(members) => members,
-            None => return,
-        };
-        println!("Updating {} cached member nicks", members.len());
-        for (user_id, member) in members {
-            let nick = member.nick.as_deref().unwrap_or(member.user.name.as_str());
-            Self::update_member_nick(&ctx, user_id, nick).await.ok();
         }
     }
 
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
-        // let (bot_id, shutdown_signal) = {
-        //     let data = ctx.data.read().await;
-        //     let bot_id = data.get::<BotIdKey>().expect("Bot ID was not set").clone();
-        //     let shutdown_signal = data
-        //         .get::<ShutdownSignalKey>()
-        //         .expect("Shutdown signal was not set")
-        //         .load(Ordering::Acquire);
-        //     (bot_id, shutdown_signal)
-        // };
-        // In contrast to the message handler we don't need to check that this
-        // is indeed a command.
-        let interaction = match interaction {
-            Interaction::Command(inner) => inner,
+        let (interaction, is_autocomplete) = match interaction {
+            Interaction::Command(interaction) => (interaction, false),
+            Interaction::Autocomplete(interaction) => (interaction, true),
+            Interaction::Component(interaction) => {
+                match interaction.guild_id {
+                    Some(guild_id) if Self::guild_configs(&ctx).await.contains(guild_id) => (),
+                    _ => return,
+                }
+                if let Err(err) = Self::handle_component(&ctx, &interaction).await {
+                    eprintln!("Error in component interaction handler:\n{:#?}", err);
+                }
+                return;
+            }
             _ => return,
         };
-
-        // Ignore all messages that might have come from another guild
-        // (shouldn't happen, but who knows)
-        if interaction.guild_id != Some(lib::discord::sync::ids::GUILD_ID) {
-            return;
+        // Ignore all interactions that come from a guild we don't manage
+        match interaction.guild_id {
+            Some(guild_id) if Self::guild_configs(&ctx).await.contains(guild_id) => (),
+            _ => return,
         }
-        match interaction.data.name.as_str() {
-            "link-meetup" => {
-                interaction
-                    .channel_id
-                    .say(&ctx, "Yessir! Linking Meetup")
-                    .await
-                    .ok();
-            }
-            "unlink-meetup" => {
-                interaction
-                    .channel_id
-                    .say(&ctx, "Un-linking Meetup")
-                    .await
-                    .ok();
-            }
-            _ => {
-                interaction
-                    .channel_id
-                    .say(&ctx, "Unknown command")
-                    .await
-                    .ok();
-            }
+        let res = if is_autocomplete {
+            Self::handle_autocomplete(&ctx, &interaction).await
+        } else {
+            Self::handle_slash_command(&ctx, &interaction).await
         };
+        if let Err(err) = res {
+            eprintln!("Error in interaction handler:\n{:#?}", err);
+        }
     }
 }
 
 impl Handler {
+    // Handles clicks on the RSVP buttons of event announcements
+    async fn handle_component(
+        ctx: &Context,
+        interaction: &ComponentInteraction,
+    ) -> Result<(), lib::meetup::Error> {
+        let (event_id, going) =
+            match lib::discord::rsvp::parse_custom_id(&interaction.data.custom_id) {
+                Some(rsvp) => rsvp,
+                None => return Ok(()),
+            };
+        let pool = ctx
+            .data
+            .read()
+            .await
+            .get::<PoolKey>()
+            .cloned()
+            .ok_or_else(|| simple_error::SimpleError::new("Postgres pool was not set"))?;
+        let reply =
+            match lib::discord::rsvp::rsvp(event_id, interaction.user.id, going, &pool).await {
+                Ok(outcome) => match outcome {
+                    RsvpOutcome::Going => strings::RSVP_GOING,
+                    RsvpOutcome::AlreadyGoing => strings::RSVP_ALREADY_GOING,
+                    RsvpOutcome::Full => strings::RSVP_FULL,
+                    RsvpOutcome::NotGoing => strings::RSVP_NOT_GOING,
+                    RsvpOutcome::NotRsvped => strings::RSVP_NOT_RSVPED,
+                    RsvpOutcome::RsvpedOnMeetup => strings::RSVP_MEETUP_ORIGIN,
+                    RsvpOutcome::EventOver => strings::RSVP_EVENT_OVER,
+                },
+                Err(err) => {
+                    eprintln!("Could not record RSVP:\n{:#?}", err);
+                    strings::UNSPECIFIED_ERROR
+                }
+            };
+        interaction
+            .create_response(
+                &ctx.http,
+                CreateInteractionResponse::Message(
+                    CreateInteractionResponseMessage::new()
+                        .content(reply)
+                        .ephemeral(true),
+                ),
+            )
+            .await?;
+        // Show the new attendee list
+        let discord_api: lib::discord::CacheAndHttp = ctx.into();
+        lib::discord::rsvp::sync_event_announcement(event_id, &pool, &discord_api).await
+    }
+
+    async fn guild_configs(ctx: &Context) -> Arc<GuildConfigs> {
+        ctx.data
+            .read()
+            .await
+            .get::<GuildConfigsKey>()
+            .cloned()
+            .expect("Guild configs were not set")
+    }
+
     async fn send_welcome_message(ctx: &Context, user: &User) {
         user.direct_message(ctx, CreateMessage::new().content(strings::WELCOME_MESSAGE))
             .await
             .ok();
     }
 
+    // Stores the member's nick in the given guild. The nick in the default
+    // guild is also stored as the member's main Discord nick.
     async fn update_member_nick(
         ctx: &Context,
+        guild_id: GuildId,
         discord_id: UserId,
         nick: &str,
     ) -> Result<(), lib::meetup::Error> {
-        let pool = {
+        let (pool, is_default_guild) = {
             let data = ctx.data.read().await;
-            data.get::<super::bot::PoolKey>()
+            let pool = data
+                .get::<super::bot::PoolKey>()
                 .cloned()
-                .ok_or_else(|| simple_error::SimpleError::new("Postgres pool was not set"))?
+                .ok_or_else(|| simple_error::SimpleError::new("Postgres pool was not set"))?;
+            let is_default_guild = data
+                .get::<GuildConfigsKey>()
+                .ok_or_else(|| simple_error::SimpleError::new("Guild configs were not set"))?
+                .default_guild()
+                .guild_id
+                == guild_id;
+            (pool, is_default_guild)
         };
         let mut tx = pool.begin().await?;
         let member_id = lib::db::get_or_create_member_for_discord_id(&mut tx, discord_id).await?;
         sqlx::query!(
-            r#"UPDATE member SET discord_nick = $2 WHERE id = $1 AND discord_nick IS DISTINCT FROM $2"#,
+            r#"INSERT INTO guild_member (guild_id, member_id, discord_nick) VALUES ($1, $2, $3)
+            ON CONFLICT (guild_id, member_id) DO UPDATE SET discord_nick = EXCLUDED.discord_nick
+            WHERE guild_member.discord_nick IS DISTINCT FROM EXCLUDED.discord_nick"#,
+            guild_id.get() as i64,
             member_id.0,
             nick
         )
         .execute(&mut *tx)
         .await?;
+        if is_default_guild {
+            sqlx::query!(
+                r#"UPDATE member SET discord_nick = $2 WHERE id = $1 AND discord_nick IS DISTINCT FROM $2"#,
+                member_id.0,
+                nick
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
         tx.commit().await?;
         Ok(())
     }
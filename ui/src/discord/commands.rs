@@ -1,17 +1,23 @@
 use std::{future::Future, pin::Pin, sync::Arc};
 
-use futures_util::lock::Mutex as AsyncMutex;
+use lib::discord::config::{GuildConfig, GuildConfigs};
 use once_cell::sync::OnceCell;
 use regex::{Regex, RegexSet};
 use serenity::{
+    builder::{
+        CreateEmbed, CreateInteractionResponseFollowup, CreateMessage, EditInteractionResponse,
+    },
     model::{
-        channel::{Channel, Message},
-        id::UserId,
+        application::{AutocompleteOption, CommandInteraction},
+        channel::{Channel, Message, ReactionType},
+        id::{ChannelId, UserId},
     },
     prelude::*,
 };
 
 mod add_user;
+mod audit;
+mod calendar;
 // mod clone_event;
 mod count_inactive;
 mod end_adventure;
@@ -23,11 +29,15 @@ mod list_players;
 mod list_subscriptions;
 mod login;
 mod manage_channel;
+mod manage_session;
 // mod mention_channel;
 mod numcached;
+mod orphans;
+mod poll_dates;
 // mod refresh_meetup_token;
 mod remind_expiration;
 mod schedule_session;
+mod session_reminders;
 mod snooze;
 mod stop;
 mod sync_discord;
@@ -46,6 +56,8 @@ static ALL_COMMANDS: &[&Command] = &[
     &topic::SET_VOICE_TOPIC_COMMAND,
     &sync_meetup::SYNC_MEETUP_COMMAND,
     &sync_discord::SYNC_DISCORD_COMMAND,
+    &sync_discord::SYNC_DISCORD_DRY_RUN_COMMAND,
+    &sync_discord::SYNC_QUEUE_COMMAND,
     &remind_expiration::REMIND_EXPIRATION_COMMAND,
     &add_user::ADD_USER_COMMAND,
     &add_user::ADD_HOST_COMMAND,
@@ -55,6 +67,9 @@ static ALL_COMMANDS: &[&Command] = &[
     &help::HELP_COMMAND,
     // &refresh_meetup_token::REFRESH_MEETUP_TOKEN_COMMAND,
     &schedule_session::SCHEDULE_SESSION_COMMAND,
+    &manage_session::RESCHEDULE_SESSION_COMMAND,
+    &manage_session::CANCEL_SESSION_COMMAND,
+    &poll_dates::POLL_DATES_COMMAND,
     &whois::WHOIS_COMMAND,
     &list_players::LIST_PLAYERS_COMMAND,
     #[cfg(feature = "bottest")]
@@ -70,6 +85,13 @@ static ALL_COMMANDS: &[&Command] = &[
     // &clone_event::CLONE_EVENT_COMMAND,
     // &test::TEST_COMMAND,
     &login::LOGIN_COMMAND,
+    &calendar::CALENDAR_COMMAND,
+    &session_reminders::SESSION_REMINDERS_COMMAND,
+    &audit::AUDIT_COMMAND,
+    &orphans::LIST_ORPHANS_COMMAND,
+    &orphans::ADOPT_ORPHAN_COMMAND,
+    &orphans::RELINK_ORPHAN_COMMAND,
+    &orphans::DELETE_ORPHAN_COMMAND,
 ];
 
 const MENTION_PATTERN: &'static str = r"(?:<@!?(?P<mention_id>[0-9]+)>)";
@@ -92,6 +114,24 @@ pub struct RegexParts<'a> {
 
 type CommandResult<'a> = Pin<Box<dyn Future<Output = Result<(), lib::meetup::Error>> + Send + 'a>>;
 
+type AutocompleteResult<'a> =
+    Pin<Box<dyn Future<Output = Result<Vec<AutocompleteChoice>, lib::meetup::Error>> + Send + 'a>>;
+
+// A suggestion for an option's value. The name is what the user gets to see.
+pub(crate) struct AutocompleteChoice {
+    pub name: String,
+    pub value: String,
+}
+
+impl From<String> for AutocompleteChoice {
+    fn from(value: String) -> Self {
+        AutocompleteChoice {
+            name: value.clone(),
+            value,
+        }
+    }
+}
+
 pub(crate) struct Command {
     pub regex: fn(&RegexParts<'_>) -> String,
     pub level: CommandLevel,
@@ -99,6 +139,40 @@ pub(crate) struct Command {
                   + Sync
                   + 'static),
     pub help: &'static [HelpEntry],
+    pub slash: Option<&'static SlashCommand>,
+}
+
+pub(crate) enum SlashOptionKind {
+    User,
+    String,
+    Integer,
+    Channel,
+}
+
+pub(crate) struct SlashOption {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: SlashOptionKind,
+    pub required: bool,
+    pub autocomplete: bool,
+}
+
+// The slash command version of a command. An interaction is turned into the
+// equivalent text command by replacing each `{option}` in the template with
+// the option's value, such that it can be handled by the same function.
+pub(crate) struct SlashCommand {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub template: &'static str,
+    pub options: &'static [SlashOption],
+    pub autocomplete: Option<
+        &'static (dyn for<'a> Fn(
+            &'a Context,
+            &'a CommandInteraction,
+            AutocompleteOption<'a>,
+        ) -> AutocompleteResult<'a>
+                      + Sync),
+    >,
 }
 
 pub(crate) struct HelpEntry {
@@ -112,30 +186,165 @@ pub struct CommandContext {
     // pub captures: regex::Captures<'a>,
     redis_client: OnceCell<redis::Client>,
     async_redis_connection: OnceCell<redis::aio::Connection>,
-    meetup_client: OnceCell<Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>>,
+    meetup_connection: OnceCell<lib::meetup::connection::MeetupConnection>,
     oauth2_consumer: OnceCell<Arc<lib::meetup::oauth2::OAuth2Consumer>>,
     stripe_client: OnceCell<Arc<stripe::Client>>,
+    guild_configs: OnceCell<Arc<GuildConfigs>>,
     bot_id: OnceCell<UserId>,
     channel: OnceCell<Channel>,
     pool: OnceCell<sqlx::PgPool>,
+    // Slash commands are answered through their (deferred) interaction since
+    // their message is made up and can't be replied or reacted to
+    interaction: Option<CommandInteraction>,
+    has_responded: bool,
+}
+
+// Answers a command after the command itself has returned. Slash commands get
+// a follow-up, which Discord accepts for 15 minutes after the command was used.
+pub struct LateReply {
+    http: Arc<serenity::http::Http>,
+    channel_id: ChannelId,
+    interaction: Option<CommandInteraction>,
+}
+
+impl LateReply {
+    pub async fn say(&self, content: impl Into<String>) -> Result<(), lib::meetup::Error> {
+        let content = content.into();
+        if let Some(interaction) = &self.interaction {
+            let followup = CreateInteractionResponseFollowup::new()
+                .content(content.clone())
+                .ephemeral(true);
+            match interaction.create_followup(&self.http, followup).await {
+                Ok(_) => return Ok(()),
+                // Fall back to the channel if the interaction expired
+                Err(err) => eprintln!("Could not send a follow-up:\n{:#?}", err),
+            }
+        }
+        self.channel_id.say(&self.http, content).await?;
+        Ok(())
+    }
 }
 
 impl CommandContext {
     pub fn new(ctx: Context, msg: Message) -> Self {
+        Self::with_interaction(ctx, msg, None)
+    }
+
+    pub fn from_interaction(
+        ctx: Context,
+        interaction: CommandInteraction,
+        content: String,
+    ) -> Self {
+        let msg = super::slash::command_message(&interaction, content);
+        Self::with_interaction(ctx, msg, Some(interaction))
+    }
+
+    fn with_interaction(
+        ctx: Context,
+        msg: Message,
+        interaction: Option<CommandInteraction>,
+    ) -> Self {
         CommandContext {
             ctx,
             msg,
             redis_client: OnceCell::new(),
             async_redis_connection: OnceCell::new(),
-            meetup_client: OnceCell::new(),
+            meetup_connection: OnceCell::new(),
             oauth2_consumer: OnceCell::new(),
             stripe_client: OnceCell::new(),
+            guild_configs: OnceCell::new(),
             bot_id: OnceCell::new(),
             channel: OnceCell::new(),
             pool: OnceCell::new(),
+            interaction,
+            has_responded: false,
+        }
+    }
+
+    // Whether the command has replied or reacted already
+    pub fn has_responded(&self) -> bool {
+        self.has_responded
+    }
+
+    // Reacts to the command's message. Slash commands get the emoji as their
+    // response instead.
+    pub async fn react(
+        &mut self,
+        reaction: impl Into<ReactionType>,
+    ) -> Result<(), lib::meetup::Error> {
+        let reaction = reaction.into();
+        if self.interaction.is_some() {
+            return self.reply(reaction.to_string()).await;
+        }
+        self.msg.react(&self.ctx, reaction).await?;
+        self.has_responded = true;
+        Ok(())
+    }
+
+    // Answers the command in the channel it was used in. For slash commands,
+    // the first answer becomes the interaction's response and later ones are
+    // sent as follow-ups, all of them only visible to the user who used the
+    // command. Messages meant for everyone in the channel should be sent to
+    // the channel directly instead.
+    pub async fn reply(&mut self, content: impl Into<String>) -> Result<(), lib::meetup::Error> {
+        self.respond(Some(content.into()), None).await
+    }
+
+    // Like reply, but with an embed instead of text
+    pub async fn reply_embed(&mut self, embed: CreateEmbed) -> Result<(), lib::meetup::Error> {
+        self.respond(None, Some(embed)).await
+    }
+
+    // For answering once a task spawned by the command is done
+    pub fn late_reply(&self) -> LateReply {
+        LateReply {
+            http: self.ctx.http.clone(),
+            channel_id: self.msg.channel_id,
+            interaction: self.interaction.clone(),
         }
     }
 
+    async fn respond(
+        &mut self,
+        content: Option<String>,
+        embed: Option<CreateEmbed>,
+    ) -> Result<(), lib::meetup::Error> {
+        match &self.interaction {
+            Some(interaction) if self.has_responded => {
+                let mut followup = CreateInteractionResponseFollowup::new().ephemeral(true);
+                if let Some(content) = content {
+                    followup = followup.content(content);
+                }
+                if let Some(embed) = embed {
+                    followup = followup.embed(embed);
+                }
+                interaction.create_followup(&self.ctx, followup).await?;
+            }
+            Some(interaction) => {
+                let mut response = EditInteractionResponse::new();
+                if let Some(content) = content {
+                    response = response.content(content);
+                }
+                if let Some(embed) = embed {
+                    response = response.embed(embed);
+                }
+                interaction.edit_response(&self.ctx, response).await?;
+            }
+            None => {
+                let mut message = CreateMessage::new();
+                if let Some(content) = content {
+                    message = message.content(content);
+                }
+                if let Some(embed) = embed {
+                    message = message.embed(embed);
+                }
+                self.msg.channel_id.send_message(&self.ctx, message).await?;
+            }
+        }
+        self.has_responded = true;
+        Ok(())
+    }
+
     pub async fn redis_client(&self) -> Result<redis::Client, lib::meetup::Error> {
         if let Some(client) = self.redis_client.get() {
             Ok(client.clone())
@@ -181,27 +390,21 @@ impl CommandContext {
         }
     }
 
-    pub async fn meetup_client(
+    pub async fn meetup_connection(
         &self,
-    ) -> Result<Arc<AsyncMutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>, lib::meetup::Error>
-    {
-        if let Some(client) = self.meetup_client.get() {
-            Ok(Arc::clone(client))
+    ) -> Result<lib::meetup::connection::MeetupConnection, lib::meetup::Error> {
+        if let Some(connection) = self.meetup_connection.get() {
+            Ok(connection.clone())
         } else {
-            let client = self
-                .ctx
-                .data
-                .read()
-                .await
-                .get::<super::bot::AsyncMeetupClientKey>()
+            let data = self.ctx.data.read().await;
+            let connection = data
+                .get::<super::bot::MeetupConnectionKey>()
                 .cloned()
-                .ok_or_else(|| simple_error::SimpleError::new("Meetup client was not set"))?;
-            self.meetup_client.set(client).ok();
+                .ok_or_else(|| simple_error::SimpleError::new("Meetup connection was not set"))?;
             Ok(self
-                .meetup_client
-                .get()
-                .map(Arc::clone)
-                .expect("Meetup client not set. This is a bug."))
+                .meetup_connection
+                .get_or_init(move || connection)
+                .clone())
         }
     }
 
@@ -249,6 +452,40 @@ impl CommandContext {
         }
     }
 
+    pub async fn guild_configs(&self) -> Result<Arc<GuildConfigs>, lib::meetup::Error> {
+        if let Some(guild_configs) = self.guild_configs.get() {
+            Ok(Arc::clone(guild_configs))
+        } else {
+            let guild_configs = self
+                .ctx
+                .data
+                .read()
+                .await
+                .get::<super::bot::GuildConfigsKey>()
+                .cloned()
+                .ok_or_else(|| simple_error::SimpleError::new("Guild configs were not set"))?;
+            self.guild_configs.set(guild_configs).ok();
+            Ok(self
+                .guild_configs
+                .get()
+                .map(Arc::clone)
+                .expect("Guild configs not set. This is a bug."))
+        }
+    }
+
+    // The configuration of the guild this message was sent in. Direct messages
+    // use the default guild's configuration.
+    pub async fn guild_config(&self) -> Result<Arc<GuildConfig>, lib::meetup::Error> {
+        let guild_configs = self.guild_configs().await?;
+        let guild_config = match self.msg.guild_id {
+            Some(guild_id) => guild_configs.get(guild_id).ok_or_else(|| {
+                simple_error::SimpleError::new(format!("Guild {} is not configured", guild_id))
+            })?,
+            None => guild_configs.default_guild(),
+        };
+        Ok(Arc::clone(guild_config))
+    }
+
     pub async fn bot_id(&self) -> Result<UserId, lib::meetup::Error> {
         if let Some(&bot_id) = self.bot_id.get() {
             Ok(bot_id)
@@ -284,13 +521,14 @@ impl CommandContext {
     }
 
     pub async fn is_admin(&self) -> Result<bool, lib::meetup::Error> {
+        let guild_config = self.guild_config().await?;
         Ok(self
             .msg
             .author
             .has_role(
                 &self.ctx,
-                lib::discord::sync::ids::GUILD_ID,
-                lib::discord::sync::ids::BOT_ADMIN_ID,
+                guild_config.guild_id,
+                guild_config.bot_admin_role_id,
             )
             .await?)
     }
@@ -300,7 +538,8 @@ impl CommandContext {
         let channel_id = self.msg.channel_id;
         let user_id = self.msg.author.id;
         let pool = self.pool().await?;
-        lib::discord::is_host(&ctx, channel_id, user_id, &pool).await
+        let guild_config = self.guild_config().await?;
+        lib::discord::is_host(&ctx, channel_id, user_id, &pool, &guild_config).await
     }
 
     pub async fn is_game_channel(
@@ -332,6 +571,9 @@ pub(crate) struct PreparedCommands {
     pub regexes: Vec<Regex>,
     pub commands: Vec<&'static Command>,
     pub bot_mention: Regex,
+    // Slash commands together with their command and the regex matching the
+    // command's text version
+    pub slash_commands: Vec<(&'static SlashCommand, &'static Command, Regex)>,
 }
 
 pub(crate) fn prepare_commands(
@@ -351,6 +593,7 @@ pub(crate) fn prepare_commands(
     );
     let mut commands = vec![];
     let mut regexes = vec![];
+    let mut slash_commands = vec![];
     for &command in ALL_COMMANDS {
         let command_partial_regex = (command.regex)(&regex_parts);
         let command_dm_regex = format!(r"^\s*(?i){command}\s*$", command = command_partial_regex);
@@ -364,6 +607,9 @@ pub(crate) fn prepare_commands(
             Regex::new(&command_mention_regex),
         ) {
             (Ok(dm_regex), Ok(mention_regex)) => {
+                if let Some(slash) = command.slash {
+                    slash_commands.push((slash, command, dm_regex.clone()));
+                }
                 regexes.push(dm_regex);
                 commands.push(command);
                 regexes.push(mention_regex);
@@ -384,5 +630,6 @@ pub(crate) fn prepare_commands(
         regexes,
         commands,
         bot_mention: Regex::new(&format!(r"^\s*{}", bot_mention))?,
+        slash_commands,
     })
 }
@@ -4,7 +4,13 @@ use command_macro::command;
 use lib::discord::CacheAndHttp;
 use serenity::{
     all::Mentionable,
-    model::{channel::PermissionOverwriteType, id::UserId, permissions::Permissions},
+    model::{
+        application::{AutocompleteOption, CommandInteraction},
+        channel::PermissionOverwriteType,
+        id::UserId,
+        permissions::Permissions,
+    },
+    prelude::Context,
 };
 
 #[command]
@@ -14,6 +20,13 @@ use serenity::{
     "add `@some-user`",
     "_(in game channel or managed channel)_ adds a user to the channel."
 )]
+#[slash(
+    "add-user",
+    "Adds a user to this channel",
+    "add {user}",
+    autocomplete = member_autocomplete
+)]
+#[slash_option("user", string, "The user to add", autocomplete)]
 fn add_user<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -26,9 +39,7 @@ fn add_user<'a>(
         Ok(id) => UserId::from(id),
         _ => {
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -47,6 +58,13 @@ fn add_user<'a>(
     "add host `@some-user`",
     "_(in game channel or managed channel)_ makes a user an additional Host. _(Desktop only)_"
 )]
+#[slash(
+    "add-host",
+    "Makes a user an additional host of this channel",
+    "add host {user}",
+    autocomplete = member_autocomplete
+)]
+#[slash_option("user", string, "The user to make a host", autocomplete)]
 fn add_host<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -59,9 +77,7 @@ fn add_host<'a>(
         Ok(id) => UserId::from(id),
         _ => {
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -80,6 +96,13 @@ fn add_host<'a>(
     "remove `@some-user`",
     "_(in game channel or managed channel)_ removes a user from the channel."
 )]
+#[slash(
+    "remove-user",
+    "Removes a user from this channel",
+    "remove {user}",
+    autocomplete = member_autocomplete
+)]
+#[slash_option("user", string, "The user to remove", autocomplete)]
 fn remove_user<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -92,9 +115,7 @@ fn remove_user<'a>(
         Ok(id) => UserId::from(id),
         _ => {
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -113,6 +134,13 @@ fn remove_user<'a>(
     "remove host `@some-user`",
     "_(in game channel or managed channel)_ makes a user no longer a Host."
 )]
+#[slash(
+    "remove-host",
+    "Makes a user no longer a host of this channel",
+    "remove host {user}",
+    autocomplete = member_autocomplete
+)]
+#[slash_option("user", string, "The host to remove", autocomplete)]
 fn remove_host<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -125,9 +153,7 @@ fn remove_host<'a>(
         Ok(id) => UserId::from(id),
         _ => {
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -139,6 +165,46 @@ fn remove_host<'a>(
     .await
 }
 
+// Suggests the cached guild members whose username or nick contains the typed
+// text. The value is the member's mention, which is what the commands expect.
+fn member_autocomplete<'a>(
+    ctx: &'a Context,
+    interaction: &'a CommandInteraction,
+    option: AutocompleteOption<'a>,
+) -> super::AutocompleteResult<'a> {
+    Box::pin(async move {
+        let partial = option.value.to_lowercase();
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(vec![]),
+        };
+        let mut choices: Vec<_> = match guild_id.to_guild_cached(ctx) {
+            Some(guild) => guild
+                .members
+                .values()
+                .filter(|member| !member.user.bot)
+                .filter(|member| {
+                    member.user.name.to_lowercase().contains(&partial)
+                        || member
+                            .nick
+                            .as_ref()
+                            .is_some_and(|nick| nick.to_lowercase().contains(&partial))
+                })
+                .map(|member| super::AutocompleteChoice {
+                    name: match &member.nick {
+                        Some(nick) => format!("{} ({})", nick, member.user.name),
+                        None => member.user.name.clone(),
+                    },
+                    value: member.user.id.mention().to_string(),
+                })
+                .collect(),
+            None => vec![],
+        };
+        choices.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        Ok(choices)
+    })
+}
+
 async fn channel_add_or_remove_user_impl(
     context: &mut super::CommandContext,
     discord_id: UserId,
@@ -151,16 +217,12 @@ async fn channel_add_or_remove_user_impl(
     let is_bot_admin = context.is_admin().await?;
     // Only bot admins can add/remove hosts
     if !is_bot_admin && as_host {
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::NOT_A_BOT_ADMIN)
-            .await
-            .ok();
+        context.reply(lib::strings::NOT_A_BOT_ADMIN).await.ok();
         return Ok(());
     }
     // Managed channels and hosts don't use roles but user-specific permission overwrites
     let discord_api: CacheAndHttp = Into::into(&context.ctx);
+    let guild_config = context.guild_config().await?;
     let pool = context.pool().await?;
     let mut tx = pool.begin().await?;
     if is_game_channel && !is_managed_channel {
@@ -169,9 +231,7 @@ async fn channel_add_or_remove_user_impl(
             Some(roles) => roles,
             None => {
                 context
-                    .msg
-                    .channel_id
-                    .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+                    .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
                     .await
                     .ok();
                 return Ok(());
@@ -179,12 +239,7 @@ async fn channel_add_or_remove_user_impl(
         };
         // Only bot admins can add users
         if !is_bot_admin && add {
-            context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::NOT_A_BOT_ADMIN)
-                .await
-                .ok();
+            context.reply(lib::strings::NOT_A_BOT_ADMIN).await.ok();
             return Ok(());
         }
         // Figure out whether there is a voice channel
@@ -205,7 +260,7 @@ async fn channel_add_or_remove_user_impl(
                 .ctx
                 .http
                 .add_member_role(
-                    lib::discord::sync::ids::GUILD_ID,
+                    guild_config.guild_id,
                     discord_id,
                     channel_roles.user,
                     Some("Role assignment due to 'add user' command"),
@@ -213,7 +268,7 @@ async fn channel_add_or_remove_user_impl(
                 .await
             {
                 Ok(()) => {
-                    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+                    context.react('\u{2705}').await.ok();
                     context
                         .msg
                         .channel_id
@@ -224,11 +279,11 @@ async fn channel_add_or_remove_user_impl(
                 Err(err) => {
                     eprintln!("Could not assign channel role: {}", err);
                     context
-                        .msg
-                        .channel_id
-                        .say(&context.ctx, lib::strings::CHANNEL_ROLE_ADD_ERROR)
+                        .reply(lib::strings::CHANNEL_ROLE_ADD_ERROR)
                         .await
                         .ok();
+                    // Nothing changed, so there is nothing to sync or audit
+                    return Ok(());
                 }
             }
             if as_host {
@@ -247,12 +302,7 @@ async fn channel_add_or_remove_user_impl(
                     Err(err) => {
                         eprintln!("Could not assign channel permissions:\n{:#?}", err);
                         context
-                            .msg
-                            .channel_id
-                            .say(
-                                &context.ctx,
-                                "Something went wrong assigning the channel permissions",
-                            )
+                            .reply("Something went wrong assigning the channel permissions")
                             .await
                             .ok();
                     }
@@ -287,12 +337,7 @@ async fn channel_add_or_remove_user_impl(
                     {
                         eprintln!("Could not assign voice channel permissions:\n{:#?}", err);
                         context
-                            .msg
-                            .channel_id
-                            .say(
-                                &context.ctx,
-                                "Something went wrong assigning the voice channel permissions",
-                            )
+                            .reply("Something went wrong assigning the voice channel permissions")
                             .await
                             .ok();
                     }
@@ -305,7 +350,7 @@ async fn channel_add_or_remove_user_impl(
                     .ctx
                     .http
                     .remove_member_role(
-                        lib::discord::sync::ids::GUILD_ID,
+                        guild_config.guild_id,
                         discord_id,
                         host_role,
                         Some("Role removal due to 'remove user' command"),
@@ -314,9 +359,7 @@ async fn channel_add_or_remove_user_impl(
                 {
                     eprintln!("Could not remove host channel role:\n{:#?}", err);
                     context
-                        .msg
-                        .channel_id
-                        .say(&context.ctx, lib::strings::CHANNEL_ROLE_REMOVE_ERROR)
+                        .reply(lib::strings::CHANNEL_ROLE_REMOVE_ERROR)
                         .await
                         .ok();
                 }
@@ -335,12 +378,7 @@ async fn channel_add_or_remove_user_impl(
                 {
                     eprintln!("Could not reduce channel permissions:\n{:#?}", err);
                     context
-                        .msg
-                        .channel_id
-                        .say(
-                            &context.ctx,
-                            "Something went wrong reducing the channel permissions",
-                        )
+                        .reply("Something went wrong reducing the channel permissions")
                         .await
                         .ok();
                 }
@@ -360,12 +398,7 @@ async fn channel_add_or_remove_user_impl(
                     {
                         eprintln!("Could not reduce voice channel permissions:\n{:#?}", err);
                         context
-                            .msg
-                            .channel_id
-                            .say(
-                                &context.ctx,
-                                "Something went wrong reducing the voice channel permissions",
-                            )
+                            .reply("Something went wrong reducing the voice channel permissions")
                             .await
                             .ok();
                     }
@@ -381,12 +414,7 @@ async fn channel_add_or_remove_user_impl(
                 {
                     eprintln!("Could not remove channel permissions:\n{:#?}", err);
                     context
-                        .msg
-                        .channel_id
-                        .say(
-                            &context.ctx,
-                            "Something went wrong revoking the channel permissions",
-                        )
+                        .reply("Something went wrong revoking the channel permissions")
                         .await
                         .ok();
                 }
@@ -401,12 +429,7 @@ async fn channel_add_or_remove_user_impl(
                     {
                         eprintln!("Could not revoke voice channel permissions:\n{:#?}", err);
                         context
-                            .msg
-                            .channel_id
-                            .say(
-                                &context.ctx,
-                                "Something went wrong revoking the voice channel permissions",
-                            )
+                            .reply("Something went wrong revoking the voice channel permissions")
                             .await
                             .ok();
                     }
@@ -415,7 +438,7 @@ async fn channel_add_or_remove_user_impl(
                     .ctx
                     .http
                     .remove_member_role(
-                        lib::discord::sync::ids::GUILD_ID,
+                        guild_config.guild_id,
                         discord_id,
                         channel_roles.user,
                         Some("Role removal due to 'remove user' command"),
@@ -425,16 +448,14 @@ async fn channel_add_or_remove_user_impl(
                     Err(err) => {
                         eprintln!("Could not remove channel role: {}", err);
                         context
-                            .msg
-                            .channel_id
-                            .say(&context.ctx, lib::strings::CHANNEL_ROLE_REMOVE_ERROR)
+                            .reply(lib::strings::CHANNEL_ROLE_REMOVE_ERROR)
                             .await
                             .ok();
                     }
                     _ => (),
                 }
             }
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react('\u{2705}').await.ok();
             // Remember which users were removed manually
             let series_id = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
             match series_id {
@@ -491,20 +512,20 @@ async fn channel_add_or_remove_user_impl(
                     .await
                     .ok();
             }
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react('\u{2705}').await.ok();
         } else {
             // Assume that users with the VIEW_CHANNEL, MANAGE_MESSAGES and
             // MENTION_EVERYONE permission are channel hosts
-            let target_is_host =
-                lib::discord::is_host(&discord_api, context.msg.channel_id, discord_id, &pool)
-                    .await?;
+            let target_is_host = lib::discord::is_host(
+                &discord_api,
+                context.msg.channel_id,
+                discord_id,
+                &pool,
+                &guild_config,
+            )
+            .await?;
             if target_is_host && !is_bot_admin {
-                context
-                    .msg
-                    .channel_id
-                    .say(&context.ctx, lib::strings::NOT_A_BOT_ADMIN)
-                    .await
-                    .ok();
+                context.reply(lib::strings::NOT_A_BOT_ADMIN).await.ok();
                 return Ok(());
             }
             let permissions_to_remove = if as_host {
@@ -523,8 +544,39 @@ async fn channel_add_or_remove_user_impl(
                 permissions_to_remove,
             )
             .await?;
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react('\u{2705}').await.ok();
+        }
+    } else {
+        return Ok(());
+    }
+    // Sync the series right away so that its channels and roles catch up with
+    // the change without waiting for the next Discord sync
+    if add && is_game_channel {
+        if let Some(series_id) = lib::get_channel_series(context.msg.channel_id, &mut tx).await? {
+            if let Err(err) = lib::sync_queue::enqueue(
+                lib::sync_queue::SyncJob::EventSeries(series_id),
+                "add user",
+                &pool,
+            )
+            .await
+            {
+                eprintln!("Could not enqueue the event series sync:\n{:#?}", err);
+            }
         }
     }
+    let action = match (add, as_host) {
+        (true, false) => lib::audit::Action::UserAdded,
+        (true, true) => lib::audit::Action::HostAdded,
+        (false, false) => lib::audit::Action::UserRemoved,
+        (false, true) => lib::audit::Action::HostRemoved,
+    };
+    lib::audit::record(
+        lib::audit::Entry::new(action, lib::audit::Target::User(discord_id))
+            .actor(context.msg.author.id)
+            .channel(context.msg.channel_id)
+            .after(serde_json::json!({ "game_channel": is_game_channel })),
+        &pool,
+    )
+    .await;
     Ok(())
 }
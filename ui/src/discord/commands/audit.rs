@@ -0,0 +1,88 @@
+use std::{fmt::Write, num::NonZeroU64};
+
+use command_macro::command;
+use serenity::{all::Mentionable, builder::CreateEmbed, model::id::ChannelId};
+
+// Number of entries shown on Discord. The web UI shows more.
+const NUM_ENTRIES: i64 = 15;
+
+#[command]
+#[regex(r"audit\s+<#(?P<channel_id>[0-9]+)>")]
+#[level(admin)]
+#[help(
+    "audit `#some-channel`",
+    "shows the most recent actions the bot took concerning a channel."
+)]
+#[slash(
+    "audit",
+    "Shows the most recent actions the bot took concerning a channel",
+    "audit {channel}"
+)]
+#[slash_option("channel", channel, "The channel to show the audit log of")]
+fn audit<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let channel_id = match captures
+        .name("channel_id")
+        .unwrap()
+        .as_str()
+        .parse::<NonZeroU64>()
+    {
+        Ok(id) => ChannelId::from(id),
+        Err(_) => {
+            context
+                .reply("Seems like the specified channel is invalid")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let filter = lib::audit::Filter {
+        channel_id: Some(channel_id),
+        limit: Some(NUM_ENTRIES),
+        ..Default::default()
+    };
+    let entries = lib::audit::query(&filter, &pool).await?;
+    if entries.is_empty() {
+        context
+            .reply(lib::strings::AUDIT_NO_ENTRIES(channel_id))
+            .await
+            .ok();
+        return Ok(());
+    }
+    let mut description = String::new();
+    // Show the oldest entry first
+    for entry in entries.iter().rev() {
+        let actor = match entry.actor {
+            Some(actor) => actor.mention().to_string(),
+            None => "Bot".to_string(),
+        };
+        writeln!(
+            &mut description,
+            "<t:{}:f> {} `{}` {}",
+            entry.action_time.timestamp(),
+            actor,
+            entry.action,
+            entry.target_mention()
+        )
+        .ok();
+    }
+    write!(
+        &mut description,
+        "\n[More on the web]({}/audit?channel={})",
+        lib::urls::BASE_URL,
+        channel_id
+    )
+    .ok();
+    context
+        .reply_embed(
+            CreateEmbed::new()
+                .title(format!("Audit log of {}", channel_id.mention()))
+                .description(description),
+        )
+        .await
+        .ok();
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use command_macro::command;
+use serenity::builder::CreateMessage;
+
+#[command]
+#[regex(r"calendar")]
+#[help(
+    "calendar",
+    "sends you links to calendar feeds of your sessions that you can subscribe to in your calendar app."
+)]
+#[slash(
+    "calendar",
+    "Sends you links to calendar feeds of your sessions",
+    "calendar"
+)]
+fn calendar<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let mut tx = pool.begin().await?;
+    let member_id =
+        lib::db::get_or_create_member_for_discord_id(&mut tx, context.msg.author.id).await?;
+    // If this is a bot controlled channel, also send the channel's calendar
+    let event_series = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
+    tx.commit().await?;
+    let token = lib::ical::get_or_create_calendar_token(member_id, &pool).await?;
+    let member_url = format!("{}/calendar/member/{}", lib::urls::BASE_URL, token);
+    let series_url = event_series.map(|series_id| {
+        format!(
+            "{}/calendar/series/{}/{}",
+            lib::urls::BASE_URL,
+            series_id.0,
+            token
+        )
+    });
+    let club_url = format!("{}/calendar/club.ics", lib::urls::BASE_URL);
+    let dm = context
+        .msg
+        .author
+        .direct_message(
+            &context.ctx,
+            CreateMessage::new().content(lib::strings::CALENDAR_LINKS(
+                &member_url,
+                series_url.as_deref(),
+                &club_url,
+            )),
+        )
+        .await;
+    match dm {
+        Ok(_) => {
+            context.react('\u{2705}').await.ok();
+        }
+        Err(why) => {
+            eprintln!("Error sending calendar DM: {:?}", why);
+            context
+                .reply(
+                    "There was an error trying to send you the calendar links.\nDo you have \
+                     direct messages disabled? In that case send me a private message with the \
+                     text \"calendar\".",
+                )
+                .await
+                .ok();
+        }
+    }
+    Ok(())
+}
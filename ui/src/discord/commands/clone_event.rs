@@ -45,7 +45,7 @@ fn clone_event<'a>(
             .channel_id
             .say(context.ctx, "Could not transfer all RSVPs to the new event");
     }
-    context.msg.react(context.ctx, '\u{2705}').await.ok();
+    context.react('\u{2705}').await.ok();
     context
         .msg
         .channel_id
@@ -10,6 +10,7 @@ use serenity::model::channel::Channel;
     "sets the channel for closure at the end of an adventure. The channel won't be deleted \
      immediately but within 24 hours."
 )]
+#[slash("end-adventure", "Sets this channel for closure", "end adventure")]
 fn end_adventure<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -17,34 +18,24 @@ fn end_adventure<'a>(
     let pool = context.pool().await?;
     let mut tx = pool.begin().await?;
     let end_adventure_result =
-        lib::end_adventure::end_adventure(context.msg.channel_id, &mut tx).await?;
+        lib::end_adventure::end_adventure(context.msg.channel_id, context.msg.author.id, &mut tx)
+            .await?;
     tx.commit().await?;
     match end_adventure_result {
         EndAdventureResult::NotAGameChannel => context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
             .await
             .ok(),
         EndAdventureResult::NoExpirationTime => context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_NO_EXPIRATION)
+            .reply(lib::strings::CHANNEL_NO_EXPIRATION)
             .await
             .ok(),
         EndAdventureResult::NotYetExpired => context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_NOT_YET_CLOSEABLE)
+            .reply(lib::strings::CHANNEL_NOT_YET_CLOSEABLE)
             .await
             .ok(),
         EndAdventureResult::AlreadyMarkedForDeletion(_) => context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                lib::strings::CHANNEL_ALREADY_MARKED_FOR_CLOSING,
-            )
+            .reply(lib::strings::CHANNEL_ALREADY_MARKED_FOR_CLOSING)
             .await
             .ok(),
         EndAdventureResult::NewlyMarkedForDeletion(_) => context
@@ -52,6 +43,7 @@ fn end_adventure<'a>(
             .channel_id
             .say(&context.ctx, lib::strings::CHANNEL_MARKED_FOR_CLOSING)
             .await
+            .map(|_| ())
             .ok(),
     };
     let channel = context.channel().await;
@@ -59,7 +51,7 @@ fn end_adventure<'a>(
         Ok(Channel::Guild(channel)) => &channel.name,
         _ => "'unknown'",
     };
-    if let Some(bot_alerts_channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID {
+    if let Some(bot_alerts_channel_id) = context.guild_config().await?.bot_alerts_channel_id {
         bot_alerts_channel_id
             .say(
                 &context.ctx,
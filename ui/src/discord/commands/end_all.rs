@@ -6,11 +6,13 @@ use serenity::{all::ChannelId, model::channel::Channel};
 #[regex(r"end\s*all")]
 #[level(admin)]
 #[help("end all", "ends all expired adventure channels")]
+#[slash("end-all", "Ends all expired adventure channels", "end all")]
 fn end_all<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let pool = context.pool().await?;
+    let bot_alerts_channel_id = context.guild_config().await?.bot_alerts_channel_id;
     // Find all channels which can be ended
     let channel_ids = sqlx::query!(
         r#"SELECT discord_id FROM event_series_text_channel WHERE
@@ -25,30 +27,21 @@ fn end_all<'a>(
     .await?;
     if channel_ids.is_empty() {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                "Found no adventure channels that can be ended",
-            )
+            .reply("Found no adventure channels that can be ended")
             .await?;
         return Ok(());
     } else {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                format!(
-                    "Found {} adventure channels that can possibly be ended, hang on...",
-                    channel_ids.len()
-                ),
-            )
+            .reply(format!(
+                "Found {} adventure channels that can possibly be ended, hang on...",
+                channel_ids.len()
+            ))
             .await?;
     }
     for channel_id in channel_ids {
         let mut tx = pool.begin().await?;
-        let end_adventure_result = lib::end_adventure::end_adventure(channel_id, &mut tx).await?;
+        let end_adventure_result =
+            lib::end_adventure::end_adventure(channel_id, context.msg.author.id, &mut tx).await?;
         tx.commit().await?;
         match end_adventure_result {
             EndAdventureResult::NewlyMarkedForDeletion(_) => channel_id
@@ -66,19 +59,14 @@ fn end_all<'a>(
             _ => "'unknown'",
         };
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                lib::strings::CHANNEL_MARKED_FOR_CLOSING_ALERT(
-                    channel_id,
-                    channel_name,
-                    context.msg.author.id,
-                ),
-            )
+            .reply(lib::strings::CHANNEL_MARKED_FOR_CLOSING_ALERT(
+                channel_id,
+                channel_name,
+                context.msg.author.id,
+            ))
             .await
             .ok();
-        if let Some(bot_alerts_channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID {
+        if let Some(bot_alerts_channel_id) = bot_alerts_channel_id {
             if bot_alerts_channel_id != context.msg.channel_id {
                 bot_alerts_channel_id
                     .say(
@@ -11,6 +11,7 @@ use std::fmt::Write;
 #[command]
 #[regex(r"help")]
 #[help("help", "do I really need to explain this one?")]
+#[slash("help", "Sends you a list of all commands", "help")]
 fn help<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
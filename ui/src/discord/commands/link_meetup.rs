@@ -12,6 +12,7 @@ use std::{borrow::Cow, num::NonZeroU64};
 #[command]
 #[regex(r"link[ -]?meetup")]
 #[help("link meetup", "starts the process to link your Meetup and Discord profiles. If you haven't yet, you should really do that now.")]
+#[slash("link-meetup", "Links your Meetup and Discord profiles", "link meetup")]
 fn link_meetup<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -56,15 +57,18 @@ fn link_meetup<'a>(
         .await;
     match dm {
         Ok(_) => {
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react('\u{2705}').await.ok();
         }
         Err(why) => {
             eprintln!("Error sending Meetup linking DM: {:?}", why);
-            context.msg.reply(
-                &context.ctx,
-                "There was an error trying to send you instructions.\nDo you have direct messages \
-                 disabled? In that case send me a private message with the text \"link meetup\".",
-            ).await.ok();
+            context
+                .reply(
+                    "There was an error trying to send you instructions.\nDo you have direct \
+                     messages disabled? In that case send me a private message with the text \
+                     \"link meetup\".",
+                )
+                .await
+                .ok();
         }
     }
     Ok(())
@@ -73,6 +77,11 @@ fn link_meetup<'a>(
 #[command]
 #[regex(r"unlink[ -]?meetup")]
 #[help("unlink meetup", "unlinks your Meetup and Discord profiles.")]
+#[slash(
+    "unlink-meetup",
+    "Unlinks your Meetup and Discord profiles",
+    "unlink meetup"
+)]
 fn unlink_meetup<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -91,6 +100,13 @@ fn unlink_meetup<'a>(
     "link meetup `@some-user` `meetup-ID`",
     "link another user's Meetup and Discord profile."
 )]
+#[slash(
+    "admin-link-meetup",
+    "Links another user's Meetup and Discord profiles",
+    "link meetup {user} {meetup-id}"
+)]
+#[slash_option("user", user, "The Discord user to link")]
+#[slash_option("meetup-id", integer, "The ID of the Meetup profile to link")]
 fn link_meetup_bot_admin<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -102,10 +118,7 @@ fn link_meetup_bot_admin<'a>(
     {
         (Ok(id1), Ok(id2)) => (UserId::from(id1), id2),
         _ => {
-            let _ = context.msg.channel_id.say(
-                &context.ctx,
-                "Seems like the specified Discord or Meetup ID is invalid",
-            );
+            let _ = context.reply("Seems like the specified Discord or Meetup ID is invalid");
             return Ok(());
         }
     };
@@ -119,15 +132,10 @@ fn link_meetup_bot_admin<'a>(
             ..
         } => {
             context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    format!(
-                        "All good, this Meetup account was already linked to {}",
-                        discord_id.mention()
-                    ),
-                )
+                .reply(format!(
+                    "All good, this Meetup account was already linked to {}",
+                    discord_id.mention()
+                ))
                 .await
                 .ok();
         }
@@ -135,16 +143,25 @@ fn link_meetup_bot_admin<'a>(
             action: LinkingAction::Linked | LinkingAction::NewMember | LinkingAction::MergedMember,
             ..
         } => {
-            let message_builder = CreateMessage::new().embed(
-                CreateEmbed::new().title("Linked Meetup account").description(format!(
-                    "Successfully linked {} to this Meetup account: https://www.meetup.com/members/{}/",
-                    discord_id.mention(), meetup_id
-                ))
-            );
-            let _ = context
-                .msg
-                .channel_id
-                .send_message(&context.ctx, message_builder);
+            lib::audit::record(
+                lib::audit::Entry::new(
+                    lib::audit::Action::MeetupLinked,
+                    lib::audit::Target::User(discord_id),
+                )
+                .actor(context.msg.author.id)
+                .after(serde_json::json!({ "meetup_id": meetup_id })),
+                &pool,
+            )
+            .await;
+            context
+                .reply_embed(
+                    CreateEmbed::new().title("Linked Meetup account").description(format!(
+                        "Successfully linked {} to this Meetup account: https://www.meetup.com/members/{}/",
+                        discord_id.mention(), meetup_id
+                    )),
+                )
+                .await
+                .ok();
         }
         LinkingResult::Conflict {
             member_with_meetup:
@@ -170,7 +187,7 @@ fn link_meetup_bot_admin<'a>(
             Meetup ID: {meetup_id2:?}\n\
             Discord ID: {discord_id2:?}");
             // TODO: answer in DM?
-            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            context.reply(message).await.ok();
         }
     };
     Ok(())
@@ -183,6 +200,12 @@ fn link_meetup_bot_admin<'a>(
     "unlink meetup `@some-user`",
     "unlink another user's Meetup and Discord profile."
 )]
+#[slash(
+    "admin-unlink-meetup",
+    "Unlinks another user's Meetup and Discord profiles",
+    "unlink meetup {user}"
+)]
+#[slash_option("user", user, "The Discord user to unlink")]
 fn unlink_meetup_bot_admin<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -192,10 +215,7 @@ fn unlink_meetup_bot_admin<'a>(
     let discord_id = match discord_id.parse::<NonZeroU64>() {
         Ok(id) => UserId::from(id),
         _ => {
-            let _ = context.msg.channel_id.say(
-                &context.ctx,
-                "Seems like the specified Discord ID is invalid",
-            );
+            let _ = context.reply("Seems like the specified Discord ID is invalid");
             return Ok(());
         }
     };
@@ -213,12 +233,21 @@ async fn unlink_meetup_impl(
     tx.commit().await?;
     match result {
         UnlinkingResult::Success => {
+            lib::audit::record(
+                lib::audit::Entry::new(
+                    lib::audit::Action::MeetupUnlinked,
+                    lib::audit::Target::User(user_id),
+                )
+                .actor(context.msg.author.id),
+                &pool,
+            )
+            .await;
             let message = if is_bot_admin_command {
                 format!("Unlinked {}'s Meetup account", user_id.mention())
             } else {
                 lib::strings::MEETUP_UNLINK_SUCCESS(context.bot_id().await?)
             };
-            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            context.reply(message).await.ok();
         }
         UnlinkingResult::NotLinked => {
             let message = if is_bot_admin_command {
@@ -229,7 +258,7 @@ async fn unlink_meetup_impl(
             } else {
                 Cow::Borrowed(lib::strings::MEETUP_UNLINK_NOT_LINKED)
             };
-            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            context.reply(message).await.ok();
         }
     }
     Ok(())
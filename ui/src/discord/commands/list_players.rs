@@ -10,11 +10,17 @@ use std::collections::HashMap;
     "list players",
     "shows information about people in this channel and people signed up for this channel's events on Meetup."
 )]
+#[slash(
+    "list-players",
+    "Shows information about the players of this channel",
+    "list players"
+)]
 fn list_players<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let pool = context.pool().await?;
+    let guild_id = context.guild_config().await?.guild_id;
     let mut tx = pool.begin().await?;
     // Check whether this is a bot controlled channel
     let event_series = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
@@ -23,9 +29,7 @@ fn list_players<'a>(
         (Some(series), Some(roles)) => (series, roles),
         _ => {
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+                .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
                 .await
                 .ok();
             return Ok(());
@@ -40,12 +44,7 @@ fn list_players<'a>(
         match last_event {
             Some(event) => vec![event],
             None => {
-                context
-                    .msg
-                    .channel_id
-                    .say(&context.ctx, "There are no events")
-                    .await
-                    .ok();
+                context.reply("There are no events").await.ok();
                 return Ok(());
             }
         }
@@ -71,6 +70,9 @@ fn list_players<'a>(
         return Ok(());
     };
     let channel_members = db::discord_ids_to_members(&discord_player_ids, &pool).await?;
+    // Discord users who marked the events as "interested" in the Discord client
+    let interested_user_ids =
+        lib::discord::scheduled_events::interested_users(&event_ids, &pool).await?;
 
     // Four categories of users:
     // - RSVPd member (with Discord ID) [The following Discord users signed up for this event (in channel? yes / no)]
@@ -150,10 +152,7 @@ fn list_players<'a>(
             // in order not to unnecessarily ping them
             let user_mention = if is_in_channel {
                 match discord_id.to_user(&context.ctx).await {
-                    Ok(user) => match user
-                        .nick_in(&context.ctx, lib::discord::sync::ids::GUILD_ID)
-                        .await
-                    {
+                    Ok(user) => match user.nick_in(&context.ctx, guild_id).await {
                         Some(nick) => nick,
                         None => user.name,
                     },
@@ -218,21 +217,32 @@ fn list_players<'a>(
             reply += &format!("• {}\n", discord_id.mention());
         }
     }
+
+    let interested_but_not_rsvpd: Vec<_> = interested_user_ids
+        .into_iter()
+        .filter(|discord_id| {
+            !rsvpd_member_with_discord_id
+                .values()
+                .any(|(rsvpd_discord_id, _)| rsvpd_discord_id == discord_id)
+        })
+        .collect();
+    if !interested_but_not_rsvpd.is_empty() {
+        reply += "\nDiscord users interested in an upcoming event on Discord but not \
+                  signed up for it:\n";
+        for discord_id in &interested_but_not_rsvpd {
+            reply += &format!("• {}\n", discord_id.mention());
+        }
+    }
     const LIMIT: usize = serenity::constants::MESSAGE_CODE_LIMIT;
     // Split the reply if necessary
     let mut reply = reply.as_str();
     while reply.chars().count() > 0 {
         if let Some((idx, _c)) = reply.char_indices().skip(LIMIT / 2).next() {
-            context
-                .msg
-                .channel_id
-                .say(&context.ctx, &reply[..idx])
-                .await
-                .ok();
+            context.reply(&reply[..idx]).await.ok();
             reply = &reply[idx..];
         } else {
             // Send the rest of the message
-            context.msg.channel_id.say(&context.ctx, reply).await.ok();
+            context.reply(reply).await.ok();
             break;
         }
     }
@@ -5,6 +5,11 @@ use serenity::builder::CreateMessage;
 #[regex(r"list\s*subscriptions")]
 #[level(admin)]
 #[help("list subscriptions", "returns a list of active Stripe subscriptions")]
+#[slash(
+    "list-subscriptions",
+    "Lists the active Stripe subscriptions",
+    "list subscriptions"
+)]
 fn list_subscriptions<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -22,9 +27,11 @@ fn list_subscriptions<'a>(
     let subscriptions = lib::stripe::list_active_subscriptions(&stripe_client).await?;
     let mut message = String::new();
     for subscription in &subscriptions {
-        let (customer, product) =
-            lib::tasks::subscription_roles::get_customer_and_product(&stripe_client, subscription)
-                .await?;
+        let (customer, product) = lib::tasks::subscription_roles::get_customer_and_product(
+            stripe_client.as_ref(),
+            subscription,
+        )
+        .await?;
         let discord_handle = customer
             .metadata
             .as_ref()
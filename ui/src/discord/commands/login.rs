@@ -5,6 +5,7 @@ use serenity::builder::CreateMessage;
 #[regex(r"login")]
 #[level(admin)]
 #[help("login", "Log in to the web interface")]
+#[slash("login", "Logs you in to the web interface", "login")]
 fn login<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -23,14 +24,12 @@ fn login<'a>(
         .await;
     match dm {
         Ok(_) => {
-            context.msg.react(&context.ctx, '\u{2705}').await.ok();
+            context.react('\u{2705}').await.ok();
         }
         Err(why) => {
             eprintln!("Error sending login DM: {:?}", why);
             context
-                .msg
                 .reply(
-                    &context.ctx,
                     "There was an error trying to send you a login link.\nDo you have direct \
                      messages disabled? In that case send me a private message with the text \
                      \"login\".",
@@ -11,6 +11,11 @@ use serenity::model::{
     "manage channel",
     "Enable the `[add|remove] user` and `[add|remove] host` commands for this channel."
 )]
+#[slash(
+    "manage-channel",
+    "Enables the add and remove commands for this channel",
+    "manage channel"
+)]
 fn manage_channel<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -21,37 +26,27 @@ fn manage_channel<'a>(
     let mut tx = pool.begin().await?;
     let is_game_channel = context.is_game_channel(Some(&mut tx)).await?;
     if is_game_channel {
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, "Can not manage this channel")
-            .await
-            .ok();
+        context.reply("Can not manage this channel").await.ok();
         return Ok(());
     }
+    let guild_id = context.msg.guild_id.map(|guild_id| guild_id.get() as i64);
     sqlx::query!(
-        r#"INSERT INTO managed_channel (discord_id) VALUES ($1) ON CONFLICT DO NOTHING"#,
-        channel_id.get() as i64
+        r#"INSERT INTO managed_channel (discord_id, guild_id) VALUES ($1, $2) ON CONFLICT DO NOTHING"#,
+        channel_id.get() as i64,
+        guild_id
     )
     .execute(&mut *tx)
     .await?;
     let channel = match context.msg.channel(&context.ctx).await {
         Ok(Channel::Guild(channel)) => channel,
         Ok(_) => {
-            context
-                .msg
-                .channel_id
-                .say(&context.ctx, "Can not manage this channel")
-                .await
-                .ok();
+            context.reply("Can not manage this channel").await.ok();
             return Ok(());
         }
         Err(err) => {
             eprintln!("manage channel failed:\n{:#?}", err);
             context
-                .msg
-                .channel_id
-                .say(&context.ctx, "Error when trying to manage this channel")
+                .reply("Error when trying to manage this channel")
                 .await
                 .ok();
             return Ok(());
@@ -95,6 +90,6 @@ fn manage_channel<'a>(
             .await?;
     }
     tx.commit().await?;
-    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+    context.react('\u{2705}').await.ok();
     Ok(())
 }
@@ -0,0 +1,99 @@
+use command_macro::command;
+use serenity::builder::CreateMessage;
+
+#[command]
+#[regex(r"reschedule\s*session")]
+#[level(host)]
+#[help(
+    "reschedule session",
+    "allows you to move one of the upcoming sessions of your adventure to a different time."
+)]
+#[slash(
+    "reschedule-session",
+    "Moves an upcoming session of this adventure to a different time",
+    "reschedule session"
+)]
+fn reschedule_session<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    send_manage_session_link(
+        context,
+        "reschedule_session",
+        "Use the following link to reschedule one of your upcoming sessions",
+    )
+    .await
+}
+
+#[command]
+#[regex(r"cancel\s*session")]
+#[level(host)]
+#[help(
+    "cancel session",
+    "allows you to cancel one of the upcoming sessions of your adventure."
+)]
+#[slash(
+    "cancel-session",
+    "Cancels an upcoming session of this adventure",
+    "cancel session"
+)]
+fn cancel_session<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    send_manage_session_link(
+        context,
+        "cancel_session",
+        "Use the following link to cancel one of your upcoming sessions",
+    )
+    .await
+}
+
+// Creates a new flow for the channel's series and sends its link to the host
+async fn send_manage_session_link(
+    context: &mut super::CommandContext,
+    path: &str,
+    message: &str,
+) -> Result<(), lib::meetup::Error> {
+    // Find the series belonging to the channel
+    let pool = context.pool().await?;
+    let mut tx = pool.begin().await?;
+    let event_series = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
+    let event_series = if let Some(event_series) = event_series {
+        event_series
+    } else {
+        context
+            .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .await
+            .ok();
+        return Ok(());
+    };
+    let upcoming_events = lib::db::get_upcoming_events_for_series(&pool, event_series).await?;
+    if upcoming_events.is_empty() {
+        context
+            .reply(lib::strings::CHANNEL_NO_UPCOMING_SESSIONS)
+            .await
+            .ok();
+        return Ok(());
+    }
+    // Create a new Flow
+    let author_id = context.msg.author.id;
+    let flow = lib::flow::ManageSessionFlow::new(
+        context.async_redis_connection().await?,
+        event_series,
+        author_id,
+    )
+    .await?;
+    let link = format!("{}/{}/{}", lib::urls::BASE_URL, path, flow.id);
+    context
+        .msg
+        .author
+        .direct_message(
+            &context.ctx,
+            CreateMessage::new().content(format!("{}:\n{}", message, link)),
+        )
+        .await
+        .ok();
+    context.react('\u{2705}').await.ok();
+    Ok(())
+}
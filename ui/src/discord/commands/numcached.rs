@@ -4,32 +4,26 @@ use command_macro::command;
 #[regex(r"numcached")]
 #[level(admin)]
 #[help("numcached", "Shows the number of known server members")]
+#[slash("numcached", "Shows the number of known server members", "numcached")]
 pub fn numcached<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
-    let num_cached_members = lib::discord::sync::ids::GUILD_ID
+    let guild_id = context.guild_config().await?.guild_id;
+    let num_cached_members = guild_id
         .to_guild_cached(&context.ctx)
         .map(|guild| guild.members.len());
     if let Some(num_cached_members) = num_cached_members {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                format!(
-                    "I have {} members cached for this guild",
-                    num_cached_members
-                ),
-            )
+            .reply(format!(
+                "I have {} members cached for this guild",
+                num_cached_members
+            ))
             .await
             .ok();
     } else {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
+            .reply(
                 "No guild associated with this message (use the command from a guild channel \
                  instead of a direct message).",
             )
@@ -0,0 +1,222 @@
+use command_macro::command;
+use lib::discord::orphans::ResolveOutcome;
+
+// Discord allows up to 2000 characters per message
+const MAX_MESSAGE_LENGTH: usize = 1900;
+
+fn parse_id(captures: &regex::Captures<'_>, name: &str) -> Option<u64> {
+    captures
+        .name(name)
+        .and_then(|id| id.as_str().parse().ok())
+        .filter(|&id| id != 0)
+}
+
+async fn reply_outcome(
+    context: &mut super::CommandContext,
+    outcome: ResolveOutcome,
+    success: &str,
+) -> Result<(), lib::meetup::Error> {
+    let reply = match outcome {
+        ResolveOutcome::Resolved => success,
+        ResolveOutcome::NotAnOrphan => {
+            "This is not a known orphan of the right kind. Use _list orphans_ to see them."
+        }
+        ResolveOutcome::SeriesNotFound => "Could not find the event series",
+        ResolveOutcome::SeriesHasOne => {
+            "The event series already has a channel or role of this kind"
+        }
+    };
+    context.reply(reply).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(r"list\s*orphans")]
+#[level(admin)]
+#[help(
+    "list orphans",
+    "lists the channels and roles in managed categories which no event series knows about, and the event series channels and roles which are gone from Discord."
+)]
+#[slash("list-orphans", "Lists orphaned channels and roles", "list orphans")]
+fn list_orphans<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let orphans =
+        lib::discord::orphans::reconcile_guild_orphans(&pool, &discord_api, &guild_config).await?;
+    if orphans.is_empty() {
+        context
+            .reply("There are no orphaned channels or roles")
+            .await
+            .ok();
+        return Ok(());
+    }
+    let mut reply = String::new();
+    for orphan in orphans {
+        let line = format!("• {}\n", orphan);
+        if reply.len() + line.len() > MAX_MESSAGE_LENGTH {
+            context.reply(&reply).await.ok();
+            reply.clear();
+        }
+        reply += &line;
+    }
+    context.reply(&reply).await.ok();
+    Ok(())
+}
+
+#[command]
+#[regex(
+    r"adopt\s*orphan\s+(?P<discord_id>[0-9]+)\s+(?P<series_id>[0-9]+)(?:\s+as(?:\s+(?P<slot>host\s*role|role))?)?"
+)]
+#[level(admin)]
+#[help(
+    "adopt orphan `discord-ID` `series-ID` (as host role)",
+    "makes an orphaned channel or role the channel or role of an event series. Add _as host role_ to adopt a role as the series' host role."
+)]
+#[slash(
+    "adopt-orphan",
+    "Makes an orphaned channel or role the channel or role of an event series",
+    "adopt orphan {orphan} {series} as {as}"
+)]
+#[slash_option("orphan", string, "The ID of the orphaned channel or role")]
+#[slash_option("series", integer, "The ID of the event series")]
+#[slash_option(
+    "as",
+    string,
+    "\"host role\" to adopt a role as the host role",
+    optional
+)]
+fn adopt_orphan<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let (discord_id, series_id) = match (
+        parse_id(&captures, "discord_id"),
+        captures.name("series_id").unwrap().as_str().parse(),
+    ) {
+        (Some(discord_id), Ok(series_id)) => (discord_id, lib::db::EventSeriesId(series_id)),
+        _ => {
+            context
+                .reply("Seems like the specified IDs are invalid")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let as_host_role = captures
+        .name("slot")
+        .is_some_and(|slot| slot.as_str().starts_with("host"));
+    let pool = context.pool().await?;
+    let guild_config = context.guild_config().await?;
+    let outcome = lib::discord::orphans::adopt(
+        discord_id,
+        series_id,
+        as_host_role,
+        context.msg.author.id,
+        &pool,
+        &guild_config,
+    )
+    .await?;
+    reply_outcome(
+        context,
+        outcome,
+        "Adopted. The next Discord sync will update its name and permissions.",
+    )
+    .await?;
+    Ok(())
+}
+
+#[command]
+#[regex(r"relink\s*orphan\s+(?P<missing_id>[0-9]+)\s+(?P<discord_id>[0-9]+)")]
+#[level(admin)]
+#[help(
+    "relink orphan `missing-ID` `discord-ID`",
+    "replaces an event series' channel or role which is gone from Discord with an orphaned one."
+)]
+#[slash(
+    "relink-orphan",
+    "Replaces a missing channel or role of an event series with an orphaned one",
+    "relink orphan {missing} {orphan}"
+)]
+#[slash_option("missing", string, "The ID of the missing channel or role")]
+#[slash_option("orphan", string, "The ID of the orphaned channel or role")]
+fn relink_orphan<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let (missing_id, discord_id) = match (
+        parse_id(&captures, "missing_id"),
+        parse_id(&captures, "discord_id"),
+    ) {
+        (Some(missing_id), Some(discord_id)) => (missing_id, discord_id),
+        _ => {
+            context
+                .reply("Seems like the specified IDs are invalid")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let guild_config = context.guild_config().await?;
+    let outcome = lib::discord::orphans::relink(
+        missing_id,
+        discord_id,
+        context.msg.author.id,
+        &pool,
+        &guild_config,
+    )
+    .await?;
+    reply_outcome(
+        context,
+        outcome,
+        "Re-linked. The next Discord sync will update its name and permissions.",
+    )
+    .await?;
+    Ok(())
+}
+
+#[command]
+#[regex(r"delete\s*orphan\s+(?P<discord_id>[0-9]+)")]
+#[level(admin)]
+#[help(
+    "delete orphan `discord-ID`",
+    "deletes an orphaned channel or role from Discord, or forgets about an event series' channel or role which is gone from Discord."
+)]
+#[slash(
+    "delete-orphan",
+    "Deletes an orphaned channel or role",
+    "delete orphan {orphan}"
+)]
+#[slash_option("orphan", string, "The ID of the orphaned channel or role")]
+fn delete_orphan<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let discord_id = match parse_id(&captures, "discord_id") {
+        Some(discord_id) => discord_id,
+        None => {
+            context
+                .reply("Seems like the specified ID is invalid")
+                .await
+                .ok();
+            return Ok(());
+        }
+    };
+    let pool = context.pool().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let outcome = lib::discord::orphans::delete(
+        discord_id,
+        context.msg.author.id,
+        &pool,
+        &discord_api,
+        &guild_config,
+    )
+    .await?;
+    reply_outcome(context, outcome, "Deleted").await?;
+    Ok(())
+}
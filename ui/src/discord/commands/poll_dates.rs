@@ -0,0 +1,53 @@
+use command_macro::command;
+use serenity::builder::CreateMessage;
+
+#[command]
+#[regex(r"poll\s*dates")]
+#[level(host)]
+#[help(
+    "poll dates",
+    "lets the players of your adventure vote on candidate dates for the next session."
+)]
+#[slash(
+    "poll-dates",
+    "Starts a poll to find a date for the next session of this adventure",
+    "poll dates"
+)]
+fn poll_dates<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    // Find the series belonging to the channel
+    let pool = context.pool().await?;
+    let mut tx = pool.begin().await?;
+    let event_series = lib::get_channel_series(context.msg.channel_id, &mut tx).await?;
+    let event_series = if let Some(event_series) = event_series {
+        event_series
+    } else {
+        context
+            .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .await
+            .ok();
+        return Ok(());
+    };
+    // Create a new Flow
+    let author_id = context.msg.author.id;
+    let flow = lib::flow::PollDatesFlow::new(
+        context.async_redis_connection().await?,
+        event_series,
+        author_id,
+    )
+    .await?;
+    let link = format!("{}/poll_dates/{}", lib::urls::BASE_URL, flow.id);
+    context
+        .msg
+        .author
+        .direct_message(
+            &context.ctx,
+            CreateMessage::new().content(format!("{}:\n{}", lib::strings::DATE_POLL_LINK, link)),
+        )
+        .await
+        .ok();
+    context.react('\u{2705}').await.ok();
+    Ok(())
+}
@@ -14,10 +14,7 @@ fn refresh_meetup_token<'a>(
     let discord_id = match discord_id.parse::<u64>() {
         Ok(id) => id,
         _ => {
-            context
-                .msg
-                .channel_id
-                .say(context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+            context.reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -33,10 +30,7 @@ fn refresh_meetup_token<'a>(
     let meetup_id = match res {
         Some(meetup_id) => meetup_id,
         None => {
-            context
-                .msg
-                .channel_id
-                .say(context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+            context.reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                 .await
                 .ok();
             return Ok(());
@@ -51,6 +45,6 @@ fn refresh_meetup_token<'a>(
             redis_connection,
         )
         .await?;
-    context.msg.react(context.ctx, '\u{2705}').await.ok();
+    context.react('\u{2705}').await.ok();
     Ok(())
 }
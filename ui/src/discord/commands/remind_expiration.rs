@@ -7,22 +7,19 @@ use command_macro::command;
     "remind expiration",
     "runs the end-of-game task, reminding channels of their expiration and deleting closed ones"
 )]
+#[slash("remind-expiration", "Runs the end-of-game task", "remind expiration")]
 fn remind_expiration<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let pool = context.pool().await?;
     let bot_id = context.bot_id().await?;
-    let mut discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_configs = context.guild_configs().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
     // Spawn the end-of-game task
     tokio::spawn(async move {
-        lib::tasks::end_of_game::end_of_game_task(&pool, &mut discord_api, bot_id).await
+        lib::tasks::end_of_game::end_of_game_task(&pool, &discord_api, &guild_configs, bot_id).await
     });
-    context
-        .msg
-        .channel_id
-        .say(&context.ctx, "Started expiration reminder task")
-        .await
-        .ok();
+    context.reply("Started expiration reminder task").await.ok();
     Ok(())
 }
@@ -8,6 +8,11 @@ use serenity::builder::CreateMessage;
     "schedule session",
     "allows you to schedule a new session for your adventure."
 )]
+#[slash(
+    "schedule-session",
+    "Schedules a new session for this adventure",
+    "schedule session"
+)]
 fn schedule_session<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
@@ -20,9 +25,7 @@ fn schedule_session<'a>(
         event_series
     } else {
         context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
             .await
             .ok();
         return Ok(());
@@ -44,6 +47,6 @@ fn schedule_session<'a>(
         )
         .await
         .ok();
-    context.msg.react(&context.ctx, '\u{2705}').await.ok();
+    context.react('\u{2705}').await.ok();
     Ok(())
 }
@@ -0,0 +1,43 @@
+use command_macro::command;
+
+#[command]
+#[regex(r"reminders\s+(?P<state>on|off)")]
+#[help(
+    "reminders `on|off`",
+    "turns the reminders I send you via DM before your sessions on or off."
+)]
+#[slash(
+    "reminders",
+    "Turns session reminders via direct message on or off",
+    "reminders {state}"
+)]
+#[slash_option("state", string, "Either \"on\" or \"off\"")]
+fn session_reminders<'a>(
+    context: &'a mut super::CommandContext,
+    captures: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let enable = captures
+        .name("state")
+        .expect("Regex capture does not contain 'state'")
+        .as_str()
+        == "on";
+    let pool = context.pool().await?;
+    let mut tx = pool.begin().await?;
+    let member_id =
+        lib::db::get_or_create_member_for_discord_id(&mut tx, context.msg.author.id).await?;
+    sqlx::query!(
+        r#"UPDATE "member" SET session_reminder_dms = $2 WHERE id = $1"#,
+        member_id.0,
+        enable
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    let reply = if enable {
+        lib::strings::SESSION_REMINDER_DMS_ENABLED
+    } else {
+        lib::strings::SESSION_REMINDER_DMS_DISABLED
+    };
+    context.reply(reply).await.ok();
+    Ok(())
+}
@@ -1,4 +1,8 @@
 use command_macro::command;
+use serenity::{
+    model::application::{AutocompleteOption, CommandInteraction},
+    prelude::Context,
+};
 
 #[command]
 #[regex(r"snooze\s+(?P<num_days>[0-9]+)\s*d(ay)?s?")]
@@ -7,6 +11,18 @@ use command_macro::command;
     "snooze `X` days",
     "_(in game channel)_ snoozes reminders for _X_ days"
 )]
+#[slash(
+    "snooze",
+    "Snoozes the expiration reminders of this channel",
+    "snooze {days} days",
+    autocomplete = snooze_autocomplete
+)]
+#[slash_option(
+    "days",
+    integer,
+    "For how many days to snooze the reminders",
+    autocomplete
+)]
 fn snooze<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -23,9 +39,7 @@ fn snooze<'a>(
     let is_game_channel: bool = context.is_game_channel(None).await?;
     if !is_game_channel {
         context
-            .msg
-            .channel_id
-            .say(&context.ctx, lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
+            .reply(lib::strings::CHANNEL_NOT_BOT_CONTROLLED)
             .await
             .ok();
         return Ok(());
@@ -39,12 +53,7 @@ fn snooze<'a>(
         )
         .execute(&pool)
         .await?;
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, "Disabled snoozing.")
-            .await
-            .ok();
+        context.reply("Disabled snoozing.").await.ok();
     } else {
         let snooze_until = chrono::Utc::now() + chrono::Duration::days(num_days as i64);
         // Set a new snooze date
@@ -56,11 +65,34 @@ fn snooze<'a>(
         .execute(&pool)
         .await?;
         context
-            .msg
-            .channel_id
-            .say(&context.ctx, format!("Snoozing for {} days.", num_days))
+            .reply(format!("Snoozing for {} days.", num_days))
             .await
             .ok();
     }
     Ok(())
 }
+
+// Suggests common snooze durations starting with the typed number, including
+// turning snoozing off
+fn snooze_autocomplete<'a>(
+    _ctx: &'a Context,
+    _interaction: &'a CommandInteraction,
+    option: AutocompleteOption<'a>,
+) -> super::AutocompleteResult<'a> {
+    Box::pin(async move {
+        let choices = [0, 1, 3, 7, 14, 30, 60, 90]
+            .into_iter()
+            .map(|days: u32| days.to_string())
+            .filter(|days| days.starts_with(option.value.trim()))
+            .map(|days| super::AutocompleteChoice {
+                name: match days.as_str() {
+                    "0" => "0 days (stop snoozing)".to_string(),
+                    "1" => "1 day".to_string(),
+                    _ => format!("{} days", days),
+                },
+                value: days,
+            })
+            .collect();
+        Ok(choices)
+    })
+}
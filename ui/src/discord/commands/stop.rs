@@ -4,6 +4,7 @@ use command_macro::command;
 #[regex(r"stop")]
 #[level(admin)]
 #[help("stop", "shuts down Hyperion")]
+#[slash("stop", "Shuts down Hyperion", "stop")]
 pub fn stop<'a>(
     _context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
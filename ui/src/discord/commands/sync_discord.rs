@@ -1,27 +1,164 @@
 use command_macro::command;
 
+// Discord allows up to 2000 characters per message
+const MAX_MESSAGE_LENGTH: usize = 1900;
+// Failed sync jobs only show the beginning of their error
+const MAX_ERROR_LENGTH: usize = 200;
+
 #[command]
 #[regex(r"sync\s*discord")]
 #[level(admin)]
 #[help("sync discord", "Synchronizes Discord with the database")]
+#[slash(
+    "sync-discord",
+    "Synchronizes Discord with the database",
+    "sync discord"
+)]
 fn sync_discord<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let mut redis_connection = context.redis_client().await?.get_async_connection().await?;
     let pool = context.pool().await?;
-    let mut discord_api = (&context.ctx).into();
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_configs = context.guild_configs().await?;
     let bot_id = context.bot_id().await?;
-    // Spawn the syncing task
+    let late_reply = context.late_reply();
+    // Spawn the syncing task and report back once it's done
     tokio::spawn(async move {
-        lib::discord::sync::sync_discord(&mut redis_connection, &pool, &mut discord_api, bot_id)
-            .await
+        let reply = match lib::discord::sync::sync_discord_with_summary(
+            &mut redis_connection,
+            &pool,
+            &discord_api,
+            &guild_configs,
+            bot_id,
+        )
+        .await
+        {
+            Ok(summary) => format!("Discord synchronization finished. {}", summary),
+            Err(err) => {
+                eprintln!("Discord synchronization failed:\n{:#?}", err);
+                "Discord synchronization failed".to_string()
+            }
+        };
+        late_reply.say(reply).await.ok();
     });
     context
-        .msg
-        .channel_id
-        .say(&context.ctx, "Started Discord synchronization task")
+        .reply("Started Discord synchronization task")
         .await
         .ok();
     Ok(())
 }
+
+#[command]
+#[regex(r"sync\s*discord\s+dry[ -]?run")]
+#[level(admin)]
+#[help(
+    "sync discord dry run",
+    "Shows what synchronizing Discord with the database would change, without changing anything"
+)]
+#[slash(
+    "sync-discord-dry-run",
+    "Shows what synchronizing Discord would change",
+    "sync discord dry run"
+)]
+fn sync_discord_dry_run<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let mut redis_connection = context.redis_client().await?.get_async_connection().await?;
+    let pool = context.pool().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
+    let guild_configs = context.guild_configs().await?;
+    let bot_id = context.bot_id().await?;
+    let plan = lib::discord::sync::plan_sync_discord(
+        &mut redis_connection,
+        &pool,
+        &discord_api,
+        &guild_configs,
+        bot_id,
+    )
+    .await?;
+    let mut lines: Vec<String> = plan
+        .changes()
+        .iter()
+        .map(|change| format!("- {}", change))
+        .collect();
+    lines.extend(plan.errors().iter().map(|(series_id, error)| {
+        format!("- Could not check event series {}: {}", series_id.0, error)
+    }));
+    if lines.is_empty() {
+        context
+            .reply(lib::strings::SYNC_DISCORD_DRY_RUN_NO_CHANGES)
+            .await
+            .ok();
+        return Ok(());
+    }
+    // Split the plan into messages that don't exceed Discord's length limit
+    let mut messages = vec![lib::strings::SYNC_DISCORD_DRY_RUN_CHANGES(
+        plan.changes().len(),
+    )];
+    for line in lines {
+        let current_message = messages.last_mut().unwrap();
+        if current_message.len() + line.len() + 1 > MAX_MESSAGE_LENGTH {
+            messages.push(line);
+        } else {
+            current_message.push('\n');
+            current_message.push_str(&line);
+        }
+    }
+    for message in messages {
+        context.reply(message).await.ok();
+    }
+    Ok(())
+}
+
+#[command]
+#[regex(r"sync\s*queue")]
+#[level(admin)]
+#[help(
+    "sync queue",
+    "Shows the pending and running targeted sync jobs and the most recently finished ones"
+)]
+#[slash("sync-queue", "Shows the sync job queue", "sync queue")]
+fn sync_queue<'a>(
+    context: &'a mut super::CommandContext,
+    _: regex::Captures<'a>,
+) -> super::CommandResult<'a> {
+    let pool = context.pool().await?;
+    let jobs = lib::sync_queue::queue_status(10, &pool).await?;
+    if jobs.is_empty() {
+        context.reply("The sync queue is empty").await.ok();
+        return Ok(());
+    }
+    let mut reply = String::new();
+    for job in jobs {
+        let description = match job.job {
+            Some(sync_job) => sync_job.to_string(),
+            None => "Unknown job".to_string(),
+        };
+        let status = match (job.started_time, job.finished_time, &job.error) {
+            (_, Some(finished_time), Some(error)) => format!(
+                "failed <t:{}:R>: {}",
+                finished_time.timestamp(),
+                error.chars().take(MAX_ERROR_LENGTH).collect::<String>()
+            ),
+            (_, Some(finished_time), None) => format!("done <t:{}:R>", finished_time.timestamp()),
+            (Some(started_time), None, _) => {
+                format!("running since <t:{}:R>", started_time.timestamp())
+            }
+            (None, None, _) => format!("pending since <t:{}:R>", job.created_time.timestamp()),
+        };
+        let line = format!(
+            "• #{} {} ({}): {}\n",
+            job.id, description, job.reason, status
+        );
+        if reply.len() + line.len() > MAX_MESSAGE_LENGTH {
+            context.reply(&reply).await.ok();
+            reply.clear();
+        }
+        reply += &line;
+    }
+    context.reply(&reply).await.ok();
+    Ok(())
+}
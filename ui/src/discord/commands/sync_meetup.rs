@@ -10,16 +10,17 @@ use std::time::Duration;
     "Synchronizes the database with the current Meetup data. Please use carefully due to Meetup \
      API usage limits."
 )]
+#[slash("sync-meetup", "Synchronizes the database with Meetup", "sync meetup")]
 fn sync_meetup<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     // Send the syncing future to the executor
     let pool = context.pool().await?;
-    let async_meetup_client = context.meetup_client().await?;
+    let meetup_connection = context.meetup_connection().await?;
     let sync_task = {
         let task = async move {
-            lib::meetup::sync::sync_task(async_meetup_client, &pool)
+            lib::meetup::sync::sync_task(&meetup_connection, &pool)
                 .await
                 .map(|_| ())
         };
@@ -36,12 +37,7 @@ fn sync_meetup<'a>(
     };
     tokio::spawn(sync_task);
     context
-        .msg
-        .channel_id
-        .say(
-            &context.ctx,
-            "Started asynchronous Meetup synchronization task",
-        )
+        .reply("Started asynchronous Meetup synchronization task")
         .await
         .ok();
     Ok(())
@@ -4,15 +4,28 @@ use command_macro::command;
 #[regex(r"sync\s*subscriptions")]
 #[level(admin)]
 #[help("sync subscriptions", "Syncs the active Stripe subscriptions")]
+#[slash(
+    "sync-subscriptions",
+    "Syncs the active Stripe subscriptions",
+    "sync subscriptions"
+)]
 fn sync_subscriptions<'a>(
     context: &'a mut super::CommandContext,
     _: regex::Captures<'a>,
 ) -> super::CommandResult<'a> {
     let stripe_client = context.stripe_client().await?;
-    let discord_api = (&context.ctx).into();
+    let guild_config = context.guild_config().await?;
+    let pool = context.pool().await?;
+    let discord_api: lib::discord::CacheAndHttp = (&context.ctx).into();
     tokio::spawn(async move {
-        lib::tasks::subscription_roles::update_roles(&discord_api, &stripe_client).await
+        lib::tasks::subscription_roles::update_roles(
+            &pool,
+            &discord_api,
+            stripe_client.as_ref(),
+            &guild_config,
+        )
+        .await
     });
-    let _ = context.msg.channel_id.say(&context.ctx, "Copy that");
+    let _ = context.reply("Copy that");
     Ok(())
 }
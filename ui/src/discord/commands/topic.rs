@@ -1,6 +1,13 @@
 use command_macro::command;
 use redis::{AsyncCommands, RedisResult};
-use serenity::{builder::EditChannel, model::channel::Channel};
+use serenity::{
+    builder::EditChannel,
+    model::{
+        application::{AutocompleteOption, CommandInteraction},
+        channel::Channel,
+    },
+    prelude::Context,
+};
 use std::time::Duration;
 
 #[command]
@@ -9,6 +16,13 @@ use std::time::Duration;
     "topic `some topic`",
     "renames the user topic voice channel to the specified topic"
 )]
+#[slash(
+    "topic",
+    "Renames the user topic voice channel",
+    "topic {topic}",
+    autocomplete = topic_autocomplete
+)]
+#[slash_option("topic", string, "The new topic", autocomplete)]
 fn set_voice_topic<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -16,47 +30,32 @@ fn set_voice_topic<'a>(
     // Indicate that something is happening
     let _typing_indicator = context.msg.channel_id.start_typing(&context.ctx.http);
     // Check if there is a user topic voice channel
-    let voice_channel_id = if let Some(id) = lib::discord::sync::ids::USER_TOPIC_VOICE_CHANNEL_ID {
-        id
-    } else {
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, "No voice channel has been configured")
-            .await?;
-        return Ok(());
-    };
+    let voice_channel_id =
+        if let Some(id) = context.guild_config().await?.user_topic_voice_channel_id {
+            id
+        } else {
+            context
+                .reply("No voice channel has been configured")
+                .await?;
+            return Ok(());
+        };
     // Check whether the specified topic is ok
     let topic = if let Some(topic) = captures.name("topic") {
         topic.as_str()
     } else {
-        context
-            .msg
-            .channel_id
-            .say(&context.ctx, "I had trouble parsing the new topic")
-            .await?;
+        context.reply("I had trouble parsing the new topic").await?;
         return Ok(());
     };
     let topic = topic.trim();
     let topic_len = topic.chars().count();
     if topic_len < 2 {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                "That topic is too short, it needs to have at least 2 characters.",
-            )
+            .reply("That topic is too short, it needs to have at least 2 characters.")
             .await?;
         return Ok(());
     }
     if topic_len > 100 {
-        context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                "That topic is over 100 characters. Have you considered the benefits of conciseness?",
+        context.reply("That topic is over 100 characters. Have you considered the benefits of conciseness?",
             )
             .await?;
         return Ok(());
@@ -67,12 +66,7 @@ fn set_voice_topic<'a>(
             channel
         } else {
             context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    "I could not find the user topic voice channel :(",
-                )
+                .reply("I could not find the user topic voice channel :(")
                 .await?;
             return Ok(());
         };
@@ -81,12 +75,7 @@ fn set_voice_topic<'a>(
     let is_empty = voice_channel.members(&context.ctx)?.is_empty();
     if !has_default_name && !is_empty {
         // Someone is already using the voice channel
-        context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                "Sorry but it seems there is already a topic going on. Try again when the voice channel is empty.",
+        context.reply("Sorry but it seems there is already a topic going on. Try again when the voice channel is empty.",
             )
             .await?;
         return Ok(());
@@ -102,24 +91,14 @@ fn set_voice_topic<'a>(
     {
         Err(_) => {
             // The timeout elapsed
-            context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    "Hold your horses. A topic was introduced recently. Please wait 10 minutes before changing it again.",
+            context.reply("Hold your horses. A topic was introduced recently. Please wait 10 minutes before changing it again.",
                 )
                 .await?;
             return Ok(());
         }
         Ok(Err(err)) => {
             context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    "There was an error renaming the voice channel :(",
-                )
+                .reply("There was an error renaming the voice channel :(")
                 .await?;
             return Err(err.into());
         }
@@ -129,18 +108,57 @@ fn set_voice_topic<'a>(
     if let Ok(con) = context.async_redis_connection().await {
         let _: RedisResult<()> = con
             .set(
-                "user_topic_voice_channel_topic_time",
+                format!("user_topic_voice_channel:{}:topic_time", voice_channel_id),
                 chrono::Utc::now().to_rfc3339(),
             )
             .await;
     }
     context
-        .msg
-        .channel_id
-        .say(
-            &context.ctx,
-            format!("The voice channel is now yours! New topic: _{}_", topic),
-        )
+        .reply(format!(
+            "The voice channel is now yours! New topic: _{}_",
+            topic
+        ))
         .await?;
     Ok(())
 }
+
+// Suggests the titles of the guild's games taking place around now
+fn topic_autocomplete<'a>(
+    ctx: &'a Context,
+    interaction: &'a CommandInteraction,
+    option: AutocompleteOption<'a>,
+) -> super::AutocompleteResult<'a> {
+    Box::pin(async move {
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(vec![]),
+        };
+        let pool = ctx
+            .data
+            .read()
+            .await
+            .get::<crate::discord::bot::PoolKey>()
+            .cloned()
+            .ok_or_else(|| simple_error::SimpleError::new("Postgres pool was not set"))?;
+        let titles = sqlx::query_scalar!(
+            r#"SELECT DISTINCT event.title
+            FROM event
+            INNER JOIN event_series ON event.event_series_id = event_series.id
+            WHERE event.deleted IS NULL
+                AND event.start_time BETWEEN NOW() - INTERVAL '6 hours' AND NOW() + INTERVAL '1 day'
+                AND (event_series.guild_id = $1 OR event_series.guild_id IS NULL)
+                AND event.title ILIKE '%' || $2 || '%'
+            ORDER BY event.title"#,
+            guild_id.get() as i64,
+            option.value.trim()
+        )
+        .fetch_all(&pool)
+        .await?;
+        // Voice channel names are limited to 100 characters
+        Ok(titles
+            .into_iter()
+            .filter(|title| title.chars().count() <= 100)
+            .map(Into::into)
+            .collect())
+    })
+}
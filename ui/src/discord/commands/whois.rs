@@ -2,7 +2,14 @@ use std::num::NonZeroU64;
 
 use command_macro::command;
 use lib::db;
-use serenity::{all::Mentionable, model::id::UserId};
+use serenity::{
+    all::Mentionable,
+    model::{
+        application::{AutocompleteOption, CommandInteraction},
+        id::UserId,
+    },
+    prelude::Context,
+};
 
 #[command]
 #[regex(
@@ -25,6 +32,18 @@ use serenity::{all::Mentionable, model::id::UserId};
     "whois meetup `meetup-ID`",
     "shows the Discord user linked to the provided Meetup profile"
 )]
+#[slash(
+    "whois",
+    "Shows who is behind a Discord or Meetup profile",
+    "whois {who}",
+    autocomplete = whois_autocomplete
+)]
+#[slash_option(
+    "who",
+    string,
+    "A Discord username or `meetup <Meetup ID>`",
+    autocomplete
+)]
 fn whois<'a>(
     context: &'a mut super::CommandContext,
     captures: regex::Captures<'a>,
@@ -37,9 +56,7 @@ fn whois<'a>(
             Ok(id) => UserId::from(id),
             _ => {
                 context
-                    .msg
-                    .channel_id
-                    .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                    .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                     .await
                     .ok();
                 return Ok(());
@@ -62,9 +79,7 @@ fn whois<'a>(
             Ok(id) => id,
             _ => {
                 context
-                    .msg
-                    .channel_id
-                    .say(&context.ctx, lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
+                    .reply(lib::strings::CHANNEL_ADD_USER_INVALID_DISCORD)
                     .await
                     .ok();
                 return Ok(());
@@ -75,6 +90,32 @@ fn whois<'a>(
     Ok(())
 }
 
+// Suggests the usernames of cached guild members containing the typed text
+fn whois_autocomplete<'a>(
+    ctx: &'a Context,
+    interaction: &'a CommandInteraction,
+    option: AutocompleteOption<'a>,
+) -> super::AutocompleteResult<'a> {
+    Box::pin(async move {
+        let partial = option.value.to_lowercase();
+        let guild_id = match interaction.guild_id {
+            Some(guild_id) => guild_id,
+            None => return Ok(vec![]),
+        };
+        let mut usernames: Vec<String> = match guild_id.to_guild_cached(ctx) {
+            Some(guild) => guild
+                .members
+                .values()
+                .map(|member| member.user.name.clone())
+                .filter(|username| username.to_lowercase().contains(&partial))
+                .collect(),
+            None => vec![],
+        };
+        usernames.sort_unstable();
+        Ok(usernames.into_iter().map(Into::into).collect())
+    })
+}
+
 async fn whois_by_discord_id(
     context: &mut super::CommandContext,
     user_id: UserId,
@@ -85,11 +126,13 @@ async fn whois_by_discord_id(
         [(
             _,
             Some(db::MemberWithDiscord {
+                id,
                 meetup_id: Some(meetup_id),
                 discord_nick,
                 ..
             }),
         )] => {
+            let discord_nick = nick_in_command_guild(context, *id, discord_nick.clone()).await?;
             let message = if let Some(discord_nick) = discord_nick {
                 format!(
                     "{} ({}) is linked to https://www.meetup.com/members/{}/",
@@ -104,19 +147,14 @@ async fn whois_by_discord_id(
                     meetup_id
                 )
             };
-            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            context.reply(message).await.ok();
         }
         _ => {
             context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    format!(
-                        "{} does not seem to be linked to a Meetup account",
-                        user_id.mention()
-                    ),
-                )
+                .reply(format!(
+                    "{} does not seem to be linked to a Meetup account",
+                    user_id.mention()
+                ))
                 .await
                 .ok();
         }
@@ -128,16 +166,15 @@ async fn whois_by_discord_username_tag(
     context: &mut super::CommandContext,
     username_tag: &str,
 ) -> Result<(), lib::meetup::Error> {
-    let discord_id = lib::discord::sync::ids::GUILD_ID
+    let guild_id = context.guild_config().await?.guild_id;
+    let discord_id = guild_id
         .to_guild_cached(&context.ctx)
         .map(|guild| guild.member_named(username_tag).map(|m| m.user.id));
     let discord_id = if let Some(discord_id) = discord_id {
         discord_id
     } else {
         context
-            .msg
-            .channel_id
-            .say(&context.ctx, "Something went wrong (guild not found)")
+            .reply("Something went wrong (guild not found)")
             .await
             .ok();
         return Ok(());
@@ -147,12 +184,7 @@ async fn whois_by_discord_username_tag(
         whois_by_discord_id(context, discord_id).await?;
     } else {
         context
-            .msg
-            .channel_id
-            .say(
-                &context.ctx,
-                format!("{} is not a Discord user", username_tag),
-            )
+            .reply(format!("{} is not a Discord user", username_tag))
             .await
             .ok();
     }
@@ -169,11 +201,13 @@ async fn whois_by_meetup_id(
         [(
             _,
             Some(db::MemberWithMeetup {
+                id,
                 discord_id: Some(discord_id),
                 discord_nick,
                 ..
             }),
         )] => {
+            let discord_nick = nick_in_command_guild(context, *id, discord_nick.clone()).await?;
             let message = if let Some(discord_nick) = discord_nick {
                 format!(
                     "https://www.meetup.com/members/{}/ is linked to {} ({})",
@@ -188,23 +222,32 @@ async fn whois_by_meetup_id(
                     discord_id.mention()
                 )
             };
-            context.msg.channel_id.say(&context.ctx, message).await.ok();
+            context.reply(message).await.ok();
         }
         _ => {
             context
-                .msg
-                .channel_id
-                .say(
-                    &context.ctx,
-                    format!(
-                        "https://www.meetup.com/members/{}/ does not seem to be linked to a \
+                .reply(format!(
+                    "https://www.meetup.com/members/{}/ does not seem to be linked to a \
                          Discord user",
-                        meetup_id
-                    ),
-                )
+                    meetup_id
+                ))
                 .await
                 .ok();
         }
     }
     Ok(())
 }
+
+// Prefers the member's nick in the guild the command was used in over their
+// nick in the default guild
+async fn nick_in_command_guild(
+    context: &super::CommandContext,
+    member_id: db::MemberId,
+    default_nick: Option<String>,
+) -> Result<Option<String>, lib::meetup::Error> {
+    let guild_nick = match context.msg.guild_id {
+        Some(guild_id) => db::guild_nick(guild_id, member_id, &context.pool().await?).await?,
+        None => None,
+    };
+    Ok(guild_nick.or(default_nick))
+}
@@ -1,3 +1,4 @@
 pub mod bot;
 pub mod commands;
+pub mod slash;
 pub mod spam;
@@ -0,0 +1,145 @@
+use serenity::{
+    builder::{CreateCommand, CreateCommandOption},
+    model::{
+        application::{
+            Command as DiscordCommand, CommandDataOptionValue, CommandInteraction,
+            CommandOptionType,
+        },
+        channel::Message,
+        id::GuildId,
+        mention::Mentionable,
+        permissions::Permissions,
+    },
+    prelude::*,
+};
+
+use super::commands::{CommandLevel, PreparedCommands, SlashCommand, SlashOptionKind};
+
+fn option_type(kind: &SlashOptionKind) -> CommandOptionType {
+    match kind {
+        SlashOptionKind::User => CommandOptionType::User,
+        SlashOptionKind::String => CommandOptionType::String,
+        SlashOptionKind::Integer => CommandOptionType::Integer,
+        SlashOptionKind::Channel => CommandOptionType::Channel,
+    }
+}
+
+// Admin commands are only shown to guild administrators by default. They can
+// make them available to the bot admin role in the guild's integration
+// settings. The permission level is checked again when the command is invoked.
+fn default_member_permissions(level: &CommandLevel) -> Option<Permissions> {
+    match level {
+        CommandLevel::AdminOnly => Some(Permissions::empty()),
+        CommandLevel::Everybody | CommandLevel::HostAndAdminOnly => None,
+    }
+}
+
+fn create_command(slash: &SlashCommand, level: &CommandLevel) -> CreateCommand {
+    let mut command = CreateCommand::new(slash.name)
+        .description(slash.description)
+        .set_options(
+            slash
+                .options
+                .iter()
+                .map(|option| {
+                    CreateCommandOption::new(
+                        option_type(&option.kind),
+                        option.name,
+                        option.description,
+                    )
+                    .required(option.required)
+                    .set_autocomplete(option.autocomplete)
+                })
+                .collect(),
+        );
+    if let Some(permissions) = default_member_permissions(level) {
+        command = command.default_member_permissions(permissions);
+    }
+    command
+}
+
+// Checks whether the command registered with Discord matches our definition
+fn is_up_to_date(slash: &SlashCommand, level: &CommandLevel, existing: &DiscordCommand) -> bool {
+    existing.name == slash.name
+        && existing.description == slash.description
+        && existing.default_member_permissions == default_member_permissions(level)
+        && existing.options.len() == slash.options.len()
+        && existing
+            .options
+            .iter()
+            .zip(slash.options)
+            .all(|(existing, option)| {
+                existing.name == option.name
+                    && existing.description == option.description
+                    && existing.kind == option_type(&option.kind)
+                    && existing.required == option.required
+                    && existing.autocomplete == option.autocomplete
+            })
+}
+
+// Registers the slash commands in the given guild, unless Discord already
+// knows about the current version of all of them
+pub(crate) async fn register_slash_commands(
+    ctx: &Context,
+    guild_id: GuildId,
+    commands: &PreparedCommands,
+) -> Result<(), lib::meetup::Error> {
+    let existing_commands = guild_id.get_commands(&ctx.http).await?;
+    let up_to_date = existing_commands.len() == commands.slash_commands.len()
+        && commands.slash_commands.iter().all(|(slash, command, _)| {
+            existing_commands
+                .iter()
+                .any(|existing| is_up_to_date(slash, &command.level, existing))
+        });
+    if up_to_date {
+        println!("Slash commands of guild {} are up to date", guild_id);
+        return Ok(());
+    }
+    let new_commands = commands
+        .slash_commands
+        .iter()
+        .map(|(slash, command, _)| create_command(slash, &command.level))
+        .collect();
+    let registered_commands = guild_id.set_commands(&ctx.http, new_commands).await?;
+    println!(
+        "Registered {} slash commands in guild {}",
+        registered_commands.len(),
+        guild_id
+    );
+    Ok(())
+}
+
+// Turns a slash command interaction into the equivalent text command
+pub(crate) fn command_text(slash: &SlashCommand, interaction: &CommandInteraction) -> String {
+    let mut text = slash.template.to_string();
+    for option in slash.options {
+        let value = interaction
+            .data
+            .options
+            .iter()
+            .find(|data_option| data_option.name == option.name)
+            .map(|data_option| match &data_option.value {
+                CommandDataOptionValue::User(user_id) => user_id.mention().to_string(),
+                CommandDataOptionValue::String(value) => value.clone(),
+                CommandDataOptionValue::Integer(value) => value.to_string(),
+                CommandDataOptionValue::Channel(channel_id) => channel_id.mention().to_string(),
+                _ => String::new(),
+            })
+            .unwrap_or_default();
+        text = text.replace(&format!("{{{}}}", option.name), &value);
+    }
+    text
+}
+
+// Creates the message the text version of the command would have been sent
+// with. Commands read the channel and the author from this message, but it
+// doesn't exist on Discord, so replies and reactions have to go through the
+// interaction (see CommandContext::reply).
+pub(crate) fn command_message(interaction: &CommandInteraction, content: String) -> Message {
+    let mut msg = Message::default();
+    msg.author = interaction.user.clone();
+    msg.channel_id = interaction.channel_id;
+    msg.guild_id = interaction.guild_id;
+    msg.content = content;
+    msg
+}
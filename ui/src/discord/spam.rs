@@ -22,8 +22,8 @@ impl TypeMapKey for GameChannelsListKey {
 pub async fn message_hook(
     cmdctx: &mut super::commands::CommandContext,
 ) -> Result<(), lib::meetup::Error> {
-    let alert_channel_id = if let Some(channel_id) = lib::discord::sync::ids::BOT_ALERTS_CHANNEL_ID
-    {
+    let guild_config = cmdctx.guild_config().await?;
+    let alert_channel_id = if let Some(channel_id) = guild_config.bot_alerts_channel_id {
         channel_id
     } else {
         return Ok(());
@@ -39,7 +39,7 @@ pub async fn message_hook(
         let word = &word_list[mat.pattern()];
         let mut msg = serenity::utils::MessageBuilder::new();
         msg.push_bold("Spam Alert ");
-        if let Some(admin_role_id) = lib::discord::sync::ids::ADMIN_ROLE_ID {
+        if let Some(admin_role_id) = guild_config.admin_role_id {
             msg.mention(&admin_role_id);
         }
         msg.push("\nTrigger: ");
@@ -50,7 +50,7 @@ pub async fn message_hook(
         msg.push_line_safe(&cmdctx.msg.content);
         msg.push(format!(
             "https://discordapp.com/channels/{guild_id}/{channel_id}/{message_id}",
-            guild_id = lib::discord::sync::ids::GUILD_ID.get(),
+            guild_id = guild_config.guild_id.get(),
             channel_id = cmdctx.msg.channel_id.get(),
             message_id = cmdctx.msg.id.get()
         ));
@@ -24,6 +24,7 @@ pub fn create_routes() -> Router {
             get(check_discord_username_handler),
         )
         .route("/list_players/:meetup_event_id", get(list_players_handler))
+        .route("/meetup_stats", get(meetup_stats_handler))
 }
 
 struct ApiKeyHeader(String);
@@ -137,6 +138,7 @@ async fn check_discord_username_handler(
 ) -> Result<StatusCode, WebError> {
     let id = lib::tasks::subscription_roles::discord_username_to_id(
         &state.discord_cache_http,
+        state.guild_configs.default_guild(),
         &discord_username.0,
     )
     .await?;
@@ -181,3 +183,20 @@ async fn list_players_handler(
     });
     Ok(Json(hosts.chain(players).collect()))
 }
+
+#[derive(Serialize)]
+struct MeetupStats {
+    health: lib::meetup::connection::ConnectionHealth,
+    #[serde(flatten)]
+    requests: lib::meetup::rate_limit::RequestStatsSnapshot,
+}
+
+async fn meetup_stats_handler(
+    _: ApiKeyIsValid,
+    Extension(state): Extension<Arc<State>>,
+) -> Json<MeetupStats> {
+    Json(MeetupStats {
+        health: state.meetup_connection.health(),
+        requests: lib::meetup::rate_limit::STATS.snapshot(),
+    })
+}
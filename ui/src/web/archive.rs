@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Path},
+    http::header,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono_tz::Europe;
+use lib::{
+    archive::{self, ChannelArchive, ChannelArchiveId},
+    db,
+};
+
+use super::{
+    auth::{require_bot_admin, AuthenticatedMember},
+    server::State,
+    MessageTemplate, WebError,
+};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route(
+            "/archives",
+            get(archives_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+        .route(
+            "/archive/:archive_id",
+            get(archive_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+        .route(
+            "/archive/:archive_id/json",
+            get(archive_json_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+}
+
+struct ArchiveRow {
+    id: i32,
+    channel_name: String,
+    local_time: String, // In Europe::Zurich timezone
+    num_messages: i32,
+}
+
+#[derive(Template)]
+#[template(path = "archives.html")]
+struct ArchivesTemplate {
+    archives: Vec<ArchiveRow>,
+}
+
+async fn archives_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    let archives = archive::list_for_member(member_id, &state.pool).await?;
+    let template = ArchivesTemplate {
+        archives: archives
+            .into_iter()
+            .map(|archive| ArchiveRow {
+                id: archive.id.0,
+                channel_name: archive.channel_name,
+                local_time: archive
+                    .created_time
+                    .with_timezone(&Europe::Zurich)
+                    .format("%a, %d %b %Y")
+                    .to_string(),
+                num_messages: archive.num_messages,
+            })
+            .collect(),
+    };
+    Ok(template.into_response())
+}
+
+// Former players and hosts of the series as well as bot admins may view its
+// archives
+async fn viewable_archive(
+    state: &State,
+    archive_id: i32,
+    member_id: db::MemberId,
+) -> Result<Result<ChannelArchive, MessageTemplate>, WebError> {
+    let archive = match archive::get(ChannelArchiveId(archive_id), &state.pool).await? {
+        Some(archive) => archive,
+        None => {
+            return Ok(Err((
+                "Archive not found",
+                "Seems like this archive does not exist",
+            )
+                .into()))
+        }
+    };
+    if !db::is_series_participant(archive.event_series_id, member_id, &state.pool).await? {
+        require_bot_admin(state, member_id).await?;
+    }
+    Ok(Ok(archive))
+}
+
+async fn archive_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(archive_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let archive = match viewable_archive(&state, archive_id, member_id).await? {
+        Ok(archive) => archive,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let html = archive::get_html(archive.id, &state.pool)
+        .await?
+        .unwrap_or_default();
+    Ok(Html(html).into_response())
+}
+
+async fn archive_json_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(archive_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let archive = match viewable_archive(&state, archive_id, member_id).await? {
+        Ok(archive) => archive,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let json = archive::get_json(archive.id, &state.pool)
+        .await?
+        .unwrap_or_default();
+    Ok(([(header::CONTENT_TYPE, "application/json")], json).into_response())
+}
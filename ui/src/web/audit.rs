@@ -0,0 +1,129 @@
+use std::{num::NonZeroU64, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{Extension, Query},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use serenity::model::id::{ChannelId, UserId};
+
+use super::{
+    auth::{require_bot_admin, AuthenticatedMember},
+    server::State,
+    WebError,
+};
+
+pub fn create_routes() -> Router {
+    Router::new().route(
+        "/audit",
+        get(audit_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+    )
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    channel: Option<String>,
+    actor: Option<String>,
+    action: Option<String>,
+}
+
+struct AuditRow {
+    time: String,
+    actor: String,
+    action: String,
+    target: String,
+    channel: String,
+    before: String,
+    after: String,
+}
+
+#[derive(Template)]
+#[template(path = "audit.html")]
+struct AuditTemplate<'a> {
+    channel: &'a str,
+    actor: &'a str,
+    action: &'a str,
+    actions: Vec<(&'static str, bool)>, // Action name and whether it is selected
+    rows: Vec<AuditRow>,
+}
+
+// Only bot admins may browse the audit log
+async fn audit_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Response, WebError> {
+    require_bot_admin(&state, member_id).await?;
+    // Empty or invalid filter values (including 0, which is no valid ID) are
+    // ignored
+    let channel = query.channel.unwrap_or_default();
+    let actor = query.actor.unwrap_or_default();
+    let action = query.action.unwrap_or_default();
+    let filter = lib::audit::Filter {
+        channel_id: channel
+            .trim()
+            .parse::<NonZeroU64>()
+            .ok()
+            .map(ChannelId::from),
+        actor: actor.trim().parse::<NonZeroU64>().ok().map(UserId::from),
+        action: lib::audit::Action::from_name(&action),
+        limit: None,
+    };
+    let entries = lib::audit::query(&filter, &state.pool).await?;
+    let cache = &state.discord_cache_http.cache;
+    let rows = entries
+        .into_iter()
+        .map(|entry| {
+            let target = match entry.target_type.as_str() {
+                "channel" => cache
+                    .channel(ChannelId::new(entry.target_id as u64))
+                    .map(|channel| format!("#{}", channel.name)),
+                "user" => cache
+                    .user(UserId::new(entry.target_id as u64))
+                    .map(|user| format!("@{}", user.name)),
+                _ => None,
+            };
+            AuditRow {
+                time: entry
+                    .action_time
+                    .format("%Y-%m-%d %H:%M:%S UTC")
+                    .to_string(),
+                actor: match entry.actor {
+                    Some(actor) => cache
+                        .user(actor)
+                        .map(|user| format!("@{} ({})", user.name, actor))
+                        .unwrap_or_else(|| actor.to_string()),
+                    None => state.bot_name.clone(),
+                },
+                action: entry.action,
+                target: match target {
+                    Some(name) => format!("{} {} ({})", entry.target_type, name, entry.target_id),
+                    None => format!("{} {}", entry.target_type, entry.target_id),
+                },
+                channel: match entry.channel_id {
+                    Some(channel_id) => cache
+                        .channel(channel_id)
+                        .map(|channel| format!("#{} ({})", channel.name, channel_id))
+                        .unwrap_or_else(|| channel_id.to_string()),
+                    None => String::new(),
+                },
+                before: entry.before.unwrap_or_default(),
+                after: entry.after.unwrap_or_default(),
+            }
+        })
+        .collect();
+    let template = AuditTemplate {
+        channel: &channel,
+        actor: &actor,
+        action: &action,
+        actions: lib::audit::Action::ALL
+            .iter()
+            .map(|candidate| (candidate.as_str(), candidate.as_str() == action))
+            .collect(),
+        rows,
+    };
+    Ok(template.into_response())
+}
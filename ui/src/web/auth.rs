@@ -63,8 +63,45 @@ struct AuthTemplate<'a> {
     auth_id: &'a str,
 }
 
+#[derive(Clone)]
 pub struct AuthenticatedMember(pub MemberId);
 
+// Makes sure that the authenticated member is a bot admin in (at least) one of
+// the configured guilds
+pub async fn require_bot_admin(state: &State, member_id: MemberId) -> Result<(), WebError> {
+    let discord_id = sqlx::query_scalar!(
+        r#"SELECT discord_id FROM "member" WHERE id = $1"#,
+        member_id.0
+    )
+    .fetch_one(&state.pool)
+    .await?
+    .map(|id| UserId::new(id as u64))
+    .ok_or(WebError::Unauthorized(None))?;
+    let user = discord_id
+        .to_user(&state.discord_cache_http)
+        .await
+        .map_err(lib::meetup::Error::from)?;
+    for guild_config in state.guild_configs.iter() {
+        let is_bot_admin = match user
+            .has_role(
+                &state.discord_cache_http,
+                guild_config.guild_id,
+                guild_config.bot_admin_role_id,
+            )
+            .await
+        {
+            Ok(is_bot_admin) => is_bot_admin,
+            // Not a member of this guild
+            Err(err) if lib::discord::api::is_not_found(&err) => false,
+            Err(err) => return Err(lib::meetup::Error::from(err).into()),
+        };
+        if is_bot_admin {
+            return Ok(());
+        }
+    }
+    Err(WebError::Unauthorized(None))
+}
+
 async fn auth_handler_get(
     Path(auth_id): Path<String>,
     state: Extension<Arc<State>>,
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Extension, Path},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use lib::db;
+
+use super::{server::State, WebError};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route("/calendar/club.ics", get(club_calendar_handler))
+        .route("/calendar/member/:token", get(member_calendar_handler))
+        .route(
+            "/calendar/series/:series_id/:token",
+            get(series_calendar_handler),
+        )
+}
+
+fn calendar_response(calendar: String) -> Response {
+    (
+        [(header::CONTENT_TYPE, "text/calendar; charset=utf-8")],
+        calendar,
+    )
+        .into_response()
+}
+
+async fn club_calendar_handler(
+    Extension(state): Extension<Arc<State>>,
+) -> Result<Response, WebError> {
+    let calendar = lib::ical::club_feed(&state.pool).await?;
+    Ok(calendar_response(calendar))
+}
+
+async fn member_calendar_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(token): Path<String>,
+) -> Result<Response, WebError> {
+    let member_id = lib::ical::member_for_calendar_token(&token, &state.pool)
+        .await?
+        .ok_or(WebError::Unauthorized(None))?;
+    let calendar = lib::ical::member_feed(member_id, &state.pool).await?;
+    Ok(calendar_response(calendar))
+}
+
+// Series calendars are only available to the series' hosts and players
+async fn series_calendar_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path((series_id, token)): Path<(i32, String)>,
+) -> Result<Response, WebError> {
+    let series_id = db::EventSeriesId(series_id);
+    let member_id = lib::ical::member_for_calendar_token(&token, &state.pool)
+        .await?
+        .ok_or(WebError::Unauthorized(None))?;
+    if !lib::ical::is_series_member(member_id, series_id, &state.pool).await? {
+        return Err(WebError::Unauthorized(None));
+    }
+    let calendar = lib::ical::series_feed(series_id, &state.pool).await?;
+    Ok(calendar_response(calendar))
+}
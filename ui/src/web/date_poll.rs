@@ -0,0 +1,450 @@
+use std::{collections::HashMap, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{DefaultBodyLimit, Extension, Form, Path},
+    handler::Handler,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use chrono::{offset::TimeZone, NaiveDateTime};
+use chrono_tz::Europe;
+use lib::{
+    date_poll::{self, Answer, DatePoll, DatePollId, DatePollSlotId},
+    db,
+};
+
+use super::{
+    auth::AuthenticatedMember,
+    schedule_session::{filters, finish_scheduling},
+    server::State,
+    MessageTemplate, WebError,
+};
+
+pub fn create_routes() -> Router {
+    let routes = Router::new()
+        .route(
+            "/poll_dates/:flow_id",
+            get(poll_dates_handler)
+                .post(poll_dates_post_handler.layer(DefaultBodyLimit::max(32768))),
+        )
+        .route(
+            "/date_poll/:poll_id",
+            get(date_poll_handler)
+                .post(date_poll_post_handler.layer(DefaultBodyLimit::max(32768)))
+                .layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+        .route(
+            "/date_poll/:poll_id/schedule",
+            post(date_poll_schedule_handler.layer(DefaultBodyLimit::max(32768)))
+                .layer(axum::middleware::from_fn(super::auth::auth)),
+        );
+    // The following routes are just to be able to take a look at the
+    // templates without using an actual flow or poll
+    #[cfg(feature = "bottest")]
+    let routes = routes
+        .route(
+            "/poll_dates/test",
+            get(|| {
+                let template = PollDatesTemplate {
+                    num_slots: date_poll::MAX_SLOTS,
+                    duration: 240,
+                };
+                futures::future::ready(template.into_response())
+            }),
+        )
+        .route(
+            "/date_poll/test",
+            get(|| {
+                let local_time = chrono::Utc::now()
+                    .with_timezone(&Europe::Zurich)
+                    .format("%a, %d %b %Y %H:%M")
+                    .to_string();
+                let template = DatePollTemplate {
+                    poll_id: 1,
+                    slots: vec![SlotRow {
+                        id: 1,
+                        local_time,
+                        yes: 2,
+                        maybe: 1,
+                        no: 0,
+                        voted: None,
+                        is_winner: true,
+                    }],
+                    num_voters: 3,
+                    is_open: true,
+                    may_schedule: true,
+                };
+                futures::future::ready(template.into_response())
+            }),
+        );
+    routes
+}
+
+#[derive(Template)]
+#[template(path = "date_poll_create.html")]
+struct PollDatesTemplate {
+    num_slots: usize,
+    duration: u16, // In minutes
+}
+
+#[derive(Template)]
+#[template(path = "date_poll.html")]
+struct DatePollTemplate {
+    poll_id: i32,
+    slots: Vec<SlotRow>,
+    num_voters: usize,
+    is_open: bool,
+    // Whether the member may turn a slot into a session
+    may_schedule: bool,
+}
+
+struct SlotRow {
+    id: i32,
+    local_time: String, // In Europe::Zurich timezone
+    yes: usize,
+    maybe: usize,
+    no: usize,
+    // The authenticated member's answer, if any
+    voted: Option<&'static str>,
+    is_winner: bool,
+}
+
+impl SlotRow {
+    fn new(poll: &DatePoll, member_id: db::MemberId) -> Vec<Self> {
+        let winning_slot_id = poll.winning_slot().map(|slot| slot.id);
+        poll.slots
+            .iter()
+            .map(|slot| SlotRow {
+                id: slot.id.0,
+                local_time: slot
+                    .start_time
+                    .with_timezone(&Europe::Zurich)
+                    .format("%a, %d %b %Y %H:%M")
+                    .to_string(),
+                yes: slot.count(Answer::Yes),
+                maybe: slot.count(Answer::Maybe),
+                no: slot.count(Answer::No),
+                voted: slot
+                    .votes
+                    .iter()
+                    .find(|(voter_id, _)| *voter_id == member_id)
+                    .map(|(_, answer)| answer.as_str()),
+                is_winner: Some(slot.id) == winning_slot_id,
+            })
+            .collect()
+    }
+}
+
+async fn poll_dates_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let flow = lib::flow::PollDatesFlow::retrieve(&mut redis_connection, flow_id).await?;
+    if flow.is_none() {
+        let template: MessageTemplate = ("Link expired", "Please request a new link").into();
+        return Ok(template.into_response());
+    }
+    let template = PollDatesTemplate {
+        num_slots: date_poll::MAX_SLOTS,
+        duration: 4 * 60,
+    };
+    Ok(template.into_response())
+}
+
+async fn poll_dates_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let flow = lib::flow::PollDatesFlow::retrieve(&mut redis_connection, flow_id).await?;
+    let flow = match flow {
+        Some(flow) => flow,
+        None => {
+            let template: MessageTemplate = ("Link expired", "Please request a new link").into();
+            return Ok(template.into_response());
+        }
+    };
+    let start_times = match form_slots(&form_data) {
+        Ok(start_times) => start_times,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let duration = match form_data.get("duration") {
+        None => 4 * 60,
+        Some(duration) => match duration.parse::<u16>() {
+            Err(_) => 4 * 60,
+            Ok(duration) => duration.min(12 * 60),
+        },
+    };
+    let created_by = {
+        let mut tx = state.pool.begin().await?;
+        let member_id = db::get_or_create_member_for_discord_id(&mut tx, flow.discord_id).await?;
+        tx.commit().await?;
+        member_id
+    };
+    let poll_id = date_poll::create(
+        flow.event_series_id,
+        created_by,
+        &start_times,
+        chrono::Duration::minutes(duration as i64),
+        &state.pool,
+    )
+    .await?;
+    flow.delete(&mut redis_connection).await?;
+    // Post the summary in the Discord channel
+    if let Err(err) = date_poll::sync_summary(poll_id, &state.pool, &state.discord_cache_http).await
+    {
+        eprintln!(
+            "Encountered an error when trying to post the date poll summary in the channel:\n{:#?}",
+            err
+        );
+    }
+    let template: MessageTemplate = (
+        "Success!",
+        format!(
+            "The date poll has been posted in your channel. You can vote and schedule the winning \
+             slot here: {}/date_poll/{}",
+            lib::urls::BASE_URL,
+            poll_id.0
+        ),
+    )
+        .into();
+    Ok(template.into_response())
+}
+
+// Reads the candidate slots from the submitted form, ignoring empty fields.
+// The times are in Europe::Zurich timezone.
+fn form_slots(
+    form_data: &HashMap<String, String>,
+) -> Result<Vec<chrono::DateTime<chrono::Utc>>, MessageTemplate> {
+    let mut start_times = vec![];
+    for i in 0..date_poll::MAX_SLOTS {
+        let value = match form_data.get(&format!("slot_{}", i)) {
+            Some(value) if !value.trim().is_empty() => value.trim(),
+            _ => continue,
+        };
+        let date_time = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M")
+            .ok()
+            .and_then(|naive_date_time| {
+                Europe::Zurich
+                    .from_local_datetime(&naive_date_time)
+                    .single()
+            });
+        let date_time = match date_time {
+            Some(date_time) => date_time.with_timezone(&chrono::Utc),
+            None => {
+                return Err((
+                    "Invalid data",
+                    "Seems like one of the specified times is invalid",
+                )
+                    .into())
+            }
+        };
+        if date_time < chrono::Utc::now() {
+            return Err((
+                "Invalid data",
+                "Seems like one of the specified times is in the past",
+            )
+                .into());
+        }
+        start_times.push(date_time);
+    }
+    start_times.sort_unstable();
+    start_times.dedup();
+    if start_times.is_empty() {
+        return Err(("Invalid data", "Please specify at least one slot").into());
+    }
+    Ok(start_times)
+}
+
+// Retrieves the poll if the member may vote on it
+async fn poll_for_voter(
+    state: &State,
+    poll_id: i32,
+    member_id: db::MemberId,
+) -> Result<Result<DatePoll, MessageTemplate>, WebError> {
+    let poll = match date_poll::get(DatePollId(poll_id), &state.pool).await? {
+        Some(poll) => poll,
+        None => {
+            return Ok(Err((
+                "Poll not found",
+                "Seems like this date poll does not exist",
+            )
+                .into()))
+        }
+    };
+    if !date_poll::is_eligible_voter(poll.event_series_id, member_id, &state.pool).await? {
+        return Err(WebError::Unauthorized(Some(
+            "Only the players of this adventure can vote on its date poll".into(),
+        )));
+    }
+    Ok(Ok(poll))
+}
+
+async fn date_poll_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(poll_id): Path<i32>,
+) -> Result<Response, WebError> {
+    let poll = match poll_for_voter(&state, poll_id, member_id).await? {
+        Ok(poll) => poll,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let template = DatePollTemplate {
+        poll_id: poll.id.0,
+        slots: SlotRow::new(&poll, member_id),
+        num_voters: poll.num_voters(),
+        is_open: poll.is_open(),
+        may_schedule: date_poll::may_schedule(&poll, member_id, &state.pool).await?,
+    };
+    Ok(template.into_response())
+}
+
+async fn date_poll_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(poll_id): Path<i32>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let poll = match poll_for_voter(&state, poll_id, member_id).await? {
+        Ok(poll) => poll,
+        Err(template) => return Ok(template.into_response()),
+    };
+    if !poll.is_open() {
+        let template: MessageTemplate =
+            ("Poll closed", "The next session has already been scheduled").into();
+        return Ok(template.into_response());
+    }
+    let answers: Vec<_> = poll
+        .slots
+        .iter()
+        .filter_map(|slot| {
+            form_data
+                .get(&format!("slot_{}", slot.id.0))
+                .and_then(|answer| Answer::from_name(answer))
+                .map(|answer| (slot.id, answer))
+        })
+        .collect();
+    date_poll::vote(&poll, member_id, &answers, &state.pool).await?;
+    if let Err(err) = date_poll::sync_summary(poll.id, &state.pool, &state.discord_cache_http).await
+    {
+        eprintln!(
+            "Encountered an error when trying to update the date poll summary:\n{:#?}",
+            err
+        );
+    }
+    Ok(Redirect::to(&format!("/date_poll/{}", poll.id.0)).into_response())
+}
+
+// Turns one of the slots into a new session of the series
+async fn date_poll_schedule_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(poll_id): Path<i32>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let poll = match poll_for_voter(&state, poll_id, member_id).await? {
+        Ok(poll) => poll,
+        Err(template) => return Ok(template.into_response()),
+    };
+    if !date_poll::may_schedule(&poll, member_id, &state.pool).await? {
+        return Err(WebError::Unauthorized(Some(
+            "Only the hosts of this adventure can schedule a session".into(),
+        )));
+    }
+    if !poll.is_open() {
+        let template: MessageTemplate =
+            ("Poll closed", "The next session has already been scheduled").into();
+        return Ok(template.into_response());
+    }
+    let slot = form_data
+        .get("slot_id")
+        .and_then(|slot_id| slot_id.parse::<i32>().ok())
+        .and_then(|slot_id| poll.slot(DatePollSlotId(slot_id)));
+    let slot = match slot {
+        Some(slot) if slot.start_time > chrono::Utc::now() => slot,
+        _ => {
+            let template: MessageTemplate = (
+                "Invalid data",
+                "Seems like the selected slot does not exist or is in the past",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
+    let is_open_game = form_data
+        .get("open_game")
+        .map(|value| value == "yes")
+        .unwrap_or(false);
+    // Only one submission may schedule a session, even if several hosts click
+    // at the same time
+    if !date_poll::claim(poll.id, &state.pool).await? {
+        let template: MessageTemplate = (
+            "Poll closed",
+            "The next session has already been scheduled or is being scheduled right now",
+        )
+            .into();
+        return Ok(template.into_response());
+    }
+    let date_times = [slot.start_time];
+    let sessions = match schedule_slot(&state, &poll, &date_times, is_open_game).await {
+        Ok(sessions) => sessions,
+        Err(err) => {
+            if let Err(release_err) = date_poll::release(poll.id, &state.pool).await {
+                eprintln!(
+                    "Could not release date poll {} after a failed scheduling:\n{:#?}",
+                    poll.id.0, release_err
+                );
+            }
+            return Err(err);
+        }
+    };
+    date_poll::close(poll.id, sessions[0].event_id, &state.pool).await?;
+    if let Err(err) = date_poll::sync_summary(poll.id, &state.pool, &state.discord_cache_http).await
+    {
+        eprintln!(
+            "Encountered an error when trying to update the date poll summary:\n{:#?}",
+            err
+        );
+    }
+    finish_scheduling(
+        &state,
+        poll.event_series_id,
+        &sessions,
+        &date_times,
+        is_open_game,
+        true,
+    )
+    .await
+}
+
+async fn schedule_slot(
+    state: &State,
+    poll: &DatePoll,
+    date_times: &[chrono::DateTime<chrono::Utc>],
+    is_open_game: bool,
+) -> Result<Vec<lib::flow::ScheduledSession>, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let flow =
+        lib::flow::ScheduleSessionFlow::new(&mut redis_connection, poll.event_series_id).await?;
+    let sessions = flow
+        .schedule_recurring(
+            state.pool.clone(),
+            redis_connection,
+            &state.meetup_connection,
+            date_times,
+            lib::flow::SessionOptions {
+                duration: poll.duration,
+                is_open_event: is_open_game,
+                transfer_rsvps: true,
+            },
+        )
+        .await
+        // A single session is either scheduled or not, so there is nothing
+        // to keep if it failed
+        .map_err(|partially_scheduled| partially_scheduled.error)?;
+    Ok(sessions)
+}
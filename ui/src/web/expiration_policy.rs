@@ -0,0 +1,118 @@
+use std::{collections::HashMap, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{DefaultBodyLimit, Extension, Form, Path},
+    handler::Handler,
+    response::{IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Router,
+};
+use lib::expiration_policy::{self, ExpirationPolicy};
+
+use super::{
+    auth::{require_bot_admin, AuthenticatedMember},
+    server::State,
+    MessageTemplate, WebError,
+};
+
+pub fn create_routes() -> Router {
+    Router::new()
+        .route(
+            "/expiration_policies",
+            get(expiration_policies_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+        .route(
+            "/expiration_policies/:event_series_type",
+            post(expiration_policy_post_handler.layer(DefaultBodyLimit::max(32768)))
+                .layer(axum::middleware::from_fn(super::auth::auth)),
+        )
+}
+
+#[derive(Template)]
+#[template(path = "expiration_policies.html")]
+struct ExpirationPoliciesTemplate {
+    policies: Vec<ExpirationPolicy>,
+}
+
+// Only bot admins may view and change the channel expiration policies
+async fn expiration_policies_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    require_bot_admin(&state, member_id).await?;
+    let policies = expiration_policy::list(&state.pool).await?;
+    Ok(ExpirationPoliciesTemplate { policies }.into_response())
+}
+
+// Reads a number of hours (or reminders) from the submitted form. Empty
+// fields are None.
+fn form_number(form_data: &HashMap<String, String>, name: &str) -> Result<Option<i32>, ()> {
+    match form_data.get(name).map(|value| value.trim()) {
+        None | Some("") => Ok(None),
+        Some(value) => match value.parse() {
+            Ok(number) if number >= 0 => Ok(Some(number)),
+            _ => Err(()),
+        },
+    }
+}
+
+fn form_policy(
+    event_series_type: String,
+    form_data: &HashMap<String, String>,
+) -> Result<ExpirationPolicy, ()> {
+    let required = |name: &str| form_number(form_data, name)?.ok_or(());
+    let policy = ExpirationPolicy {
+        expiration_delay_hours: required("expiration_delay_hours")?,
+        first_reminder_delay_hours: required("first_reminder_delay_hours")?,
+        reminder_interval_hours: required("reminder_interval_hours")?,
+        max_reminders: form_number(form_data, "max_reminders")?,
+        deletion_grace_hours: required("deletion_grace_hours")?,
+        // Unchecked checkboxes are not submitted
+        archive: form_data.contains_key("archive"),
+        event_series_type,
+    };
+    if policy.reminder_interval_hours == 0 {
+        return Err(());
+    }
+    Ok(policy)
+}
+
+async fn expiration_policy_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+    Path(event_series_type): Path<String>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    require_bot_admin(&state, member_id).await?;
+    let is_known_type = expiration_policy::list(&state.pool)
+        .await?
+        .iter()
+        .any(|policy| policy.event_series_type == event_series_type);
+    if !is_known_type {
+        let template: MessageTemplate = (
+            "Unknown event series type",
+            format!("There is no event series type \"{}\"", event_series_type),
+        )
+            .into();
+        return Ok(template.into_response());
+    }
+    let policy = match form_policy(event_series_type, &form_data) {
+        Ok(policy) => policy,
+        Err(()) => {
+            let template: MessageTemplate = (
+                "Invalid policy",
+                "All durations must be whole numbers of hours and the reminder interval must be \
+                 at least one hour. Leave the maximum number of reminders empty for no limit.",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+    };
+    expiration_policy::save(&policy, &state.pool).await?;
+    println!(
+        "Member {} changed the channel expiration policy to {:?}",
+        member_id.0, policy
+    );
+    Ok(Redirect::to("/expiration_policies").into_response())
+}
@@ -151,7 +151,6 @@ async fn authorize_redirect_handler(
     // }
     // Exchange the code with a token.
     let code = AuthorizationCode::new(query.code);
-    let async_meetup_client = state.async_meetup_client.clone();
     let token_res = state
         .oauth2_consumer
         .authorization_client
@@ -162,7 +161,7 @@ async fn authorize_redirect_handler(
     let new_async_meetup_client =
         lib::meetup::newapi::AsyncClient::new(token_res.access_token().secret());
     let user_memberships =
-        lib::meetup::util::get_group_memberships(new_async_meetup_client.clone()).await?;
+        lib::meetup::util::get_group_memberships(new_async_meetup_client).await?;
     let is_organizer = user_memberships.iter().all(|membership| {
         use lib::meetup::newapi::group_membership_query::*;
         let is_organizer = match &membership.membership_metadata {
@@ -195,8 +194,10 @@ async fn authorize_redirect_handler(
         .execute(&mut *tx)
         .await?;
     tx.commit().await?;
-    // Replace the meetup client
-    *async_meetup_client.lock().await = Some(Arc::new(new_async_meetup_client));
+    // Replace the organizer token of the Meetup connection
+    state
+        .meetup_connection
+        .set_access_token(token_res.access_token().secret());
     Ok(("Thanks for logging in :)", "").into())
 }
 
@@ -348,6 +349,16 @@ async fn link_redirect_handler(
             action: LinkingAction::Linked | LinkingAction::NewMember | LinkingAction::MergedMember,
             member_id,
         } => {
+            lib::audit::record(
+                lib::audit::Entry::new(
+                    lib::audit::Action::MeetupLinked,
+                    lib::audit::Target::User(discord_id),
+                )
+                .actor(discord_id)
+                .after(serde_json::json!({ "meetup_id": meetup_user.id.0 })),
+                &state.pool,
+            )
+            .await;
             // If the "rsvp" scope is part of the token result, store the tokens as well
             if with_rsvp_scope.0 {
                 if let Some(refresh_token) = token_res.refresh_token() {
@@ -0,0 +1,332 @@
+use std::{collections::HashMap, sync::Arc};
+
+use askama::Template;
+use axum::{
+    extract::{DefaultBodyLimit, Extension, Form, Path},
+    handler::Handler,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use chrono::{Datelike, Timelike};
+use chrono_tz::Europe;
+use lib::db;
+
+use super::{
+    schedule_session::{filters, form_date_time_and_duration},
+    server::State,
+    MessageTemplate, WebError,
+};
+
+pub fn create_routes() -> Router {
+    let routes = Router::new()
+        .route(
+            "/reschedule_session/:flow_id",
+            get(reschedule_session_handler)
+                .post(reschedule_session_post_handler.layer(DefaultBodyLimit::max(32768))),
+        )
+        .route(
+            "/cancel_session/:flow_id",
+            get(cancel_session_handler)
+                .post(cancel_session_post_handler.layer(DefaultBodyLimit::max(32768))),
+        );
+    // The following routes are just to be able to take a look at the
+    // templates without using an actual flow
+    #[cfg(feature = "bottest")]
+    let routes = routes
+        .route(
+            "/reschedule_session/test",
+            get(|| {
+                let local_time = chrono::Utc::now().with_timezone(&Europe::Zurich);
+                let template = RescheduleSessionTemplate {
+                    sessions: vec![SessionOption {
+                        event_id: 1,
+                        title: "Test event".into(),
+                        local_time: local_time.format("%a, %d %b %Y %H:%M").to_string(),
+                    }],
+                    day: local_time.day() as u8,
+                    month: local_time.month() as u8,
+                    year: local_time.year() as u16,
+                    hour: local_time.hour() as u8,
+                    minute: local_time.minute() as u8,
+                    selectable_years: &[local_time.year() as u16, local_time.year() as u16 + 1],
+                    duration: 150,
+                };
+                futures::future::ready(template.into_response())
+            }),
+        )
+        .route(
+            "/cancel_session/test",
+            get(|| {
+                let local_time = chrono::Utc::now().with_timezone(&Europe::Zurich);
+                let template = CancelSessionTemplate {
+                    sessions: vec![SessionOption {
+                        event_id: 1,
+                        title: "Test event".into(),
+                        local_time: local_time.format("%a, %d %b %Y %H:%M").to_string(),
+                    }],
+                };
+                futures::future::ready(template.into_response())
+            }),
+        );
+    routes
+}
+
+struct SessionOption {
+    event_id: i32,
+    title: String,
+    local_time: String, // In Europe::Zurich timezone
+}
+
+impl From<&db::Event> for SessionOption {
+    fn from(event: &db::Event) -> Self {
+        SessionOption {
+            event_id: event.id.0,
+            title: event.title.clone(),
+            local_time: event
+                .time
+                .with_timezone(&Europe::Zurich)
+                .format("%a, %d %b %Y %H:%M")
+                .to_string(),
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "reschedule_session.html")]
+struct RescheduleSessionTemplate<'a> {
+    sessions: Vec<SessionOption>,
+    day: u8, // In Europe::Zurich timezone
+    month: u8,
+    year: u16,
+    hour: u8,
+    minute: u8,
+    selectable_years: &'a [u16],
+    duration: u16, // In minutes
+}
+
+#[derive(Template)]
+#[template(path = "cancel_session.html")]
+struct CancelSessionTemplate {
+    sessions: Vec<SessionOption>,
+}
+
+// Retrieves the flow and the upcoming sessions of its series or returns the
+// message to show instead
+async fn flow_and_sessions(
+    state: &State,
+    redis_connection: &mut redis::aio::Connection,
+    flow_id: u64,
+) -> Result<Result<(lib::flow::ManageSessionFlow, Vec<db::Event>), MessageTemplate>, WebError> {
+    let flow = lib::flow::ManageSessionFlow::retrieve(redis_connection, flow_id).await?;
+    let flow = match flow {
+        Some(flow) => flow,
+        None => return Ok(Err(("Link expired", "Please request a new link").into())),
+    };
+    let sessions = db::get_upcoming_events_for_series(&state.pool, flow.event_series_id).await?;
+    if sessions.is_empty() {
+        return Ok(Err((
+            "No upcoming sessions",
+            "There are no upcoming sessions to change",
+        )
+            .into()));
+    }
+    Ok(Ok((flow, sessions)))
+}
+
+// Reads the selected session from the submitted form
+fn form_event_id(
+    form_data: &HashMap<String, String>,
+    sessions: &[db::Event],
+) -> Result<db::EventId, MessageTemplate> {
+    let event_id = form_data
+        .get("event_id")
+        .and_then(|event_id| event_id.parse::<i32>().ok())
+        .map(db::EventId);
+    match event_id {
+        Some(event_id) if sessions.iter().any(|session| session.id == event_id) => Ok(event_id),
+        _ => Err((
+            "Invalid data",
+            "Seems like the selected session does not exist or is already over",
+        )
+            .into()),
+    }
+}
+
+async fn reschedule_session_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let sessions = match flow_and_sessions(&state, &mut redis_connection, flow_id).await? {
+        Ok((_flow, sessions)) => sessions,
+        Err(template) => return Ok(template.into_response()),
+    };
+    // Propose the time of the next session
+    let local_time = sessions[0].time.with_timezone(&Europe::Zurich);
+    let template = RescheduleSessionTemplate {
+        sessions: sessions.iter().map(Into::into).collect(),
+        day: local_time.day() as u8,
+        month: local_time.month() as u8,
+        year: local_time.year() as u16,
+        hour: local_time.hour() as u8,
+        minute: local_time.minute() as u8,
+        selectable_years: &[local_time.year() as u16, local_time.year() as u16 + 1],
+        duration: 4 * 60,
+    };
+    Ok(template.into_response())
+}
+
+async fn reschedule_session_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let (flow, sessions) = match flow_and_sessions(&state, &mut redis_connection, flow_id).await? {
+        Ok(flow_and_sessions) => flow_and_sessions,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let event_id = match form_event_id(&form_data, &sessions) {
+        Ok(event_id) => event_id,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let (date_time, duration) = match form_date_time_and_duration(&form_data) {
+        Ok(date_time_and_duration) => date_time_and_duration,
+        Err(template) => return Ok(template.into_response()),
+    };
+    if date_time < chrono::Utc::now() {
+        let template: MessageTemplate = (
+            "Invalid data",
+            "Seems like the specified time is in the past",
+        )
+            .into();
+        return Ok(template.into_response());
+    }
+    let session = match flow
+        .reschedule(
+            &state.pool,
+            &mut redis_connection,
+            &state.meetup_connection,
+            event_id,
+            date_time,
+            chrono::Duration::minutes(duration as i64),
+        )
+        .await
+    {
+        Ok(session) => session,
+        Err(lib::meetup::Error::NewAPIError(err)) => {
+            eprintln!("Could not reschedule the session on Meetup:\n{:#?}", err);
+            let template: MessageTemplate = (
+                "Could not reschedule the session",
+                "The session could not be updated on Meetup. Please try again later or \
+                 contact an organiser.",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // Announce the new time in the Discord channel
+    if let Err(err) = lib::discord::rsvp::announce_event_rescheduled(
+        session.event_id,
+        session.old_start_time,
+        &state.pool,
+        &state.discord_cache_http,
+    )
+    .await
+    {
+        eprintln!(
+            "Encountered an error when trying to announce the rescheduled session in the \
+             channel:\n{:#?}",
+            err
+        );
+    }
+    let template: MessageTemplate = (
+        "Success!",
+        match session.meetup_url {
+            Some(meetup_url) => format!(
+                "\"{}\" has been rescheduled. Please check that your Meetup event looks \
+                 correct: {}",
+                session.title, meetup_url
+            ),
+            None => format!("\"{}\" has been rescheduled.", session.title),
+        },
+    )
+        .into();
+    Ok(template.into_response())
+}
+
+async fn cancel_session_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let sessions = match flow_and_sessions(&state, &mut redis_connection, flow_id).await? {
+        Ok((_flow, sessions)) => sessions,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let template = CancelSessionTemplate {
+        sessions: sessions.iter().map(Into::into).collect(),
+    };
+    Ok(template.into_response())
+}
+
+async fn cancel_session_post_handler(
+    Extension(state): Extension<Arc<State>>,
+    Path(flow_id): Path<u64>,
+    Form(form_data): Form<HashMap<String, String>>,
+) -> Result<Response, WebError> {
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let (flow, sessions) = match flow_and_sessions(&state, &mut redis_connection, flow_id).await? {
+        Ok(flow_and_sessions) => flow_and_sessions,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let event_id = match form_event_id(&form_data, &sessions) {
+        Ok(event_id) => event_id,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let session = match flow
+        .cancel(
+            &state.pool,
+            &mut redis_connection,
+            &state.meetup_connection,
+            event_id,
+        )
+        .await
+    {
+        Ok(session) => session,
+        Err(lib::meetup::Error::NewAPIError(err)) => {
+            eprintln!("Could not cancel the session on Meetup:\n{:#?}", err);
+            let template: MessageTemplate = (
+                "Could not cancel the session",
+                "The session could not be cancelled on Meetup. Please try again later or \
+                 contact an organiser.",
+            )
+                .into();
+            return Ok(template.into_response());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    // Announce the cancellation in the Discord channel. Should this fail, the
+    // next Discord sync will announce it instead.
+    if let Err(err) = lib::discord::rsvp::announce_event_cancellation(
+        session.event_id,
+        &state.pool,
+        &state.discord_cache_http,
+    )
+    .await
+    {
+        eprintln!(
+            "Encountered an error when trying to announce the cancelled session in the \
+             channel:\n{:#?}",
+            err
+        );
+    }
+    let template: MessageTemplate = (
+        "Success!",
+        format!("\"{}\" has been cancelled.", session.title),
+    )
+        .into();
+    Ok(template.into_response())
+}
@@ -1,9 +1,16 @@
 pub mod api;
+pub mod archive;
+pub mod audit;
 pub mod auth;
+pub mod calendar;
+pub mod date_poll;
+pub mod expiration_policy;
 pub mod linking;
+pub mod manage_session;
 pub mod schedule_session;
 pub mod server;
 pub mod stripe_webhook_endpoint;
+pub mod sync_discord;
 
 use std::{backtrace::Backtrace, borrow::Cow};
 
@@ -10,7 +10,10 @@ use axum::{
 };
 use chrono::{offset::TimeZone, Datelike, NaiveDateTime, Timelike};
 use chrono_tz::Europe;
-use lib::{db, DefaultStr};
+use lib::{
+    db,
+    flow::recurrence::{self, Frequency, Recurrence, RecurrenceEnd},
+};
 use serenity::all::Mentionable;
 
 use super::{server::State, MessageTemplate, WebError};
@@ -37,6 +40,7 @@ pub fn create_routes() -> Router {
                     minute: local_time.minute() as u8,
                     selectable_years: &[local_time.year() as u16, local_time.year() as u16 + 1],
                     duration: 150,
+                    max_sessions: recurrence::MAX_SESSIONS,
                     title: "Test event",
                     link: Some("https://meetup.com/"),
                 };
@@ -47,8 +51,13 @@ pub fn create_routes() -> Router {
             "/schedule_session/test/success",
             get(|| {
                 let template = ScheduleSessionSuccessTemplate {
-                    title: "Test event",
-                    link: "https://meetup.com/",
+                    sessions: vec![ScheduledSessionSummary {
+                        title: "Test event",
+                        link: Some("https://meetup.com/"),
+                        meetup_error: None,
+                    }],
+                    unscheduled_sessions: vec![],
+                    unpublished_sessions: false,
                     transferred_all_rsvps: Some(true),
                     closed_rsvps: true,
                 };
@@ -68,19 +77,43 @@ struct ScheduleSessionTemplate<'a> {
     minute: u8,
     selectable_years: &'a [u16],
     duration: u16, // In minutes
+    max_sessions: usize,
     title: &'a str,
     link: Option<&'a str>,
 }
 
+#[derive(Template)]
+#[template(path = "schedule_session_preview.html")]
+struct ScheduleSessionPreviewTemplate<'a> {
+    sessions: Vec<PlannedSessionSummary>,
+    // The submitted form, to be submitted again once confirmed
+    form_fields: Vec<(&'a str, &'a str)>,
+    flow_id: u64,
+}
+
+struct PlannedSessionSummary {
+    local_time: String, // In Europe::Zurich timezone
+    skipped_holiday: Option<&'static str>,
+}
+
 #[derive(Template)]
 #[template(path = "schedule_session_success.html")]
 struct ScheduleSessionSuccessTemplate<'a> {
-    title: &'a str,
-    link: &'a str,
+    sessions: Vec<ScheduledSessionSummary<'a>>,
+    // Sessions which could not be scheduled, in Europe::Zurich timezone
+    unscheduled_sessions: Vec<String>,
+    // Whether some open sessions could not be published on Meetup
+    unpublished_sessions: bool,
     transferred_all_rsvps: Option<bool>,
     closed_rsvps: bool,
 }
 
+struct ScheduledSessionSummary<'a> {
+    title: &'a str,
+    link: Option<&'a str>, // Only set if the session was published on Meetup
+    meetup_error: Option<String>,
+}
+
 pub mod filters {
     pub fn isequal<T: num_traits::PrimInt>(num: &T, val: &T) -> Result<bool, askama::Error> {
         Ok(num == val)
@@ -146,6 +179,7 @@ async fn schedule_session_handler(
                     next_event_local_datetime.year() as u16 + 1,
                 ],
                 duration: 4 * 60,
+                max_sessions: recurrence::MAX_SESSIONS,
                 title: &event.title,
                 link: event
                     .meetup_event
@@ -180,6 +214,334 @@ async fn schedule_session_post_handler(
         .get("open_game")
         .map(|value| value == "yes")
         .unwrap_or(false);
+    let (date_time, duration) = match form_date_time_and_duration(&form_data) {
+        Ok(date_time_and_duration) => date_time_and_duration,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let recurrence = match form_recurrence(&form_data) {
+        Ok(recurrence) => recurrence,
+        Err(template) => return Ok(template.into_response()),
+    };
+    let date_times = match recurrence {
+        None => vec![date_time],
+        Some(recurrence) => {
+            let planned_sessions = recurrence.plan(date_time.with_timezone(&Europe::Zurich));
+            // Show the planned sessions first and only schedule them once the
+            // host confirmed them
+            if form_data.get("confirm").map(String::as_str) != Some("yes") {
+                let mut form_fields: Vec<_> = form_data
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect();
+                form_fields.sort();
+                let template = ScheduleSessionPreviewTemplate {
+                    sessions: planned_sessions
+                        .iter()
+                        .map(|session| PlannedSessionSummary {
+                            local_time: session.date_time.format("%a, %d %b %Y %H:%M").to_string(),
+                            skipped_holiday: session.skipped_holiday,
+                        })
+                        .collect(),
+                    form_fields,
+                    flow_id,
+                };
+                return Ok(template.into_response());
+            }
+            planned_sessions
+                .into_iter()
+                .filter(|session| session.skipped_holiday.is_none())
+                .map(|session| session.date_time.with_timezone(&chrono::Utc))
+                .collect()
+        }
+    };
+    if date_times.is_empty() {
+        let template: MessageTemplate = (
+            "Nothing to schedule",
+            "Seems like all of the sessions fall on public holidays",
+        )
+            .into();
+        return Ok(template.into_response());
+    }
+    let event_series_id = flow.event_series_id;
+    let sessions = match flow
+        .schedule_recurring(
+            state.pool.clone(),
+            redis_connection,
+            &state.meetup_connection,
+            &date_times,
+            lib::flow::SessionOptions {
+                duration: chrono::Duration::minutes(duration as i64),
+                is_open_event: is_open_game,
+                transfer_rsvps,
+            },
+        )
+        .await
+    {
+        Ok(sessions) => sessions,
+        Err(partially_scheduled) if partially_scheduled.sessions.is_empty() => {
+            return Err(partially_scheduled.error.into())
+        }
+        Err(partially_scheduled) => {
+            // Announce the sessions which were created and tell the host
+            // which ones are missing
+            eprintln!(
+                "Only {} of {} sessions of event series {} could be scheduled:\n{:#?}",
+                partially_scheduled.sessions.len(),
+                date_times.len(),
+                event_series_id.0,
+                partially_scheduled.error
+            );
+            partially_scheduled.sessions
+        }
+    };
+    finish_scheduling(
+        &state,
+        event_series_id,
+        &sessions,
+        &date_times,
+        is_open_game,
+        transfer_rsvps,
+    )
+    .await
+}
+
+// Closes the RSVPs of freshly scheduled sessions, announces them and shows
+// the result to the host. The sessions correspond to the first date times,
+// any remaining date times are shown as not scheduled.
+pub(super) async fn finish_scheduling(
+    state: &State,
+    event_series_id: db::EventSeriesId,
+    sessions: &[lib::flow::ScheduledSession],
+    date_times: &[chrono::DateTime<chrono::Utc>],
+    is_open_game: bool,
+    transfer_rsvps: bool,
+) -> Result<Response, WebError> {
+    let transferred_all_rsvps = if transfer_rsvps { Some(true) } else { None };
+    // Close the Meetup RSVPs, ignoring errors
+    let mut rsvps_are_closed = true;
+    for new_event in sessions
+        .iter()
+        .filter_map(|session| session.meetup_event.as_ref())
+    {
+        if let Err(err) = state
+            .meetup_connection
+            .close_rsvps(new_event.id.0.clone())
+            .await
+        {
+            eprintln!(
+                "RSVPs for event {} could not be closed:\n{:#?}",
+                &new_event.id, err
+            );
+            rsvps_are_closed = false;
+        }
+    }
+    // Remove any possibly existing channel snoozes
+    {
+        let mut tx = state.pool.begin().await?;
+        if let Ok(Some(channel_id)) = lib::get_series_text_channel(event_series_id, &mut tx).await {
+            sqlx::query!(
+                r#"UPDATE event_series_text_channel SET snooze_until = NULL WHERE discord_id = $1"#,
+                channel_id.get() as i64
+            )
+            .execute(&mut *tx)
+            .await
+            .ok();
+            tx.commit().await.ok();
+        }
+    }
+    // Announce the new sessions in the Discord channel
+    let session_link = sessions
+        .iter()
+        .zip(date_times)
+        .map(|(session, date_time)| match &session.meetup_event {
+            Some(new_event) => new_event.short_url.clone(),
+            None => format!("<t:{}:F>", date_time.timestamp()),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+    let channel_roles =
+        lib::get_event_series_roles(event_series_id, &mut state.pool.begin().await?).await?;
+    let message = if let Some(channel_roles) = channel_roles {
+        format!(
+            "Your adventure continues here, heroes of {channel_role_mention}: {link}. Slay the \
+             dragon, save the prince, get the treasure, or whatever shenanigans you like to get \
+             into.",
+            link = &session_link,
+            channel_role_mention = channel_roles.user.mention()
+        )
+    } else {
+        format!(
+            "Your adventure continues @here: {link}. Slay the dragon, save the prince, get the \
+             treasure, or whatever shenanigans you like to get into.",
+            link = &session_link
+        )
+    };
+    if let Err(err) = lib::discord::util::say_in_event_channel(
+        sessions[0].event_id,
+        &message,
+        &state.pool,
+        &state.discord_cache_http,
+    )
+    .await
+    {
+        eprintln!(
+            "Encountered an error when trying to announce the new session in the channel:\n{:#?}",
+            err
+        );
+    }
+    for session in sessions {
+        if let Err(err) = lib::discord::rsvp::sync_event_announcement(
+            session.event_id,
+            &state.pool,
+            &state.discord_cache_http,
+        )
+        .await
+        {
+            eprintln!(
+                "Encountered an error when trying to post the RSVP announcement of the new \
+                 session:\n{:#?}",
+                err
+            );
+        }
+    }
+    // If this is an open game, ask the organisers to announce the sessions
+    if is_open_game {
+        let guild_config = state
+            .guild_configs
+            .for_event_series(event_series_id, &state.pool)
+            .await?;
+        for session in sessions {
+            let message = match &session.meetup_event {
+                Some(new_event) => format!(
+                    "{organiser_mention}, a new session has been scheduled:\n{link}.\nPlease \
+                     announce this session for new players to join. Don't forget to **open \
+                     RSVPs** when you do that.",
+                    organiser_mention = guild_config.organiser_role_id.mention(),
+                    link = &new_event.event_url,
+                ),
+                None => format!(
+                    "{organiser_mention}, a new open session \"{title}\" has been scheduled but \
+                     could not be published on Meetup. Please publish it manually.",
+                    organiser_mention = guild_config.organiser_role_id.mention(),
+                    title = &session.title,
+                ),
+            };
+            if let Err(err) = lib::discord::util::say_in_bot_alerts_channel(
+                &message,
+                &state.discord_cache_http,
+                guild_config,
+            )
+            .await
+            {
+                eprintln!(
+                    "Encountered an error when trying to announce a new session in the bot \
+                     alerts channel:\n{:#?}",
+                    err
+                );
+            }
+        }
+    }
+    let template = ScheduleSessionSuccessTemplate {
+        sessions: sessions
+            .iter()
+            .map(|session| ScheduledSessionSummary {
+                title: &session.title,
+                link: session
+                    .meetup_event
+                    .as_ref()
+                    .map(|new_event| new_event.event_url.as_str()),
+                meetup_error: session.meetup_error.as_ref().map(ToString::to_string),
+            })
+            .collect(),
+        unscheduled_sessions: date_times[sessions.len()..]
+            .iter()
+            .map(|date_time| {
+                date_time
+                    .with_timezone(&Europe::Zurich)
+                    .format("%a, %d %b %Y %H:%M")
+                    .to_string()
+            })
+            .collect(),
+        unpublished_sessions: sessions
+            .iter()
+            .any(|session| session.meetup_error.is_some()),
+        transferred_all_rsvps,
+        closed_rsvps: rsvps_are_closed,
+    };
+    Ok(template.into_response())
+}
+
+// Reads the optional recurrence of the sessions from the submitted form
+fn form_recurrence(
+    form_data: &HashMap<String, String>,
+) -> Result<Option<Recurrence>, MessageTemplate> {
+    let frequency = match form_data.get("recurrence").map(String::as_str) {
+        None | Some("none") => return Ok(None),
+        Some(name) => match Frequency::from_name(name) {
+            Some(frequency) => frequency,
+            None => {
+                return Err((
+                    "Invalid data",
+                    "Seems like the specified recurrence is invalid",
+                )
+                    .into())
+            }
+        },
+    };
+    let end = match form_data.get("recurrence_end").map(String::as_str) {
+        Some("until") => {
+            let until = match (
+                form_data.get("until_year").map(|year| year.parse::<i32>()),
+                form_data
+                    .get("until_month")
+                    .map(|month| month.parse::<u32>()),
+                form_data.get("until_day").map(|day| day.parse::<u32>()),
+            ) {
+                (Some(Ok(year)), Some(Ok(month)), Some(Ok(day))) => {
+                    chrono::NaiveDate::from_ymd_opt(year, month, day)
+                }
+                _ => None,
+            };
+            match until {
+                Some(until) => RecurrenceEnd::Until(until),
+                None => {
+                    return Err((
+                        "Invalid data",
+                        "Seems like the specified end date is invalid",
+                    )
+                        .into())
+                }
+            }
+        }
+        _ => match form_data.get("count").map(|count| count.parse::<u32>()) {
+            Some(Ok(count)) if count >= 1 => {
+                RecurrenceEnd::Count(count.min(recurrence::MAX_SESSIONS as u32))
+            }
+            _ => {
+                return Err((
+                    "Invalid data",
+                    "Seems like the specified number of sessions is invalid",
+                )
+                    .into())
+            }
+        },
+    };
+    let skip_holidays = form_data
+        .get("skip_holidays")
+        .map(|value| value == "yes")
+        .unwrap_or(false);
+    Ok(Some(Recurrence {
+        frequency,
+        end,
+        skip_holidays,
+    }))
+}
+
+// Reads the date, time and duration of a session from the submitted form.
+// The date and time are in Europe::Zurich timezone.
+pub(super) fn form_date_time_and_duration(
+    form_data: &HashMap<String, String>,
+) -> Result<(chrono::DateTime<chrono::Utc>, u16), MessageTemplate> {
     let (year, month, day, hour, minute) = match (
         form_data.get("year"),
         form_data.get("month"),
@@ -196,7 +558,7 @@ async fn schedule_session_post_handler(
                 "Seems like the submitted data is incomplete",
             )
                 .into();
-            return Ok(template.into_response());
+            return Err(template);
         }
     };
     let duration = match form_data.get("duration") {
@@ -226,20 +588,20 @@ async fn schedule_session_post_handler(
                                     "Seems like the specified time is ambiguous or non-existent",
                                 )
                                     .into();
-                                return Ok(template.into_response());
+                                return Err(template);
                             }
                         }
                     }
                     _ => {
                         let template: MessageTemplate =
                             ("Invalid data", "Seems like the specified time is invalid").into();
-                        return Ok(template.into_response());
+                        return Err(template);
                     }
                 },
                 _ => {
                     let template: MessageTemplate =
                         ("Invalid data", "Seems like the specified date is invalid").into();
-                    return Ok(template.into_response());
+                    return Err(template);
                 }
             }
         }
@@ -249,174 +611,9 @@ async fn schedule_session_post_handler(
                 "Seems like the submitted data has an invalid format",
             )
                 .into();
-            return Ok(template.into_response());
+            return Err(template);
         }
     };
     // Convert time to UTC
-    let date_time = date_time.with_timezone(&chrono::Utc);
-    let meetup_client = match *(state.async_meetup_client).lock().await {
-        Some(ref meetup_client) => meetup_client.clone(),
-        None => {
-            let template: MessageTemplate =
-                ("Meetup API unavailable", "Please try again later").into();
-            return Ok(template.into_response());
-        }
-    };
-    // We go from the latest event to the oldest event until we find one
-    // that has not been deleted to use as a template
-    let event_series_id = flow.event_series_id.clone();
-    let events = db::get_events_for_series(&state.pool, event_series_id).await?;
-    for event in events {
-        let meetup_event = if let Some(meetup_event) = event.meetup_event {
-            meetup_event
-        } else {
-            continue;
-        };
-        let new_event_hook = Box::new(|mut new_event: lib::meetup::newapi::NewEvent| {
-            new_event.duration = Some(chrono::Duration::minutes(duration as i64).into());
-            new_event.publish_status =
-                Some(lib::meetup::newapi::create_event_mutation::PublishStatus::PUBLISHED);
-            let result = lib::flow::ScheduleSessionFlow::new_event_hook(
-                new_event,
-                date_time,
-                &meetup_event.meetup_id,
-                is_open_game,
-            );
-            if let Ok(event_input) = &result {
-                println!(
-                    "Trying to create a Meetup event with the following details:\n{:#?}",
-                    event_input
-                );
-            }
-            result
-        }) as _;
-        let new_event = match lib::meetup::util::clone_event(
-            &meetup_event.urlname,
-            &meetup_event.meetup_id,
-            &meetup_client,
-            Some(new_event_hook),
-        )
-        .await
-        {
-            Err(lib::meetup::Error::NewAPIError(lib::meetup::newapi::Error::ResourceNotFound)) => {
-                // Event was deleted, try the next one
-                continue;
-            }
-            Err(err) => return Err(err.into()),
-            Ok(new_event) => new_event,
-        };
-        // Delete the flow, ignoring errors
-        if let Err(err) = flow.delete(&mut redis_connection).await {
-            eprintln!(
-                "Encountered an error when trying to delete a schedule session flow:\n{:#?}",
-                err
-            );
-        }
-        let transferred_all_rsvps = if transfer_rsvps {
-            // // Try to transfer the RSVPs to the new event
-            // if let Err(_) = lib::meetup::util::clone_rsvps(
-            //     &event.urlname,
-            //     &event.id,
-            //     &new_event.id,
-            //     redis_connection,
-            //     &meetup_client,
-            //     oauth2_consumer.as_ref(),
-            // )
-            // .await
-            // {
-            //     Some(false)
-            // } else {
-            //     Some(true)
-            // }
-            Some(false)
-        } else {
-            None
-        };
-        // // Close the RSVPs, ignoring errors
-        let rsvps_are_closed =
-            if let Err(err) = meetup_client.close_rsvps(new_event.id.0.clone()).await {
-                eprintln!(
-                    "RSVPs for event {} could not be closed:\n{:#?}",
-                    &new_event.id, err
-                );
-                false
-            } else {
-                true
-            };
-        // Remove any possibly existing channel snoozes
-        {
-            let mut tx = state.pool.begin().await?;
-            if let Ok(Some(channel_id)) =
-                lib::get_series_text_channel(event_series_id, &mut tx).await
-            {
-                sqlx::query!(r#"UPDATE event_series_text_channel SET snooze_until = NULL WHERE discord_id = $1"#, channel_id.get() as i64).execute(&mut *tx).await.ok();
-                tx.commit().await.ok();
-            }
-        }
-        // Announce the new session in the Discord channel
-        let channel_roles =
-            lib::get_event_series_roles(event_series_id, &mut state.pool.begin().await?).await?;
-        let message = if let Some(channel_roles) = channel_roles {
-            format!(
-                "Your adventure continues here, heroes of {channel_role_mention}: {link}. Slay the \
-                 dragon, save the prince, get the treasure, or whatever shenanigans you like to \
-                 get into.",
-                link = &new_event.short_url,
-                channel_role_mention = channel_roles.user.mention()
-            )
-        } else {
-            format!(
-                "Your adventure continues @here: {link}. Slay the dragon, save the prince, get \
-                 the treasure, or whatever shenanigans you like to get into.",
-                link = &new_event.short_url
-            )
-        };
-        if let Err(err) = lib::discord::util::say_in_event_channel(
-            event.id,
-            &message,
-            &state.pool,
-            &state.discord_cache_http,
-        )
-        .await
-        {
-            eprintln!(
-                "Encountered an error when trying to announce the new session in the \
-                 channel:\n{:#?}",
-                err
-            );
-        }
-        // If RSVPs were not transferred, announce the new session in the bot alerts channel
-        if is_open_game {
-            let message = format!(
-                "{organiser_mention}, a new session has been scheduled:\n{link}.\nPlease announce \
-                 this session for new players to join. Don't forget to **open RSVPs** when you do \
-                 that.",
-                organiser_mention = lib::discord::sync::ids::ORGANISER_ID.mention(),
-                link = &new_event.event_url,
-            );
-            if let Err(err) =
-                lib::discord::util::say_in_bot_alerts_channel(&message, &state.discord_cache_http)
-                    .await
-            {
-                eprintln!(
-                    "Encountered an error when trying to announce a new session in the bot alerts \
-                     channel:\n{:#?}",
-                    err
-                );
-            }
-        }
-        let template = ScheduleSessionSuccessTemplate {
-            title: new_event.title.unwrap_or_str("No title"),
-            link: &new_event.event_url,
-            transferred_all_rsvps: transferred_all_rsvps,
-            closed_rsvps: rsvps_are_closed,
-        };
-        return Ok(template.into_response());
-    }
-    let template: MessageTemplate = (
-        "No prior event found",
-        "Cannot schedule a continuation session without an initial event",
-    )
-        .into();
-    Ok(template.into_response())
+    Ok((date_time.with_timezone(&chrono::Utc), duration))
 }
@@ -7,17 +7,20 @@ use axum::{
     routing::{get, get_service},
     Router,
 };
-use futures_util::lock::Mutex;
 use tower_http::services::ServeDir;
 
-use super::{api, auth, linking, schedule_session, stripe_webhook_endpoint};
+use super::{
+    api, archive, audit, auth, calendar, date_poll, expiration_policy, linking, manage_session,
+    schedule_session, stripe_webhook_endpoint, sync_discord,
+};
 
 pub struct State {
     pub oauth2_consumer: Arc<lib::meetup::oauth2::OAuth2Consumer>,
     pub redis_client: redis::Client,
     pub pool: sqlx::PgPool,
-    pub async_meetup_client: Arc<Mutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
+    pub meetup_connection: lib::meetup::connection::MeetupConnection,
     pub discord_cache_http: lib::discord::CacheAndHttp,
+    pub guild_configs: Arc<lib::discord::config::GuildConfigs>,
     pub bot_name: String,
     pub stripe_webhook_secret: Option<String>,
     pub stripe_client: Arc<stripe::Client>,
@@ -37,8 +40,9 @@ pub fn create_server(
     addr: std::net::SocketAddr,
     redis_client: redis::Client,
     pool: sqlx::PgPool,
-    async_meetup_client: Arc<Mutex<Option<Arc<lib::meetup::newapi::AsyncClient>>>>,
+    meetup_connection: lib::meetup::connection::MeetupConnection,
     discord_cache_http: lib::discord::CacheAndHttp,
+    guild_configs: Arc<lib::discord::config::GuildConfigs>,
     bot_name: String,
     stripe_webhook_secret: Option<String>,
     stripe_client: Arc<stripe::Client>,
@@ -50,8 +54,9 @@ pub fn create_server(
         oauth2_consumer,
         redis_client,
         pool,
-        async_meetup_client,
+        meetup_connection,
         discord_cache_http,
+        guild_configs,
         bot_name,
         stripe_webhook_secret,
         stripe_client,
@@ -59,17 +64,31 @@ pub fn create_server(
     });
     let linking_routes = linking::create_routes();
     let schedule_session_routes = schedule_session::create_routes();
+    let manage_session_routes = manage_session::create_routes();
+    let date_poll_routes = date_poll::create_routes();
     let stripe_webhook_routes = stripe_webhook_endpoint::create_routes();
     let auth_routes = auth::create_routes();
     let api_routes = api::create_routes();
+    let calendar_routes = calendar::create_routes();
+    let audit_routes = audit::create_routes();
+    let archive_routes = archive::create_routes();
+    let sync_discord_routes = sync_discord::create_routes();
+    let expiration_policy_routes = expiration_policy::create_routes();
     let static_route: Router = Router::new().nest_service(
         "/static",
         get_service(ServeDir::new(static_file_directory).append_index_html_on_directories(false)),
     );
     let router = linking_routes
         .merge(schedule_session_routes)
+        .merge(manage_session_routes)
+        .merge(date_poll_routes)
         .merge(stripe_webhook_routes)
         .merge(auth_routes)
+        .merge(calendar_routes)
+        .merge(audit_routes)
+        .merge(archive_routes)
+        .merge(sync_discord_routes)
+        .merge(expiration_policy_routes)
         .route(
             "/",
             get(main_handler).layer(axum::middleware::from_fn(auth::auth)),
@@ -9,6 +9,7 @@ use axum::{
     Router,
 };
 use lazy_static::lazy_static;
+use lib::discord::config::GuildConfig;
 
 use super::server::State;
 
@@ -90,8 +91,10 @@ async fn stripe_webhook_handler(
         if event.type_ == stripe::EventType::CustomerSubscriptionCreated {
             if let stripe::EventObject::Subscription(subscription) = event.data.object {
                 if let Err(err) = handle_new_subscription(
+                    &state.pool,
                     &state.discord_cache_http,
                     &state.stripe_client,
+                    state.guild_configs.default_guild(),
                     &subscription,
                 )
                 .await
@@ -106,10 +109,25 @@ async fn stripe_webhook_handler(
 }
 
 async fn handle_new_subscription(
+    db_connection: &sqlx::PgPool,
     discord_api: &lib::discord::CacheAndHttp,
     stripe_client: &stripe::Client,
+    guild_config: &GuildConfig,
     subscription: &stripe::Subscription,
 ) -> Result<(), lib::meetup::Error> {
+    let (champion_id, insider_id, gm_champion_id) = match (
+        guild_config.champion_role_id,
+        guild_config.insider_role_id,
+        guild_config.gm_champion_role_id,
+    ) {
+        (Some(champion_id), Some(insider_id), Some(gm_champion_id)) => {
+            (champion_id, insider_id, gm_champion_id)
+        }
+        _ => {
+            eprintln!("Subscription roles are not configured, ignoring new subscription");
+            return Ok(());
+        }
+    };
     // let customer = match &subscription.customer {
     //     stripe::Expandable::Object(customer) => *customer.clone(),
     //     stripe::Expandable::Id(customer_id) => {
@@ -134,8 +152,12 @@ async fn handle_new_subscription(
         .and_then(|metadata| metadata.get("Discord"))
     {
         // Try to find the Discord user associated with this subscription
-        let id =
-            lib::tasks::subscription_roles::discord_username_to_id(discord_api, username).await?;
+        let id = lib::tasks::subscription_roles::discord_username_to_id(
+            discord_api,
+            guild_config,
+            username,
+        )
+        .await?;
         if let Some(discord_id) = id {
             // TODO: might block
             let discord_user = discord_id.to_user(discord_api).await?;
@@ -149,16 +171,18 @@ async fn handle_new_subscription(
                 if let Ok(true) = discord_user
                     .has_role(
                         discord_api,
-                        lib::discord::sync::ids::GUILD_ID,
-                        lib::discord::sync::ids::GAME_MASTER_ID,
+                        guild_config.guild_id,
+                        guild_config.game_master_role_id,
                     )
                     .await
                 {
                     println!("Adding GM Champion role");
                     lib::tasks::subscription_roles::add_member_role(
+                        db_connection,
                         discord_api,
+                        guild_config,
                         discord_id,
-                        lib::tasks::subscription_roles::ids::GM_CHAMPION_ID,
+                        gm_champion_id,
                         Some(
                             "Automatic role assignment due to being a GM champion (via Stripe \
                              Webhook)",
@@ -168,9 +192,11 @@ async fn handle_new_subscription(
                 } else {
                     println!("Adding Champion role");
                     lib::tasks::subscription_roles::add_member_role(
+                        db_connection,
                         discord_api,
+                        guild_config,
                         discord_id,
-                        lib::tasks::subscription_roles::ids::CHAMPION_ID,
+                        champion_id,
                         Some(
                             "Automatic role assignment due to being a champion (via Stripe \
                              Webhook)",
@@ -182,13 +208,25 @@ async fn handle_new_subscription(
             if is_insider_product {
                 println!("Adding Insider role");
                 lib::tasks::subscription_roles::add_member_role(
+                    db_connection,
                     discord_api,
+                    guild_config,
                     discord_id,
-                    lib::tasks::subscription_roles::ids::INSIDER_ID,
+                    insider_id,
                     Some("Automatic role assignment due to being an insider (via Stripe Webhook)"),
                 )
                 .await?;
             }
+            // Also bring the subscriber's game roles up to date
+            if let Err(err) = lib::sync_queue::enqueue(
+                lib::sync_queue::SyncJob::MemberRoles(discord_id),
+                "Stripe subscription",
+                db_connection,
+            )
+            .await
+            {
+                eprintln!("Could not enqueue the member roles sync:\n{:#?}", err);
+            }
         } else {
             eprintln!(
                 "Could not match the Discord username '{}' to an actual Discord user",
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+
+use askama::Template;
+use axum::{
+    extract::Extension,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use super::{
+    auth::{require_bot_admin, AuthenticatedMember},
+    server::State,
+    WebError,
+};
+
+pub fn create_routes() -> Router {
+    Router::new().route(
+        "/sync_discord/dry_run",
+        get(sync_discord_dry_run_handler).layer(axum::middleware::from_fn(super::auth::auth)),
+    )
+}
+
+#[derive(Template)]
+#[template(path = "sync_discord_dry_run.html")]
+struct SyncDiscordDryRunTemplate {
+    changes: Vec<String>,
+    errors: Vec<String>,
+}
+
+// Shows what "sync discord" would change without changing anything.
+// Only available to bot admins.
+async fn sync_discord_dry_run_handler(
+    Extension(state): Extension<Arc<State>>,
+    Extension(AuthenticatedMember(member_id)): Extension<AuthenticatedMember>,
+) -> Result<Response, WebError> {
+    require_bot_admin(&state, member_id).await?;
+    let mut redis_connection = state.redis_client.get_async_connection().await?;
+    let bot_id = state.discord_cache_http.cache.current_user().id;
+    let plan = lib::discord::sync::plan_sync_discord(
+        &mut redis_connection,
+        &state.pool,
+        &state.discord_cache_http,
+        &state.guild_configs,
+        bot_id,
+    )
+    .await?;
+    let template = SyncDiscordDryRunTemplate {
+        changes: plan.changes().iter().map(ToString::to_string).collect(),
+        errors: plan
+            .errors()
+            .iter()
+            .map(|(series_id, error)| format!("Event series {}: {}", series_id.0, error))
+            .collect(),
+    };
+    Ok(template.into_response())
+}